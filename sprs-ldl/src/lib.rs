@@ -9,15 +9,39 @@
 ///!
 ///! The easiest way to use this API is to create a `LdlNumeric` instance from
 ///! a matrix, then use the `LdlNumeric::solve` method.
+///! `LdlNumeric::solve_in_place` solves into a caller buffer without
+///! allocating, `LdlNumeric::solve_multi` solves for the columns of a 2D
+///! array, and `LdlNumeric::solve_sparse` solves for a sparse right-hand
+///! side, returning a sparse vector.
+///!
+///! Hermitian matrices, eg with complex values, can be decomposed as
+///! `A = L D L^H` using `LdlNumeric::new_hermitian`, where D is real and
+///! `L^H` is the conjugate transpose of L.
 ///!
 ///! It is possible to update a decomposition if the sparsity structure of a
 ///! matrix does not change. In that case the `LdlNumeric::update` method can
 ///! be used.
 ///!
+///! When a matrix changes by a low rank term, its decomposition can be
+///! modified without being recomputed, using `LdlNumeric::rank_one_update`,
+///! `LdlNumeric::rank_one_downdate` and their rank-k counterparts. Rows and
+///! columns can be deleted and added back with
+///! `LdlNumeric::delete_row_col` and `LdlNumeric::add_row_col`.
+///!
 ///! When only the sparsity structure of a matrix is known, it is possible
 ///! to precompute part of the factorization by using the `LdlSymbolic` struct.
 ///! This struct can the be converted into a `LdlNumeric` once the non-zero
 ///! values are known, using the `LdlSymbolic::factor` method.
+///!
+///! The decompositions report their failures through `SprsError`: input
+///! which is not square or not symmetric, and singular input, where
+///! `StructurallySingular` and `NumericallySingular` give the column of the
+///! zero pivot. The symmetry check can be skipped using
+///! `LdlSymbolic::new_perm_with_check` and a `SymmetryCheck`.
+///!
+///! Symmetric positive definite matrices whose factor has large dense parts
+///! are better handled by the supernodal Cholesky decomposition of the
+///! `supernodal` module, which has the same symbolic/numeric split.
 
 // This method is adapted from the LDL library by Tim Davis:
 //
@@ -55,22 +79,46 @@
 
 extern crate sprs;
 extern crate num;
+extern crate ndarray;
 
-use std::ops::Deref;
+use std::cell::RefCell;
+use std::cmp;
+use std::mem;
+use std::ops::{Deref, Range};
 use std::ops::IndexMut;
 
+use ndarray::{Array, ArrayView, ArrayViewMut, Axis};
 use num::traits::Num;
 
 use sprs::{
-    CsMat,
-    CsMatView,
+    Conjugate,
+    CsMatBase,
+    CsMatViewI,
+    CsVecI,
+    CsVecViewI,
+    Ix2,
+    is_hermitian,
     is_symmetric,
-    Permutation,
-    PermOwned,
+    PermutationBase,
+    PermOwnedI,
+    SpIndex,
 };
+use sprs::errors::SprsError;
 use sprs::linalg;
-use sprs::stack::DStack;
+use sprs::stack::{self, DStack, StackVal};
 
+pub mod supernodal;
+mod updown;
+
+pub use supernodal::{SupernodalSymbolic, SupernodalNumeric};
+
+/// Whether the symmetry of a matrix should be checked before decomposing it.
+///
+/// Checking the symmetry, or the hermitian-ness, of a matrix costs about as
+/// much as a transposition. Callers who already know their input is
+/// symmetric can skip the check. The full non-zero pattern of the matrix
+/// should be stored in any case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SymmetryCheck {
     CheckSymmetry,
     DontCheckSymmetry,
@@ -78,38 +126,46 @@ pub enum SymmetryCheck {
 
 /// Structure to compute and hold a symbolic LDLT decomposition
 #[derive(Debug)]
-pub struct LdlSymbolic {
-    colptr: Vec<usize>,
+pub struct LdlSymbolic<I: SpIndex = usize> {
+    colptr: Vec<I>,
     parents: linalg::etree::ParentsOwned,
     nz: Vec<usize>,
     flag_workspace: Vec<usize>,
-    perm: Permutation<Vec<usize>>,
+    perm: PermOwnedI<I>,
+    hermitian: bool,
 }
 
 /// Structure to hold a numeric LDLT decomposition
 #[derive(Debug)]
-pub struct LdlNumeric<N> {
-    symbolic: LdlSymbolic,
-    l_indices: Vec<usize>,
+pub struct LdlNumeric<N, I: SpIndex = usize> {
+    symbolic: LdlSymbolic<I>,
+    l_indices: Vec<I>,
     l_data: Vec<N>,
     diag: Vec<N>,
     y_workspace: Vec<N>,
     pattern_workspace: DStack<usize>,
+    visited: Vec<bool>,
+    reach_workspace: DStack<StackVal<usize>>,
+    precond_workspace: RefCell<Vec<N>>,
 }
 
-impl LdlSymbolic {
+impl<I: SpIndex> LdlSymbolic<I> {
     /// Compute the symbolic LDLT of the given matrix
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// * if mat is not symmetric
-    pub fn new<N, IpS, IS, DS>(mat: &CsMat<N, IpS, IS, DS>) -> LdlSymbolic
+    /// * `NonSquareMatrix` if mat is not square
+    /// * `NonSymmetricMatrix` if mat is not symmetric
+    /// * `StructurallySingular(col)` if the column `col` of mat leads to a
+    ///   structurally zero pivot
+    pub fn new<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<LdlSymbolic<I>, SprsError>
     where N: Copy + PartialEq,
-          IpS: Deref<Target = [usize]>,
-          IS: Deref<Target = [usize]>,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
           DS: Deref<Target = [N]>
     {
-        let perm: Permutation<Vec<usize>> = Permutation::identity();
+        let perm: PermOwnedI<I> = PermutationBase::identity();
         LdlSymbolic::new_perm(mat, perm)
     }
 
@@ -119,38 +175,139 @@ impl LdlSymbolic {
     /// Using a good permutation matrix can reduce the non-zero count in L,
     /// thus making the decomposition and the solves faster.
     ///
-    /// # Panics
+    /// # Errors
+    ///
+    /// Same as `new`.
+    pub fn new_perm<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                    perm: PermOwnedI<I>)
+                                    -> Result<LdlSymbolic<I>, SprsError>
+    where N: Copy + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        LdlSymbolic::new_perm_with_check(mat,
+                                         perm,
+                                         SymmetryCheck::CheckSymmetry)
+    }
+
+    /// Compute the symbolic decomposition L D L^T = P A P^T
+    /// where P is a permutation matrix, only checking the symmetry of the
+    /// matrix if requested.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new`, `NonSymmetricMatrix` being only reported when
+    /// `check_symmetry` is `SymmetryCheck::CheckSymmetry`.
+    pub fn new_perm_with_check<N, IpS, IS, DS>(
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        perm: PermOwnedI<I>,
+        check_symmetry: SymmetryCheck)
+        -> Result<LdlSymbolic<I>, SprsError>
+    where N: Copy + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        if check_symmetry == SymmetryCheck::CheckSymmetry &&
+           mat.rows() == mat.cols() && !is_symmetric(mat) {
+            return Err(SprsError::NonSymmetricMatrix);
+        }
+        LdlSymbolic::new_perm_(mat, perm, false)
+    }
+
+    /// Compute the symbolic decomposition of a hermitian matrix, which will
+    /// be factored as L D L^H.
+    ///
+    /// # Errors
+    ///
+    /// * `NonSquareMatrix` if mat is not square
+    /// * `NonHermitianMatrix` if mat is not hermitian
+    /// * `StructurallySingular(col)` if the column `col` of mat leads to a
+    ///   structurally zero pivot
+    pub fn new_hermitian<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                         -> Result<LdlSymbolic<I>, SprsError>
+    where N: Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let perm: PermOwnedI<I> = PermutationBase::identity();
+        LdlSymbolic::new_hermitian_perm(mat, perm)
+    }
+
+    /// Compute the symbolic decomposition L D L^H = P A P^T of a hermitian
+    /// matrix, where P is a permutation matrix.
+    ///
+    /// # Errors
     ///
-    /// * if mat is not symmetric
-    pub fn new_perm<N, IpS, IS, DS>(mat: &CsMat<N, IpS, IS, DS>,
-                                    perm: PermOwned)
-                                    -> LdlSymbolic
+    /// Same as `new_hermitian`.
+    pub fn new_hermitian_perm<N, IpS, IS, DS>(
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        perm: PermOwnedI<I>)
+        -> Result<LdlSymbolic<I>, SprsError>
+    where N: Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        LdlSymbolic::new_hermitian_perm_with_check(
+            mat, perm, SymmetryCheck::CheckSymmetry)
+    }
+
+    /// Compute the symbolic decomposition L D L^H = P A P^T of a hermitian
+    /// matrix, where P is a permutation matrix, only checking that the
+    /// matrix is hermitian if requested.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new_hermitian`, `NonHermitianMatrix` being only reported
+    /// when `check_symmetry` is `SymmetryCheck::CheckSymmetry`.
+    pub fn new_hermitian_perm_with_check<N, IpS, IS, DS>(
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        perm: PermOwnedI<I>,
+        check_symmetry: SymmetryCheck)
+        -> Result<LdlSymbolic<I>, SprsError>
+    where N: Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        if check_symmetry == SymmetryCheck::CheckSymmetry &&
+           mat.rows() == mat.cols() && !is_hermitian(mat) {
+            return Err(SprsError::NonHermitianMatrix);
+        }
+        LdlSymbolic::new_perm_(mat, perm, true)
+    }
+
+    fn new_perm_<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                 perm: PermOwnedI<I>,
+                                 hermitian: bool)
+                                 -> Result<LdlSymbolic<I>, SprsError>
     where N: Copy + PartialEq,
-          IpS: Deref<Target = [usize]>,
-          IS: Deref<Target = [usize]>,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
           DS: Deref<Target = [N]>
     {
         let n = mat.cols();
-        assert!(mat.rows() == n, "matrix should be square");
-        let mut l_colptr = vec![0; n+1];
+        let mut l_colptr = vec![I::zero(); n+1];
         let mut parents = linalg::etree::ParentsOwned::new(n);
         let mut l_nz = vec![0; n];
-        let mut flag_workspace = vec![0; n];
-        ldl_symbolic(mat.view(),
-                     &perm,
-                     &mut l_colptr,
-                     parents.view_mut(),
-                     &mut l_nz,
-                     &mut flag_workspace,
-                     SymmetryCheck::CheckSymmetry);
-
-        LdlSymbolic {
+        try!(ldl_symbolic(mat.view(),
+                          &perm,
+                          &mut l_colptr,
+                          parents.view_mut(),
+                          &mut l_nz,
+                          SymmetryCheck::DontCheckSymmetry));
+
+        Ok(LdlSymbolic {
             colptr: l_colptr,
             parents: parents,
             nz: l_nz,
-            flag_workspace: flag_workspace,
+            flag_workspace: vec![0; n],
             perm: perm,
-        }
+            hermitian: hermitian,
+        })
     }
 
     /// The size of the linear system associated with this decomposition
@@ -162,22 +319,30 @@ impl LdlSymbolic {
     /// The number of non-zero entries in L
     #[inline]
     pub fn nnz(&self) -> usize {
-        let n = self.problem_size();
-        self.colptr[n]
+        self.nz.iter().sum()
     }
 
     /// Compute the numerical decomposition of the given matrix.
+    ///
+    /// The decomposition is L D L^H if this symbolic decomposition was
+    /// computed by `new_hermitian` or `new_hermitian_perm`, L D L^T
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   eliminating the column `col` of mat
     pub fn factor<N, IpS, IS, DS>(self,
-                                  mat: &CsMat<N, IpS, IS, DS>)
-                                  -> LdlNumeric<N>
-    where N: Copy + Num + PartialOrd,
-          IpS: Deref<Target = [usize]>,
-          IS: Deref<Target = [usize]>,
+                                  mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                  -> Result<LdlNumeric<N, I>, SprsError>
+    where N: Copy + Num + Conjugate,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
           DS: Deref<Target = [N]>
     {
         let n = self.problem_size();
-        let nnz = self.nnz();
-        let l_indices = vec![0; nnz];
+        let nnz = self.colptr[n].index();
+        let l_indices = vec![I::zero(); nnz];
         let l_data = vec![N::zero(); nnz];
         let diag = vec![N::zero(); n];
         let y_workspace = vec![N::zero(); n];
@@ -189,25 +354,34 @@ impl LdlSymbolic {
             diag: diag,
             y_workspace: y_workspace,
             pattern_workspace: pattern_workspace,
+            visited: vec![false; n],
+            reach_workspace: DStack::with_capacity(cmp::max(2 * n, 2)),
+            precond_workspace: RefCell::new(vec![N::zero(); n]),
         };
-        ldl_numeric.update(mat);
-        ldl_numeric
+        try!(ldl_numeric.update(mat));
+        Ok(ldl_numeric)
     }
 }
 
-impl<N> LdlNumeric<N> {
+impl<N, I: SpIndex> LdlNumeric<N, I> {
     /// Compute the numeric LDLT decomposition of the given matrix.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// * if mat is not symmetric
-    pub fn new<IpS, IS, DS>(mat: &CsMat<N, IpS, IS, DS>) -> Self
-    where N: Copy + Num + PartialOrd,
-          IpS: Deref<Target = [usize]>,
-          IS: Deref<Target = [usize]>,
+    /// * `NonSquareMatrix` if mat is not square
+    /// * `NonSymmetricMatrix` if mat is not symmetric
+    /// * `StructurallySingular(col)` if the column `col` of mat leads to a
+    ///   structurally zero pivot
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   eliminating the column `col` of mat
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                            -> Result<Self, SprsError>
+    where N: Copy + Num + Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
           DS: Deref<Target = [N]>
     {
-        let symbolic = LdlSymbolic::new(mat);
+        let symbolic = try!(LdlSymbolic::new(mat));
         symbolic.factor(mat)
     }
 
@@ -217,31 +391,80 @@ impl<N> LdlNumeric<N> {
     /// Using a good permutation matrix can reduce the non-zero count in L,
     /// thus making the decomposition and the solves faster.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// * if mat is not symmetric
-    pub fn new_perm<IpS, IS, DS>(mat: &CsMat<N, IpS, IS, DS>,
-                                 perm: PermOwned)
-                                 -> Self
-    where N: Copy + Num + PartialOrd,
-          IpS: Deref<Target = [usize]>,
-          IS: Deref<Target = [usize]>,
+    /// Same as `new`.
+    pub fn new_perm<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                 perm: PermOwnedI<I>)
+                                 -> Result<Self, SprsError>
+    where N: Copy + Num + Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
           DS: Deref<Target = [N]>
     {
-        let symbolic = LdlSymbolic::new_perm(mat, perm);
+        let symbolic = try!(LdlSymbolic::new_perm(mat, perm));
+        symbolic.factor(mat)
+    }
+
+    /// Compute the numeric L D L^H decomposition of the given hermitian
+    /// matrix. The diagonal D is real.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new`, `NonHermitianMatrix` being reported instead of
+    /// `NonSymmetricMatrix`.
+    pub fn new_hermitian<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                      -> Result<Self, SprsError>
+    where N: Copy + Num + Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = try!(LdlSymbolic::new_hermitian(mat));
+        symbolic.factor(mat)
+    }
+
+    /// Compute the numeric decomposition L D L^H = P A P^T of the given
+    /// hermitian matrix, where P is a permutation matrix.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new_hermitian`.
+    pub fn new_hermitian_perm<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                           perm: PermOwnedI<I>)
+                                           -> Result<Self, SprsError>
+    where N: Copy + Num + Conjugate + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = try!(LdlSymbolic::new_hermitian_perm(mat, perm));
         symbolic.factor(mat)
     }
 
     /// Update the decomposition with the given matrix. The matrix must
     /// have the same non-zero pattern as the original matrix, otherwise
     /// the result is unspecified.
-    pub fn update<IpS, IS, DS>(&mut self, mat: &CsMat<N, IpS, IS, DS>)
-    where N: Copy + Num + PartialOrd,
-          IpS: Deref<Target = [usize]>,
-          IS: Deref<Target = [usize]>,
+    ///
+    /// # Errors
+    ///
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   eliminating the column `col` of mat. The decomposition should not
+    ///   be used to solve systems until a successful update.
+    pub fn update<IpS, IS, DS>(&mut self,
+                               mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<(), SprsError>
+    where N: Copy + Num + Conjugate,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
           DS: Deref<Target = [N]>
     {
-        ldl_numeric(mat.view(),
+        let numeric = if self.symbolic.hermitian {
+            ldl_numeric_hermitian
+        } else {
+            ldl_numeric
+        };
+        numeric(mat.view(),
                     &self.symbolic.colptr,
                     self.symbolic.parents.view(),
                     &self.symbolic.perm,
@@ -251,34 +474,229 @@ impl<N> LdlNumeric<N> {
                     &mut self.diag,
                     &mut self.y_workspace,
                     &mut self.pattern_workspace,
-                    &mut self.symbolic.flag_workspace);
+                    &mut self.symbolic.flag_workspace)
     }
 
     /// Solve the system A x = rhs
     pub fn solve<'a, V>(&self, rhs: &V) -> Vec<N>
-    where N: 'a + Copy + Num,
+    where N: 'a + Copy + Num + Conjugate,
           V: Deref<Target = [N]>
     {
         let mut x = &self.symbolic.perm * &rhs[..];
-        let l = self.l_view();
-        ldl_lsolve(&l, &mut x);
-        linalg::diag_solve(&self.diag, &mut x);
-        ldl_ltsolve(&l, &mut x);
+        self.solve_permuted(&mut x);
         let pinv = self.symbolic.perm.inv();
         &pinv * &x
     }
 
-    fn l_view(&self) -> CsMatView<N>
+    /// Solve the system A x = rhs, where rhs is given in x and is
+    /// overwritten by the solution.
+    ///
+    /// Contrary to `solve`, this method does not allocate.
+    ///
+    /// # Panics
+    ///
+    /// * if x does not have the size of the problem
+    pub fn solve_in_place(&mut self, x: &mut [N])
+    where N: Copy + Num + Conjugate
     {
-        let n = self.symbolic.problem_size();
-        // CsMat invariants are guaranteed by the LDL algorithm
-        unsafe {
-            CsMatView::new_view_raw(sprs::CSC,
-                                    (n, n),
-                                    self.symbolic.colptr.as_ptr(),
-                                    self.l_indices.as_ptr(),
-                                    self.l_data.as_ptr())
+        let n = self.problem_size();
+        assert_eq!(x.len(), n, "Dimension mismatch");
+        let mut y = mem::replace(&mut self.y_workspace, Vec::new());
+        for (k, y_k) in y.iter_mut().enumerate() {
+            *y_k = x[self.symbolic.perm.at(k)];
+        }
+        self.solve_permuted(&mut y);
+        for (k, y_k) in y.iter_mut().enumerate() {
+            x[self.symbolic.perm.at(k)] = *y_k;
+            *y_k = N::zero();
         }
+        self.y_workspace = y;
+    }
+
+    /// Solve the system A X = B, where the columns of the 2D array `rhs`
+    /// are the right-hand sides B. The solutions overwrite `rhs`.
+    ///
+    /// # Panics
+    ///
+    /// * if rhs does not have as many rows as the size of the problem
+    pub fn solve_multi_in_place(&mut self, mut rhs: ArrayViewMut<N, Ix2>)
+    where N: Copy + Num + Conjugate
+    {
+        let n = self.problem_size();
+        assert_eq!(rhs.shape()[0], n, "Dimension mismatch");
+        let mut y = mem::replace(&mut self.y_workspace, Vec::new());
+        for mut col in rhs.axis_iter_mut(Axis(1)) {
+            for (k, y_k) in y.iter_mut().enumerate() {
+                *y_k = col[[self.symbolic.perm.at(k)]];
+            }
+            self.solve_permuted(&mut y);
+            for (k, y_k) in y.iter_mut().enumerate() {
+                col[[self.symbolic.perm.at(k)]] = *y_k;
+                *y_k = N::zero();
+            }
+        }
+        self.y_workspace = y;
+    }
+
+    /// Solve the system A X = B, where the columns of the 2D array `rhs`
+    /// are the right-hand sides B.
+    ///
+    /// # Panics
+    ///
+    /// * if rhs does not have as many rows as the size of the problem
+    pub fn solve_multi(&mut self, rhs: ArrayView<N, Ix2>) -> Array<N, Ix2>
+    where N: Copy + Num + Conjugate
+    {
+        let mut x = rhs.to_owned();
+        self.solve_multi_in_place(x.view_mut());
+        x
+    }
+
+    /// Solve the system A x = rhs for a sparse right-hand side.
+    ///
+    /// The forward substitution only visits the non-zero entries of
+    /// `L^-1 P rhs`, found by a depth first search in the graph of L with
+    /// `sprs::linalg::trisolve::sparse_reach`, which makes solving for
+    /// many very sparse right-hand sides efficient. The backward
+    /// substitution is restricted to the trees of the elimination forest
+    /// containing these entries, so the solution can only be expected to
+    /// be sparse if A is reducible.
+    ///
+    /// # Panics
+    ///
+    /// * if rhs does not have the size of the problem
+    pub fn solve_sparse(&mut self, rhs: CsVecViewI<N, I>) -> CsVecI<N, I>
+    where N: Copy + Num + Conjugate
+    {
+        let n = self.problem_size();
+        assert_eq!(rhs.dim(), n, "Dimension mismatch");
+        let mut y = mem::replace(&mut self.y_workspace, Vec::new());
+        let perm = &self.symbolic.perm;
+        let parents = &self.symbolic.parents;
+        let pattern: Vec<I> = rhs.indices()
+                                 .iter()
+                                 .map(|i| perm.at_inv(i.index()))
+                                 .map(I::from_usize)
+                                 .collect();
+        for (&k, &val) in pattern.iter().zip(rhs.data()) {
+            y[k.index()] = val;
+        }
+        let reach_cap = n + pattern.len() + self.symbolic.nnz();
+        if self.reach_workspace.capacity() < reach_cap {
+            self.reach_workspace = DStack::with_capacity(reach_cap);
+        }
+        let hermitian = self.symbolic.hermitian;
+        {
+            let colptr = &self.symbolic.colptr;
+            let nz = &self.symbolic.nz;
+            let l_indices = &self.l_indices;
+            let l_data = &self.l_data;
+            let l_col_range = |k: usize| {
+                let start = colptr[k].index();
+                start..start + nz[k]
+            };
+            let dstack = &mut self.reach_workspace;
+            let visited = &mut self.visited;
+            linalg::trisolve::sparse_reach_structure(
+                &pattern,
+                |k| &l_indices[l_col_range(k)],
+                dstack,
+                visited);
+
+            // forward substitution in topological order, and diagonal solve
+            for &k in dstack.iter_right().map(stack::extract_stack_val) {
+                let y_k = y[k];
+                let range = l_col_range(k);
+                for (i, &val) in l_indices[range.clone()]
+                                     .iter()
+                                     .zip(&l_data[range]) {
+                    let i = i.index();
+                    y[i] = y[i] - val * y_k;
+                }
+            }
+            for &k in dstack.iter_right().map(stack::extract_stack_val) {
+                y[k] = y[k] / self.diag[k];
+            }
+            dstack.clear_right();
+
+            // The non-zero pattern of L^-1 P rhs is closed under taking
+            // parents in the elimination tree, so the trees it touches are
+            // those of its roots. Parents having a larger index than their
+            // children, the nodes of these trees are marked by a backward
+            // sweep.
+            for k in (0..n).rev() {
+                if let Some(parent) = parents.get_parent(k) {
+                    visited[k] = visited[parent];
+                }
+            }
+
+            // backward substitution, restricted to the marked nodes
+            for k in (0..n).rev().filter(|&k| visited[k]) {
+                let range = l_col_range(k);
+                let mut y_k = y[k];
+                for (i, &val) in l_indices[range.clone()]
+                                     .iter()
+                                     .zip(&l_data[range]) {
+                    let val = if hermitian { val.conj() } else { val };
+                    y_k = y_k - val * y[i.index()];
+                }
+                y[k] = y_k;
+            }
+        }
+
+        let mut entries = Vec::new();
+        for k in 0..n {
+            if self.visited[k] {
+                entries.push((perm.at(k), y[k]));
+                self.visited[k] = false;
+                y[k] = N::zero();
+            }
+        }
+        self.y_workspace = y;
+        entries.sort_by_key(|&(i, _)| i);
+        let (indices, data) = entries.into_iter()
+                                     .map(|(i, val)| (I::from_usize(i), val))
+                                     .unzip();
+        CsVecI::new(n, indices, data)
+    }
+
+    /// Solve L D L^T x = rhs, or L D L^H x = rhs, for an rhs already
+    /// permuted, overwriting rhs by the solution
+    fn solve_permuted(&self, x: &mut [N])
+    where N: Copy + Num + Conjugate
+    {
+        let n = self.problem_size();
+        for k in 0..n {
+            let x_k = x[k];
+            let range = self.l_col_range(k);
+            for (i, &val) in self.l_indices[range.clone()]
+                                 .iter()
+                                 .zip(&self.l_data[range]) {
+                let i = i.index();
+                x[i] = x[i] - val * x_k;
+            }
+        }
+        linalg::diag_solve(&self.diag, &mut *x);
+        let hermitian = self.symbolic.hermitian;
+        for k in (0..n).rev() {
+            let range = self.l_col_range(k);
+            let mut x_k = x[k];
+            for (i, &val) in self.l_indices[range.clone()]
+                                 .iter()
+                                 .zip(&self.l_data[range]) {
+                let val = if hermitian { val.conj() } else { val };
+                x_k = x_k - val * x[i.index()];
+            }
+            x[k] = x_k;
+        }
+    }
+
+    /// The positions of the entries of the column k of L in `l_indices`
+    /// and `l_data`. Modifications of the decomposition can leave room
+    /// after these entries, up to the start of the next column.
+    fn l_col_range(&self, k: usize) -> Range<usize> {
+        let start = self.symbolic.colptr[k].index();
+        start..start + self.symbolic.nz[k]
     }
 
     /// The size of the linear system associated with this decomposition
@@ -295,79 +713,168 @@ impl<N> LdlNumeric<N> {
 
 }
 
+/// The LDLT decomposition of a matrix can be used as a preconditioner for
+/// iterative solvers, eg when it is the decomposition of an approximation
+/// of the system matrix.
+impl<N, I> linalg::iterative::Preconditioner<N> for LdlNumeric<N, I>
+where N: Copy + Num + Conjugate,
+      I: SpIndex
+{
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        let perm = &self.symbolic.perm;
+        let mut y = self.precond_workspace.borrow_mut();
+        for (k, y_k) in y.iter_mut().enumerate() {
+            *y_k = r[perm.at(k)];
+        }
+        self.solve_permuted(&mut y);
+        for (k, &y_k) in y.iter().enumerate() {
+            z[perm.at(k)] = y_k;
+        }
+    }
+}
+
 /// Perform a symbolic LDLt decomposition of a symmetric sparse matrix
-pub fn ldl_symbolic<N, PStorage>(mat: CsMatView<N>,
-                                 perm: &Permutation<PStorage>,
-                                 l_colptr: &mut [usize],
+///
+/// The elimination tree and the column counts of L are computed by the
+/// `sprs::linalg::etree` module, l_nz receives the number of non-zero
+/// entries below the diagonal of each column of L.
+///
+/// # Errors
+///
+/// * `NonSquareMatrix` if mat is not square
+/// * `NonSymmetricMatrix` if mat is not symmetric, when checked
+/// * `StructurallySingular(col)` if the column `col` of mat has no
+///   non-zero entry on or above the diagonal once permuted, and no fill-in
+///   can reach its diagonal, making its pivot structurally zero
+pub fn ldl_symbolic<N, I, PStorage>(mat: CsMatViewI<N, I>,
+                                    perm: &PermutationBase<I, PStorage>,
+                                    l_colptr: &mut [I],
                                  mut parents: linalg::etree::ParentsViewMut,
                                  l_nz: &mut [usize],
-                                 flag_workspace: &mut [usize],
                                  check_symmetry: SymmetryCheck)
+                                 -> Result<(), SprsError>
 where N: Clone + Copy + PartialEq,
-      PStorage: Deref<Target = [usize]>
+      I: SpIndex,
+      PStorage: Deref<Target = [I]>
 {
-
-    match check_symmetry {
-        SymmetryCheck::DontCheckSymmetry => (),
-        SymmetryCheck::CheckSymmetry => {
-            if !is_symmetric(&mat) {
-                panic!("Matrix is not symmetric")
-            }
+    let n = mat.rows();
+    if mat.cols() != n {
+        return Err(SprsError::NonSquareMatrix);
+    }
+    if check_symmetry == SymmetryCheck::CheckSymmetry && !is_symmetric(&mat) {
+        return Err(SprsError::NonSymmetricMatrix);
+    }
+    // The pivot of a column is structurally zero when the upper part of
+    // the column, diagonal included, is empty: its row of L is then empty
+    // too, and nothing can be subtracted from its missing diagonal.
+    for k in 0..n {
+        let col = perm.at(k);
+        let vec = mat.outer_view(col).expect("col is in bounds");
+        if vec.indices().iter().all(|i| perm.at_inv(i.index()) > k) {
+            return Err(SprsError::StructurallySingular(col));
         }
     }
 
-    let n = mat.rows();
-
-    let outer_it = mat.outer_iterator_perm(perm.view());
-    // compute the elimination tree of L
-    for (k, (_, vec)) in outer_it.enumerate() {
-
-        flag_workspace[k] = k; // this node is visited
-        parents.set_root(k);
-        l_nz[k] = 0;
-
-        for (inner_ind, _) in vec.iter_perm(perm.inv()) {
-            let mut i = inner_ind;
-
-            if i < k {
-                while flag_workspace[i] != k {
-                    parents.uproot(i, k);
-                    l_nz[i] += 1;
-                    flag_workspace[i] = k;
-                    i = parents.get_parent(i).expect("uprooted so not a root");
-                }
-            }
+    let etree = linalg::etree::ParentsOwned::from_symmetric(&mat, perm);
+    let counts = etree.cholesky_counts(&mat, perm);
+    for k in 0..n {
+        match etree.get_parent(k) {
+            Some(parent) => parents.uproot(k, parent),
+            None => parents.set_root(k),
         }
+        l_nz[k] = counts.col_counts[k] - 1;
     }
 
     let mut prev: usize = 0;
     for (k, colptr) in (0..n).zip(l_colptr.iter_mut()) {
-        *colptr = prev;
+        *colptr = I::from_usize(prev);
         prev += l_nz[k];
     }
-    l_colptr[n] = prev;
-
+    l_colptr[n] = I::from_usize(prev);
+    Ok(())
 }
 
 /// Perform numeric LDLT decomposition
 ///
 /// pattern_workspace is a DStack of capacity n
-pub fn ldl_numeric<N, PStorage>(mat: CsMatView<N>,
-                                l_colptr: &[usize],
-                                parents: linalg::etree::ParentsView,
-                                perm: &Permutation<PStorage>,
-                                l_nz: &mut [usize],
-                                l_indices: &mut [usize],
+///
+/// # Errors
+///
+/// * `NumericallySingular(col)` if a zero pivot is encountered when
+///   eliminating the column `col` of mat
+pub fn ldl_numeric<N, I, PStorage>(mat: CsMatViewI<N, I>,
+                                   l_colptr: &[I],
+                                   parents: linalg::etree::ParentsView,
+                                   perm: &PermutationBase<I, PStorage>,
+                                   l_nz: &mut [usize],
+                                   l_indices: &mut [I],
                                 l_data: &mut [N],
                                 diag: &mut [N],
                                 y_workspace: &mut [N],
                                 pattern_workspace: &mut DStack<usize>,
                                 flag_workspace: &mut [usize])
-where N: Clone + Copy + PartialEq + Num + PartialOrd,
-      PStorage: Deref<Target = [usize]>
+                                -> Result<(), SprsError>
+where N: Clone + Copy + PartialEq + Num,
+      I: SpIndex,
+      PStorage: Deref<Target = [I]>
+{
+    ldl_numeric_(mat, l_colptr, parents, perm, l_nz, l_indices, l_data,
+                 diag, y_workspace, pattern_workspace, flag_workspace,
+                 |x| x)
+}
+
+/// Perform numeric LDLH decomposition of a hermitian matrix
+///
+/// The arguments are the same as for `ldl_numeric`. The computed factor L
+/// is such that `A = L D L^H`, with a real D.
+pub fn ldl_numeric_hermitian<N, I, PStorage>(
+    mat: CsMatViewI<N, I>,
+    l_colptr: &[I],
+    parents: linalg::etree::ParentsView,
+    perm: &PermutationBase<I, PStorage>,
+    l_nz: &mut [usize],
+    l_indices: &mut [I],
+    l_data: &mut [N],
+    diag: &mut [N],
+    y_workspace: &mut [N],
+    pattern_workspace: &mut DStack<usize>,
+    flag_workspace: &mut [usize])
+    -> Result<(), SprsError>
+where N: Clone + Copy + PartialEq + Num + Conjugate,
+      I: SpIndex,
+      PStorage: Deref<Target = [I]>
+{
+    ldl_numeric_(mat, l_colptr, parents, perm, l_nz, l_indices, l_data,
+                 diag, y_workspace, pattern_workspace, flag_workspace,
+                 |x: N| x.conj())
+}
+
+/// Numeric decomposition, conj being the identity for LDLT and the
+/// complex conjugation for LDLH
+fn ldl_numeric_<N, I, PStorage, F>(mat: CsMatViewI<N, I>,
+                                   l_colptr: &[I],
+                                   parents: linalg::etree::ParentsView,
+                                   perm: &PermutationBase<I, PStorage>,
+                                   l_nz: &mut [usize],
+                                   l_indices: &mut [I],
+                                   l_data: &mut [N],
+                                   diag: &mut [N],
+                                   y_workspace: &mut [N],
+                                   pattern_workspace: &mut DStack<usize>,
+                                   flag_workspace: &mut [usize],
+                                   conj: F)
+                                   -> Result<(), SprsError>
+where N: Clone + Copy + PartialEq + Num,
+      I: SpIndex,
+      PStorage: Deref<Target = [I]>,
+      F: Fn(N) -> N
 {
-    let outer_it = mat.outer_iterator_perm(perm.view());
-    for (k, (_, vec)) in outer_it.enumerate() {
+    // we need the upper triangular part of the columns of A, which is
+    // the conjugate of the lower triangular part of its rows
+    let conj_val = |val| if mat.is_csr() { conj(val) } else { val };
+    for k in 0..mat.rows() {
+        // the outer vector k of P A P^T, iterated lazily
+        let vec = mat.outer_view(perm.at(k)).expect("k is in bounds");
 
         // compute the nonzero pattern of the kth row of L
         // in topological order
@@ -379,7 +886,7 @@ where N: Clone + Copy + PartialEq + Num + PartialOrd,
 
         for (inner_ind, &val) in vec.iter_perm(perm.inv())
                                     .filter(|&(i, _)| i <= k) {
-            y_workspace[inner_ind] = y_workspace[inner_ind] + val;
+            y_workspace[inner_ind] = y_workspace[inner_ind] + conj_val(val);
             let mut i = inner_ind;
             pattern_workspace.clear_left();
             while flag_workspace[i] != k {
@@ -397,33 +904,35 @@ where N: Clone + Copy + PartialEq + Num + PartialOrd,
         'pattern: for &i in pattern_workspace.iter_right() {
             let yi = y_workspace[i];
             y_workspace[i] = N::zero();
-            let p2 = l_colptr[i] + l_nz[i];
-            for p in l_colptr[i]..p2 {
+            let p2 = l_colptr[i].index() + l_nz[i];
+            for p in l_colptr[i].index()..p2 {
                 // we cannot go inside this loop before something has actually
                 // be written into l_indices[l_colptr[i]..p2] so this
                 // read is actually not into garbage
                 // actually each iteration of the 'pattern loop adds writes the
                 // value in l_indices that will be read on the next iteration
                 // TODO: can some design change make this fact more obvious?
-                let y_index = l_indices[p];
+                let y_index = l_indices[p].index();
                 y_workspace[y_index] = y_workspace[y_index] - l_data[p] * yi;
             }
-            let l_ki = yi / diag[i];
+            let l_ki = conj(yi / diag[i]);
             diag[k] = diag[k] - l_ki * yi;
-            l_indices[p2] = k;
+            l_indices[p2] = I::from_usize(k);
             l_data[p2] = l_ki;
             l_nz[i] += 1;
         }
         if diag[k] == N::zero() {
-            panic!("Matrix is singular");
+            return Err(SprsError::NumericallySingular(perm.at(k)));
         }
     }
+    Ok(())
 }
 
 /// Triangular solve specialized on lower triangular matrices
 /// produced by ldlt (diagonal terms are omitted and assumed to be 1).
-pub fn ldl_lsolve<N, V: ?Sized>(l: &CsMatView<N>, x: &mut V)
+pub fn ldl_lsolve<N, I, V: ?Sized>(l: &CsMatViewI<N, I>, x: &mut V)
 where N: Clone + Copy + Num,
+      I: SpIndex,
       V: IndexMut<usize, Output = N>
 {
     for (col_ind, vec) in l.outer_iterator().enumerate() {
@@ -436,8 +945,9 @@ where N: Clone + Copy + Num,
 
 /// Triangular transposed solve specialized on lower triangular matrices
 /// produced by ldlt (diagonal terms are omitted and assumed to be 1).
-pub fn ldl_ltsolve<N, V: ?Sized>(l: &CsMatView<N>, x: &mut V)
+pub fn ldl_ltsolve<N, I, V: ?Sized>(l: &CsMatViewI<N, I>, x: &mut V)
 where N: Clone + Copy + Num,
+      I: SpIndex,
       V: IndexMut<usize, Output = N>
 {
     for (outer_ind, vec) in l.outer_iterator().enumerate().rev() {
@@ -449,6 +959,22 @@ where N: Clone + Copy + Num,
     }
 }
 
+/// Conjugate transposed solve specialized on lower triangular matrices
+/// produced by ldlh (diagonal terms are omitted and assumed to be 1).
+pub fn ldl_lhsolve<N, I, V: ?Sized>(l: &CsMatViewI<N, I>, x: &mut V)
+where N: Clone + Copy + Num + Conjugate,
+      I: SpIndex,
+      V: IndexMut<usize, Output = N>
+{
+    for (outer_ind, vec) in l.outer_iterator().enumerate().rev() {
+        let mut x_outer = x[outer_ind];
+        for (inner_ind, &value) in vec.iter() {
+            x_outer = x_outer - value.conj() * x[inner_ind];
+        }
+        x[outer_ind] = x_outer;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use sprs::{
@@ -456,11 +982,17 @@ mod test {
         CsMat,
         CsMatView,
         CsMatOwned,
+        CsMatI,
+        CsVecOwned,
         Permutation,
         linalg,
     };
+    use ndarray::{Array, Axis};
     use super::SymmetryCheck;
+    use sprs::errors::SprsError;
+    use sprs::linalg::iterative::Preconditioner;
     use sprs::stack::DStack;
+    use num::complex::Complex64;
 
     fn test_mat1() -> CsMatOwned<f64> {
         let indptr = vec![0, 2, 5, 6, 7, 13, 14, 17, 20, 24, 28];
@@ -557,8 +1089,7 @@ mod test {
                             &mut l_colptr,
                             parents.view_mut(),
                             &mut l_nz,
-                            &mut flag_workspace,
-                            SymmetryCheck::CheckSymmetry);
+                            SymmetryCheck::CheckSymmetry).unwrap();
 
         let nnz = l_colptr[10];
         let mut l_indices = vec![0; nnz];
@@ -576,7 +1107,7 @@ mod test {
                            &mut diag,
                            &mut y_workspace,
                            &mut pattern_workspace,
-                           &mut flag_workspace);
+                           &mut flag_workspace).unwrap();
 
         let (expected_lp, expected_li, expected_lx, expected_d) =
             expected_factors1();
@@ -613,7 +1144,7 @@ mod test {
     fn test_factor_solve1() {
         let mat = test_mat1();
         let b = test_vec1();
-        let ldlt = super::LdlNumeric::new(&mat);
+        let ldlt = super::LdlNumeric::new(&mat).unwrap();
         let x = ldlt.solve(&b);
         let x0 = expected_res1();
         assert_eq!(x, x0);
@@ -639,10 +1170,273 @@ mod test {
 
         let perm = Permutation::new(vec![0, 2, 1, 3]);
 
-        let ldlt = super::LdlNumeric::new_perm(&mat, perm);
+        let ldlt = super::LdlNumeric::new_perm(&mat, perm).unwrap();
         let b = vec![9, 60, 18, 34];
         let x0 = vec![1, 2, 3, 4];
         let x = ldlt.solve(&b);
         assert_eq!(x, x0);
     }
+
+    #[test]
+    fn ldl_solve_u32_indices() {
+        let mat: CsMatI<f64, u32> = test_mat1().to_other_index().unwrap();
+        let b = test_vec1();
+        let ldlt = super::LdlNumeric::new(&mat).unwrap();
+        let x = ldlt.solve(&b);
+        let x0 = expected_res1();
+        assert_eq!(x, x0);
+    }
+
+    #[test]
+    fn amd_ordered_ldl_solve() {
+        let mat = test_mat1();
+        let perm = linalg::ordering::amd(&mat);
+        let ldlt = super::LdlNumeric::new_perm(&mat, perm).unwrap();
+        let natural_nnz = super::LdlSymbolic::new(&mat).unwrap().nnz();
+        assert!(ldlt.nnz() <= natural_nnz);
+        let b = test_vec1();
+        let x = ldlt.solve(&b);
+        let x0 = expected_res1();
+        for (xi, x0i) in x.iter().zip(x0.iter()) {
+            assert!((xi - x0i).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn csc_permuted_ldl_solve() {
+        let mat = test_mat1().to_csc();
+        let perm = linalg::ordering::amd(&mat);
+        let ldlt = super::LdlNumeric::new_perm(&mat, perm).unwrap();
+        let x = ldlt.solve(&test_vec1());
+        assert_close_f64(&x, &expected_res1());
+    }
+
+    #[test]
+    fn ldl_preconditioner() {
+        let mat = test_mat1();
+        let b = test_vec1();
+        // the exact factorization makes pcg converge in one iteration
+        let ldlt = super::LdlNumeric::new(&mat).unwrap();
+        let sol = linalg::iterative::pcg(&mat, &b, &ldlt, 1e-10, 10).unwrap();
+        assert!(sol.converged());
+        assert_eq!(sol.iterations(), 1);
+        let x0 = expected_res1();
+        for (xi, x0i) in sol.x.iter().zip(x0.iter()) {
+            assert!((xi - x0i).abs() < 1e-12);
+        }
+
+        // the permutation is applied on the way in and out
+        let perm = Permutation::new((0..10).rev().collect());
+        let ldlt = super::LdlNumeric::new_perm(&mat, perm).unwrap();
+        let mut z = vec![0.; 10];
+        ldlt.apply(&b, &mut z);
+        assert_close_f64(&z, &x0);
+    }
+
+    fn hermitian_mat() -> CsMatOwned<Complex64> {
+        // |1          | |2    | |1  -i  1+i|   | 2    -2i   2+2i|
+        // |i    1     | |  3  | |    1   2 | = | 2i    5    4+2i|
+        // |1-i  2    1| |    1| |        1 |   | 2-2i  4-2i  17  |
+        //       L          D         L^H     =           A
+        let c = Complex64::new;
+        CsMat::new_csc((3, 3),
+                       vec![0, 3, 6, 9],
+                       vec![0, 1, 2, 0, 1, 2, 0, 1, 2],
+                       vec![c(2., 0.), c(0., 2.), c(2., -2.),
+                            c(0., -2.), c(5., 0.), c(4., -2.),
+                            c(2., 2.), c(4., 2.), c(17., 0.)])
+    }
+
+    fn assert_close(x: &[Complex64], x0: &[Complex64]) {
+        for (xi, x0i) in x.iter().zip(x0.iter()) {
+            assert!((xi - x0i).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn hermitian_ldl() {
+        let c = Complex64::new;
+        let mat = hermitian_mat();
+        let b = vec![c(4., 4.), c(2., 13.), c(21., 19.)];
+        let x0 = vec![c(1., 0.), c(0., 1.), c(1., 1.)];
+
+        let ldlh = super::LdlNumeric::new_hermitian(&mat).unwrap();
+        assert_eq!(&ldlh.diag, &[c(2., 0.), c(3., 0.), c(1., 0.)]);
+        assert_eq!(&ldlh.l_data, &[c(0., 1.), c(1., -1.), c(2., 0.)]);
+        assert_close(&ldlh.solve(&b), &x0);
+
+        // the lower triangular part of rows is conjugated for csr matrices
+        let ldlh = super::LdlNumeric::new_hermitian(&mat.to_csr()).unwrap();
+        assert_close(&ldlh.solve(&b), &x0);
+
+        let perm = Permutation::new(vec![2, 0, 1]);
+        let ldlh = super::LdlNumeric::new_hermitian_perm(&mat, perm).unwrap();
+        assert_close(&ldlh.solve(&b), &x0);
+    }
+
+    #[test]
+    fn complex_symmetric_ldl() {
+        // |2  i| |1|   | 1|
+        // |i  3| |i| = |4i|
+        let c = Complex64::new;
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 2, 4],
+                                      vec![0, 1, 0, 1],
+                                      vec![c(2., 0.), c(0., 1.),
+                                           c(0., 1.), c(3., 0.)]);
+        let ldlt = super::LdlNumeric::new(&mat).unwrap();
+        let x = ldlt.solve(&vec![c(1., 0.), c(0., 4.)]);
+        assert_close(&x, &[c(1., 0.), c(0., 1.)]);
+    }
+
+    #[test]
+    fn hermitian_ldl_fails_on_non_hermitian() {
+        let mat = hermitian_mat().map(|&x| x * Complex64::new(0., 1.));
+        let res = super::LdlNumeric::new_hermitian(&mat);
+        assert_eq!(res.unwrap_err(), SprsError::NonHermitianMatrix);
+    }
+
+    #[test]
+    fn ldl_shape_and_symmetry_errors() {
+        let mat = CsMatOwned::new_csc((2, 3),
+                                      vec![0, 1, 2, 3],
+                                      vec![0, 1, 0],
+                                      vec![1., 1., 1.]);
+        let res = super::LdlSymbolic::new(&mat);
+        assert_eq!(res.unwrap_err(), SprsError::NonSquareMatrix);
+
+        // | 2 1 |
+        // | 0 2 |
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 1, 3],
+                                      vec![0, 0, 1],
+                                      vec![2., 1., 2.]);
+        let res = super::LdlNumeric::new(&mat);
+        assert_eq!(res.unwrap_err(), SprsError::NonSymmetricMatrix);
+
+        // skipping the check on a symmetric matrix
+        let mat = test_mat1();
+        let perm = Permutation::identity();
+        let symbolic = super::LdlSymbolic::new_perm_with_check(
+            &mat, perm, SymmetryCheck::DontCheckSymmetry).unwrap();
+        let ldlt = symbolic.factor(&mat).unwrap();
+        assert_close_f64(&ldlt.solve(&test_vec1()), &expected_res1());
+    }
+
+    #[test]
+    fn ldl_singular_errors() {
+        // | 2 0 1 |
+        // | 0 0 0 |
+        // | 1 0 2 |
+        let mat = CsMatOwned::new_csc((3, 3),
+                                      vec![0, 2, 2, 4],
+                                      vec![0, 2, 0, 2],
+                                      vec![2., 1., 1., 2.]);
+        let res = super::LdlSymbolic::new(&mat);
+        assert_eq!(res.unwrap_err(), SprsError::StructurallySingular(1));
+
+        // | 1 1 |
+        // | 1 1 |
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 2, 4],
+                                      vec![0, 1, 0, 1],
+                                      vec![1.; 4]);
+        let res = super::LdlNumeric::new(&mat);
+        assert_eq!(res.unwrap_err(), SprsError::NumericallySingular(1));
+        let perm = Permutation::new(vec![1, 0]);
+        let res = super::LdlNumeric::new_perm(&mat, perm);
+        assert_eq!(res.unwrap_err(), SprsError::NumericallySingular(0));
+
+        // | 2 1 |
+        // | 1 2 |
+        let eye: CsMatOwned<f64> = CsMat::eye_csc(2);
+        let non_singular = &mat + &eye;
+        let mut ldlt = super::LdlNumeric::new(&non_singular).unwrap();
+        let res = ldlt.update(&mat);
+        assert_eq!(res.unwrap_err(), SprsError::NumericallySingular(1));
+        ldlt.update(&non_singular).unwrap();
+        assert_close_f64(&ldlt.solve(&vec![3., 3.]), &[1., 1.]);
+    }
+
+    fn to_dense<N: Copy + Default>(vec: &CsVecOwned<N>) -> Vec<N> {
+        let mut dense = vec![N::default(); vec.dim()];
+        vec.scatter(&mut dense);
+        dense
+    }
+
+    fn assert_close_f64(x: &[f64], x0: &[f64]) {
+        for (xi, x0i) in x.iter().zip(x0) {
+            assert!((xi - x0i).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn solve_in_place_and_multi() {
+        let mat = test_mat1();
+        let b = test_vec1();
+        let perm = Permutation::new((0..10).rev().collect());
+        let mut ldlt = super::LdlNumeric::new_perm(&mat, perm).unwrap();
+        let x = ldlt.solve(&b);
+        assert_close_f64(&x, &expected_res1());
+
+        let mut x_in_place = b.clone();
+        ldlt.solve_in_place(&mut x_in_place);
+        assert_eq!(x_in_place, x);
+
+        let b2: Vec<f64> = b.iter().map(|&v| 2. * v).collect();
+        let rhs = Array::from_vec(b.iter().chain(&b2).cloned().collect())
+                      .into_shape((2, 10))
+                      .unwrap();
+        let rhs = rhs.t();
+        let sol = ldlt.solve_multi(rhs);
+        assert_eq!(sol.shape(), &[10, 2]);
+        for (col, scale) in sol.axis_iter(Axis(1)).zip(&[1., 2.]) {
+            let expected: Vec<f64> = x.iter().map(|&v| v * scale).collect();
+            assert_close_f64(&col.iter().cloned().collect::<Vec<_>>(),
+                             &expected);
+        }
+    }
+
+    #[test]
+    fn solve_sparse() {
+        // two independent copies of test_mat1, so that a right-hand side
+        // in the second block has a solution in the second block only
+        let eye: CsMatOwned<f64> = CsMat::eye(2);
+        let mat = sprs::kron(eye.view(), test_mat1().view(), sprs::CSC);
+        let perm = Permutation::new((0..20).map(|i| (i * 7) % 20).collect());
+        let mut ldlt = super::LdlNumeric::new_perm(&mat, perm).unwrap();
+
+        let rhs = CsVecOwned::new(20, vec![12, 17], vec![1., -2.]);
+        let x = ldlt.solve_sparse(rhs.view());
+        assert!(x.indices().iter().all(|&i| i >= 10));
+        let x_dense = ldlt.solve(&to_dense(&rhs));
+        assert_close_f64(&to_dense(&x), &x_dense);
+
+        // the workspaces are left clean for the next solve
+        let rhs = CsVecOwned::new(20, vec![3], vec![1.]);
+        let x = ldlt.solve_sparse(rhs.view());
+        assert!(x.indices().iter().all(|&i| i < 10));
+        let x_dense = ldlt.solve(&to_dense(&rhs));
+        assert_close_f64(&to_dense(&x), &x_dense);
+
+        let rhs = CsVecOwned::new(20, vec![], vec![]);
+        assert_eq!(ldlt.solve_sparse(rhs.view()).nnz(), 0);
+    }
+
+    #[test]
+    fn hermitian_solve_sparse() {
+        let c = Complex64::new;
+        let mat = hermitian_mat();
+        let perm = Permutation::new(vec![2, 0, 1]);
+        let mut ldlh = super::LdlNumeric::new_hermitian_perm(&mat, perm)
+                           .unwrap();
+        let rhs = CsVecOwned::new(3, vec![1], vec![c(2., 13.)]);
+        let x = ldlh.solve_sparse(rhs.view());
+        let x_dense = ldlh.solve(&to_dense(&rhs));
+        assert_close(&to_dense(&x), &x_dense);
+
+        let mut x_in_place = to_dense(&rhs);
+        ldlh.solve_in_place(&mut x_in_place);
+        assert_close(&x_in_place, &x_dense);
+    }
 }
@@ -0,0 +1,758 @@
+///! Supernodal Cholesky factorization.
+///!
+///! This decomposition operates on symmetric positive definite matrices,
+///! and is written `P A P^T = L L^T` where L is lower triangular and P is a
+///! permutation matrix.
+///!
+///! Consecutive columns of L sharing the same non-zero pattern below their
+///! diagonal block are grouped into supernodes, whose values are stored as
+///! dense blocks. The factorization and the solves then work on these blocks
+///! with dense kernels, which is much faster than the column by column LDLT
+///! when L has large dense parts, eg for 3D finite element matrices.
+///!
+///! The supernodes are the fundamental supernodes of the elimination tree:
+///! the column `j + 1` extends the supernode of `j` if it is the parent of
+///! `j`, `j` is its only child, and their columns have the same pattern
+///! below `j + 1`. The elimination tree and the column counts are computed
+///! by the `sprs::linalg::etree` module.
+///!
+///! As for `LdlNumeric`, the easiest way to use this API is to create a
+///! `SupernodalNumeric` from a matrix, then to use its `solve` method.
+
+use std::ops::{Deref, Range};
+
+use ndarray::{Array, Axis, Ix, LinalgScalar};
+use num::traits::Float;
+
+use sprs::{
+    CsMatBase,
+    CsMatI,
+    is_symmetric,
+    permute_sym,
+    PermutationBase,
+    PermOwnedI,
+    PermViewI,
+    SpIndex,
+};
+use sprs::errors::SprsError;
+use sprs::linalg;
+
+use super::SymmetryCheck;
+
+/// Structure to compute and hold the supernodal structure of a Cholesky
+/// decomposition
+#[derive(Debug)]
+pub struct SupernodalSymbolic<I: SpIndex = usize> {
+    perm: PermOwnedI<I>,
+    parents: linalg::etree::ParentsOwned,
+    /// supernode s holds the columns `sn_cols[s]..sn_cols[s + 1]`
+    sn_cols: Vec<usize>,
+    /// the sorted rows of supernode s are
+    /// `sn_rows[sn_rowptr[s]..sn_rowptr[s + 1]]`, starting with its columns
+    sn_rowptr: Vec<usize>,
+    sn_rows: Vec<usize>,
+    /// the supernode of each column
+    col_sn: Vec<usize>,
+}
+
+/// Structure to hold a numeric supernodal Cholesky decomposition
+#[derive(Debug)]
+pub struct SupernodalNumeric<N, I: SpIndex = usize> {
+    symbolic: SupernodalSymbolic<I>,
+    /// the dense block of each supernode, with a row for each row of the
+    /// supernode and a column for each of its columns
+    blocks: Vec<Array<N, (Ix, Ix)>>,
+}
+
+impl<I: SpIndex> SupernodalSymbolic<I> {
+    /// Compute the supernodal structure of the Cholesky factor of the given
+    /// matrix
+    ///
+    /// # Errors
+    ///
+    /// * `NonSquareMatrix` if mat is not square
+    /// * `NonSymmetricMatrix` if mat is not symmetric
+    pub fn new<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<SupernodalSymbolic<I>, SprsError>
+    where N: Copy + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let perm: PermOwnedI<I> = PermutationBase::identity();
+        SupernodalSymbolic::new_perm(mat, perm)
+    }
+
+    /// Compute the supernodal structure of the decomposition
+    /// L L^T = P A P^T where P is a permutation matrix.
+    ///
+    /// Using a good permutation matrix can reduce the non-zero count in L,
+    /// thus making the decomposition and the solves faster.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new`.
+    pub fn new_perm<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                    perm: PermOwnedI<I>)
+                                    -> Result<SupernodalSymbolic<I>,
+                                              SprsError>
+    where N: Copy + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        SupernodalSymbolic::new_perm_with_check(mat,
+                                                perm,
+                                                SymmetryCheck::CheckSymmetry)
+    }
+
+    /// Compute the supernodal structure of the decomposition
+    /// L L^T = P A P^T where P is a permutation matrix, only checking the
+    /// symmetry of the matrix if requested.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new`, `NonSymmetricMatrix` being only reported when
+    /// `check_symmetry` is `SymmetryCheck::CheckSymmetry`.
+    pub fn new_perm_with_check<N, IpS, IS, DS>(
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        perm: PermOwnedI<I>,
+        check_symmetry: SymmetryCheck)
+        -> Result<SupernodalSymbolic<I>, SprsError>
+    where N: Copy + PartialEq,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let n = mat.cols();
+        if mat.rows() != n {
+            return Err(SprsError::NonSquareMatrix);
+        }
+        if check_symmetry == SymmetryCheck::CheckSymmetry &&
+           !is_symmetric(mat) {
+            return Err(SprsError::NonSymmetricMatrix);
+        }
+        let parents = linalg::etree::ParentsOwned::from_symmetric(mat, &perm);
+        let col_counts = parents.cholesky_counts(mat, &perm).col_counts;
+
+        // fundamental supernodes
+        let mut nb_children = vec![0; n];
+        for j in 0..n {
+            if let Some(parent) = parents.get_parent(j) {
+                nb_children[parent] += 1;
+            }
+        }
+        let mut sn_cols = Vec::new();
+        for j in 0..n {
+            let extends = j > 0 && parents.get_parent(j - 1) == Some(j) &&
+                          nb_children[j] == 1 &&
+                          col_counts[j - 1] == col_counts[j] + 1;
+            if !extends {
+                sn_cols.push(j);
+            }
+        }
+        sn_cols.push(n);
+        let nb_supernodes = sn_cols.len() - 1;
+        let mut col_sn = vec![0; n];
+        for s in 0..nb_supernodes {
+            for j in sn_cols[s]..sn_cols[s + 1] {
+                col_sn[j] = s;
+            }
+        }
+
+        // The rows of a supernode are its columns, the rows of its columns
+        // in A, and the rows of its children, below its columns.
+        let mat = permute_sym(mat, &perm);
+        let mut children = vec![Vec::new(); nb_supernodes];
+        let mut marks = vec![None; n];
+        let mut sn_rowptr = Vec::with_capacity(nb_supernodes + 1);
+        let mut sn_rows = Vec::new();
+        sn_rowptr.push(0);
+        for s in 0..nb_supernodes {
+            let cols = sn_cols[s]..sn_cols[s + 1];
+            let start = sn_rows.len();
+            sn_rows.extend(cols.clone());
+            for j in cols.clone() {
+                let vec = mat.outer_view(j).expect("j is in bounds");
+                for row in vec.indices().iter().map(|i| i.index()) {
+                    if row >= cols.end && marks[row] != Some(s) {
+                        marks[row] = Some(s);
+                        sn_rows.push(row);
+                    }
+                }
+            }
+            for &child in &children[s] {
+                for p in sn_rowptr[child]..sn_rowptr[child + 1] {
+                    let row = sn_rows[p];
+                    if row >= cols.end && marks[row] != Some(s) {
+                        marks[row] = Some(s);
+                        sn_rows.push(row);
+                    }
+                }
+            }
+            sn_rows[start + cols.len()..].sort();
+            debug_assert_eq!(sn_rows.len() - start, col_counts[cols.start]);
+            sn_rowptr.push(sn_rows.len());
+            if let Some(parent) = parents.get_parent(cols.end - 1) {
+                children[col_sn[parent]].push(s);
+            }
+        }
+
+        Ok(SupernodalSymbolic {
+            perm: perm,
+            parents: parents,
+            sn_cols: sn_cols,
+            sn_rowptr: sn_rowptr,
+            sn_rows: sn_rows,
+            col_sn: col_sn,
+        })
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.parents.nb_nodes()
+    }
+
+    /// The number of non-zero entries in L, including its diagonal
+    pub fn nnz(&self) -> usize {
+        (0..self.nb_supernodes()).map(|s| {
+            let ncols = self.supernode_cols(s).len();
+            let nrows = self.supernode_rows(s).len();
+            ncols * (ncols + 1) / 2 + ncols * (nrows - ncols)
+        }).sum()
+    }
+
+    /// The number of supernodes
+    #[inline]
+    pub fn nb_supernodes(&self) -> usize {
+        self.sn_cols.len() - 1
+    }
+
+    /// The columns of L grouped in the supernode `s`
+    ///
+    /// # Panics
+    ///
+    /// * if s is out of bounds
+    pub fn supernode_cols(&self, s: usize) -> Range<usize> {
+        self.sn_cols[s]..self.sn_cols[s + 1]
+    }
+
+    /// The sorted rows of the supernode `s`, starting with its columns
+    ///
+    /// # Panics
+    ///
+    /// * if s is out of bounds
+    pub fn supernode_rows(&self, s: usize) -> &[usize] {
+        &self.sn_rows[self.sn_rowptr[s]..self.sn_rowptr[s + 1]]
+    }
+
+    /// The elimination tree of `P A P^T`
+    pub fn etree(&self) -> &linalg::etree::ParentsOwned {
+        &self.parents
+    }
+
+    /// The permutation P of the decomposition
+    pub fn perm(&self) -> PermViewI<I> {
+        self.perm.view()
+    }
+
+    /// Compute the numerical decomposition of the given matrix.
+    ///
+    /// # Errors
+    ///
+    /// `NotPositiveDefinite(col)` if a non-positive pivot is encountered
+    /// when eliminating the column `col` of mat
+    pub fn factor<N, IpS, IS, DS>(self,
+                                  mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                  -> Result<SupernodalNumeric<N, I>,
+                                            SprsError>
+    where N: Float + LinalgScalar,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let blocks = (0..self.nb_supernodes()).map(|s| {
+            let shape = (self.supernode_rows(s).len(),
+                         self.supernode_cols(s).len());
+            Array::zeros(shape)
+        }).collect();
+        let mut numeric = SupernodalNumeric {
+            symbolic: self,
+            blocks: blocks,
+        };
+        try!(numeric.update(mat));
+        Ok(numeric)
+    }
+}
+
+impl<N, I: SpIndex> SupernodalNumeric<N, I> {
+    /// Compute the numeric supernodal Cholesky decomposition of the given
+    /// matrix.
+    ///
+    /// # Errors
+    ///
+    /// * `NonSquareMatrix` if mat is not square
+    /// * `NonSymmetricMatrix` if mat is not symmetric
+    /// * `NotPositiveDefinite(col)` if a non-positive pivot is encountered
+    ///   when eliminating the column `col` of mat
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                            -> Result<Self, SprsError>
+    where N: Float + LinalgScalar,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = try!(SupernodalSymbolic::new(mat));
+        symbolic.factor(mat)
+    }
+
+    /// Compute the numeric decomposition L L^T = P A P^T
+    /// where P is a permutation matrix.
+    ///
+    /// # Errors
+    ///
+    /// Same as `new`.
+    pub fn new_perm<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                 perm: PermOwnedI<I>)
+                                 -> Result<Self, SprsError>
+    where N: Float + LinalgScalar,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = try!(SupernodalSymbolic::new_perm(mat, perm));
+        symbolic.factor(mat)
+    }
+
+    /// Update the decomposition with the given matrix. The matrix must
+    /// have the same non-zero pattern as the original matrix, otherwise
+    /// the result is unspecified.
+    ///
+    /// # Errors
+    ///
+    /// `NotPositiveDefinite(col)` if a non-positive pivot is encountered
+    /// when eliminating the column `col` of mat
+    pub fn update<IpS, IS, DS>(&mut self,
+                               mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<(), SprsError>
+    where N: Float + LinalgScalar,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let n = self.problem_size();
+        assert!(mat.rows() == n && mat.cols() == n, "Dimension mismatch");
+        let symbolic = &self.symbolic;
+        let mat = permute_sym(mat, &symbolic.perm);
+        // position of the rows in the supernode being assembled or updated
+        let mut positions = vec![0; n];
+
+        // scatter the lower triangular part of the matrix into the blocks
+        for (s, block) in self.blocks.iter_mut().enumerate() {
+            block.assign_scalar(&N::zero());
+            for (pos, &row) in symbolic.supernode_rows(s).iter().enumerate() {
+                positions[row] = pos;
+            }
+            let cols = symbolic.supernode_cols(s);
+            for j in cols.clone() {
+                let vec = mat.outer_view(j).expect("j is in bounds");
+                for (row, &val) in vec.iter().filter(|&(row, _)| row >= j) {
+                    block[[positions[row], j - cols.start]] = val;
+                }
+            }
+        }
+
+        for s in 0..symbolic.nb_supernodes() {
+            let cols = symbolic.supernode_cols(s);
+            let ncols = cols.len();
+            if let Err(k) = factor_block(&mut self.blocks[s], ncols) {
+                let col = symbolic.perm.at(cols.start + k);
+                return Err(SprsError::NotPositiveDefinite(col));
+            }
+
+            // subtract L21 L21^T from the blocks of the ancestors
+            let below = &symbolic.supernode_rows(s)[ncols..];
+            if below.is_empty() {
+                continue;
+            }
+            let update = {
+                let (_, l21) = self.blocks[s].view().split_at(Axis(0), ncols);
+                l21.dot(&l21.t())
+            };
+            // the columns of a supernode are consecutive, so the rows below
+            // are grouped by target supernode
+            let mut jj = 0;
+            while jj < below.len() {
+                let target_sn = symbolic.col_sn[below[jj]];
+                let target_rows = symbolic.supernode_rows(target_sn);
+                for (pos, &row) in target_rows.iter().enumerate() {
+                    positions[row] = pos;
+                }
+                let first_col = symbolic.sn_cols[target_sn];
+                let target = &mut self.blocks[target_sn];
+                while jj < below.len() &&
+                      symbolic.col_sn[below[jj]] == target_sn {
+                    let col = below[jj] - first_col;
+                    for ii in jj..below.len() {
+                        let pos = positions[below[ii]];
+                        target[[pos, col]] = target[[pos, col]] -
+                                             update[[ii, jj]];
+                    }
+                    jj += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Solve the system A x = rhs
+    ///
+    /// # Panics
+    ///
+    /// * if rhs does not have the size of the system
+    pub fn solve<V>(&self, rhs: &V) -> Vec<N>
+    where N: Float + LinalgScalar,
+          V: Deref<Target = [N]>
+    {
+        assert!(rhs.len() == self.problem_size(), "Dimension mismatch");
+        let mut x = &self.symbolic.perm * &rhs[..];
+        self.lsolve(&mut x);
+        self.ltsolve(&mut x);
+        let pinv = self.symbolic.perm.inv();
+        &pinv * &x
+    }
+
+    /// Solve L x = rhs in place, supernode by supernode
+    fn lsolve(&self, x: &mut [N])
+    where N: Float + LinalgScalar
+    {
+        for (s, block) in self.blocks.iter().enumerate() {
+            let cols = self.symbolic.supernode_cols(s);
+            let ncols = cols.len();
+            let first = cols.start;
+            for k in 0..ncols {
+                let x_k = x[first + k] / block[[k, k]];
+                x[first + k] = x_k;
+                for i in k + 1..ncols {
+                    x[first + i] = x[first + i] - block[[i, k]] * x_k;
+                }
+            }
+            let below = &self.symbolic.supernode_rows(s)[ncols..];
+            if below.is_empty() {
+                continue;
+            }
+            let (_, l21) = block.view().split_at(Axis(0), ncols);
+            let x_cols = Array::from_iter(x[cols].iter().cloned());
+            let y = l21.dot(&x_cols);
+            for (&row, &val) in below.iter().zip(y.iter()) {
+                x[row] = x[row] - val;
+            }
+        }
+    }
+
+    /// Solve L^T x = rhs in place, supernode by supernode
+    fn ltsolve(&self, x: &mut [N])
+    where N: Float + LinalgScalar
+    {
+        for (s, block) in self.blocks.iter().enumerate().rev() {
+            let cols = self.symbolic.supernode_cols(s);
+            let ncols = cols.len();
+            let first = cols.start;
+            let below = &self.symbolic.supernode_rows(s)[ncols..];
+            if !below.is_empty() {
+                let (_, l21) = block.view().split_at(Axis(0), ncols);
+                let x_below = Array::from_iter(below.iter().map(|&r| x[r]));
+                let y = l21.t().dot(&x_below);
+                for (x_k, &val) in x[cols].iter_mut().zip(y.iter()) {
+                    *x_k = *x_k - val;
+                }
+            }
+            for k in (0..ncols).rev() {
+                let mut x_k = x[first + k];
+                for i in k + 1..ncols {
+                    x_k = x_k - block[[i, k]] * x[first + i];
+                }
+                x[first + k] = x_k / block[[k, k]];
+            }
+        }
+    }
+
+    /// The lower triangular factor L, as a CSC matrix
+    pub fn l(&self) -> CsMatI<N, I>
+    where N: Copy
+    {
+        let n = self.problem_size();
+        let mut colptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        colptr.push(I::zero());
+        for (s, block) in self.blocks.iter().enumerate() {
+            let rows = self.symbolic.supernode_rows(s);
+            for (k, col) in block.axis_iter(Axis(1)).enumerate() {
+                for (&row, &val) in rows[k..].iter().zip(col.iter().skip(k)) {
+                    indices.push(I::from_usize(row));
+                    data.push(val);
+                }
+                colptr.push(I::from_usize(indices.len()));
+            }
+        }
+        CsMatI::new_csc((n, n), colptr, indices, data)
+    }
+
+    /// The symbolic structure of this decomposition
+    pub fn symbolic(&self) -> &SupernodalSymbolic<I> {
+        &self.symbolic
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.symbolic.problem_size()
+    }
+
+    /// The number of non-zero entries in L, including its diagonal
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.symbolic.nnz()
+    }
+}
+
+/// The Cholesky decomposition of a matrix can be used as a preconditioner
+/// for iterative solvers
+impl<N, I> linalg::iterative::Preconditioner<N> for SupernodalNumeric<N, I>
+where N: Float + LinalgScalar,
+      I: SpIndex
+{
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        let x = self.solve(&r);
+        z.copy_from_slice(&x);
+    }
+}
+
+/// Dense Cholesky factorization of the `ncols` first rows of a supernode
+/// block, and triangular solve of the rows below against the computed
+/// diagonal block.
+///
+/// Fails with the column of the block where a non-positive pivot is
+/// encountered.
+fn factor_block<N>(block: &mut Array<N, (Ix, Ix)>,
+                   ncols: usize)
+                   -> Result<(), usize>
+where N: Float
+{
+    let nrows = block.rows();
+    for k in 0..ncols {
+        let pivot = block[[k, k]];
+        if !(pivot > N::zero()) {
+            return Err(k);
+        }
+        let pivot = pivot.sqrt();
+        block[[k, k]] = pivot;
+        for i in k + 1..nrows {
+            block[[i, k]] = block[[i, k]] / pivot;
+        }
+        for j in k + 1..ncols {
+            let l_jk = block[[j, k]];
+            for i in j..nrows {
+                block[[i, j]] = block[[i, j]] - block[[i, k]] * l_jk;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sprs::{
+        self,
+        CsMatOwned,
+        CsMatI,
+        Permutation,
+        linalg,
+    };
+    use sprs::errors::SprsError;
+    use sprs::prod;
+    use super::{SupernodalNumeric, SupernodalSymbolic};
+    use super::super::SymmetryCheck;
+
+    /// The laplacian of a 2D grid of size n by n
+    fn laplacian_2d(n: usize) -> CsMatOwned<f64> {
+        let lower = vec![-1.; n - 1];
+        let diag = vec![2.; n];
+        let t: CsMatOwned<f64> = sprs::from_diags((n, n),
+                                                  &[&lower, &diag, &lower],
+                                                  &[-1, 0, 1]);
+        sprs::kronsum(t.view(), t.view(), sprs::CSC)
+    }
+
+    fn mat_vec(mat: &CsMatOwned<f64>, x: &[f64]) -> Vec<f64> {
+        let mut res = vec![0.; mat.rows()];
+        prod::mul_acc_mat_vec_csc(mat.view(), x, &mut res);
+        res
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-10, "{:?} != {:?}", a, b);
+        }
+    }
+
+    /// Check that L L^T = P A P^T
+    fn check_factor(mat: &CsMatOwned<f64>, chol: &SupernodalNumeric<f64>) {
+        let l = chol.l();
+        assert_eq!(l.nnz(), chol.nnz());
+        let llt = &l * &l.transpose_view();
+        let pap = sprs::permute_sym(mat, &chol.symbolic().perm());
+        let (llt, pap) = (llt.to_dense(), pap.to_dense());
+        for (x, y) in llt.iter().zip(pap.iter()) {
+            assert!((x - y).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn supernodes_of_tridiagonal() {
+        // L is bidiagonal, only its last two columns share their pattern
+        let n = 6;
+        let lower = vec![-1.; n - 1];
+        let diag = vec![2.; n];
+        let mat: CsMatOwned<f64> = sprs::from_diags((n, n),
+                                                    &[&lower, &diag, &lower],
+                                                    &[-1, 0, 1]);
+        let symbolic = SupernodalSymbolic::new(&mat).unwrap();
+        assert_eq!(symbolic.nb_supernodes(), n - 1);
+        assert_eq!(symbolic.supernode_cols(0), 0..1);
+        assert_eq!(symbolic.supernode_rows(0), &[0, 1]);
+        assert_eq!(symbolic.supernode_cols(n - 2), n - 2..n);
+        assert_eq!(symbolic.supernode_rows(n - 2), &[n - 2, n - 1]);
+        assert_eq!(symbolic.nnz(), 2 * n - 1);
+        let chol = symbolic.factor(&mat).unwrap();
+        check_factor(&mat, &chol);
+    }
+
+    #[test]
+    fn dense_single_supernode() {
+        // | 4 2 2 |
+        // | 2 5 3 |
+        // | 2 3 6 |
+        let mat = CsMatOwned::new_csc((3, 3),
+                                      vec![0, 3, 6, 9],
+                                      vec![0, 1, 2, 0, 1, 2, 0, 1, 2],
+                                      vec![4., 2., 2., 2., 5., 3., 2., 3.,
+                                           6.]);
+        let chol = SupernodalNumeric::new(&mat).unwrap();
+        assert_eq!(chol.symbolic().nb_supernodes(), 1);
+        assert_eq!(chol.nnz(), 6);
+        let l = chol.l();
+        assert_eq!(l.get(0, 0), Some(&2.));
+        assert_eq!(l.get(1, 0), Some(&1.));
+        assert_eq!(l.get(2, 0), Some(&1.));
+        assert_eq!(l.get(1, 1), Some(&2.));
+        check_factor(&mat, &chol);
+        let x0 = vec![1., -1., 2.];
+        let b = mat_vec(&mat, &x0);
+        assert_close(&chol.solve(&b), &x0);
+    }
+
+    #[test]
+    fn laplacian_solve() {
+        let mat = laplacian_2d(6);
+        let x0: Vec<f64> = (0..36).map(|i| (i as f64).sin()).collect();
+        let b = mat_vec(&mat, &x0);
+
+        let chol = SupernodalNumeric::new(&mat).unwrap();
+        assert!(chol.symbolic().nb_supernodes() < 36);
+        check_factor(&mat, &chol);
+        assert_close(&chol.solve(&b), &x0);
+
+        // same factor as the LDLT decomposition
+        let ldl = super::super::LdlNumeric::new(&mat).unwrap();
+        assert_eq!(chol.nnz(), ldl.nnz() + 36);
+
+        let perm = linalg::ordering::amd(&mat);
+        let chol = SupernodalNumeric::new_perm(&mat, perm).unwrap();
+        check_factor(&mat, &chol);
+        assert_close(&chol.solve(&b), &x0);
+
+        let perm = Permutation::new((0..36).rev().collect());
+        let chol = SupernodalNumeric::new_perm(&mat.to_csr(), perm).unwrap();
+        check_factor(&mat, &chol);
+        assert_close(&chol.solve(&b), &x0);
+    }
+
+    #[test]
+    fn update_and_preconditioner() {
+        let mat = laplacian_2d(4);
+        let mut chol = SupernodalNumeric::new(&mat).unwrap();
+        let shifted = mat.map(|&x| 2. * x);
+        chol.update(&shifted).unwrap();
+        check_factor(&shifted, &chol);
+
+        // the exact factorization makes pcg converge in one iteration
+        let b = vec![1.; 16];
+        let sol = linalg::iterative::pcg(&shifted, &b, &chol, 1e-10, 10)
+            .unwrap();
+        assert!(sol.converged());
+        assert_eq!(sol.iterations(), 1);
+        assert_close(&mat_vec(&shifted, &sol.x), &b);
+    }
+
+    #[test]
+    fn not_positive_definite() {
+        // | 1 2 |
+        // | 2 1 |
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 2, 4],
+                                      vec![0, 1, 0, 1],
+                                      vec![1., 2., 2., 1.]);
+        let res = SupernodalNumeric::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::NotPositiveDefinite(1)));
+
+        // | -1 0 |
+        // |  0 1 |
+        // the failing column is reported in the numbering of mat
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 1, 2],
+                                      vec![0, 1],
+                                      vec![-1., 1.]);
+        let res = SupernodalNumeric::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::NotPositiveDefinite(0)));
+        let perm = Permutation::new(vec![1, 0]);
+        let res = SupernodalNumeric::new_perm(&mat, perm);
+        assert_eq!(res.err(), Some(SprsError::NotPositiveDefinite(0)));
+    }
+
+    #[test]
+    fn bad_input() {
+        let mat: CsMatOwned<f64> = CsMatOwned::new_csc((2, 3),
+                                                       vec![0, 1, 2, 2],
+                                                       vec![0, 1],
+                                                       vec![1., 1.]);
+        let res = SupernodalSymbolic::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::NonSquareMatrix));
+
+        // | 2 1 |
+        // | 3 2 |
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 2, 4],
+                                      vec![0, 1, 0, 1],
+                                      vec![2., 3., 1., 2.]);
+        let res = SupernodalNumeric::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::NonSymmetricMatrix));
+        let perm = Permutation::new(vec![0, 1]);
+        let symbolic = SupernodalSymbolic::new_perm_with_check(
+            &mat, perm, SymmetryCheck::DontCheckSymmetry).unwrap();
+        assert_eq!(symbolic.nb_supernodes(), 1);
+    }
+
+    #[test]
+    fn u32_indices() {
+        let mat = laplacian_2d(3);
+        let x0: Vec<f64> = (0..9).map(|i| i as f64).collect();
+        let b = mat_vec(&mat, &x0);
+        let mat: CsMatI<f64, u32> = mat.to_other_index().unwrap();
+        let chol = SupernodalNumeric::new(&mat).unwrap();
+        assert_close(&chol.solve(&b), &x0);
+    }
+}
@@ -0,0 +1,782 @@
+///! Modifications of an existing LDLT decomposition.
+///!
+///! When a matrix changes by a low rank term, eg `A + w w^T` or `A - w w^T`,
+///! its decomposition can be modified much faster than it can be
+///! recomputed. The methods of this module implement the approach of
+///! Davis and Hager (Modifying a sparse Cholesky factorization, SIAM J.
+///! Matrix Anal. Appl., 1999): a rank one modification only changes the
+///! columns of L on the path from the first non-zero of `P w` to the root
+///! of the elimination tree. The non-zero pattern of these columns is
+///! extended first, then their values are modified using the method C1 of
+///! Gill, Golub, Murray and Saunders.
+///!
+///! Deleting and adding a row and column of the matrix are built on these
+///! modifications: deleting the row and column `k` replaces them by those of
+///! the identity, and adding them reverts such a deletion.
+///!
+///! The non-zero pattern of L is never reduced by a modification, entries
+///! becoming zero are kept explicitly. This means the decomposition keeps
+///! the pattern of the modified matrix, so `LdlNumeric::update` should only
+///! be called with matrices having the non-zero pattern of the modified
+///! matrix, where an explicit zero is stored for each value cancelled by a
+///! downdate or a deletion.
+///!
+///! For hermitian decompositions, the rank one terms are `w w^H`.
+
+use std::collections::BTreeSet;
+use std::mem;
+
+use num::traits::Num;
+
+use sprs::{
+    Conjugate,
+    CsMatViewI,
+    CsVecViewI,
+    SpIndex,
+};
+use sprs::errors::SprsError;
+use sprs::linalg;
+use sprs::stack::{self, DStack};
+
+use super::LdlNumeric;
+
+impl<N, I> LdlNumeric<N, I>
+where N: Copy + Num + Conjugate,
+      I: SpIndex
+{
+    /// Modify the decomposition of A into the decomposition of
+    /// `A + w w^T`, or `A + w w^H` for a hermitian decomposition.
+    ///
+    /// # Errors
+    ///
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   modifying the column `col`. The decomposition should not be used
+    ///   after such an error.
+    ///
+    /// # Panics
+    ///
+    /// * if w does not have the size of the problem
+    pub fn rank_one_update(&mut self,
+                           w: CsVecViewI<N, I>)
+                           -> Result<(), SprsError> {
+        self.rank_one_modify(w, N::one())
+    }
+
+    /// Modify the decomposition of A into the decomposition of
+    /// `A - w w^T`, or `A - w w^H` for a hermitian decomposition.
+    ///
+    /// # Errors
+    ///
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   modifying the column `col`, ie `A - w w^T` is singular. The
+    ///   decomposition should not be used after such an error.
+    ///
+    /// # Panics
+    ///
+    /// * if w does not have the size of the problem
+    pub fn rank_one_downdate(&mut self,
+                             w: CsVecViewI<N, I>)
+                             -> Result<(), SprsError> {
+        self.rank_one_modify(w, N::zero() - N::one())
+    }
+
+    /// Modify the decomposition of A into the decomposition of
+    /// `A + W W^T`, or `A + W W^H` for a hermitian decomposition, by
+    /// successive rank one updates with the columns of W.
+    ///
+    /// # Errors
+    ///
+    /// Same as `rank_one_update`.
+    ///
+    /// # Panics
+    ///
+    /// * if W is not a csc matrix
+    /// * if W does not have as many rows as the size of the problem
+    pub fn rank_update(&mut self,
+                       w: CsMatViewI<N, I>)
+                       -> Result<(), SprsError> {
+        assert!(w.is_csc(), "Storage mismatch");
+        for col in w.outer_iterator() {
+            try!(self.rank_one_update(col));
+        }
+        Ok(())
+    }
+
+    /// Modify the decomposition of A into the decomposition of
+    /// `A - W W^T`, or `A - W W^H` for a hermitian decomposition, by
+    /// successive rank one downdates with the columns of W.
+    ///
+    /// # Errors
+    ///
+    /// Same as `rank_one_downdate`.
+    ///
+    /// # Panics
+    ///
+    /// * if W is not a csc matrix
+    /// * if W does not have as many rows as the size of the problem
+    pub fn rank_downdate(&mut self,
+                         w: CsMatViewI<N, I>)
+                         -> Result<(), SprsError> {
+        assert!(w.is_csc(), "Storage mismatch");
+        for col in w.outer_iterator() {
+            try!(self.rank_one_downdate(col));
+        }
+        Ok(())
+    }
+
+    /// Modify the decomposition to delete the row and the column `k` of
+    /// the matrix, which are replaced by the row and the column `k` of the
+    /// identity. The size of the problem does not change, and the
+    /// component `k` of the solution of a system is the component `k` of
+    /// its right-hand side.
+    ///
+    /// The column `col` is the column `k` of the matrix, of which only the
+    /// non-zero pattern is used: it gives the row `k` of L through the
+    /// elimination tree. It should hold all the non-zero entries of the
+    /// column, as for `add_row_col`.
+    ///
+    /// # Errors
+    ///
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   modifying the column `col`. The decomposition should not be used
+    ///   after such an error.
+    ///
+    /// # Panics
+    ///
+    /// * if k is out of bounds
+    /// * if col does not have the size of the problem
+    pub fn delete_row_col(&mut self,
+                          k: usize,
+                          col: CsVecViewI<N, I>)
+                          -> Result<(), SprsError> {
+        let n = self.problem_size();
+        assert!(k < n, "k is out of bounds");
+        assert_eq!(col.dim(), n, "Dimension mismatch");
+        let node = self.symbolic.perm.at_inv(k);
+        let row = self.row_subtree(node, col.indices());
+        let mut y = mem::replace(&mut self.y_workspace, Vec::new());
+
+        // The trailing part of A is L31 D1 L31^T + l32 d l32^T + L33 D3 L33^T,
+        // once l32 is removed from L, l32 d l32^T has to be added to
+        // L33 D3 L33^T.
+        let range = self.l_col_range(node);
+        let mut pattern = Vec::with_capacity(range.len());
+        for p in range {
+            let row = self.l_indices[p].index();
+            y[row] = self.l_data[p];
+            self.l_data[p] = N::zero();
+            pattern.push(row);
+        }
+        for j in row {
+            if let Some(p) = self.find_entry(node, j) {
+                self.l_data[p] = N::zero();
+            }
+        }
+        let alpha = self.diag[node];
+        self.diag[node] = N::one();
+
+        let path = self.extend_pattern(&pattern);
+        let res = self.modify_path(&path, alpha, &mut y);
+        self.y_workspace = y;
+        res
+    }
+
+    /// Modify the decomposition to add the row and the column `k` of the
+    /// matrix, given by the column `col`, where the row and the column `k`
+    /// of the decomposed matrix are those of the identity, eg after a call
+    /// to `delete_row_col`. The column should hold all the non-zero entries
+    /// of the column `k` of the new matrix, its row being deduced by
+    /// symmetry.
+    ///
+    /// # Errors
+    ///
+    /// * `NumericallySingular(col)` if a zero pivot is encountered when
+    ///   computing the column `col`, eg `NumericallySingular(k)` if the
+    ///   matrix with the added row and column is singular. The
+    ///   decomposition should not be used after such an error.
+    ///
+    /// # Panics
+    ///
+    /// * if k is out of bounds
+    /// * if col does not have the size of the problem
+    /// * if the row and the column `k` of L are not zero
+    pub fn add_row_col(&mut self,
+                       k: usize,
+                       col: CsVecViewI<N, I>)
+                       -> Result<(), SprsError> {
+        let n = self.problem_size();
+        assert!(k < n, "k is out of bounds");
+        assert_eq!(col.dim(), n, "Dimension mismatch");
+        let node = self.symbolic.perm.at_inv(k);
+        let range = self.l_col_range(node);
+        assert!(self.l_data[range].iter().all(|&v| v == N::zero()),
+                "column should be deleted first");
+        let row = self.row_subtree(node, col.indices());
+        assert!(row.iter().all(|&j| {
+                    self.find_entry(node, j)
+                        .map_or(true, |p| self.l_data[p] == N::zero())
+                }),
+                "row should be deleted first");
+
+        let mut y = mem::replace(&mut self.y_workspace, Vec::new());
+        let pattern: Vec<I> = col.indices()
+                                 .iter()
+                                 .map(|i| self.symbolic.perm.at_inv(i.index()))
+                                 .filter(|&i| i != node)
+                                 .map(I::from_usize)
+                                 .collect();
+        let mut a_kk = N::zero();
+        for (i, &val) in col.iter() {
+            let i = self.symbolic.perm.at_inv(i);
+            if i == node {
+                a_kk = val;
+            } else {
+                y[i] = val;
+            }
+        }
+
+        // Solve L11 y1 = a12, and compute y3 = a3 - L31 y1. The columns
+        // of L after node are not part of the solve, and are leaves of the
+        // depth first search.
+        let reach_cap = n + pattern.len() + self.symbolic.nnz();
+        if self.reach_workspace.capacity() < reach_cap {
+            self.reach_workspace = DStack::with_capacity(reach_cap);
+        }
+        let reached: Vec<usize> = {
+            let colptr = &self.symbolic.colptr;
+            let nz = &self.symbolic.nz;
+            let l_indices = &self.l_indices;
+            let l_data = &self.l_data;
+            let l_col_range = |j: usize| {
+                let start = colptr[j].index();
+                start..start + nz[j]
+            };
+            let dstack = &mut self.reach_workspace;
+            let visited = &mut self.visited;
+            linalg::trisolve::sparse_reach_structure(
+                &pattern,
+                |j| if j < node { &l_indices[l_col_range(j)] } else { &[] },
+                dstack,
+                visited);
+            let reached: Vec<usize> = dstack.iter_right()
+                                            .map(stack::extract_stack_val)
+                                            .cloned()
+                                            .collect();
+            dstack.clear_right();
+            for &j in reached.iter().filter(|&&j| j < node) {
+                let y_j = y[j];
+                let range = l_col_range(j);
+                for (i, &val) in l_indices[range.clone()]
+                                     .iter()
+                                     .zip(&l_data[range]) {
+                    let i = i.index();
+                    y[i] = y[i] - val * y_j;
+                }
+            }
+            reached
+        };
+        for &j in &reached {
+            self.visited[j] = false;
+        }
+
+        // the row of L is the conjugate of D1^-1 y1, its column is
+        // y3 / d, where d is the Schur complement a_kk - l_k1 y1
+        let mut additions = Vec::new();
+        for &j in reached.iter().filter(|&&j| j < node) {
+            if self.find_entry(node, j).is_none() {
+                additions.push((j, node));
+            }
+        }
+        let mut lower: Vec<usize> = reached.iter()
+                                           .cloned()
+                                           .filter(|&i| i > node)
+                                           .collect();
+        lower.sort();
+        for &i in &lower {
+            if self.find_entry(i, node).is_none() {
+                additions.push((node, i));
+            }
+        }
+        self.insert_entries(additions);
+
+        let hermitian = self.symbolic.hermitian;
+        let mut d = a_kk;
+        for &j in reached.iter().filter(|&&j| j < node) {
+            let l_kj = y[j] / self.diag[j];
+            let l_kj = if hermitian { l_kj.conj() } else { l_kj };
+            d = d - l_kj * y[j];
+            y[j] = N::zero();
+            let p = self.find_entry(node, j).expect("inserted entry");
+            self.l_data[p] = l_kj;
+            // node is now in the column of j
+            let parent = self.symbolic.parents.get_parent(j);
+            if parent.map_or(true, |parent| node < parent) {
+                self.symbolic.parents.set_parent(j, node);
+            }
+        }
+        match lower.first() {
+            Some(&parent) => self.symbolic.parents.set_parent(node, parent),
+            None => self.symbolic.parents.set_root(node),
+        }
+        if d == N::zero() {
+            for &i in &lower {
+                y[i] = N::zero();
+            }
+            self.y_workspace = y;
+            return Err(SprsError::NumericallySingular(k));
+        }
+        self.diag[node] = d;
+        for &i in &lower {
+            y[i] = y[i] / d;
+            let p = self.find_entry(i, node).expect("inserted entry");
+            self.l_data[p] = y[i];
+        }
+
+        // The trailing part of A is L31 D1 L31^T + l32 d l32^T + L33 D3 L33^T
+        // where the last term is the decomposition before the addition,
+        // l32 d l32^T has to be removed from it.
+        let path = self.extend_pattern(&lower);
+        let res = self.modify_path(&path, N::zero() - d, &mut y);
+        self.y_workspace = y;
+        res
+    }
+
+    /// Compute the decomposition of `A + sigma w w^T`
+    fn rank_one_modify(&mut self,
+                       w: CsVecViewI<N, I>,
+                       sigma: N)
+                       -> Result<(), SprsError> {
+        let n = self.problem_size();
+        assert_eq!(w.dim(), n, "Dimension mismatch");
+        let mut y = mem::replace(&mut self.y_workspace, Vec::new());
+        let mut pattern = Vec::with_capacity(w.nnz());
+        for (i, &val) in w.iter() {
+            let i = self.symbolic.perm.at_inv(i);
+            y[i] = val;
+            pattern.push(i);
+        }
+        let path = self.extend_pattern(&pattern);
+        let res = self.modify_path(&path, sigma, &mut y);
+        self.y_workspace = y;
+        res
+    }
+
+    /// Extend the non-zero pattern of L and the elimination tree for a
+    /// rank one modification by a vector of the given permuted non-zero
+    /// pattern, returning the path of the columns to modify.
+    fn extend_pattern(&mut self, w_pattern: &[usize]) -> Vec<usize> {
+        // The pattern of w during the modification of column j is the
+        // pattern of w itself merged with the patterns of the modified
+        // columns, and is added to the pattern of column j. Its next
+        // non-zero is the new parent of j in the elimination tree.
+        let mut w_set: BTreeSet<usize> = w_pattern.iter().cloned().collect();
+        let mut path = Vec::new();
+        let mut additions = Vec::new();
+        while let Some(j) = w_set.iter().next().cloned() {
+            w_set.remove(&j);
+            path.push(j);
+            let col = &self.l_indices[self.l_col_range(j)];
+            for &i in &w_set {
+                if col.binary_search(&I::from_usize(i)).is_err() {
+                    additions.push((j, i));
+                }
+            }
+            w_set.extend(col.iter().map(|i| i.index()));
+            match w_set.iter().next() {
+                Some(&parent) => self.symbolic.parents.set_parent(j, parent),
+                None => self.symbolic.parents.set_root(j),
+            }
+        }
+        self.insert_entries(additions);
+        path
+    }
+
+    /// Modify the values of the columns of L in the path by
+    /// `alpha w w^T`, w being stored in y. This is the method C1 of Gill,
+    /// Golub, Murray and Saunders. y is zero once done.
+    fn modify_path(&mut self,
+                   path: &[usize],
+                   alpha: N,
+                   y: &mut [N])
+                   -> Result<(), SprsError> {
+        let hermitian = self.symbolic.hermitian;
+        let mut alpha = alpha;
+        for &j in path {
+            let p = y[j];
+            y[j] = N::zero();
+            let p_conj = if hermitian { p.conj() } else { p };
+            let d = self.diag[j];
+            let d_bar = d + alpha * p * p_conj;
+            if d_bar == N::zero() {
+                for &i in path {
+                    y[i] = N::zero();
+                }
+                let col = self.symbolic.perm.at(j);
+                return Err(SprsError::NumericallySingular(col));
+            }
+            let beta = alpha * p_conj / d_bar;
+            alpha = alpha * d / d_bar;
+            self.diag[j] = d_bar;
+            for ind in self.l_col_range(j) {
+                let i = self.l_indices[ind].index();
+                let l_ij = self.l_data[ind];
+                y[i] = y[i] - p * l_ij;
+                self.l_data[ind] = l_ij + beta * y[i];
+            }
+        }
+        Ok(())
+    }
+
+    /// The columns j < node where the row `node` of L can have non-zero
+    /// entries, given the non-zero pattern of the column of the matrix.
+    ///
+    /// As shown by Davis and Hager, they form the subtree of the
+    /// elimination tree reached by walking up from the rows of the upper
+    /// part of the column. The pattern of L is never reduced, so these
+    /// paths go through entries that are present in L.
+    fn row_subtree(&mut self, node: usize, col_pattern: &[I]) -> Vec<usize> {
+        let mut row = Vec::new();
+        self.visited[node] = true;
+        for i in col_pattern {
+            let mut j = self.symbolic.perm.at_inv(i.index());
+            while j < node && !self.visited[j] {
+                self.visited[j] = true;
+                row.push(j);
+                j = self.symbolic.parents.get_parent(j).unwrap_or(node);
+            }
+        }
+        self.visited[node] = false;
+        for &j in &row {
+            self.visited[j] = false;
+        }
+        row
+    }
+
+    /// Find the position of the entry at (row, col) in L
+    fn find_entry(&self, row: usize, col: usize) -> Option<usize> {
+        let range = self.l_col_range(col);
+        let start = range.start;
+        self.l_indices[range]
+            .binary_search(&I::from_usize(row))
+            .ok()
+            .map(|p| start + p)
+    }
+
+    /// Insert explicit zeros in L at the given (col, row) locations, which
+    /// should not be present yet.
+    ///
+    /// The new entries are merged in place into their columns, so that
+    /// only the modified columns are touched, as long as these columns
+    /// have room for them. Otherwise L is first moved to a larger storage.
+    fn insert_entries(&mut self, mut additions: Vec<(usize, usize)>) {
+        if additions.is_empty() {
+            return;
+        }
+        additions.sort();
+        let mut groups = Vec::new();
+        let mut group_start = 0;
+        for pos in 1..additions.len() + 1 {
+            if pos == additions.len() ||
+               additions[pos].0 != additions[group_start].0 {
+                groups.push(group_start..pos);
+                group_start = pos;
+            }
+        }
+        let full = groups.iter().any(|group| {
+            let col = additions[group.start].0;
+            let capacity = self.symbolic.colptr[col + 1].index() -
+                           self.symbolic.colptr[col].index();
+            self.symbolic.nz[col] + group.len() > capacity
+        });
+        if full {
+            self.grow_storage(&additions);
+        }
+
+        for group in groups {
+            let col = additions[group.start].0;
+            let rows = &additions[group];
+            let start = self.symbolic.colptr[col].index();
+            // merge from the end of the column, so that entries are moved
+            // before being overwritten
+            let mut nb_old = self.symbolic.nz[col];
+            let mut nb_new = rows.len();
+            while nb_new > 0 {
+                let dest = start + nb_old + nb_new - 1;
+                let added = rows[nb_new - 1].1;
+                let last_old = start + nb_old;
+                if nb_old > 0 && self.l_indices[last_old - 1].index() > added {
+                    self.l_indices[dest] = self.l_indices[last_old - 1];
+                    self.l_data[dest] = self.l_data[last_old - 1];
+                    nb_old -= 1;
+                } else {
+                    self.l_indices[dest] = I::from_usize(added);
+                    self.l_data[dest] = N::zero();
+                    nb_new -= 1;
+                }
+            }
+            self.symbolic.nz[col] += rows.len();
+        }
+    }
+
+    /// Move L to a new storage where each column has room for its
+    /// additions, and for half as many entries as it then has, plus one,
+    /// so that later modifications can be done in place.
+    fn grow_storage(&mut self, additions: &[(usize, usize)]) {
+        let n = self.problem_size();
+        let mut nb_added = vec![0; n];
+        for &(col, _) in additions {
+            nb_added[col] += 1;
+        }
+        let mut colptr = Vec::with_capacity(n + 1);
+        colptr.push(I::zero());
+        let mut len = 0;
+        for col in 0..n {
+            let nnz = self.symbolic.nz[col] + nb_added[col];
+            len += nnz + nnz / 2 + 1;
+            colptr.push(I::from_usize(len));
+        }
+        let mut indices = vec![I::zero(); len];
+        let mut data = vec![N::zero(); len];
+        for col in 0..n {
+            let old = self.l_col_range(col);
+            let new = colptr[col].index()..colptr[col].index() + old.len();
+            indices[new.clone()].copy_from_slice(&self.l_indices[old.clone()]);
+            data[new].copy_from_slice(&self.l_data[old]);
+        }
+        self.symbolic.colptr = colptr;
+        self.l_indices = indices;
+        self.l_data = data;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num::complex::Complex64;
+    use sprs::{
+        CsMatOwned,
+        CsVecOwned,
+        Permutation,
+        TripletMat,
+    };
+    use sprs::errors::SprsError;
+    use super::super::LdlNumeric;
+
+    /// The 1D laplacian, whose decomposition has no fill-in
+    fn laplacian(n: usize) -> CsMatOwned<f64> {
+        let mut mat = TripletMat::new((n, n));
+        for i in 0..n {
+            mat.add_triplet(i, i, 4.);
+            if i + 1 < n {
+                mat.add_triplet(i, i + 1, -1.);
+                mat.add_triplet(i + 1, i, -1.);
+            }
+        }
+        mat.to_csc()
+    }
+
+    /// Compute mat + sigma w w^T
+    fn add_outer(mat: &CsMatOwned<f64>,
+                 w: &CsVecOwned<f64>,
+                 sigma: f64)
+                 -> CsMatOwned<f64> {
+        let mut res = TripletMat::new((mat.rows(), mat.cols()));
+        for (col, vec) in mat.outer_iterator().enumerate() {
+            for (row, &val) in vec.iter() {
+                res.add_triplet(row, col, val);
+            }
+        }
+        for (i, &w_i) in w.iter() {
+            for (j, &w_j) in w.iter() {
+                res.add_triplet(i, j, sigma * w_i * w_j);
+            }
+        }
+        res.to_csc()
+    }
+
+    fn assert_same_solve(ldlt: &LdlNumeric<f64>, mat: &CsMatOwned<f64>) {
+        let n = mat.rows();
+        let b: Vec<f64> = (0..n).map(|i| 1. + i as f64).collect();
+        let x = ldlt.solve(&b);
+        let expected = LdlNumeric::new(mat).unwrap().solve(&b);
+        for (xi, ei) in x.iter().zip(&expected) {
+            assert!((xi - ei).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn rank_one_update_and_downdate() {
+        let mat = laplacian(10);
+        // w couples 1 and 7, creating fill-in in the columns 1 to 6
+        let w = CsVecOwned::new(10, vec![1, 7], vec![1., 2.]);
+        let updated = add_outer(&mat, &w, 1.);
+
+        let mut ldlt = LdlNumeric::new(&mat).unwrap();
+        let nnz = ldlt.nnz();
+        ldlt.rank_one_update(w.view()).unwrap();
+        assert!(ldlt.nnz() > nnz);
+        assert_same_solve(&ldlt, &updated);
+
+        ldlt.rank_one_downdate(w.view()).unwrap();
+        assert_same_solve(&ldlt, &mat);
+
+        let perm = Permutation::new(vec![3, 8, 0, 5, 1, 9, 2, 7, 4, 6]);
+        let mut ldlt = LdlNumeric::new_perm(&updated, perm).unwrap();
+        ldlt.rank_one_downdate(w.view()).unwrap();
+        assert_same_solve(&ldlt, &mat);
+    }
+
+    #[test]
+    fn fill_in_place() {
+        let mat = laplacian(10);
+        let mut ldlt = LdlNumeric::new(&mat).unwrap();
+        // the first fill-in moves L to a storage with room in each column
+        let w0 = CsVecOwned::new(10, vec![1, 4], vec![1., 1.]);
+        ldlt.rank_one_update(w0.view()).unwrap();
+        let len = ldlt.l_indices.len();
+        assert!(len > ldlt.nnz());
+
+        // later fill-in fitting in that room is inserted in place
+        let w1 = CsVecOwned::new(10, vec![2, 5], vec![1., -1.]);
+        let nnz = ldlt.nnz();
+        ldlt.rank_one_update(w1.view()).unwrap();
+        assert!(ldlt.nnz() > nnz);
+        assert_eq!(ldlt.l_indices.len(), len);
+        let updated = add_outer(&add_outer(&mat, &w0, 1.), &w1, 1.);
+        assert_same_solve(&ldlt, &updated);
+
+        let b = CsVecOwned::new(10, vec![0], vec![1.]);
+        let mut x = vec![0.; 10];
+        ldlt.solve_sparse(b.view()).scatter(&mut x);
+        let mut dense_b = vec![0.; 10];
+        b.scatter(&mut dense_b);
+        let expected = LdlNumeric::new(&updated).unwrap().solve(&dense_b);
+        for (xi, ei) in x.iter().zip(&expected) {
+            assert!((xi - ei).abs() < 1e-10);
+        }
+
+        // refactoring keeps the storage
+        let scaled = updated.map(|&x| 2. * x);
+        ldlt.update(&scaled).unwrap();
+        assert_eq!(ldlt.l_indices.len(), len);
+        assert_same_solve(&ldlt, &scaled);
+    }
+
+    #[test]
+    fn rank_k_update() {
+        let mat = laplacian(8);
+        let w = CsMatOwned::new_csc((8, 2),
+                                    vec![0, 2, 4],
+                                    vec![0, 5, 2, 7],
+                                    vec![1., -1., 0.5, 3.]);
+        let w0 = CsVecOwned::new(8, vec![0, 5], vec![1., -1.]);
+        let w1 = CsVecOwned::new(8, vec![2, 7], vec![0.5, 3.]);
+        let updated = add_outer(&add_outer(&mat, &w0, 1.), &w1, 1.);
+
+        let perm = Permutation::new(vec![7, 6, 5, 4, 3, 2, 1, 0]);
+        let mut ldlt = LdlNumeric::new_perm(&mat, perm).unwrap();
+        ldlt.rank_update(w.view()).unwrap();
+        assert_same_solve(&ldlt, &updated);
+        ldlt.rank_downdate(w.view()).unwrap();
+        assert_same_solve(&ldlt, &mat);
+    }
+
+    #[test]
+    fn singular_downdate() {
+        // 4 - 2 * 2 = 0
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 1, 2],
+                                      vec![0, 1],
+                                      vec![4., 1.]);
+        let mut ldlt = LdlNumeric::new(&mat).unwrap();
+        let w = CsVecOwned::new(2, vec![0], vec![2.]);
+        let res = ldlt.rank_one_downdate(w.view());
+        assert_eq!(res.unwrap_err(), SprsError::NumericallySingular(0));
+    }
+
+    #[test]
+    fn hermitian_rank_one_update() {
+        let c = Complex64::new;
+        // | 2   i | + | 1 | | 1 -i |   | 3  0 |
+        // | -i  2 |   | i |          = | 0  3 |
+        let mat = CsMatOwned::new_csc((2, 2),
+                                      vec![0, 2, 4],
+                                      vec![0, 1, 0, 1],
+                                      vec![c(2., 0.), c(0., -1.),
+                                           c(0., 1.), c(2., 0.)]);
+        let mut ldlh = LdlNumeric::new_hermitian(&mat).unwrap();
+        let w = CsVecOwned::new(2, vec![0, 1], vec![c(1., 0.), c(0., 1.)]);
+        ldlh.rank_one_update(w.view()).unwrap();
+        let x = ldlh.solve(&vec![c(3., 3.), c(6., 0.)]);
+        let expected = [c(1., 1.), c(2., 0.)];
+        for (xi, ei) in x.iter().zip(&expected) {
+            assert!((xi - ei).norm() < 1e-12);
+        }
+    }
+
+    /// Replace the row and column k of mat by those of the identity
+    fn delete(mat: &CsMatOwned<f64>, k: usize) -> CsMatOwned<f64> {
+        let mut res = TripletMat::new((mat.rows(), mat.cols()));
+        for (col, vec) in mat.outer_iterator().enumerate() {
+            for (row, &val) in vec.iter() {
+                if row != k && col != k {
+                    res.add_triplet(row, col, val);
+                }
+            }
+        }
+        res.add_triplet(k, k, 1.);
+        res.to_csc()
+    }
+
+    #[test]
+    fn delete_and_add_row_col() {
+        let mut mat = TripletMat::new((6, 6));
+        for i in 0..6 {
+            mat.add_triplet(i, i, 5.);
+        }
+        for &(i, j) in &[(0, 2), (1, 3), (2, 5), (3, 5), (0, 4)] {
+            mat.add_triplet(i, j, 1.);
+            mat.add_triplet(j, i, 1.);
+        }
+        let mat = mat.to_csc();
+        for k in 0..6 {
+            let perm = Permutation::new(vec![4, 0, 2, 5, 1, 3]);
+            let mut ldlt = LdlNumeric::new_perm(&mat, perm).unwrap();
+            let col = mat.outer_view(k).unwrap().to_owned();
+            ldlt.delete_row_col(k, col.view()).unwrap();
+            assert_same_solve(&ldlt, &delete(&mat, k));
+
+            ldlt.add_row_col(k, col.view()).unwrap();
+            assert_same_solve(&ldlt, &mat);
+        }
+
+        // adding a column to a matrix with a deleted column
+        let deleted = delete(&mat, 2);
+        let mut ldlt = LdlNumeric::new(&deleted).unwrap();
+        let col = mat.outer_view(2).unwrap().to_owned();
+        ldlt.add_row_col(2, col.view()).unwrap();
+        assert_same_solve(&ldlt, &mat);
+    }
+
+    #[test]
+    fn delete_row_col_through_fill_in() {
+        // the row 7 of L gets entries in the columns 1 to 6 from the
+        // update, reached from the entry (1, 7) of the updated matrix
+        let mat = laplacian(10);
+        let w = CsVecOwned::new(10, vec![1, 7], vec![1., 2.]);
+        let updated = add_outer(&mat, &w, 1.);
+        let mut ldlt = LdlNumeric::new(&mat).unwrap();
+        ldlt.rank_one_update(w.view()).unwrap();
+        let col = updated.outer_view(7).unwrap().to_owned();
+        ldlt.delete_row_col(7, col.view()).unwrap();
+        assert_same_solve(&ldlt, &delete(&updated, 7));
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_row_col_needs_deletion() {
+        let mat = laplacian(4);
+        let mut ldlt = LdlNumeric::new(&mat).unwrap();
+        let col = mat.outer_view(1).unwrap().to_owned();
+        let _ = ldlt.add_row_col(1, col.view());
+    }
+}
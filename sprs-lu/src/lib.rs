@@ -0,0 +1,715 @@
+///! Sparse LU factorization module.
+///!
+///! This decomposition operates on square, possibly unsymmetric, matrices
+///! and is written `P A Q = L U` where L is unit lower triangular,
+///! U is upper triangular, and P and Q are row and column permutation
+///! matrices.
+///!
+///! The factorization is a left-looking Gilbert-Peierls algorithm: each
+///! column of L and U is computed by a sparse triangular solve against
+///! the columns of L computed so far, and its row pivot is chosen by partial
+///! pivoting. The non-zero pattern of each solve is found with
+///! `sprs::linalg::trisolve::sparse_reach`.
+///!
+///! The easiest way to use this API is to create a `LuNumeric` instance from
+///! a matrix, then use the `LuNumeric::solve` method.
+///!
+///! Since partial pivoting depends on the numerical values of the matrix,
+///! the structure of L and U is only known once the numeric factorization
+///! is done. The `LuSymbolic` struct thus only holds the column ordering
+///! and an estimate of the memory required by the factors. It can be
+///! converted into a `LuNumeric` once the non-zero values are known, using
+///! the `LuSymbolic::factor` method. A decomposition can be recomputed for
+///! a new matrix with the same column ordering using `LuNumeric::update`.
+
+extern crate sprs;
+extern crate num;
+
+use std::cell::RefCell;
+use std::cmp;
+use std::ops::Deref;
+
+use num::traits::{Num, Signed};
+
+use sprs::{
+    CsMatBase,
+    CsMatI,
+    CsMatViewI,
+    PermutationBase,
+    PermOwnedI,
+    PermViewI,
+    SpIndex,
+};
+use sprs::errors::SprsError;
+use sprs::linalg;
+use sprs::linalg::trisolve;
+use sprs::stack::{DStack, StackVal};
+
+/// Structure to hold the column ordering of a LU decomposition
+#[derive(Debug)]
+pub struct LuSymbolic<I: SpIndex = usize> {
+    n: usize,
+    nnz_estimate: usize,
+    col_perm: PermOwnedI<I>,
+}
+
+/// Structure to hold a numeric LU decomposition
+#[derive(Debug)]
+pub struct LuNumeric<N, I: SpIndex = usize> {
+    symbolic: LuSymbolic<I>,
+    row_perm: PermOwnedI<I>,
+    l: CsMatI<N, I>,
+    u: CsMatI<N, I>,
+    x_workspace: Vec<N>,
+    visited: Vec<bool>,
+    dstack: DStack<StackVal<usize>>,
+    solve_workspace: RefCell<Vec<N>>,
+}
+
+impl<I: SpIndex> LuSymbolic<I> {
+    /// Prepare the LU decomposition of the given matrix, without any
+    /// column reordering.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> LuSymbolic<I>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let perm: PermOwnedI<I> = PermutationBase::identity();
+        LuSymbolic::new_perm(mat, perm)
+    }
+
+    /// Prepare the decomposition P A Q = L U where Q is the given column
+    /// permutation matrix. The row permutation P is determined by
+    /// partial pivoting during the numeric factorization.
+    ///
+    /// Using a good column permutation can reduce the non-zero count in
+    /// L and U, thus making the decomposition and the solves faster.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new_perm<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                    col_perm: PermOwnedI<I>)
+                                    -> LuSymbolic<I>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let n = mat.cols();
+        assert!(mat.rows() == n, "matrix should be square");
+        LuSymbolic {
+            n: n,
+            // same guess as in CSparse, the factors grow if it is too small
+            nnz_estimate: 4 * mat.nnz() + n,
+            col_perm: col_perm,
+        }
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.n
+    }
+
+    /// The column permutation Q of the decomposition
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.col_perm.view()
+    }
+
+    /// Compute the numerical decomposition of the given matrix.
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if no non-zero pivot can be found for a column.
+    pub fn factor<N, IpS, IS, DS>(self,
+                                  mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                  -> Result<LuNumeric<N, I>, SprsError>
+    where N: Copy + Num + Signed + PartialOrd + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let n = self.problem_size();
+        let mut lu_numeric = LuNumeric {
+            symbolic: self,
+            row_perm: PermutationBase::identity(),
+            l: CsMatI::zero((n, n)),
+            u: CsMatI::zero((n, n)),
+            x_workspace: vec![N::zero(); n],
+            visited: vec![false; n],
+            dstack: DStack::with_capacity(cmp::max(3 * n, 2)),
+            solve_workspace: RefCell::new(vec![N::zero(); n]),
+        };
+        try!(lu_numeric.update(mat));
+        Ok(lu_numeric)
+    }
+}
+
+impl<N, I: SpIndex> LuNumeric<N, I> {
+    /// Compute the numeric LU decomposition of the given matrix.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if no non-zero pivot can be found for a column.
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                            -> Result<Self, SprsError>
+    where N: Copy + Num + Signed + PartialOrd + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = LuSymbolic::new(mat);
+        symbolic.factor(mat)
+    }
+
+    /// Compute the numeric decomposition P A Q = L U
+    /// where Q is the given column permutation matrix.
+    ///
+    /// Using a good column permutation can reduce the non-zero count in
+    /// L and U, thus making the decomposition and the solves faster.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if no non-zero pivot can be found for a column.
+    pub fn new_perm<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                 col_perm: PermOwnedI<I>)
+                                 -> Result<Self, SprsError>
+    where N: Copy + Num + Signed + PartialOrd + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = LuSymbolic::new_perm(mat, col_perm);
+        symbolic.factor(mat)
+    }
+
+    /// Recompute the decomposition for the given matrix, keeping the
+    /// column permutation. The row permutation is recomputed by partial
+    /// pivoting, so the non-zero pattern of the matrix is allowed to change.
+    ///
+    /// # Panics
+    ///
+    /// * if mat does not have the size of the original matrix
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if no non-zero pivot can be found for a column.
+    /// In that case the previous decomposition is left untouched.
+    pub fn update<IpS, IS, DS>(&mut self,
+                               mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<(), SprsError>
+    where N: Copy + Num + Signed + PartialOrd + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let n = self.problem_size();
+        assert!(mat.rows() == n && mat.cols() == n, "Dimension mismatch");
+        let csc;
+        let mat = if mat.is_csc() {
+            mat.view()
+        } else {
+            csc = mat.to_csc();
+            csc.view()
+        };
+        let nnz_estimate = self.symbolic.nnz_estimate;
+        let mut l_colptr = Vec::with_capacity(n + 1);
+        let mut l_indices = Vec::with_capacity(nnz_estimate);
+        let mut l_data = Vec::with_capacity(nnz_estimate);
+        let mut u_colptr = Vec::with_capacity(n + 1);
+        let mut u_indices = Vec::with_capacity(nnz_estimate);
+        let mut u_data = Vec::with_capacity(nnz_estimate);
+        let mut row_perm = vec![I::zero(); n];
+        let res = lu_numeric(mat,
+                             &self.symbolic.col_perm,
+                             &mut l_colptr,
+                             &mut l_indices,
+                             &mut l_data,
+                             &mut u_colptr,
+                             &mut u_indices,
+                             &mut u_data,
+                             &mut row_perm,
+                             &mut self.x_workspace,
+                             &mut self.visited,
+                             &mut self.dstack);
+        if let Err(e) = res {
+            // leave the workspaces clean for the next factorization
+            for x in self.x_workspace.iter_mut() {
+                *x = N::zero();
+            }
+            for v in self.visited.iter_mut() {
+                *v = false;
+            }
+            self.dstack.clear_left();
+            self.dstack.clear_right();
+            return Err(e);
+        }
+        // the constructors sort the indices of each column
+        self.l = CsMatI::new_csc((n, n), l_colptr, l_indices, l_data);
+        self.u = CsMatI::new_csc((n, n), u_colptr, u_indices, u_data);
+        self.row_perm = PermutationBase::new(row_perm);
+        Ok(())
+    }
+
+    /// Solve the system A x = rhs
+    ///
+    /// # Panics
+    ///
+    /// * if rhs does not have the size of the system
+    pub fn solve<'a, V>(&self, rhs: &V) -> Vec<N>
+    where N: 'a + Copy + Num,
+          V: Deref<Target = [N]>
+    {
+        assert!(rhs.len() == self.problem_size(), "Dimension mismatch");
+        let mut x = vec![N::zero(); rhs.len()];
+        self.solve_into(rhs, &mut x);
+        x
+    }
+
+    /// Solve the system A x = rhs into x. The permuted system
+    /// P A Q y = P rhs is solved in the workspace, then x = Q y.
+    fn solve_into(&self, rhs: &[N], x: &mut [N])
+    where N: Copy + Num
+    {
+        let mut y = self.solve_workspace.borrow_mut();
+        for (k, y_k) in y.iter_mut().enumerate() {
+            *y_k = rhs[self.row_perm.at(k)];
+        }
+        trisolve::lsolve_csc_dense_rhs(self.l.view(), &mut y[..])
+            .expect("L has a unit diagonal");
+        trisolve::usolve_csc_dense_rhs(self.u.view(), &mut y[..])
+            .expect("U has a non-zero diagonal");
+        for (k, &y_k) in y.iter().enumerate() {
+            x[self.symbolic.col_perm.at(k)] = y_k;
+        }
+    }
+
+    /// The unit lower triangular factor L, in CSC storage
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor U, in CSC storage
+    pub fn u(&self) -> CsMatViewI<N, I> {
+        self.u.view()
+    }
+
+    /// The row permutation P of the decomposition
+    pub fn row_perm(&self) -> PermViewI<I> {
+        self.row_perm.view()
+    }
+
+    /// The column permutation Q of the decomposition
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.symbolic.col_perm()
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.symbolic.problem_size()
+    }
+
+    /// The number of non-zero entries in L and U
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.l.nnz() + self.u.nnz()
+    }
+}
+
+impl<N, I> linalg::iterative::Preconditioner<N> for LuNumeric<N, I>
+where N: Copy + Num,
+      I: SpIndex
+{
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        self.solve_into(r, z);
+    }
+}
+
+/// Perform a numeric LU decomposition with partial pivoting of the csc
+/// matrix `mat`, with its columns ordered by `col_perm`.
+///
+/// The columns of L and U are appended to the given vectors. The row indices
+/// of L and U are expressed in the pivot order, ie the factors are those of
+/// `P A Q` where `row_perm` holds the row of A chosen as the pivot of each
+/// column. The row indices inside a column are not sorted.
+///
+/// `x_workspace` should be a zero vector of length n, `visited` should be a
+/// false vector of length n, and `dstack` should be empty. They are left
+/// in that state on success.
+///
+/// # Errors
+///
+/// `SingularMatrix` if no non-zero pivot can be found for a column.
+pub fn lu_numeric<N, I, PStorage>(mat: CsMatViewI<N, I>,
+                                  col_perm: &PermutationBase<I, PStorage>,
+                                  l_colptr: &mut Vec<I>,
+                                  l_indices: &mut Vec<I>,
+                                  l_data: &mut Vec<N>,
+                                  u_colptr: &mut Vec<I>,
+                                  u_indices: &mut Vec<I>,
+                                  u_data: &mut Vec<N>,
+                                  row_perm: &mut [I],
+                                  x_workspace: &mut [N],
+                                  visited: &mut [bool],
+                                  dstack: &mut DStack<StackVal<usize>>)
+                                  -> Result<(), SprsError>
+where N: Copy + Num + Signed + PartialOrd,
+      I: SpIndex,
+      PStorage: Deref<Target = [I]>
+{
+    let n = mat.cols();
+    assert!(mat.is_csc(), "Storage mismatch");
+    assert!(mat.rows() == n, "matrix should be square");
+    assert!(x_workspace.len() == n && visited.len() == n,
+            "workspaces should be of len n");
+    // maps a row of A to the column of L where it was chosen as pivot
+    let mut pivot_cols: Vec<Option<usize>> = vec![None; n];
+    l_colptr.clear();
+    l_indices.clear();
+    l_data.clear();
+    u_colptr.clear();
+    u_indices.clear();
+    u_data.clear();
+    l_colptr.push(I::zero());
+    u_colptr.push(I::zero());
+
+    for k in 0..n {
+        let col_ind = col_perm.at(k);
+        let col = mat.outer_view(col_ind).expect("col_ind is in bounds");
+
+        // The rows of L are still expressed in the rows of A, so the
+        // partial L is triangular once its rows are permuted by the pivot
+        // order. Rows which have not been chosen as pivots yet have no
+        // column in L, and are leaves in the graph of L.
+        let reach_cap = n + col.nnz() + l_indices.len();
+        if dstack.capacity() < reach_cap {
+            *dstack = DStack::with_capacity(2 * reach_cap);
+        }
+        {
+            // The indices in the columns of L are not sorted yet, so L is
+            // not a valid matrix, but its structure can be traversed.
+            let pivot_cols = &pivot_cols;
+            let l_colptr: &[I] = l_colptr;
+            let l_indices: &[I] = l_indices;
+            let l_col_rows = |row: usize| match pivot_cols[row] {
+                Some(l_col) => {
+                    let start = l_colptr[l_col].index();
+                    let stop = l_colptr[l_col + 1].index();
+                    &l_indices[start..stop]
+                }
+                None => &[],
+            };
+            trisolve::sparse_reach_structure(col.indices(),
+                                             l_col_rows,
+                                             dstack,
+                                             visited);
+        }
+
+        // sparse triangular solve L x = A[:, col_ind]
+        col.scatter(x_workspace);
+        for &row in dstack.iter_right().map(sprs::stack::extract_stack_val) {
+            let l_col = match pivot_cols[row] {
+                Some(l_col) => l_col,
+                None => continue,
+            };
+            let x_row = x_workspace[row];
+            let start = l_colptr[l_col].index();
+            let stop = l_colptr[l_col + 1].index();
+            // the first entry of each column of L is its unit diagonal
+            for (&l_row, &l_val) in l_indices[start + 1..stop]
+                                        .iter()
+                                        .zip(&l_data[start + 1..stop]) {
+                let l_row = l_row.index();
+                x_workspace[l_row] = x_workspace[l_row] - l_val * x_row;
+            }
+        }
+
+        // the already pivoted rows go in U, the others are pivot candidates
+        let mut pivot: Option<(usize, N)> = None;
+        for &row in dstack.iter_right().map(sprs::stack::extract_stack_val) {
+            let x_row = x_workspace[row];
+            match pivot_cols[row] {
+                Some(u_row) => {
+                    u_indices.push(I::from_usize(u_row));
+                    u_data.push(x_row);
+                }
+                None => {
+                    let better = match pivot {
+                        None => true,
+                        Some((pivot_row, pivot_val)) => {
+                            let (abs, pivot_abs) = (x_row.abs(),
+                                                    pivot_val.abs());
+                            // prefer the diagonal in case of a tie
+                            abs > pivot_abs ||
+                            (abs == pivot_abs && row == col_ind &&
+                             pivot_row != col_ind)
+                        }
+                    };
+                    if better {
+                        pivot = Some((row, x_row));
+                    }
+                }
+            }
+        }
+        let (pivot_row, pivot_val) = match pivot {
+            Some((row, val)) if val != N::zero() => (row, val),
+            _ => {
+                for &row in dstack.iter_right()
+                                  .map(sprs::stack::extract_stack_val) {
+                    x_workspace[row] = N::zero();
+                    visited[row] = false;
+                }
+                dstack.clear_right();
+                return Err(SprsError::SingularMatrix);
+            }
+        };
+        pivot_cols[pivot_row] = Some(k);
+        row_perm[k] = I::from_usize(pivot_row);
+        u_indices.push(I::from_usize(k));
+        u_data.push(pivot_val);
+        u_colptr.push(I::from_usize(u_indices.len()));
+
+        l_indices.push(I::from_usize(pivot_row));
+        l_data.push(N::one());
+        for &row in dstack.iter_right().map(sprs::stack::extract_stack_val) {
+            if pivot_cols[row].is_none() {
+                l_indices.push(I::from_usize(row));
+                l_data.push(x_workspace[row] / pivot_val);
+            }
+            x_workspace[row] = N::zero();
+            visited[row] = false;
+        }
+        l_colptr.push(I::from_usize(l_indices.len()));
+        dstack.clear_right();
+    }
+
+    // express the rows of L in the pivot order
+    for row in l_indices.iter_mut() {
+        let pivot_col = pivot_cols[row.index()].expect("all rows pivoted");
+        *row = I::from_usize(pivot_col);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sprs::{
+        CsMat,
+        CsMatOwned,
+        CsMatI,
+        Permutation,
+    };
+    use sprs::errors::SprsError;
+    use sprs::prod;
+    use sprs::linalg::iterative::{gmres, PrecondSide, Preconditioner,
+                                  StoppingCriterion};
+    use super::{LuNumeric, LuSymbolic};
+
+    fn test_mat1() -> CsMatOwned<f64> {
+        // |0  2  0  1 |
+        // |3  0  0  0 |
+        // |0  4  5  0 |
+        // |1  0  6  7 |
+        CsMat::new_csc((4, 4),
+                       vec![0, 2, 4, 6, 8],
+                       vec![1, 3, 0, 2, 2, 3, 0, 3],
+                       vec![3., 1., 2., 4., 5., 6., 1., 7.])
+    }
+
+    fn mat_vec(mat: &CsMatOwned<f64>, x: &[f64]) -> Vec<f64> {
+        let mut res = vec![0.; mat.rows()];
+        prod::mul_acc_mat_vec_csc(mat.view(), x, &mut res);
+        res
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-12, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn check_factors(mat: &CsMatOwned<f64>, lu: &LuNumeric<f64>) {
+        let n = lu.problem_size();
+        let l = lu.l().to_dense();
+        let u = lu.u().to_dense();
+        let a = mat.to_dense();
+        for i in 0..n {
+            assert_eq!(l[[i, i]], 1.);
+            for j in 0..n {
+                if j > i {
+                    assert_eq!(l[[i, j]], 0.);
+                }
+                if j < i {
+                    assert_eq!(u[[i, j]], 0.);
+                }
+                let mut lu_ij = 0.;
+                for k in 0..n {
+                    lu_ij += l[[i, k]] * u[[k, j]];
+                }
+                let paq_ij = a[[lu.row_perm().at(i), lu.col_perm().at(j)]];
+                assert!((lu_ij - paq_ij).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn lu_factors() {
+        let mat = test_mat1();
+        let lu = LuNumeric::new(&mat).unwrap();
+        check_factors(&mat, &lu);
+        // the zero diagonal forces a row exchange on the first column
+        assert_eq!(lu.row_perm().at(0), 1);
+    }
+
+    #[test]
+    fn lu_solve() {
+        let mat = test_mat1();
+        let x0 = vec![1., -2., 3., 0.5];
+        let b = mat_vec(&mat, &x0);
+        let lu = LuNumeric::new(&mat).unwrap();
+        let x = lu.solve(&b);
+        assert_close(&x, &x0);
+
+        // same system with a csr matrix
+        let lu = LuNumeric::new(&mat.to_csr()).unwrap();
+        let x = lu.solve(&b);
+        assert_close(&x, &x0);
+    }
+
+    #[test]
+    fn lu_solve_larger() {
+        // unsymmetric matrix with fill-in
+        let indptr = vec![0, 3, 5, 8, 10, 14, 15, 18, 21, 24, 28];
+        let indices = vec![0, 5, 8, 1, 9, 2, 4, 7, 0, 3, 1, 4, 6, 9, 5,
+                           2, 6, 9, 4, 7, 8, 0, 3, 8, 1, 4, 6, 9];
+        let data = vec![1.7, 0.5, 0.13, 1., 0.01, 1.5, -0.3, 0.8, 0.9, 1.1,
+                        0.02, 2.6, 0.16, 0.53, 1.2, 0.4, 1.3, 0.56, 0.09,
+                        1.6, 0.11, -0.2, 0.7, 1.4, 0.01, 0.53, 0.56, 3.1];
+        let mat = CsMat::new_csc((10, 10), indptr, indices, data);
+        let lu = LuNumeric::new(&mat).unwrap();
+        check_factors(&mat, &lu);
+        let x0: Vec<f64> = (0..10).map(|i| i as f64 - 3.5).collect();
+        let b = mat_vec(&mat, &x0);
+        assert_close(&lu.solve(&b), &x0);
+    }
+
+    #[test]
+    fn lu_col_perm() {
+        let mat = test_mat1();
+        let perm = Permutation::new(vec![2, 0, 3, 1]);
+        let lu = LuNumeric::new_perm(&mat, perm).unwrap();
+        check_factors(&mat, &lu);
+        let x0 = vec![-1., 2., 0.25, 4.];
+        let b = mat_vec(&mat, &x0);
+        assert_close(&lu.solve(&b), &x0);
+        let mut z = vec![0.; 4];
+        lu.apply(&b, &mut z);
+        assert_close(&z, &x0);
+    }
+
+    #[test]
+    fn lu_partial_pivoting() {
+        // the largest entry of each column should be chosen as pivot
+        // |1  2|
+        // |4  3|
+        let mat = CsMat::new_csc((2, 2),
+                                 vec![0, 2, 4],
+                                 vec![0, 1, 0, 1],
+                                 vec![1., 4., 2., 3.]);
+        let lu = LuNumeric::new(&mat).unwrap();
+        assert_eq!(lu.row_perm().at(0), 1);
+        assert_eq!(lu.row_perm().at(1), 0);
+        assert_eq!(lu.l().get(1, 0), Some(&0.25));
+        assert_eq!(lu.u().get(0, 0), Some(&4.));
+        assert_eq!(lu.u().get(1, 1), Some(&1.25));
+        assert_eq!(lu.nnz(), 6);
+    }
+
+    #[test]
+    fn lu_singular() {
+        // |1  2  0|
+        // |2  4  0|
+        // |0  0  1|
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 2, 4, 5],
+                                 vec![0, 1, 0, 1, 2],
+                                 vec![1., 2., 2., 4., 1.]);
+        let res = LuNumeric::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::SingularMatrix));
+
+        // structurally singular: empty column
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 2, 2, 3],
+                                 vec![0, 1, 2],
+                                 vec![1., 2., 1.]);
+        let res = LuNumeric::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::SingularMatrix));
+    }
+
+    #[test]
+    fn lu_update() {
+        let mat = test_mat1();
+        let symbolic = LuSymbolic::new(&mat);
+        assert_eq!(symbolic.problem_size(), 4);
+        let mut lu = symbolic.factor(&mat).unwrap();
+
+        let singular = CsMat::new_csc((4, 4),
+                                      vec![0, 2, 4, 6, 6],
+                                      vec![1, 3, 0, 2, 2, 3],
+                                      vec![3., 1., 2., 4., 5., 6.]);
+        assert_eq!(lu.update(&singular), Err(SprsError::SingularMatrix));
+        check_factors(&mat, &lu);
+
+        let mat2 = CsMat::new_csc((4, 4),
+                                  vec![0, 2, 4, 6, 8],
+                                  vec![0, 1, 1, 2, 2, 3, 0, 3],
+                                  vec![2., 1., 3., 1., 4., 1., 1., 5.]);
+        lu.update(&mat2).unwrap();
+        check_factors(&mat2, &lu);
+        let x0 = vec![1., 2., 3., 4.];
+        let b = mat_vec(&mat2, &x0);
+        assert_close(&lu.solve(&b), &x0);
+    }
+
+    #[test]
+    fn lu_u32_indices() {
+        let mat = test_mat1();
+        let mat: CsMatI<f64, u32> = mat.to_other_index().unwrap();
+        let lu = LuNumeric::new(&mat).unwrap();
+        let x0 = vec![1., -2., 3., 0.5];
+        let b = mat_vec(&test_mat1(), &x0);
+        assert_close(&lu.solve(&b), &x0);
+    }
+
+    #[test]
+    fn lu_preconditioner() {
+        let mat = test_mat1();
+        let x0 = vec![1., -2., 3., 0.5];
+        let b = mat_vec(&mat, &x0);
+        // the exact factorization makes gmres converge in one iteration
+        let lu = LuNumeric::new(&mat).unwrap();
+        for &side in &[PrecondSide::Left, PrecondSide::Right] {
+            let sol = gmres(&mat, &b, &lu, side, 4,
+                            StoppingCriterion::RelativeResidual(1e-10), 10)
+                .unwrap();
+            assert!(sol.converged());
+            assert_eq!(sol.iterations(), 1);
+            assert_close(&sol.x, &x0);
+        }
+    }
+}
@@ -0,0 +1,887 @@
+///! Sparse QR factorization module.
+///!
+///! This decomposition operates on rectangular matrices with at least as
+///! many rows as columns, and is written `P A Q = Q_h R` where R is upper
+///! triangular, `Q_h` is orthogonal and P and Q are row and column
+///! permutation matrices. It is mainly used to solve sparse least-squares
+///! problems `min ||A x - b||`, with A of full column rank.
+///!
+///! The factorization is a left-looking Householder QR, as described in
+///! Tim Davis' book "Direct Methods for Sparse Linear Systems". The
+///! orthogonal factor is never formed, it is kept as the product of the
+///! Householder reflections `Q_h = H_0 H_1 ... H_{n-1}`, each reflection
+///! being stored as a sparse vector.
+///!
+///! The symbolic analysis is driven by the column elimination tree of A,
+///! ie the elimination tree of `A^T A`, which is also the elimination tree
+///! of R. It gives the non-zero pattern of the Householder vectors and the
+///! row permutation P. The fill-in of the factors depends on the column
+///! permutation Q, which can be computed by an ordering of `A^T A`, for
+///! instance `sprs::linalg::ordering::amd`.
+///!
+///! When A is structurally rank deficient, some columns have no row to
+///! eliminate, in which case empty rows are appended to A. The row
+///! permutation and `Q_h` then have more rows than A.
+///!
+///! The easiest way to use this API is to create a `QrNumeric` instance from
+///! a matrix, then use the `QrNumeric::solve` method.
+
+extern crate sprs;
+extern crate num;
+
+use std::ops::Deref;
+
+use num::traits::Float;
+
+use sprs::{
+    CsMatBase,
+    CsMatI,
+    CsMatViewI,
+    PermutationBase,
+    PermOwnedI,
+    PermViewI,
+    SpIndex,
+};
+use sprs::errors::SprsError;
+use sprs::linalg::etree::ParentsOwned;
+use sprs::linalg::iterative::LinearOperator;
+use sprs::linalg::trisolve;
+
+/// Structure to hold the symbolic analysis of a QR decomposition
+#[derive(Debug)]
+pub struct QrSymbolic<I: SpIndex = usize> {
+    nrows: usize,
+    ncols: usize,
+    col_perm: PermOwnedI<I>,
+    parents: ParentsOwned,
+    /// The leftmost column (in the column permutation) of each row
+    leftmost: Vec<Option<usize>>,
+    /// The position of each row of A in the pivot order, including the
+    /// empty rows appended to A
+    row_perm_inv: Vec<usize>,
+    v_nnz: usize,
+}
+
+/// Structure to hold a numeric QR decomposition
+#[derive(Debug)]
+pub struct QrNumeric<N, I: SpIndex = usize> {
+    symbolic: QrSymbolic<I>,
+    row_perm: PermOwnedI<I>,
+    v: CsMatI<N, I>,
+    beta: Vec<N>,
+    r: CsMatI<N, I>,
+}
+
+/// The orthogonal factor of a QR decomposition, as a product of Householder
+/// reflections. Its rows are in the order of the row permutation of the
+/// decomposition.
+#[derive(Debug)]
+pub struct HouseholderQ<'a, N: 'a, I: 'a + SpIndex> {
+    v: CsMatViewI<'a, N, I>,
+    beta: &'a [N],
+}
+
+impl<I: SpIndex> QrSymbolic<I> {
+    /// Prepare the QR decomposition of the given matrix, without any
+    /// column reordering.
+    ///
+    /// # Panics
+    ///
+    /// * if mat has less rows than columns
+    pub fn new<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> QrSymbolic<I>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let perm: PermOwnedI<I> = PermutationBase::identity();
+        QrSymbolic::new_perm(mat, perm)
+    }
+
+    /// Prepare the decomposition P A Q = Q_h R where Q is the given column
+    /// permutation matrix. The row permutation P is determined by the
+    /// symbolic analysis.
+    ///
+    /// Using a good column permutation, such as a fill-reducing ordering of
+    /// `A^T A`, can reduce the non-zero count in R and in the Householder
+    /// vectors, thus making the decomposition and the solves faster.
+    ///
+    /// # Panics
+    ///
+    /// * if mat has less rows than columns
+    /// * if the permutation does not have the number of columns of mat
+    pub fn new_perm<N, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                    col_perm: PermOwnedI<I>)
+                                    -> QrSymbolic<I>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let (m, n) = mat.shape();
+        assert!(m >= n, "matrix should have at least as many rows as cols");
+        let parents = ParentsOwned::from_ata(mat, &col_perm);
+        let mut leftmost = vec![None; m];
+        for (outer, vec) in mat.outer_iterator().enumerate() {
+            for &inner in vec.indices() {
+                let (row, col) = if mat.is_csc() {
+                    (inner.index(), outer)
+                } else {
+                    (outer, inner.index())
+                };
+                let k = col_perm.at_inv(col);
+                if leftmost[row].map_or(true, |left| k < left) {
+                    leftmost[row] = Some(k);
+                }
+            }
+        }
+        let (row_perm_inv, v_nnz) = householder_rows(&parents, &leftmost);
+        QrSymbolic {
+            nrows: m,
+            ncols: n,
+            col_perm: col_perm,
+            parents: parents,
+            leftmost: leftmost,
+            row_perm_inv: row_perm_inv,
+            v_nnz: v_nnz,
+        }
+    }
+
+    /// The shape of the factorized matrix
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// The number of rows of the decomposition, ie the number of rows of
+    /// the matrix and of the empty rows added for structurally rank
+    /// deficient matrices.
+    pub fn padded_rows(&self) -> usize {
+        self.row_perm_inv.len()
+    }
+
+    /// The column permutation Q of the decomposition
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.col_perm.view()
+    }
+
+    /// The column elimination tree, ie the elimination tree of R
+    pub fn etree(&self) -> &ParentsOwned {
+        &self.parents
+    }
+
+    /// The number of non-zero entries in the Householder vectors
+    pub fn householder_nnz(&self) -> usize {
+        self.v_nnz
+    }
+
+    /// Compute the numerical decomposition of the given matrix.
+    ///
+    /// # Panics
+    ///
+    /// * if mat does not have the shape of the analyzed matrix
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if R has a zero diagonal entry, ie if the matrix
+    /// is not of full column rank.
+    pub fn factor<N, IpS, IS, DS>(self,
+                                  mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                  -> Result<QrNumeric<N, I>, SprsError>
+    where N: Float + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let (m2, n) = (self.padded_rows(), self.ncols);
+        let mut row_perm = vec![I::zero(); m2];
+        for (row, &pos) in self.row_perm_inv.iter().enumerate() {
+            row_perm[pos] = I::from_usize(row);
+        }
+        let mut qr_numeric = QrNumeric {
+            symbolic: self,
+            row_perm: PermutationBase::new(row_perm),
+            v: CsMatI::zero((m2, n)),
+            beta: Vec::with_capacity(n),
+            r: CsMatI::zero((n, n)),
+        };
+        try!(qr_numeric.update(mat));
+        Ok(qr_numeric)
+    }
+}
+
+impl<N, I: SpIndex> QrNumeric<N, I> {
+    /// Compute the numeric QR decomposition of the given matrix.
+    ///
+    /// # Panics
+    ///
+    /// * if mat has less rows than columns
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if the matrix is not of full column rank.
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                            -> Result<Self, SprsError>
+    where N: Float + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = QrSymbolic::new(mat);
+        symbolic.factor(mat)
+    }
+
+    /// Compute the numeric decomposition P A Q = Q_h R
+    /// where Q is the given column permutation matrix.
+    ///
+    /// # Panics
+    ///
+    /// * if mat has less rows than columns
+    /// * if the permutation does not have the number of columns of mat
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if the matrix is not of full column rank.
+    pub fn new_perm<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                 col_perm: PermOwnedI<I>)
+                                 -> Result<Self, SprsError>
+    where N: Float + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        let symbolic = QrSymbolic::new_perm(mat, col_perm);
+        symbolic.factor(mat)
+    }
+
+    /// Recompute the decomposition for the given matrix, keeping the
+    /// symbolic analysis. The matrix should have the non-zero pattern of
+    /// the matrix that was analyzed.
+    ///
+    /// # Panics
+    ///
+    /// * if mat does not have the shape of the analyzed matrix
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if the matrix is not of full column rank. In that
+    /// case the decomposition is still computed, but cannot be used for
+    /// solves.
+    pub fn update<IpS, IS, DS>(&mut self,
+                               mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<(), SprsError>
+    where N: Float + Default,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        assert!(mat.shape() == self.symbolic.shape(), "Dimension mismatch");
+        let csc;
+        let mat = if mat.is_csc() {
+            mat.view()
+        } else {
+            csc = mat.to_csc();
+            csc.view()
+        };
+        let (m2, n) = (self.symbolic.padded_rows(), self.symbolic.ncols);
+        let mut v_colptr = Vec::with_capacity(n + 1);
+        let mut v_indices = Vec::with_capacity(self.symbolic.v_nnz);
+        let mut v_data = Vec::with_capacity(self.symbolic.v_nnz);
+        let mut r_colptr = Vec::with_capacity(n + 1);
+        let mut r_indices = Vec::new();
+        let mut r_data = Vec::new();
+        self.beta.clear();
+        qr_numeric(mat,
+                   &self.symbolic,
+                   &mut v_colptr,
+                   &mut v_indices,
+                   &mut v_data,
+                   &mut self.beta,
+                   &mut r_colptr,
+                   &mut r_indices,
+                   &mut r_data);
+        // the constructors sort the indices of each column
+        self.v = CsMatI::new_csc((m2, n), v_colptr, v_indices, v_data);
+        self.r = CsMatI::new_csc((n, n), r_colptr, r_indices, r_data);
+        // the diagonal is the last entry of each column of R
+        let singular = self.r.outer_iterator().any(|col| {
+            col.data().last().map_or(true, |&d| d == N::zero())
+        });
+        if singular {
+            return Err(SprsError::SingularMatrix);
+        }
+        Ok(())
+    }
+
+    /// Solve the least-squares problem `min ||A x - rhs||`.
+    ///
+    /// When A is square, this is the solution of the system `A x = rhs`.
+    ///
+    /// # Panics
+    ///
+    /// * if rhs does not have the number of rows of the matrix
+    pub fn solve<V>(&self, rhs: &V) -> Vec<N>
+    where N: Float,
+          V: Deref<Target = [N]>
+    {
+        let (m, n) = self.symbolic.shape();
+        assert!(rhs.len() == m, "Dimension mismatch");
+        let mut x = vec![N::zero(); self.symbolic.padded_rows()];
+        for (&val, &pos) in rhs.iter().zip(&self.symbolic.row_perm_inv) {
+            x[pos] = val;
+        }
+        self.q().apply_transpose_in_place(&mut x);
+        x.truncate(n);
+        trisolve::usolve_csc_dense_rhs(self.r.view(), &mut x)
+            .expect("R has a non-zero diagonal");
+        let col_perm_inv = self.symbolic.col_perm.inv();
+        &col_perm_inv * &x
+    }
+
+    /// The upper triangular factor R, in CSC storage
+    pub fn r(&self) -> CsMatViewI<N, I> {
+        self.r.view()
+    }
+
+    /// The orthogonal factor `Q_h`, as a linear operator
+    pub fn q(&self) -> HouseholderQ<N, I> {
+        HouseholderQ {
+            v: self.v.view(),
+            beta: &self.beta,
+        }
+    }
+
+    /// The row permutation P of the decomposition. It has
+    /// `padded_rows()` entries.
+    pub fn row_perm(&self) -> PermViewI<I> {
+        self.row_perm.view()
+    }
+
+    /// The column permutation Q of the decomposition
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.symbolic.col_perm()
+    }
+
+    /// The shape of the factorized matrix
+    pub fn shape(&self) -> (usize, usize) {
+        self.symbolic.shape()
+    }
+
+    /// The number of rows of the decomposition, see
+    /// `QrSymbolic::padded_rows`
+    pub fn padded_rows(&self) -> usize {
+        self.symbolic.padded_rows()
+    }
+
+    /// The number of non-zero entries in R and in the Householder vectors
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.r.nnz() + self.v.nnz()
+    }
+}
+
+impl<'a, N, I> HouseholderQ<'a, N, I>
+where N: 'a + Float,
+      I: 'a + SpIndex
+{
+    /// The Householder vectors, stored as the columns of a CSC matrix
+    pub fn householder_vectors(&self) -> CsMatViewI<N, I> {
+        self.v.view()
+    }
+
+    /// The coefficients of the Householder reflections
+    /// `H_k = I - beta_k v_k v_k^T`
+    pub fn betas(&self) -> &'a [N] {
+        self.beta
+    }
+
+    /// Compute `x = Q_h x`.
+    ///
+    /// # Panics
+    ///
+    /// * if the length of x is not the number of rows of the operator
+    pub fn apply_in_place(&self, x: &mut [N]) {
+        assert!(x.len() == self.v.rows(), "Dimension mismatch");
+        for k in (0..self.v.cols()).rev() {
+            self.reflect(k, x);
+        }
+    }
+
+    /// Compute `x = Q_h^T x`.
+    ///
+    /// # Panics
+    ///
+    /// * if the length of x is not the number of rows of the operator
+    pub fn apply_transpose_in_place(&self, x: &mut [N]) {
+        assert!(x.len() == self.v.rows(), "Dimension mismatch");
+        for k in 0..self.v.cols() {
+            self.reflect(k, x);
+        }
+    }
+
+    /// Compute `y = Q_h^T x`, overwriting the contents of y.
+    ///
+    /// # Panics
+    ///
+    /// * if the length of x or y is not the number of rows of the operator
+    pub fn apply_transpose(&self, x: &[N], y: &mut [N]) {
+        y.copy_from_slice(x);
+        self.apply_transpose_in_place(y);
+    }
+
+    fn reflect(&self, k: usize, x: &mut [N]) {
+        let v = self.v.outer_view(k).expect("k is in bounds");
+        householder_apply(v.indices(), v.data(), self.beta[k], x);
+    }
+}
+
+impl<'a, N, I> LinearOperator<N> for HouseholderQ<'a, N, I>
+where N: 'a + Float,
+      I: 'a + SpIndex
+{
+    fn shape(&self) -> (usize, usize) {
+        (self.v.rows(), self.v.rows())
+    }
+
+    fn apply(&self, x: &[N], y: &mut [N]) {
+        y.copy_from_slice(x);
+        self.apply_in_place(y);
+    }
+}
+
+/// Compute the row permutation of the QR decomposition, and the non-zero
+/// count of the Householder vectors, given the column elimination tree and
+/// the leftmost column of each row.
+///
+/// Each column k is assigned a pivot row among the rows whose leftmost
+/// column is k. The other rows of k are not yet eliminated, and become
+/// candidate rows for the parent of k. A column without any candidate row
+/// gets a new empty row, appended after the rows of the matrix.
+///
+/// Returns the position of each row in the pivot order, including the
+/// appended rows, and the non-zero count of the Householder vectors.
+fn householder_rows(parents: &ParentsOwned,
+                    leftmost: &[Option<usize>])
+                    -> (Vec<usize>, usize) {
+    let m = leftmost.len();
+    let n = parents.nb_nodes();
+    // linked lists of the candidate rows of each column
+    let mut next: Vec<Option<usize>> = vec![None; m];
+    let mut head: Vec<Option<usize>> = vec![None; n];
+    let mut tail: Vec<Option<usize>> = vec![None; n];
+    let mut nb_candidates = vec![0; n];
+    for row in (0..m).rev() {
+        let k = match leftmost[row] {
+            Some(k) => k,
+            None => continue,
+        };
+        if nb_candidates[k] == 0 {
+            tail[k] = Some(row);
+        }
+        nb_candidates[k] += 1;
+        next[row] = head[k];
+        head[k] = Some(row);
+    }
+    let mut row_perm_inv: Vec<Option<usize>> = vec![None; m];
+    let mut v_nnz = 0;
+    for k in 0..n {
+        v_nnz += 1;
+        let row = match head[k] {
+            Some(row) => row,
+            None => {
+                row_perm_inv.push(Some(k));
+                continue;
+            }
+        };
+        row_perm_inv[row] = Some(k);
+        nb_candidates[k] -= 1;
+        if nb_candidates[k] == 0 {
+            continue;
+        }
+        v_nnz += nb_candidates[k];
+        if let Some(parent) = parents.get_parent(k) {
+            // move the remaining candidates to the parent
+            if nb_candidates[parent] == 0 {
+                tail[parent] = tail[k];
+            }
+            let tail_k = tail[k].expect("k has candidates");
+            next[tail_k] = head[parent];
+            head[parent] = next[row];
+            nb_candidates[parent] += nb_candidates[k];
+        }
+    }
+    // the rows which were not chosen as pivots come last
+    let mut pos = n;
+    let row_perm_inv = row_perm_inv.into_iter()
+                                   .map(|p| {
+                                       p.unwrap_or_else(|| {
+                                           pos += 1;
+                                           pos - 1
+                                       })
+                                   })
+                                   .collect();
+    (row_perm_inv, v_nnz)
+}
+
+/// Apply the Householder reflection `I - beta v v^T` to x
+fn householder_apply<N, I>(v_indices: &[I], v_data: &[N], beta: N, x: &mut [N])
+where N: Float,
+      I: SpIndex
+{
+    let mut tau = N::zero();
+    for (&row, &val) in v_indices.iter().zip(v_data) {
+        tau = tau + val * x[row.index()];
+    }
+    tau = tau * beta;
+    for (&row, &val) in v_indices.iter().zip(v_data) {
+        let row = row.index();
+        x[row] = x[row] - val * tau;
+    }
+}
+
+/// Compute the Householder reflection `H = I - beta v v^T` such that
+/// `H x = s e_0`, overwriting x with v. Returns `(s, beta)`.
+fn householder<N: Float>(x: &mut [N]) -> (N, N) {
+    let zero = N::zero();
+    let two = N::one() + N::one();
+    let sigma = x[1..].iter().fold(zero, |acc, &val| acc + val * val);
+    if sigma == zero {
+        let s = x[0].abs();
+        let beta = if x[0] <= zero { two } else { zero };
+        x[0] = N::one();
+        (s, beta)
+    } else {
+        let s = (x[0] * x[0] + sigma).sqrt();
+        x[0] = if x[0] <= zero { x[0] - s } else { -sigma / (x[0] + s) };
+        (s, -N::one() / (s * x[0]))
+    }
+}
+
+/// Perform a numeric QR decomposition of the csc matrix `mat`, given its
+/// symbolic analysis.
+///
+/// The columns of the Householder vectors and of R are appended to the
+/// given vectors. The row indices of the Householder vectors are expressed
+/// in the pivot order. The first entry of each Householder vector is its
+/// diagonal, the other row indices inside a column are not sorted, and
+/// neither are the row indices inside a column of R.
+///
+/// # Panics
+///
+/// * if the non-zero pattern of `mat` is not that of the analyzed matrix
+pub fn qr_numeric<N, I>(mat: CsMatViewI<N, I>,
+                        symbolic: &QrSymbolic<I>,
+                        v_colptr: &mut Vec<I>,
+                        v_indices: &mut Vec<I>,
+                        v_data: &mut Vec<N>,
+                        beta: &mut Vec<N>,
+                        r_colptr: &mut Vec<I>,
+                        r_indices: &mut Vec<I>,
+                        r_data: &mut Vec<N>)
+where N: Float,
+      I: SpIndex
+{
+    assert!(mat.is_csc(), "Storage mismatch");
+    assert!(mat.shape() == symbolic.shape(), "Dimension mismatch");
+    let (m2, n) = (symbolic.padded_rows(), symbolic.ncols);
+    let parents = &symbolic.parents;
+    let mut x = vec![N::zero(); m2];
+    // marks of the rows of the current Householder vector, and of the
+    // columns of the current column of R. The pivot row of column i is at
+    // position i, so marking a column of R also excludes its pivot row from
+    // the Householder vector.
+    let mut marks: Vec<Option<usize>> = vec![None; m2];
+    // the pattern of the current column of R in topological order, filled
+    // from the end, while the beginning is used to store a path of the etree
+    let mut stack = vec![0; n];
+    v_colptr.clear();
+    v_indices.clear();
+    v_data.clear();
+    r_colptr.clear();
+    r_indices.clear();
+    r_data.clear();
+    v_colptr.push(I::zero());
+    r_colptr.push(I::zero());
+
+    for k in 0..n {
+        let v_start = v_indices.len();
+        marks[k] = Some(k);
+        v_indices.push(I::from_usize(k));
+        let mut top = n;
+        let col = mat.outer_view(symbolic.col_perm.at(k))
+                     .expect("col is in bounds");
+        for (row, &val) in col.iter() {
+            // the columns of R are on the path from the leftmost column of
+            // this row to k in the etree
+            let mut node = symbolic.leftmost[row].expect("row is not empty");
+            let mut len = 0;
+            while marks[node] != Some(k) {
+                stack[len] = node;
+                len += 1;
+                marks[node] = Some(k);
+                node = parents.get_parent(node)
+                              .expect("pattern of the analyzed matrix");
+            }
+            while len > 0 {
+                len -= 1;
+                top -= 1;
+                stack[top] = stack[len];
+            }
+            let pos = symbolic.row_perm_inv[row];
+            x[pos] = val;
+            if pos > k && marks[pos] < Some(k) {
+                v_indices.push(I::from_usize(pos));
+                marks[pos] = Some(k);
+            }
+        }
+        for &i in &stack[top..] {
+            let v_i = v_colptr[i].index()..v_colptr[i + 1].index();
+            householder_apply(&v_indices[v_i.clone()],
+                              &v_data[v_i.clone()],
+                              beta[i],
+                              &mut x);
+            r_indices.push(I::from_usize(i));
+            r_data.push(x[i]);
+            x[i] = N::zero();
+            if parents.get_parent(i) == Some(k) {
+                // the rows of the child vector are rows of this vector
+                for p in v_i {
+                    let row = v_indices[p].index();
+                    if marks[row] < Some(k) {
+                        marks[row] = Some(k);
+                        v_indices.push(I::from_usize(row));
+                    }
+                }
+            }
+        }
+        for p in v_start..v_indices.len() {
+            let row = v_indices[p].index();
+            v_data.push(x[row]);
+            x[row] = N::zero();
+        }
+        let (diag, beta_k) = householder(&mut v_data[v_start..]);
+        beta.push(beta_k);
+        r_indices.push(I::from_usize(k));
+        r_data.push(diag);
+        v_colptr.push(I::from_usize(v_indices.len()));
+        r_colptr.push(I::from_usize(r_indices.len()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sprs::{
+        CsMat,
+        CsMatOwned,
+        CsMatI,
+        Permutation,
+    };
+    use sprs::errors::SprsError;
+    use sprs::linalg::iterative::LinearOperator;
+    use sprs::linalg::ordering;
+    use sprs::prod;
+    use super::{QrNumeric, QrSymbolic};
+
+    fn test_mat1() -> CsMatOwned<f64> {
+        // | 1  0  2 |
+        // | 0  3  0 |
+        // | 4  0  0 |
+        // | 0  5  6 |
+        // | 0  0  7 |
+        CsMat::new_csc((5, 3),
+                       vec![0, 2, 4, 7],
+                       vec![0, 2, 1, 3, 0, 3, 4],
+                       vec![1., 4., 3., 5., 2., 6., 7.])
+    }
+
+    fn mat_vec(mat: &CsMatOwned<f64>, x: &[f64]) -> Vec<f64> {
+        let mut res = vec![0.; mat.rows()];
+        if mat.is_csc() {
+            prod::mul_acc_mat_vec_csc(mat.view(), x, &mut res);
+        } else {
+            prod::mul_acc_mat_vec_csr(mat.view(), x, &mut res);
+        }
+        res
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-10, "{:?} != {:?}", a, b);
+        }
+    }
+
+    /// Check that `Q_h^T P A Q = R`, column by column
+    fn check_factors(mat: &CsMatOwned<f64>, qr: &QrNumeric<f64>) {
+        let (m, n) = mat.shape();
+        let m2 = qr.padded_rows();
+        let a = mat.to_csc();
+        let r = qr.r().to_dense();
+        assert_eq!(qr.q().householder_vectors().nnz(),
+                   qr.symbolic.householder_nnz());
+        for j in 0..n {
+            let col = a.outer_view(qr.col_perm().at(j)).unwrap();
+            let mut x = vec![0.; m2];
+            for i in 0..m2 {
+                let row = qr.row_perm().at(i);
+                if row < m {
+                    x[i] = col.get(row).cloned().unwrap_or(0.);
+                }
+            }
+            qr.q().apply_transpose_in_place(&mut x);
+            for i in 0..m2 {
+                let r_ij = if i < n { r[[i, j]] } else { 0. };
+                if i > j {
+                    assert_eq!(r_ij, 0.);
+                }
+                assert!((x[i] - r_ij).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn qr_factors() {
+        let mat = test_mat1();
+        let qr = QrNumeric::new(&mat).unwrap();
+        assert_eq!(qr.shape(), (5, 3));
+        assert_eq!(qr.padded_rows(), 5);
+        check_factors(&mat, &qr);
+        // the diagonal of R is the norm of the eliminated columns
+        assert!((qr.r().get(0, 0).unwrap() - 17f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn qr_q_operator() {
+        let qr = QrNumeric::new(&test_mat1()).unwrap();
+        let q = qr.q();
+        assert_eq!(q.shape(), (5, 5));
+        let x = vec![1., -2., 0.5, 3., 4.];
+        let mut y = vec![0.; 5];
+        q.apply(&x, &mut y);
+        // Q_h is orthogonal
+        let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        assert!((norm(&x) - norm(&y)).abs() < 1e-12);
+        let mut z = vec![0.; 5];
+        q.apply_transpose(&y, &mut z);
+        assert_close(&z, &x);
+    }
+
+    #[test]
+    fn qr_solve_consistent() {
+        let mat = test_mat1();
+        let x0 = vec![1., -2., 0.5];
+        let b = mat_vec(&mat, &x0);
+        let qr = QrNumeric::new(&mat).unwrap();
+        assert_close(&qr.solve(&b), &x0);
+
+        // same system with a csr matrix
+        let qr = QrNumeric::new(&mat.to_csr()).unwrap();
+        assert_close(&qr.solve(&b), &x0);
+
+        // square systems are solved exactly
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 2, 3, 5],
+                                 vec![1, 2, 0, 0, 2],
+                                 vec![3., 1., 2., 4., 5.]);
+        let b = mat_vec(&mat, &x0);
+        let qr = QrNumeric::new(&mat).unwrap();
+        check_factors(&mat, &qr);
+        assert_close(&qr.solve(&b), &x0);
+    }
+
+    #[test]
+    fn qr_least_squares() {
+        let mat = test_mat1();
+        let b = vec![1., 2., 3., 4., 5.];
+        let qr = QrNumeric::new(&mat).unwrap();
+        let x = qr.solve(&b);
+        // the residual is orthogonal to the columns of A
+        let ax = mat_vec(&mat, &x);
+        let residual: Vec<_> = ax.iter().zip(&b).map(|(y, b)| y - b).collect();
+        let mut at_res = vec![0.; 3];
+        prod::mul_acc_mat_vec_csr(mat.transpose_view(), &residual,
+                                  &mut at_res);
+        assert_close(&at_res, &[0., 0., 0.]);
+    }
+
+    #[test]
+    fn qr_col_perm() {
+        // a sparse overdetermined system with fill-in
+        let indptr = vec![0, 3, 6, 8, 11, 14, 16];
+        let indices = vec![0, 3, 7, 1, 4, 8, 2, 5, 0, 6, 8, 1, 3, 7, 2, 6];
+        let data = vec![2., 1., 0.5, 3., -1., 1.5, 1., 2., 0.3, 4., -2., 0.8,
+                        1.2, 2.5, 0.7, 1.1];
+        let mat = CsMat::new_csc((9, 6), indptr, indices, data);
+        let ata = &mat.transpose_view() * &mat;
+        let perm = ordering::amd(&ata);
+        let x0 = vec![1., 2., -1., 0.5, 3., -2.];
+        let b = mat_vec(&mat, &x0);
+        let qr = QrNumeric::new_perm(&mat, perm).unwrap();
+        check_factors(&mat, &qr);
+        assert_close(&qr.solve(&b), &x0);
+
+        let perm = Permutation::new(vec![5, 3, 1, 0, 2, 4]);
+        let qr = QrNumeric::new_perm(&mat, perm).unwrap();
+        check_factors(&mat, &qr);
+        assert_close(&qr.solve(&b), &x0);
+
+        let qr = QrNumeric::new(&mat).unwrap();
+        check_factors(&mat, &qr);
+        assert_close(&qr.solve(&b), &x0);
+        assert!(qr.nnz() > 0);
+    }
+
+    #[test]
+    fn qr_rank_deficient() {
+        // structurally rank deficient: both columns only have row 0
+        // | 1 2 |
+        // | 0 0 |
+        // | 0 0 |
+        let mat = CsMat::new_csc((3, 2), vec![0, 1, 2], vec![0, 0],
+                                 vec![1., 2.]);
+        let symbolic = QrSymbolic::new(&mat);
+        assert_eq!(symbolic.padded_rows(), 4);
+        let res = symbolic.factor(&mat);
+        assert_eq!(res.err(), Some(SprsError::SingularMatrix));
+
+        // numerically rank deficient
+        // | 3 6 |
+        // | 4 8 |
+        // | 0 0 |
+        let mat = CsMat::new_csc((3, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+                                 vec![3., 4., 6., 8.]);
+        let res = QrNumeric::new(&mat);
+        assert_eq!(res.err(), Some(SprsError::SingularMatrix));
+    }
+
+    #[test]
+    fn qr_symbolic() {
+        let mat = test_mat1();
+        let symbolic = QrSymbolic::new(&mat);
+        assert_eq!(symbolic.shape(), (5, 3));
+        assert_eq!(symbolic.etree().get_parent(0), Some(2));
+        assert_eq!(symbolic.etree().get_parent(1), Some(2));
+        assert!(symbolic.etree().is_root(2));
+        let mut qr = symbolic.factor(&mat).unwrap();
+        assert_eq!(qr.symbolic.householder_nnz(), 7);
+
+        // same pattern, other values
+        let mat2 = CsMat::new_csc((5, 3),
+                                  vec![0, 2, 4, 7],
+                                  vec![0, 2, 1, 3, 0, 3, 4],
+                                  vec![2., -1., 1., 1., 3., 2., 1.]);
+        qr.update(&mat2).unwrap();
+        check_factors(&mat2, &qr);
+        let x0 = vec![1., 2., 3.];
+        let b = mat_vec(&mat2, &x0);
+        assert_close(&qr.solve(&b), &x0);
+    }
+
+    #[test]
+    fn qr_u32_indices() {
+        let mat = test_mat1();
+        let mat: CsMatI<f64, u32> = mat.to_other_index().unwrap();
+        let qr = QrNumeric::new(&mat).unwrap();
+        let x0 = vec![1., -2., 0.5];
+        let b = mat_vec(&test_mat1(), &x0);
+        assert_close(&qr.solve(&b), &x0);
+    }
+}
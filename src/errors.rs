@@ -2,12 +2,34 @@
 
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 #[derive(PartialEq, Debug)]
 pub enum SprsError {
     NonSortedIndices,
     UnsortedIndptr,
     SingularMatrix,
+    IoError(io::ErrorKind),
+    BadMatrixMarketHeader,
+    UnsupportedMatrixMarketFormat,
+    BadMatrixMarketEntry,
+    BadMatrixMarketEntryCount,
+    OutOfBoundsIndex,
+    IndexOverflow,
+    SolverBreakdown,
+    NotPositiveDefinite(usize),
+    BadStorageLength,
+    BadPermutation,
+    BadBinaryHeader,
+    UnsupportedBinaryVersion,
+    BinaryTypeMismatch,
+    EndiannessMismatch,
+    MisalignedData,
+    NonSquareMatrix,
+    NonSymmetricMatrix,
+    NonHermitianMatrix,
+    StructurallySingular(usize),
+    NumericallySingular(usize),
 }
 
 use self::SprsError::*;
@@ -18,6 +40,32 @@ impl SprsError {
             NonSortedIndices => "a vector's indices are not sorted",
             UnsortedIndptr => "indptr is not sorted",
             SingularMatrix => "matrix is singular",
+            IoError(_) => "an I/O error occurred",
+            BadMatrixMarketHeader => "malformed matrix market header",
+            UnsupportedMatrixMarketFormat => "unsupported matrix market format",
+            BadMatrixMarketEntry => "malformed matrix market entry",
+            BadMatrixMarketEntryCount =>
+                "matrix market entry count does not match its header",
+            OutOfBoundsIndex => "an index is out of bounds",
+            IndexOverflow =>
+                "an index does not fit in the requested index type",
+            SolverBreakdown => "breakdown of the iterative solver",
+            NotPositiveDefinite(_) => "matrix is not positive definite",
+            BadStorageLength => "storage arrays have inconsistent lengths",
+            BadPermutation => "invalid permutation",
+            BadBinaryHeader => "malformed binary matrix header",
+            UnsupportedBinaryVersion => "unsupported binary format version",
+            BinaryTypeMismatch =>
+                "binary matrix index or data type does not match",
+            EndiannessMismatch =>
+                "binary matrix was written with another byte order",
+            MisalignedData => "data is not aligned for its type",
+            NonSquareMatrix => "matrix is not square",
+            NonSymmetricMatrix => "matrix is not symmetric",
+            NonHermitianMatrix => "matrix is not hermitian",
+            StructurallySingular(_) =>
+                "matrix is structurally singular",
+            NumericallySingular(_) => "matrix is numerically singular",
         }
     }
 }
@@ -30,6 +78,23 @@ impl Error for SprsError {
 
 impl fmt::Display for SprsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.descr().fmt(f)
+        match *self {
+            StructurallySingular(col) | NumericallySingular(col) => {
+                write!(f, "{} (zero pivot in column {})", self.descr(), col)
+            }
+            NotPositiveDefinite(col) => {
+                write!(f,
+                       "{} (non-positive pivot in column {})",
+                       self.descr(),
+                       col)
+            }
+            _ => self.descr().fmt(f),
+        }
+    }
+}
+
+impl From<io::Error> for SprsError {
+    fn from(err: io::Error) -> SprsError {
+        IoError(err.kind())
     }
 }
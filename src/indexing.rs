@@ -0,0 +1,77 @@
+//! Abstraction over the integer types used to store sparse indices
+//!
+//! Sparse matrices and vectors store one index per non-zero element, so
+//! the width of the index type has a large impact on their memory usage.
+//! Storing indices as `u32` instead of `usize` halves the memory taken by the
+//! indices of a matrix on 64 bit platforms.
+
+use std::fmt::Debug;
+use num_traits::PrimInt;
+
+/// A sparse index type, which can be converted to and from `usize`.
+pub trait SpIndex: Debug + PrimInt + Default {
+    /// Convert this index into a usize.
+    fn index(self) -> usize;
+
+    /// Convert a usize into an index.
+    ///
+    /// # Panics
+    ///
+    /// If `ind` does not fit in this index type.
+    fn from_usize(ind: usize) -> Self;
+
+    /// Convert a usize into an index, returning None if `ind` does not fit
+    /// in this index type.
+    fn try_from_usize(ind: usize) -> Option<Self>;
+}
+
+macro_rules! sp_index_impl {
+    ($int: ident) => (
+        impl SpIndex for $int {
+            #[inline(always)]
+            fn index(self) -> usize {
+                self as usize
+            }
+
+            #[inline(always)]
+            fn from_usize(ind: usize) -> Self {
+                Self::try_from_usize(ind).expect("index type overflow")
+            }
+
+            #[inline(always)]
+            fn try_from_usize(ind: usize) -> Option<Self> {
+                if ind as u64 > $int::max_value() as u64 {
+                    None
+                } else {
+                    Some(ind as $int)
+                }
+            }
+        }
+    )
+}
+
+sp_index_impl!(usize);
+sp_index_impl!(u64);
+sp_index_impl!(u32);
+sp_index_impl!(u16);
+
+#[cfg(test)]
+mod test {
+    use super::SpIndex;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(u16::from_usize(65535), 65535u16);
+        assert_eq!(u16::try_from_usize(65536), None);
+        assert_eq!(u32::try_from_usize(65536), Some(65536u32));
+        assert_eq!(12u32.index(), 12usize);
+        assert_eq!(usize::try_from_usize(usize::max_value()),
+                   Some(usize::max_value()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn overflow_panics() {
+        u16::from_usize(70000);
+    }
+}
@@ -0,0 +1,1162 @@
+//! Reading and writing sparse matrices from and to files
+//!
+//! The Matrix Market exchange format is supported: coordinate and array
+//! files with real, integer or pattern values, stored in general, symmetric
+//! or skew-symmetric form, can be read into a `TripletMat`. Any `CsMat` can
+//! be written as a general coordinate file.
+//!
+//! A compact native binary format is also provided, for large matrices that
+//! should be loaded quickly or memory-mapped. See `write_binary` for a
+//! description of the format, and `binary_view` to obtain a `CsMatView`
+//! directly from a byte slice such as a memory map.
+//!
+//! # Example
+//!
+//! ```rust
+//! use sprs::io;
+//! use sprs::CsMatOwned;
+//! let mm = "%%MatrixMarket matrix coordinate real general
+//!           % a comment
+//!           2 3 2
+//!           1 1 2.5
+//!           2 3 -1";
+//! let mut reader = mm.as_bytes();
+//! let tri = io::read_matrix_market_from_bufread(&mut reader).unwrap();
+//! let mat: CsMatOwned<f64> = tri.to_csr();
+//! assert_eq!(mat.get(1, 2), Some(&-1.));
+//! ```
+
+use std::cmp;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::ops::Deref;
+use std::path::Path;
+use std::slice;
+use std::str::FromStr;
+
+use num_traits::Num;
+
+use errors::SprsError;
+use indexing::SpIndex;
+use sparse::{CsMatBase, CsMatI, CsMatViewI};
+use sparse::CompressedStorage::{self, CSC, CSR};
+use sparse::csmat::checked_from_parts;
+use sparse::triplet::TripletMatI;
+
+/// Types that can be read from and written to a Matrix Market file
+pub trait MatrixMarketData: Num + Copy + FromStr + Display {
+    /// The Matrix Market field describing this type, "real" or "integer"
+    fn field() -> &'static str;
+}
+
+macro_rules! mm_data_impl {
+    ($scalar: ty, $field: expr) => (
+        impl MatrixMarketData for $scalar {
+            fn field() -> &'static str {
+                $field
+            }
+        }
+    )
+}
+
+mm_data_impl!(i8, "integer");
+mm_data_impl!(i16, "integer");
+mm_data_impl!(i32, "integer");
+mm_data_impl!(i64, "integer");
+mm_data_impl!(isize, "integer");
+mm_data_impl!(u8, "integer");
+mm_data_impl!(u16, "integer");
+mm_data_impl!(u32, "integer");
+mm_data_impl!(u64, "integer");
+mm_data_impl!(usize, "integer");
+mm_data_impl!(f32, "real");
+mm_data_impl!(f64, "real");
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    Coordinate,
+    Array,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Field {
+    Real,
+    Integer,
+    Pattern,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+}
+
+fn parse_header(line: &str) -> Result<(Format, Field, Symmetry), SprsError> {
+    let line = line.to_lowercase();
+    let tokens: Vec<_> = line.split_whitespace().collect();
+    if tokens.len() != 5 || tokens[0] != "%%matrixmarket"
+       || tokens[1] != "matrix" {
+        return Err(SprsError::BadMatrixMarketHeader);
+    }
+    let format = match tokens[2] {
+        "coordinate" => Format::Coordinate,
+        "array" => Format::Array,
+        _ => return Err(SprsError::BadMatrixMarketHeader),
+    };
+    let field = match tokens[3] {
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        "pattern" => Field::Pattern,
+        "complex" => return Err(SprsError::UnsupportedMatrixMarketFormat),
+        _ => return Err(SprsError::BadMatrixMarketHeader),
+    };
+    let symmetry = match tokens[4] {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        "hermitian" => return Err(SprsError::UnsupportedMatrixMarketFormat),
+        _ => return Err(SprsError::BadMatrixMarketHeader),
+    };
+    if format == Format::Array && field == Field::Pattern {
+        return Err(SprsError::BadMatrixMarketHeader);
+    }
+    Ok((format, field, symmetry))
+}
+
+fn parse_token<T: FromStr>(token: Option<&str>,
+                           err: SprsError)
+                           -> Result<T, SprsError> {
+    token.and_then(|tok| tok.parse().ok()).ok_or(err)
+}
+
+/// Parse a one-based index, checking it is within `dim`
+fn parse_index(token: Option<&str>, dim: usize) -> Result<usize, SprsError> {
+    let ind: usize = try!(parse_token(token, SprsError::BadMatrixMarketEntry));
+    if ind == 0 || ind > dim {
+        return Err(SprsError::OutOfBoundsIndex);
+    }
+    Ok(ind - 1)
+}
+
+fn add_entry<N, I>(mat: &mut TripletMatI<N, I>,
+                   symmetry: Symmetry,
+                   row: usize,
+                   col: usize,
+                   val: N)
+where N: Num + Copy,
+      I: SpIndex
+{
+    mat.add_triplet(row, col, val);
+    if row == col {
+        return;
+    }
+    match symmetry {
+        Symmetry::General => (),
+        Symmetry::Symmetric => mat.add_triplet(col, row, val),
+        Symmetry::SkewSymmetric => mat.add_triplet(col, row, N::zero() - val),
+    }
+}
+
+/// Read a sparse matrix from a Matrix Market file.
+///
+/// Symmetric and skew-symmetric matrices are expanded, so the returned
+/// triplet matrix holds both triangular parts. Entries of array files
+/// which are zero are not stored.
+///
+/// # Errors
+///
+/// - `IoError` if the file cannot be read
+/// - `BadMatrixMarketHeader` if the header or the size line is malformed
+/// - `UnsupportedMatrixMarketFormat` for complex or hermitian matrices
+/// - `BadMatrixMarketEntry` if an entry cannot be parsed, or lies on the
+///   diagonal of a skew-symmetric matrix
+/// - `BadMatrixMarketEntryCount` if the number of entries does not match
+///   the size line
+/// - `OutOfBoundsIndex` if an entry lies outside the matrix
+/// - `IndexOverflow` if the dimensions of the matrix cannot be represented
+///   by the requested index type
+pub fn read_matrix_market<N, I, P>(mm_file: P)
+                                   -> Result<TripletMatI<N, I>, SprsError>
+where N: MatrixMarketData,
+      I: SpIndex,
+      P: AsRef<Path>
+{
+    let f = try!(File::open(mm_file));
+    let mut reader = BufReader::new(f);
+    read_matrix_market_from_bufread(&mut reader)
+}
+
+/// Read a sparse matrix in the Matrix Market format from a buffered reader.
+///
+/// See `read_matrix_market` for details.
+pub fn read_matrix_market_from_bufread<N, I, R>(reader: &mut R)
+                                                -> Result<TripletMatI<N, I>,
+                                                          SprsError>
+where N: MatrixMarketData,
+      I: SpIndex,
+      R: BufRead
+{
+    let mut lines = reader.lines();
+    let header = match lines.next() {
+        Some(line) => try!(line),
+        None => return Err(SprsError::BadMatrixMarketHeader),
+    };
+    let (format, field, symmetry) = try!(parse_header(&header));
+
+    // skip the comments to get the size line, then read the data lines
+    let mut size_line = None;
+    let mut data_lines = Vec::new();
+    for line in lines {
+        let line = try!(line);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        if size_line.is_none() {
+            size_line = Some(line.to_string());
+        } else {
+            data_lines.push(line.to_string());
+        }
+    }
+    let size_line = try!(size_line.ok_or(SprsError::BadMatrixMarketHeader));
+    let mut size_tokens = size_line.split_whitespace();
+    let rows: usize = try!(parse_token(size_tokens.next(),
+                                       SprsError::BadMatrixMarketHeader));
+    let cols: usize = try!(parse_token(size_tokens.next(),
+                                       SprsError::BadMatrixMarketHeader));
+    if symmetry != Symmetry::General && rows != cols {
+        return Err(SprsError::BadMatrixMarketHeader);
+    }
+    if I::try_from_usize(rows).is_none() || I::try_from_usize(cols).is_none() {
+        return Err(SprsError::IndexOverflow);
+    }
+
+    match format {
+        Format::Coordinate => {
+            let nnz: usize = try!(parse_token(
+                size_tokens.next(), SprsError::BadMatrixMarketHeader));
+            if size_tokens.next().is_some() {
+                return Err(SprsError::BadMatrixMarketHeader);
+            }
+            if data_lines.len() != nnz {
+                return Err(SprsError::BadMatrixMarketEntryCount);
+            }
+            let cap = match symmetry {
+                Symmetry::General => nnz,
+                _ => 2 * nnz,
+            };
+            let mut mat = TripletMatI::with_capacity((rows, cols), cap);
+            for line in &data_lines {
+                let mut tokens = line.split_whitespace();
+                let row = try!(parse_index(tokens.next(), rows));
+                let col = try!(parse_index(tokens.next(), cols));
+                // the diagonal of a skew-symmetric matrix is zero
+                if symmetry == Symmetry::SkewSymmetric && row == col {
+                    return Err(SprsError::BadMatrixMarketEntry);
+                }
+                let val = match field {
+                    Field::Pattern => N::one(),
+                    Field::Real | Field::Integer => {
+                        try!(parse_token(tokens.next(),
+                                         SprsError::BadMatrixMarketEntry))
+                    }
+                };
+                if tokens.next().is_some() {
+                    return Err(SprsError::BadMatrixMarketEntry);
+                }
+                add_entry(&mut mat, symmetry, row, col, val);
+            }
+            Ok(mat)
+        }
+        Format::Array => {
+            if size_tokens.next().is_some() {
+                return Err(SprsError::BadMatrixMarketHeader);
+            }
+            // array files are stored in column major order, and only
+            // list the lower triangular part of symmetric matrices
+            let mut locations = Vec::new();
+            for col in 0..cols {
+                let start = match symmetry {
+                    Symmetry::General => 0,
+                    Symmetry::Symmetric => col,
+                    Symmetry::SkewSymmetric => col + 1,
+                };
+                for row in start..rows {
+                    locations.push((row, col));
+                }
+            }
+            if data_lines.len() != locations.len() {
+                return Err(SprsError::BadMatrixMarketEntryCount);
+            }
+            let mut mat = TripletMatI::new((rows, cols));
+            for (line, &(row, col)) in data_lines.iter().zip(&locations) {
+                let mut tokens = line.split_whitespace();
+                let val: N = try!(parse_token(tokens.next(),
+                                              SprsError::BadMatrixMarketEntry));
+                if tokens.next().is_some() {
+                    return Err(SprsError::BadMatrixMarketEntry);
+                }
+                if val != N::zero() {
+                    add_entry(&mut mat, symmetry, row, col, val);
+                }
+            }
+            Ok(mat)
+        }
+    }
+}
+
+/// Write a sparse matrix into a Matrix Market file.
+///
+/// The matrix is written as a general coordinate matrix, with a "real" or
+/// "integer" field depending on its scalar type.
+pub fn write_matrix_market<N, I, IpS, IS, DS, P>(
+    mm_file: P,
+    mat: &CsMatBase<N, I, IpS, IS, DS>)
+    -> Result<(), SprsError>
+where N: MatrixMarketData,
+      I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+      P: AsRef<Path>
+{
+    let f = try!(File::create(mm_file));
+    let mut writer = BufWriter::new(f);
+    write_matrix_market_to_writer(&mut writer, mat)
+}
+
+/// Write a sparse matrix in the Matrix Market format into a writer.
+///
+/// See `write_matrix_market` for details.
+pub fn write_matrix_market_to_writer<N, I, IpS, IS, DS, W>(
+    writer: &mut W,
+    mat: &CsMatBase<N, I, IpS, IS, DS>)
+    -> Result<(), SprsError>
+where N: MatrixMarketData,
+      I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+      W: Write
+{
+    try!(writeln!(writer,
+                  "%%MatrixMarket matrix coordinate {} general",
+                  N::field()));
+    try!(writeln!(writer, "% written by sprs"));
+    try!(writeln!(writer, "{} {} {}", mat.rows(), mat.cols(), mat.nnz()));
+    for (outer_ind, vec) in mat.outer_iterator().enumerate() {
+        for (inner_ind, val) in vec.iter() {
+            let (row, col) = if mat.is_csr() {
+                (outer_ind, inner_ind)
+            } else {
+                (inner_ind, outer_ind)
+            };
+            try!(writeln!(writer, "{} {} {}", row + 1, col + 1, val));
+        }
+    }
+    try!(writer.flush());
+    Ok(())
+}
+
+/// Scalar types that can be stored in the native binary format
+///
+/// This trait is unsafe to implement because the binary reader reinterprets
+/// raw bytes as values of this type: any bit pattern of the right size must
+/// be a valid value.
+pub unsafe trait BinaryData: Copy + Default {
+    /// A code identifying the memory layout of this type in a binary file
+    fn type_code() -> u8;
+}
+
+macro_rules! binary_data_impl {
+    ($scalar: ty, $code: expr) => (
+        unsafe impl BinaryData for $scalar {
+            fn type_code() -> u8 {
+                $code
+            }
+        }
+    )
+}
+
+binary_data_impl!(i8, 1);
+binary_data_impl!(i16, 2);
+binary_data_impl!(i32, 3);
+binary_data_impl!(i64, 4);
+binary_data_impl!(u8, 5);
+binary_data_impl!(u16, 6);
+binary_data_impl!(u32, 7);
+binary_data_impl!(u64, 8);
+binary_data_impl!(f32, 9);
+binary_data_impl!(f64, 10);
+// pointer sized integers share the code of the fixed size integer with the
+// same layout, so that files stay readable across index types
+binary_data_impl!(isize, match mem::size_of::<isize>() {
+    2 => i16::type_code(),
+    4 => i32::type_code(),
+    _ => i64::type_code(),
+});
+binary_data_impl!(usize, match mem::size_of::<usize>() {
+    2 => u16::type_code(),
+    4 => u32::type_code(),
+    _ => u64::type_code(),
+});
+
+const BINARY_MAGIC: &'static [u8; 8] = b"SPRSMAT\0";
+const BINARY_VERSION: u32 = 1;
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+const BINARY_HEADER_LEN: usize = 64;
+const BINARY_ALIGN: usize = 64;
+// number of values read at once, bounding the memory allocated ahead of the
+// data actually present in a stream
+const BINARY_READ_CHUNK: usize = 4096;
+
+/// Position of the arrays of a matrix stored in the binary format
+struct BinaryLayout {
+    storage: CompressedStorage,
+    shape: (usize, usize),
+    outer: usize,
+    nnz: usize,
+    indices_offset: usize,
+    data_offset: usize,
+    len: usize,
+}
+
+fn align_binary_offset(offset: usize) -> Option<usize> {
+    offset.checked_add(BINARY_ALIGN - 1)
+          .map(|x| x / BINARY_ALIGN * BINARY_ALIGN)
+}
+
+impl BinaryLayout {
+    fn new<N, I>(storage: CompressedStorage,
+                 shape: (usize, usize),
+                 nnz: usize)
+                 -> Result<BinaryLayout, SprsError> {
+        let outer = match storage {
+            CSR => shape.0,
+            CSC => shape.1,
+        };
+        let index_size = mem::size_of::<I>();
+        let indices_offset = outer.checked_add(1)
+                                  .and_then(|n| n.checked_mul(index_size))
+                                  .and_then(|n| {
+                                      n.checked_add(BINARY_HEADER_LEN)
+                                  })
+                                  .and_then(align_binary_offset);
+        let indices_offset =
+            try!(indices_offset.ok_or(SprsError::IndexOverflow));
+        let data_offset = nnz.checked_mul(index_size)
+                             .and_then(|n| n.checked_add(indices_offset))
+                             .and_then(align_binary_offset);
+        let data_offset = try!(data_offset.ok_or(SprsError::IndexOverflow));
+        let len = nnz.checked_mul(mem::size_of::<N>())
+                     .and_then(|n| n.checked_add(data_offset));
+        let len = try!(len.ok_or(SprsError::IndexOverflow));
+        Ok(BinaryLayout {
+            storage: storage,
+            shape: shape,
+            outer: outer,
+            nnz: nnz,
+            indices_offset: indices_offset,
+            data_offset: data_offset,
+            len: len,
+        })
+    }
+
+    fn indptr_range(&self) -> (usize, usize) {
+        (BINARY_HEADER_LEN, self.outer + 1)
+    }
+
+    fn indices_range(&self) -> (usize, usize) {
+        (self.indices_offset, self.nnz)
+    }
+
+    fn data_range(&self) -> (usize, usize) {
+        (self.data_offset, self.nnz)
+    }
+}
+
+fn binary_header<N, I>(layout: &BinaryLayout) -> [u8; BINARY_HEADER_LEN]
+where N: BinaryData,
+      I: BinaryData
+{
+    let mut header = [0; BINARY_HEADER_LEN];
+    header[0..8].copy_from_slice(BINARY_MAGIC);
+    header[8..12].copy_from_slice(&BYTE_ORDER_MARK.to_ne_bytes());
+    header[12..16].copy_from_slice(&BINARY_VERSION.to_ne_bytes());
+    header[16] = match layout.storage {
+        CSR => 0,
+        CSC => 1,
+    };
+    header[17] = I::type_code();
+    header[18] = N::type_code();
+    header[24..32].copy_from_slice(&(layout.shape.0 as u64).to_ne_bytes());
+    header[32..40].copy_from_slice(&(layout.shape.1 as u64).to_ne_bytes());
+    header[40..48].copy_from_slice(&(layout.nnz as u64).to_ne_bytes());
+    header
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[..4]);
+    u32::from_ne_bytes(buf)
+}
+
+fn read_u64_as_usize(bytes: &[u8]) -> Result<usize, SprsError> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[..8]);
+    let val = u64::from_ne_bytes(buf);
+    if val > usize::max_value() as u64 {
+        return Err(SprsError::IndexOverflow);
+    }
+    Ok(val as usize)
+}
+
+fn parse_binary_header<N, I>(header: &[u8]) -> Result<BinaryLayout, SprsError>
+where N: BinaryData,
+      I: BinaryData
+{
+    if header.len() < BINARY_HEADER_LEN || &header[0..8] != BINARY_MAGIC {
+        return Err(SprsError::BadBinaryHeader);
+    }
+    let bom = read_u32(&header[8..12]);
+    if bom == BYTE_ORDER_MARK.swap_bytes() {
+        return Err(SprsError::EndiannessMismatch);
+    }
+    if bom != BYTE_ORDER_MARK {
+        return Err(SprsError::BadBinaryHeader);
+    }
+    if read_u32(&header[12..16]) != BINARY_VERSION {
+        return Err(SprsError::UnsupportedBinaryVersion);
+    }
+    let storage = match header[16] {
+        0 => CSR,
+        1 => CSC,
+        _ => return Err(SprsError::BadBinaryHeader),
+    };
+    if header[17] != I::type_code() || header[18] != N::type_code() {
+        return Err(SprsError::BinaryTypeMismatch);
+    }
+    let nrows = try!(read_u64_as_usize(&header[24..32]));
+    let ncols = try!(read_u64_as_usize(&header[32..40]));
+    let nnz = try!(read_u64_as_usize(&header[40..48]));
+    BinaryLayout::new::<N, I>(storage, (nrows, ncols), nnz)
+}
+
+fn as_bytes<T: BinaryData>(vals: &[T]) -> &[u8] {
+    let len = vals.len() * mem::size_of::<T>();
+    unsafe { slice::from_raw_parts(vals.as_ptr() as *const u8, len) }
+}
+
+fn as_bytes_mut<T: BinaryData>(vals: &mut [T]) -> &mut [u8] {
+    let len = vals.len() * mem::size_of::<T>();
+    unsafe { slice::from_raw_parts_mut(vals.as_mut_ptr() as *mut u8, len) }
+}
+
+/// Reinterpret len values of type T starting at the given offset of bytes.
+/// Bounds must have been checked by the caller.
+fn cast_bytes<T: BinaryData>(bytes: &[u8],
+                             (offset, len): (usize, usize))
+                             -> Result<&[T], SprsError> {
+    let bytes = &bytes[offset..offset + len * mem::size_of::<T>()];
+    if bytes.as_ptr() as usize % mem::align_of::<T>() != 0 {
+        return Err(SprsError::MisalignedData);
+    }
+    Ok(unsafe { slice::from_raw_parts(bytes.as_ptr() as *const T, len) })
+}
+
+/// Read len values of type T. The buffer grows by bounded chunks, so that
+/// a corrupted header cannot cause a huge allocation on a short stream.
+fn read_binary_array<T, R>(reader: &mut R, len: usize)
+                           -> Result<Vec<T>, SprsError>
+where T: BinaryData,
+      R: Read
+{
+    let mut vals = Vec::new();
+    while vals.len() < len {
+        let start = vals.len();
+        let chunk = cmp::min(len - start, BINARY_READ_CHUNK);
+        vals.resize(start + chunk, T::default());
+        try!(reader.read_exact(as_bytes_mut(&mut vals[start..])));
+    }
+    Ok(vals)
+}
+
+/// Write a sparse matrix into a file in the native binary format.
+///
+/// The format starts with a 64 bytes header, whose fields are written in the
+/// native byte order:
+///
+/// - the magic bytes `SPRSMAT\0`,
+/// - the byte order mark `0x01020304` as a `u32`,
+/// - the format version as a `u32`, currently 1,
+/// - the storage as a byte, 0 for CSR and 1 for CSC,
+/// - the type codes of the index and of the data type, one byte each,
+/// - 5 reserved bytes,
+/// - the number of rows, of columns and of non-zeros as `u64`s,
+/// - 16 reserved bytes.
+///
+/// The indptr, indices and data arrays follow, in that order, each starting
+/// at an offset multiple of 64 bytes and padded with zeros. A file can thus
+/// be memory-mapped and viewed without copying using `binary_view`.
+pub fn write_binary<N, I, IpS, IS, DS, P>(
+    path: P,
+    mat: &CsMatBase<N, I, IpS, IS, DS>)
+    -> Result<(), SprsError>
+where N: BinaryData,
+      I: SpIndex + BinaryData,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+      P: AsRef<Path>
+{
+    let f = try!(File::create(path));
+    let mut writer = BufWriter::new(f);
+    write_binary_to_writer(&mut writer, mat)
+}
+
+/// Write a sparse matrix in the native binary format into a writer.
+///
+/// See `write_binary` for details.
+pub fn write_binary_to_writer<N, I, IpS, IS, DS, W>(
+    writer: &mut W,
+    mat: &CsMatBase<N, I, IpS, IS, DS>)
+    -> Result<(), SprsError>
+where N: BinaryData,
+      I: SpIndex + BinaryData,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+      W: Write
+{
+    // views obtained by outer slicing share the arrays of their parent
+    // matrix, only their own range is written, with a rebased indptr
+    let indptr = mat.indptr();
+    let start = indptr[0].index();
+    let end = indptr[indptr.len() - 1].index();
+    let rebased: Vec<I>;
+    let indptr = if start == 0 {
+        indptr
+    } else {
+        rebased = indptr.iter()
+                        .map(|&i| I::from_usize(i.index() - start))
+                        .collect();
+        &rebased[..]
+    };
+    let indices = &mat.indices()[start..end];
+    let data = &mat.data()[start..end];
+    let layout = try!(BinaryLayout::new::<N, I>(mat.storage(),
+                                                (mat.rows(), mat.cols()),
+                                                end - start));
+    let padding = [0; BINARY_ALIGN];
+    try!(writer.write_all(&binary_header::<N, I>(&layout)));
+    try!(writer.write_all(as_bytes(indptr)));
+    let written = BINARY_HEADER_LEN + as_bytes(indptr).len();
+    try!(writer.write_all(&padding[..layout.indices_offset - written]));
+    try!(writer.write_all(as_bytes(indices)));
+    let written = layout.indices_offset + as_bytes(indices).len();
+    try!(writer.write_all(&padding[..layout.data_offset - written]));
+    try!(writer.write_all(as_bytes(data)));
+    try!(writer.flush());
+    Ok(())
+}
+
+/// Read a sparse matrix from a file in the native binary format.
+///
+/// The file is copied into an owned matrix, use `binary_view` on a memory
+/// map of the file to avoid the copy.
+///
+/// # Errors
+///
+/// - `BadBinaryHeader` if the file does not start with a valid header
+/// - `EndiannessMismatch` if the file was written with another byte order
+/// - `UnsupportedBinaryVersion` if the file was written in a later version
+///   of the format
+/// - `BinaryTypeMismatch` if the index or data type differ from `I` and `N`
+/// - `IoError(UnexpectedEof)` if the file is truncated
+/// - any error of `CsMat::new_view` if the stored structure is invalid
+pub fn read_binary<N, I, P>(path: P) -> Result<CsMatI<N, I>, SprsError>
+where N: BinaryData,
+      I: SpIndex + BinaryData,
+      P: AsRef<Path>
+{
+    let f = try!(File::open(path));
+    let mut reader = BufReader::new(f);
+    read_binary_from_reader(&mut reader)
+}
+
+/// Read a sparse matrix in the native binary format from a reader.
+///
+/// See `read_binary` for details.
+pub fn read_binary_from_reader<N, I, R>(reader: &mut R)
+                                        -> Result<CsMatI<N, I>, SprsError>
+where N: BinaryData,
+      I: SpIndex + BinaryData,
+      R: Read
+{
+    let mut header = [0; BINARY_HEADER_LEN];
+    try!(reader.read_exact(&mut header));
+    let layout = try!(parse_binary_header::<N, I>(&header));
+    let mut padding = [0; BINARY_ALIGN];
+
+    let (_, indptr_len) = layout.indptr_range();
+    let indptr = try!(read_binary_array::<I, R>(reader, indptr_len));
+    let read = BINARY_HEADER_LEN + as_bytes(&indptr).len();
+    try!(reader.read_exact(&mut padding[..layout.indices_offset - read]));
+    let indices = try!(read_binary_array::<I, R>(reader, layout.nnz));
+    let read = layout.indices_offset + as_bytes(&indices).len();
+    try!(reader.read_exact(&mut padding[..layout.data_offset - read]));
+    let data = try!(read_binary_array::<N, R>(reader, layout.nnz));
+
+    checked_from_parts(layout.storage, layout.shape, indptr, indices, data)
+}
+
+/// View a sparse matrix stored in the native binary format, without copying.
+///
+/// The bytes would typically come from a memory-mapped file, which enables
+/// working on matrices larger than the available memory. The arrays are
+/// reinterpreted in place and their structure is checked as in
+/// `CsMat::new_view`.
+///
+/// # Errors
+///
+/// - `MisalignedData` if an array does not satisfy the alignment of its
+///   type. This cannot happen when `bytes` starts at an address multiple
+///   of 64, as is the case for a memory map of a whole file.
+/// - `IoError(UnexpectedEof)` if `bytes` is too short
+/// - the errors of `read_binary` otherwise
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate sprs;
+/// extern crate memmap;
+/// # fn main() {
+/// use std::fs::File;
+/// use sprs::io;
+/// use sprs::{CsMat, CsMatView};
+/// let mat = CsMat::new((2, 3), vec![0, 1, 2], vec![2, 0], vec![1., 2.]);
+/// let path = std::env::temp_dir().join("sprs_binary_view_doc.bin");
+/// io::write_binary(&path, &mat).unwrap();
+/// let file = File::open(&path).unwrap();
+/// let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
+/// let view: CsMatView<f64> = io::binary_view(&mmap).unwrap();
+/// assert_eq!(view, mat.view());
+/// # }
+/// ```
+pub fn binary_view<N, I>(bytes: &[u8]) -> Result<CsMatViewI<N, I>, SprsError>
+where N: BinaryData,
+      I: SpIndex + BinaryData
+{
+    let layout = try!(parse_binary_header::<N, I>(bytes));
+    if bytes.len() < layout.len {
+        let kind = io::ErrorKind::UnexpectedEof;
+        return Err(SprsError::IoError(kind));
+    }
+    let indptr = try!(cast_bytes::<I>(bytes, layout.indptr_range()));
+    let indices = try!(cast_bytes::<I>(bytes, layout.indices_range()));
+    let data = try!(cast_bytes::<N>(bytes, layout.data_range()));
+    CsMatViewI::new_view(layout.storage, layout.shape, indptr, indices, data)
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::ErrorKind;
+    use memmap::Mmap;
+    use sparse::{CsMatI, CsMatOwned};
+    use sparse::triplet::{TripletMat, TripletMatI};
+    use errors::SprsError;
+    use indexing::SpIndex;
+    use test_data::{mat1, mat1_csc};
+    use super::{read_matrix_market, read_matrix_market_from_bufread,
+                write_matrix_market_to_writer, write_binary,
+                write_binary_to_writer, read_binary, read_binary_from_reader,
+                binary_view};
+
+    fn read_str<N, I>(mm: &str) -> Result<TripletMatI<N, I>, SprsError>
+    where N: super::MatrixMarketData,
+          I: SpIndex
+    {
+        let mut reader = mm.as_bytes();
+        read_matrix_market_from_bufread(&mut reader)
+    }
+
+    #[test]
+    fn read_coordinate_general() {
+        let mm = "%%MatrixMarket matrix coordinate real general\n\
+                  % a comment\n\
+                  %\n\
+                  5 5 7\n\
+                  1 3 3.\n\
+                  1 4 4.\n\
+                  2 4 2.\n\
+                  2 5 5.\n\
+                  3 3 5.\n\
+                  4 2 8.\n\
+                  5 4 7.\n";
+        let mat: TripletMat<f64> = read_str(mm).unwrap();
+        assert_eq!(mat.to_csr(), mat1());
+    }
+
+    #[test]
+    fn read_coordinate_file() {
+        let mat: TripletMat<f64> =
+            read_matrix_market("data/matrix_market/simple.mtx").unwrap();
+        assert_eq!(mat.to_csc(), mat1_csc());
+    }
+
+    #[test]
+    fn read_coordinate_symmetric_integer() {
+        // |1 2  |
+        // |2   3|
+        // |  3 4|
+        let mm = "%%MatrixMarket matrix coordinate integer symmetric\n\
+                  3 3 4\n\
+                  1 1 1\n\
+                  2 1 2\n\
+                  3 2 3\n\
+                  3 3 4\n";
+        let mat: TripletMat<i32> = read_str(mm).unwrap();
+        let expected = CsMatOwned::new((3, 3),
+                                       vec![0, 2, 4, 6],
+                                       vec![0, 1, 0, 2, 1, 2],
+                                       vec![1, 2, 2, 3, 3, 4]);
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn read_coordinate_skew_symmetric() {
+        // | 0 -2|
+        // | 2  0|
+        let mm = "%%MatrixMarket matrix coordinate real skew-symmetric\n\
+                  2 2 1\n\
+                  2 1 2.\n";
+        let mat: TripletMat<f64> = read_str(mm).unwrap();
+        let expected = CsMatOwned::new((2, 2),
+                                       vec![0, 1, 2],
+                                       vec![1, 0],
+                                       vec![-2., 2.]);
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn read_coordinate_pattern() {
+        let mm = "%%MatrixMarket matrix coordinate pattern general\n\
+                  2 3 2\n\
+                  1 3\n\
+                  2 1\n";
+        let mat: TripletMat<f64> = read_str(mm).unwrap();
+        let expected = CsMatOwned::new((2, 3),
+                                       vec![0, 1, 2],
+                                       vec![2, 0],
+                                       vec![1., 1.]);
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn read_array_general() {
+        // |1 0 3|
+        // |0 2 0|
+        let mm = "%%MatrixMarket matrix array real general\n\
+                  2 3\n\
+                  1.\n0.\n0.\n2.\n3.\n0.\n";
+        let mat: TripletMat<f64> = read_str(mm).unwrap();
+        let expected = CsMatOwned::new((2, 3),
+                                       vec![0, 2, 3],
+                                       vec![0, 2, 1],
+                                       vec![1., 3., 2.]);
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn read_array_symmetric() {
+        // |1 2|
+        // |2 0|
+        let mm = "%%MatrixMarket matrix array integer symmetric\n\
+                  2 2\n\
+                  1\n2\n0\n";
+        let mat: TripletMat<i64> = read_str(mm).unwrap();
+        let expected = CsMatOwned::new((2, 2),
+                                       vec![0, 2, 3],
+                                       vec![0, 1, 0],
+                                       vec![1, 2, 2]);
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn read_errors() {
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix coordinate real\n1 1 0\n");
+        assert_eq!(res.err(), Some(SprsError::BadMatrixMarketHeader));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix coordinate complex general\n\
+                      1 1 1\n1 1 1. 2.\n");
+        assert_eq!(res.err(), Some(SprsError::UnsupportedMatrixMarketFormat));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix coordinate real general\n\
+                      2 2 2\n1 1 1.\n");
+        assert_eq!(res.err(), Some(SprsError::BadMatrixMarketEntryCount));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix coordinate real general\n\
+                      2 2 1\n3 1 1.\n");
+        assert_eq!(res.err(), Some(SprsError::OutOfBoundsIndex));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix coordinate real general\n\
+                      2 2 1\n0 1 1.\n");
+        assert_eq!(res.err(), Some(SprsError::OutOfBoundsIndex));
+
+        let res: Result<TripletMat<i32>, _> =
+            read_str("%%MatrixMarket matrix coordinate real general\n\
+                      2 2 1\n1 1 1.5\n");
+        assert_eq!(res.err(), Some(SprsError::BadMatrixMarketEntry));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix coordinate real skew-symmetric\n\
+                      2 2 1\n2 2 1.\n");
+        assert_eq!(res.err(), Some(SprsError::BadMatrixMarketEntry));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_str("%%MatrixMarket matrix array real symmetric\n\
+                      2 3\n1.\n");
+        assert_eq!(res.err(), Some(SprsError::BadMatrixMarketHeader));
+
+        let res: Result<TripletMat<f64>, _> =
+            read_matrix_market("data/matrix_market/does_not_exist.mtx");
+        assert!(match res {
+            Err(SprsError::IoError(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn write_read_roundtrip() {
+        let a = mat1();
+        let mut buf = Vec::new();
+        write_matrix_market_to_writer(&mut buf, &a).unwrap();
+        let tri: TripletMat<f64> = read_str(&String::from_utf8(buf).unwrap())
+                                       .unwrap();
+        assert_eq!(tri.to_csr(), a);
+
+        let a = mat1_csc();
+        let mut buf = Vec::new();
+        write_matrix_market_to_writer(&mut buf, &a).unwrap();
+        let written = String::from_utf8(buf).unwrap();
+        assert!(written.starts_with(
+            "%%MatrixMarket matrix coordinate real general\n"));
+        let tri: TripletMat<f64> = read_str(&written).unwrap();
+        assert_eq!(tri.to_csc(), a);
+
+        let a: CsMatOwned<i32> = CsMatOwned::eye(3);
+        let mut buf = Vec::new();
+        write_matrix_market_to_writer(&mut buf, &a).unwrap();
+        let written = String::from_utf8(buf).unwrap();
+        assert!(written.starts_with(
+            "%%MatrixMarket matrix coordinate integer general\n"));
+        let tri: TripletMat<i32> = read_str(&written).unwrap();
+        assert_eq!(tri.to_csr(), a);
+    }
+
+    #[test]
+    fn read_smaller_index_type() {
+        let mm = "%%MatrixMarket matrix coordinate real general\n\
+                  2 3 2\n\
+                  1 3 1.5\n\
+                  2 1 -2\n";
+        let mat: TripletMatI<f64, u16> = read_str(mm).unwrap();
+        assert_eq!(mat.to_csr().to_other_index(),
+                   Ok(CsMatOwned::new((2, 3),
+                                      vec![0, 1, 2],
+                                      vec![2, 0],
+                                      vec![1.5, -2.])));
+
+        let mm = "%%MatrixMarket matrix coordinate real general\n\
+                  70000 2 1\n\
+                  1 1 1.5\n";
+        let res: Result<TripletMatI<f64, u16>, _> = read_str(mm);
+        assert_eq!(res.err(), Some(SprsError::IndexOverflow));
+    }
+
+    fn binary_bytes(mat: &CsMatOwned<f64>) -> Vec<u64> {
+        // storing the bytes in a Vec<u64> guarantees their alignment
+        let mut buf = Vec::new();
+        write_binary_to_writer(&mut buf, mat).unwrap();
+        assert_eq!(buf.len() % 8, 0);
+        buf.chunks(8)
+           .map(|c| {
+               let mut word = [0; 8];
+               word.copy_from_slice(c);
+               u64::from_ne_bytes(word)
+           })
+           .collect()
+    }
+
+    fn as_bytes(words: &[u64]) -> &[u8] {
+        super::as_bytes(words)
+    }
+
+    fn as_bytes_mut(words: &mut [u64]) -> &mut [u8] {
+        super::as_bytes_mut(words)
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let mat = mat1();
+        let mut buf = Vec::new();
+        write_binary_to_writer(&mut buf, &mat).unwrap();
+        // header, then indptr, indices and data each padded to 64 bytes
+        assert_eq!(buf.len(), 64 + 64 + 64 + 7 * 8);
+        let mut reader = &buf[..];
+        let res: CsMatOwned<f64> = read_binary_from_reader(&mut reader)
+            .unwrap();
+        assert_eq!(res, mat);
+
+        let mat = mat1_csc();
+        let words = binary_bytes(&mat);
+        let view = binary_view::<f64, usize>(as_bytes(&words)).unwrap();
+        assert_eq!(view, mat.view());
+        assert!(view.is_csc());
+    }
+
+    #[test]
+    fn binary_index_types() {
+        let mat = mat1();
+        let mat_u32: CsMatI<f32, u32> = CsMatI::new(
+            (5, 5),
+            mat.indptr().iter().map(|&i| i as u32).collect(),
+            mat.indices().iter().map(|&i| i as u32).collect(),
+            mat.data().iter().map(|&x| x as f32).collect());
+        let mut buf = Vec::new();
+        write_binary_to_writer(&mut buf, &mat_u32).unwrap();
+        let mut reader = &buf[..];
+        let res: CsMatI<f32, u32> = read_binary_from_reader(&mut reader)
+            .unwrap();
+        assert_eq!(res, mat_u32);
+        let mut reader = &buf[..];
+        let res = read_binary_from_reader::<f32, u16, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::BinaryTypeMismatch));
+        let mut reader = &buf[..];
+        let res = read_binary_from_reader::<f64, u32, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::BinaryTypeMismatch));
+    }
+
+    #[test]
+    fn binary_file_memory_map() {
+        let mat = mat1();
+        let path = env::temp_dir().join("sprs_binary_file_memory_map.bin");
+        write_binary(&path, &mat).unwrap();
+        let res: CsMatOwned<f64> = read_binary(&path).unwrap();
+        assert_eq!(res, mat);
+        {
+            let file = File::open(&path).unwrap();
+            let mmap = unsafe { Mmap::map(&file).unwrap() };
+            let view = binary_view::<f64, usize>(&mmap).unwrap();
+            assert_eq!(view, mat.view());
+            assert_eq!(view.data().as_ptr() as *const u8,
+                       mmap[192..].as_ptr());
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn binary_bad_header() {
+        let words = binary_bytes(&mat1());
+        let bytes = as_bytes(&words);
+
+        let mut corrupt = bytes.to_vec();
+        corrupt[0] = b'X';
+        let mut reader = &corrupt[..];
+        let res = read_binary_from_reader::<f64, usize, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::BadBinaryHeader));
+
+        let mut other_endian = bytes.to_vec();
+        other_endian[8..12].reverse();
+        let mut reader = &other_endian[..];
+        let res = read_binary_from_reader::<f64, usize, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::EndiannessMismatch));
+
+        let mut next_version = bytes.to_vec();
+        next_version[12..16].copy_from_slice(&2u32.to_ne_bytes());
+        let mut reader = &next_version[..];
+        let res = read_binary_from_reader::<f64, usize, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::UnsupportedBinaryVersion));
+
+        let mut reader = &bytes[..32];
+        let res = read_binary_from_reader::<f64, usize, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::IoError(ErrorKind::UnexpectedEof)));
+        let res = binary_view::<f64, usize>(&bytes[..32]);
+        assert_eq!(res, Err(SprsError::BadBinaryHeader));
+    }
+
+    #[test]
+    fn binary_bad_data() {
+        let words = binary_bytes(&mat1());
+        let bytes = as_bytes(&words);
+
+        let truncated = &bytes[..bytes.len() - 8];
+        let mut reader = truncated;
+        let res = read_binary_from_reader::<f64, usize, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::IoError(ErrorKind::UnexpectedEof)));
+        let res = binary_view::<f64, usize>(truncated);
+        assert_eq!(res, Err(SprsError::IoError(ErrorKind::UnexpectedEof)));
+
+        let mut misaligned = vec![0; words.len() + 1];
+        as_bytes_mut(&mut misaligned)[1..bytes.len() + 1]
+            .copy_from_slice(bytes);
+        let shifted = &as_bytes(&misaligned)[1..bytes.len() + 1];
+        let res = binary_view::<f64, usize>(shifted);
+        assert_eq!(res, Err(SprsError::MisalignedData));
+
+        // the indices of mat1 are stored right after the 64 bytes header
+        // and the 64 bytes of the padded indptr, its first row being [2, 3]
+        let mut bad_index = words.clone();
+        bad_index[17] = 5;
+        let res = binary_view::<f64, usize>(as_bytes(&bad_index));
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
+        bad_index[17] = 3;
+        bad_index[16] = 3;
+        let res = binary_view::<f64, usize>(as_bytes(&bad_index));
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+    }
+
+    #[test]
+    fn binary_huge_header() {
+        // a header announcing a huge matrix on a short stream should
+        // report the truncation rather than allocate the announced size
+        let words = binary_bytes(&mat1());
+        let mut corrupt = as_bytes(&words).to_vec();
+        let huge = 1u64 << 40;
+        corrupt[24..32].copy_from_slice(&huge.to_ne_bytes());
+        corrupt[40..48].copy_from_slice(&huge.to_ne_bytes());
+        let mut reader = &corrupt[..];
+        let res = read_binary_from_reader::<f64, usize, _>(&mut reader);
+        assert_eq!(res, Err(SprsError::IoError(ErrorKind::UnexpectedEof)));
+    }
+
+    #[test]
+    fn binary_outer_slice() {
+        let eye: CsMatOwned<f64> = CsMatOwned::eye(20);
+        let mat = mat1();
+        let views = [eye.slice_outer(5..9),
+                     eye.slice_outer(19..20),
+                     eye.slice_outer(7..7),
+                     eye.view().middle_outer_views(3, 10),
+                     mat.slice_outer(2..4)];
+        for view in views.iter() {
+            let mut buf = Vec::new();
+            write_binary_to_writer(&mut buf, view).unwrap();
+            let mut reader = &buf[..];
+            let res: CsMatOwned<f64> = read_binary_from_reader(&mut reader)
+                .unwrap();
+            assert_eq!(res.to_dense(), view.to_dense());
+            assert_eq!(res.indptr()[0], 0);
+        }
+    }
+}
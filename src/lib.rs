@@ -51,31 +51,65 @@ assert_eq!(a, b.to_csc());
 
 extern crate num_traits;
 extern crate ndarray;
+extern crate num_complex;
+#[cfg(feature = "multi_thread")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(test)]
+extern crate memmap;
 
 mod sparse;
+mod indexing;
 pub mod errors;
 pub mod stack;
+pub mod io;
 
 pub use ndarray::Ix as Ix_;
 
+pub use indexing::SpIndex;
+
 pub use sparse::{
+    CsMatBase,
     CsMat,
+    CsMatI,
     CsMatOwned,
+    CsMatViewI,
     CsMatView,
+    CsMatViewMutI,
+    CsMatViewMut,
+    CsVecBase,
     CsVec,
+    CsVecI,
+    CsVecViewI,
     CsVecView,
+    CsVecViewMutI,
+    CsVecViewMut,
     CsVecOwned,
 };
 
 
 pub use sparse::symmetric::{
     is_symmetric,
+    is_hermitian,
 };
 
+pub use sparse::complex::Conjugate;
+
 pub use sparse::permutation::{
+    PermutationBase,
     Permutation,
+    PermViewI,
     PermView,
+    PermOwnedI,
     PermOwned,
+    permute,
+    permute_rows,
+    permute_cols,
+    permute_sym,
 };
 
 pub use sparse::CompressedStorage::{
@@ -90,6 +124,9 @@ pub use sparse::binop;
 pub use sparse::vec;
 
 pub use sparse::triplet::{
+    TripletMatI,
+    TripletMatViewI,
+    TripletMatViewMutI,
     TripletMat,
     TripletMatView,
     TripletMatViewMut,
@@ -101,6 +138,9 @@ pub use sparse::construct::{
     bmat,
     csr_from_dense,
     csc_from_dense,
+    from_diags,
+    kron,
+    kronsum,
 };
 
 pub use sparse::to_dense::{
@@ -123,20 +163,23 @@ pub type SpRes<T> = Result<T, errors::SprsError>;
 
 mod utils {
 
-    use sparse::{csmat, CsMatView};
+    use sparse::{csmat, CsMatViewI};
+    use indexing::SpIndex;
     use ::Shape;
 
     /// Create a borrowed CsMat matrix from sliced data without
     /// checking validity. Intended for internal use only.
-    pub fn csmat_borrowed_uchk<'a, N>(storage: csmat::CompressedStorage,
-                                      shape: Shape,
-                                      indptr : &'a [usize],
-                                      indices : &'a [usize],
-                                      data : &'a [N]
-                                     ) -> CsMatView<'a, N> {
+    pub fn csmat_borrowed_uchk<'a, N, I>(storage: csmat::CompressedStorage,
+                                         shape: Shape,
+                                         indptr : &'a [I],
+                                         indices : &'a [I],
+                                         data : &'a [N]
+                                        ) -> CsMatViewI<'a, N, I>
+    where I: SpIndex
+    {
         // not actually memory unsafe here since data comes from slices
         unsafe {
-            CsMatView::new_view_raw(storage, shape,
+            CsMatViewI::new_view_raw(storage, shape,
                                     indptr.as_ptr(),
                                     indices.as_ptr(),
                                     data.as_ptr())
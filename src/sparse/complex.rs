@@ -0,0 +1,64 @@
+//! Support for complex scalars
+//!
+//! Most of sprs is generic over `num_traits::Num`, and thus works with
+//! `num_complex::Complex` values. Operations that need to distinguish real
+//! from complex scalars, such as conjugate transposition or hermitian
+//! decompositions, are bounded on the `Conjugate` trait instead.
+
+use num_complex::Complex;
+use num_traits::Num;
+use std::ops::Neg;
+
+/// Scalars with a complex conjugate. The conjugate of a real scalar is
+/// itself.
+pub trait Conjugate: Copy {
+    /// The complex conjugate of this scalar
+    fn conj(self) -> Self;
+}
+
+macro_rules! real_conj_impl {
+    ($scalar: ty) => (
+        impl Conjugate for $scalar {
+            #[inline(always)]
+            fn conj(self) -> Self {
+                self
+            }
+        }
+    )
+}
+
+real_conj_impl!(i8);
+real_conj_impl!(i16);
+real_conj_impl!(i32);
+real_conj_impl!(i64);
+real_conj_impl!(isize);
+real_conj_impl!(u8);
+real_conj_impl!(u16);
+real_conj_impl!(u32);
+real_conj_impl!(u64);
+real_conj_impl!(usize);
+real_conj_impl!(f32);
+real_conj_impl!(f64);
+
+impl<T> Conjugate for Complex<T>
+where T: Copy + Num + Neg<Output = T>
+{
+    #[inline(always)]
+    fn conj(self) -> Self {
+        Complex::conj(&self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_complex::Complex64;
+    use super::Conjugate;
+
+    #[test]
+    fn conj() {
+        assert_eq!(Conjugate::conj(2.5f64), 2.5);
+        assert_eq!(Conjugate::conj(-3i32), -3);
+        let z = Complex64::new(1., -2.);
+        assert_eq!(Conjugate::conj(z), Complex64::new(1., 2.));
+    }
+}
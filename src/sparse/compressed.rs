@@ -2,48 +2,50 @@
 
 
 use sparse::prelude::*;
-use sparse::vec::{CsVec, CsVecView};
+use sparse::vec::{CsVecBase, CsVecViewI};
 use std::ops::{Deref};
 
 /// The SpMatView trait describes data that can be seen as a view
 /// into a CsMat
-pub trait SpMatView<N> {
+pub trait SpMatView<N, I: SpIndex> {
     /// Return a view into the current matrix
-    fn view(&self) -> CsMatView<N>;
+    fn view(&self) -> CsMatViewI<N, I>;
 
     /// Return a view into the current matrix
-    fn transpose_view(&self) -> CsMatView<N>;
+    fn transpose_view(&self) -> CsMatViewI<N, I>;
 }
 
 
-impl<N, IpStorage, IndStorage, DataStorage> SpMatView<N>
-for CsMat<N, IpStorage, IndStorage, DataStorage>
-where IpStorage: Deref<Target=[usize]>,
-      IndStorage: Deref<Target=[usize]>,
+impl<N, I, IpStorage, IndStorage, DataStorage> SpMatView<N, I>
+for CsMatBase<N, I, IpStorage, IndStorage, DataStorage>
+where I: SpIndex,
+      IpStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
       DataStorage: Deref<Target=[N]> {
 
-    fn view(&self) -> CsMatView<N> {
+    fn view(&self) -> CsMatViewI<N, I> {
         self.view()
     }
 
-    fn transpose_view(&self) -> CsMatView<N> {
+    fn transpose_view(&self) -> CsMatViewI<N, I> {
         self.transpose_view()
     }
 }
 
 /// The SpVecView trait describes types that can be seen as a view into
 /// a CsVec
-pub trait SpVecView<N> {
+pub trait SpVecView<N, I: SpIndex> {
     /// Return a view into the current vector
-    fn view(&self) ->  CsVecView<N>;
+    fn view(&self) ->  CsVecViewI<N, I>;
 }
 
-impl<N, IndStorage, DataStorage> SpVecView<N>
-for CsVec<N, IndStorage, DataStorage>
-where IndStorage: Deref<Target=[usize]>,
+impl<N, I, IndStorage, DataStorage> SpVecView<N, I>
+for CsVecBase<N, I, IndStorage, DataStorage>
+where I: SpIndex,
+      IndStorage: Deref<Target=[I]>,
       DataStorage: Deref<Target=[N]> {
 
-    fn view(&self) -> CsVecView<N> {
+    fn view(&self) -> CsVecViewI<N, I> {
         self.view()
     }
 }
@@ -6,13 +6,15 @@ use sparse::prelude::*;
 use sparse::csmat::CompressedStorage;
 use ndarray::{ArrayView, Ix};
 use num_traits::{Num, Signed};
+use ::Shape;
 
 /// Stack the given matrices into a new one, using the most efficient stacking
 /// direction (ie vertical stack for CSR matrices, horizontal stack for CSC)
-pub fn same_storage_fast_stack<'a, N, MatArray>(
-    mats: &MatArray) -> CsMatOwned<N>
+pub fn same_storage_fast_stack<'a, N, I, MatArray>(
+    mats: &MatArray) -> CsMatI<N, I>
 where N: 'a + Clone,
-      MatArray: AsRef<[CsMatView<'a, N>]> {
+      I: 'a + SpIndex,
+      MatArray: AsRef<[CsMatViewI<'a, N, I>]> {
     let mats = mats.as_ref();
     if mats.len() == 0 {
         panic!("Empty stacking list");
@@ -29,7 +31,7 @@ where N: 'a + Clone,
     let outer_dim = mats.iter().map(|x| x.outer_dims()).fold(0, |x, y| x + y);
     let nnz = mats.iter().map(|x| x.nnz()).fold(0, |x, y| x + y);
 
-    let mut res = CsMatOwned::empty(storage_type, inner_dim);
+    let mut res = CsMatI::empty(storage_type, inner_dim);
     res.reserve_outer_dim_exact(outer_dim);
     res.reserve_nnz_exact(nnz);
     for mat in mats {
@@ -42,9 +44,10 @@ where N: 'a + Clone,
 }
 
 /// Construct a sparse matrix by vertically stacking other matrices
-pub fn vstack<'a, N, MatArray>(mats: &MatArray) -> CsMatOwned<N>
+pub fn vstack<'a, N, I, MatArray>(mats: &MatArray) -> CsMatI<N, I>
 where N: 'a + Clone + Default,
-      MatArray: AsRef<[CsMatView<'a, N>]> {
+      I: 'a + SpIndex,
+      MatArray: AsRef<[CsMatViewI<'a, N, I>]> {
     let mats = mats.as_ref();
     if mats.iter().all(|x| x.is_csr()) {
         return same_storage_fast_stack(&mats);
@@ -56,9 +59,10 @@ where N: 'a + Clone + Default,
 }
 
 /// Construct a sparse matrix by horizontally stacking other matrices
-pub fn hstack<'a, N, MatArray>(mats: &MatArray) -> CsMatOwned<N>
+pub fn hstack<'a, N, I, MatArray>(mats: &MatArray) -> CsMatI<N, I>
 where N: 'a + Clone + Default,
-      MatArray: AsRef<[CsMatView<'a, N>]> {
+      I: 'a + SpIndex,
+      MatArray: AsRef<[CsMatViewI<'a, N, I>]> {
     let mats = mats.as_ref();
     if mats.iter().all(|x| x.is_csc()) {
         return same_storage_fast_stack(&mats);
@@ -80,10 +84,12 @@ where N: 'a + Clone + Default,
 ///                      [None, Some(b.view())]]);
 /// assert_eq!(c.rows(), 7);
 /// ```
-pub fn bmat<'a, N, OuterArray, InnerArray>(mats: &OuterArray) -> CsMatOwned<N>
+pub fn bmat<'a, N, I, OuterArray, InnerArray>(mats: &OuterArray)
+                                              -> CsMatI<N, I>
 where N: 'a + Clone + Default,
+      I: 'a + SpIndex,
       OuterArray: 'a + AsRef<[InnerArray]>,
-      InnerArray: 'a + AsRef<[Option<CsMatView<'a, N>>]> {
+      InnerArray: 'a + AsRef<[Option<CsMatViewI<'a, N, I>>]> {
     let mats = mats.as_ref();
     let super_rows = mats.len();
     if super_rows == 0 {
@@ -123,7 +129,7 @@ where N: 'a + Clone + Default,
     for (i, row) in mats.iter().enumerate() {
         let with_zeros: Vec<_> = row.as_ref().iter().enumerate().map(|(j, m)| {
             let shape = (rows_per_row[i], cols_per_col[j]);
-            m.as_ref().map_or(CsMatOwned::zero(shape), |x| x.to_owned())
+            m.as_ref().map_or(CsMatI::zero(shape), |x| x.to_owned())
         }).collect();
         let borrows: Vec<_> = with_zeros.iter().map(|x| x.view()).collect();
         let stacked = hstack(&borrows);
@@ -183,11 +189,286 @@ where N: Num + Clone + cmp::PartialOrd + Signed
     csr_from_dense(m.reversed_axes(), epsilon).transpose_into()
 }
 
+/// Create a CSR matrix of the given shape from its diagonals, where
+/// `diags[d]` is the diagonal of offset `offsets[d]` (see `CsMatBase::diag`
+/// for the offset convention).
+///
+/// Each diagonal must have the exact length of the diagonal it describes.
+/// All its values are stored, including zeros, use `eliminate_zeros` to
+/// drop them.
+///
+/// # Panics
+///
+/// - if `diags` and `offsets` do not have the same length
+/// - if an offset is repeated or lies outside of the matrix
+/// - if a diagonal does not have the length of its offset's diagonal
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::from_diags;
+/// use sprs::CsMatOwned;
+/// // |  2 -1  0 |
+/// // | -1  2 -1 |
+/// // |  0 -1  2 |
+/// let lap: CsMatOwned<f64> = from_diags((3, 3),
+///                                       &[&[-1., -1.], &[2., 2., 2.],
+///                                         &[-1., -1.]],
+///                                       &[-1, 0, 1]);
+/// assert_eq!(lap.indptr(), &[0, 2, 5, 7]);
+/// assert_eq!(lap.data(), &[2., -1., -1., 2., -1., -1., 2.]);
+/// ```
+pub fn from_diags<N, I>(shape: Shape,
+                        diags: &[&[N]],
+                        offsets: &[isize])
+                        -> CsMatI<N, I>
+where N: Clone,
+      I: SpIndex
+{
+    assert_eq!(diags.len(), offsets.len(), "Dimension mismatch");
+    let (rows, cols) = (shape.0 as isize, shape.1 as isize);
+    // visit the diagonals by increasing offset to get sorted indices
+    let mut order: Vec<_> = (0..offsets.len()).collect();
+    order.sort_by_key(|&d| offsets[d]);
+    for (&d, &next) in order.iter().zip(order.iter().skip(1)) {
+        if offsets[d] == offsets[next] {
+            panic!("Repeated diagonal offset");
+        }
+    }
+    for (diag, &k) in diags.iter().zip(offsets) {
+        if k <= -rows || k >= cols {
+            panic!("Diagonal offset out of bounds");
+        }
+        let len = if k >= 0 {
+            cmp::min(rows, cols - k)
+        } else {
+            cmp::min(rows + k, cols)
+        };
+        if diag.len() != len as usize {
+            panic!("Diagonal length does not match its offset");
+        }
+    }
+
+    let mut indptr = Vec::with_capacity(shape.0 + 1);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    indptr.push(I::zero());
+    for row in 0..rows {
+        for &d in &order {
+            let col = row + offsets[d];
+            if col >= 0 && col < cols {
+                // the position in a diagonal is its smallest coordinate
+                let pos = cmp::min(row, col) as usize;
+                indices.push(I::from_usize(col as usize));
+                data.push(diags[d][pos].clone());
+            }
+        }
+        indptr.push(I::from_usize(indices.len()));
+    }
+    CsMatI {
+        storage: CompressedStorage::CSR,
+        nrows: shape.0,
+        ncols: shape.1,
+        indptr: indptr,
+        indices: indices,
+        data: data,
+    }
+}
+
+/// Compute the Kronecker product of `a` and `b`, stored with `storage`.
+///
+/// If `a` has shape `(m, n)` and `b` has shape `(p, q)`, the result has shape
+/// `(m * p, n * q)` and is made of the blocks `a[i, j] * b`. The inputs can
+/// have any storage, an input whose storage differs from `storage` is
+/// converted first.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{CsMatOwned, CSR};
+/// // 1D laplacian and identity
+/// let t: CsMatOwned<f64> = sprs::from_diags((3, 3),
+///                                           &[&[-1., -1.], &[2., 2., 2.],
+///                                             &[-1., -1.]],
+///                                           &[-1, 0, 1]);
+/// let eye = CsMatOwned::eye(3);
+/// let a = sprs::kron(eye.view(), t.view(), CSR);
+/// assert_eq!(a.shape(), (9, 9));
+/// assert_eq!(a.nnz(), 21);
+/// ```
+pub fn kron<N, I>(a: CsMatViewI<N, I>,
+                  b: CsMatViewI<N, I>,
+                  storage: CompressedStorage)
+                  -> CsMatI<N, I>
+where N: Num + Copy + Default,
+      I: SpIndex
+{
+    let a_conv;
+    let a = if a.storage() == storage {
+        a
+    } else {
+        a_conv = a.to_other_storage();
+        a_conv.view()
+    };
+    let b_conv;
+    let b = if b.storage() == storage {
+        b
+    } else {
+        b_conv = b.to_other_storage();
+        b_conv.view()
+    };
+
+    // the outer dimension (ia, ib) of the result is the product of the outer
+    // dimensions ia of a and ib of b, and the same holds for inner dimensions
+    let b_inner = b.inner_dims();
+    let mut indptr = Vec::with_capacity(a.outer_dims() * b.outer_dims() + 1);
+    let mut indices = Vec::with_capacity(a.nnz() * b.nnz());
+    let mut data = Vec::with_capacity(a.nnz() * b.nnz());
+    indptr.push(I::zero());
+    for a_line in a.outer_iterator() {
+        for b_line in b.outer_iterator() {
+            for (ja, &va) in a_line.iter() {
+                for (jb, &vb) in b_line.iter() {
+                    indices.push(I::from_usize(ja * b_inner + jb));
+                    data.push(va * vb);
+                }
+            }
+            indptr.push(I::from_usize(indices.len()));
+        }
+    }
+    CsMatI {
+        storage: storage,
+        nrows: a.rows() * b.rows(),
+        ncols: a.cols() * b.cols(),
+        indptr: indptr,
+        indices: indices,
+        data: data,
+    }
+}
+
+/// Compute the Kronecker sum `kron(a, I_m) + kron(I_n, b)` of the square
+/// matrices `a` of dimension `n` and `b` of dimension `m`, stored with
+/// `storage`.
+///
+/// The inputs can have any storage, an input whose storage differs from
+/// `storage` is converted first.
+///
+/// # Panics
+///
+/// - if `a` or `b` is not square
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{CsMatOwned, CSC};
+/// // 2D laplacian on a 3 by 3 grid
+/// let t: CsMatOwned<f64> = sprs::from_diags((3, 3),
+///                                           &[&[-1., -1.], &[2., 2., 2.],
+///                                             &[-1., -1.]],
+///                                           &[-1, 0, 1]);
+/// let lap = sprs::kronsum(t.view(), t.view(), CSC);
+/// assert_eq!(lap.shape(), (9, 9));
+/// assert_eq!(lap.get(4, 4), Some(&4.));
+/// assert_eq!(lap.nnz(), 33);
+/// ```
+pub fn kronsum<N, I>(a: CsMatViewI<N, I>,
+                     b: CsMatViewI<N, I>,
+                     storage: CompressedStorage)
+                     -> CsMatI<N, I>
+where N: Num + Copy + Default,
+      I: SpIndex
+{
+    if a.rows() != a.cols() || b.rows() != b.cols() {
+        panic!("Non square matrix in Kronecker sum");
+    }
+    let a_conv;
+    let a = if a.storage() == storage {
+        a
+    } else {
+        a_conv = a.to_other_storage();
+        a_conv.view()
+    };
+    let b_conv;
+    let b = if b.storage() == storage {
+        b
+    } else {
+        b_conv = b.to_other_storage();
+        b_conv.view()
+    };
+
+    // The line (i, k) of the result holds the elements (j, a_ij) of a at
+    // j * m + k, and the elements (l, b_kl) of b at i * m + l. Elements of a
+    // with j < i come first, then the block of b, in which a_ii is merged,
+    // then the elements of a with j > i.
+    let n = a.rows();
+    let m = b.rows();
+    let mut indptr = Vec::with_capacity(n * m + 1);
+    let nnz = a.nnz() * m + b.nnz() * n;
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(I::zero());
+    for (i, a_line) in a.outer_iterator().enumerate() {
+        for (k, b_line) in b.outer_iterator().enumerate() {
+            let mut a_iter = a_line.iter().peekable();
+            while let Some(&(j, &val)) = a_iter.peek() {
+                if j >= i {
+                    break;
+                }
+                indices.push(I::from_usize(j * m + k));
+                data.push(val);
+                a_iter.next();
+            }
+            let mut a_diag = match a_iter.peek() {
+                Some(&(j, &val)) if j == i => Some(val),
+                _ => None,
+            };
+            if a_diag.is_some() {
+                a_iter.next();
+            }
+            for (l, &val) in b_line.iter() {
+                if let Some(diag_val) = a_diag {
+                    if k == l {
+                        indices.push(I::from_usize(i * m + k));
+                        data.push(diag_val + val);
+                        a_diag = None;
+                        continue;
+                    }
+                    if k < l {
+                        indices.push(I::from_usize(i * m + k));
+                        data.push(diag_val);
+                        a_diag = None;
+                    }
+                }
+                indices.push(I::from_usize(i * m + l));
+                data.push(val);
+            }
+            if let Some(diag_val) = a_diag {
+                indices.push(I::from_usize(i * m + k));
+                data.push(diag_val);
+            }
+            for (j, &val) in a_iter {
+                indices.push(I::from_usize(j * m + k));
+                data.push(val);
+            }
+            indptr.push(I::from_usize(indices.len()));
+        }
+    }
+    CsMatI {
+        storage: storage,
+        nrows: n * m,
+        ncols: n * m,
+        indptr: indptr,
+        indices: indices,
+        data: data,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use sparse::CsMatOwned;
     use test_data::{mat1, mat2, mat3, mat4};
-    use ndarray::{arr2, Array};
+    use ::{CSR, CSC};
+    use ndarray::{arr1, arr2, Array};
 
     fn mat1_vstack_mat2() -> CsMatOwned<f64> {
         let indptr = vec![0, 2, 4, 5, 6, 7, 11, 13, 13, 15, 17];
@@ -369,4 +650,120 @@ mod test {
 
         assert_eq!(m_sparse, expected_output);
     }
+
+    #[test]
+    fn from_diags() {
+        // | 1 4 0 0 |
+        // | 6 2 5 0 |
+        // | 0 7 3 0 |
+        let mat: CsMatOwned<i32> = super::from_diags((3, 4),
+                                                     &[&[4, 5, 0],
+                                                       &[1, 2, 3],
+                                                       &[6, 7]],
+                                                     &[1, 0, -1]);
+        let expected = CsMatOwned::new((3, 4),
+                                       vec![0, 2, 5, 8],
+                                       vec![0, 1, 0, 1, 2, 1, 2, 3],
+                                       vec![1, 4, 6, 2, 5, 7, 3, 0]);
+        assert_eq!(mat, expected);
+        assert_eq!(mat.diag_dense(1), arr1(&[4, 5, 0]));
+        assert_eq!(mat.diag_dense(0), arr1(&[1, 2, 3]));
+        assert_eq!(mat.diag_dense(-1), arr1(&[6, 7]));
+
+        let tall: CsMatOwned<i32> = super::from_diags((3, 2),
+                                                      &[&[1, 2], &[3]],
+                                                      &[0, -2]);
+        assert_eq!(tall.to_dense(), arr2(&[[1, 0], [0, 2], [3, 0]]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_diags_bad_length() {
+        let _: CsMatOwned<i32> = super::from_diags((3, 3), &[&[1, 2]], &[0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_diags_repeated_offset() {
+        let _: CsMatOwned<i32> = super::from_diags((3, 3),
+                                                   &[&[1, 2], &[1, 2]],
+                                                   &[1, 1]);
+    }
+
+    #[test]
+    fn kron() {
+        // | 1 2 |     | 0 1 |
+        // | 0 3 |     | 1 0 |
+        let a = CsMatOwned::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                                vec![1, 2, 3]);
+        let b = CsMatOwned::new((2, 2), vec![0, 1, 2], vec![1, 0],
+                                vec![1, 1]);
+        let expected = arr2(&[[0, 1, 0, 2],
+                              [1, 0, 2, 0],
+                              [0, 0, 0, 3],
+                              [0, 0, 3, 0]]);
+        let (a_csc, b_csc) = (a.to_csc(), b.to_csc());
+        for &storage in &[CSR, CSC] {
+            for &(ref a, ref b) in &[(&a, &b), (&a_csc, &b), (&a, &b_csc),
+                                     (&a_csc, &b_csc)] {
+                let res = super::kron(a.view(), b.view(), storage);
+                assert_eq!(res.storage(), storage);
+                assert_eq!(res.to_dense(), expected);
+            }
+        }
+
+        // rectangular inputs
+        let row = CsMatOwned::new((1, 2), vec![0, 2], vec![0, 1],
+                                  vec![1, 2]);
+        let col = CsMatOwned::new_csc((2, 1), vec![0, 2], vec![0, 1],
+                                      vec![3, 4]);
+        let res = super::kron(row.view(), col.view(), CSR);
+        assert_eq!(res.to_dense(), arr2(&[[3, 6], [4, 8]]));
+        let res = super::kron(col.view(), row.view(), CSC);
+        assert_eq!(res.to_dense(), arr2(&[[3, 6], [4, 8]]));
+    }
+
+    #[test]
+    fn kronsum() {
+        // | 1 2 |     | 4 0 |
+        // | 0 3 |     | 5 6 |
+        let a = CsMatOwned::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                                vec![1, 2, 3]);
+        let b = CsMatOwned::new((2, 2), vec![0, 1, 3], vec![0, 0, 1],
+                                vec![4, 5, 6]);
+        let expected = arr2(&[[5, 0, 2, 0],
+                              [5, 7, 0, 2],
+                              [0, 0, 7, 0],
+                              [0, 0, 5, 9]]);
+        for &storage in &[CSR, CSC] {
+            let res = super::kronsum(a.view(), b.to_csc().view(), storage);
+            assert_eq!(res.storage(), storage);
+            assert_eq!(res.to_dense(), expected);
+        }
+    }
+
+    #[test]
+    fn kronsum_matches_kron() {
+        // mat1 has an incomplete diagonal, exercising all the merge cases
+        let a = mat1();
+        let b = mat4();
+        let eye_a = CsMatOwned::eye(a.rows());
+        let eye_b = CsMatOwned::eye(b.rows());
+        let expected = &super::kron(a.view(), eye_b.view(), CSR)
+                       + &super::kron(eye_a.view(), b.view(), CSR);
+        let res = super::kronsum(a.view(), b.view(), CSR);
+        assert_eq!(res, expected);
+        let res = super::kronsum(b.view(), a.view(), CSC);
+        let expected = &super::kron(b.view(), eye_a.view(), CSC)
+                       + &super::kron(eye_b.view(), a.view(), CSC);
+        assert_eq!(res.to_csr(), expected.to_csr());
+    }
+
+    #[test]
+    #[should_panic]
+    fn kronsum_non_square() {
+        let a = CsMatOwned::<f64>::eye(2);
+        let b = mat1().slice(0..2, 0..5);
+        super::kronsum(a.view(), b.view(), CSR);
+    }
 }
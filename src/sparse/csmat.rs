@@ -12,15 +12,21 @@ use std::default::Default;
 use std::slice::{self, Windows};
 use std::ops::{Deref, DerefMut, Add, Sub, Mul, Range, Index, IndexMut};
 use std::mem;
+use std::cmp;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
 use num_traits::{Num, Zero};
 
 use ndarray::{self, ArrayBase, Array, Ix, ShapeBuilder};
 use ::{Ix2, Shape};
 
 use sparse::prelude::*;
-use sparse::permutation::PermView;
-use sparse::vec::{CsVec, CsVecView, CsVecViewMut, self};
+use sparse::permutation::PermViewI;
+use sparse::vec::{CsVecBase, CsVecI, CsVecViewI, CsVecViewMutI, self};
 use sparse::compressed::SpMatView;
+use sparse::complex::Conjugate;
 use sparse::binop;
 use sparse::prod;
 use sparse::utils;
@@ -31,6 +37,7 @@ use sparse::to_dense::assign_to_dense;
 
 /// Describe the storage of a CsMat
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CompressedStorage {
     /// Compressed row storage
     CSR,
@@ -79,47 +86,47 @@ pub struct NnzIndex(pub usize);
 
 /// Iterator on the matrix' outer dimension
 /// Implemented over an iterator on the indptr array
-pub struct OuterIterator<'iter, N: 'iter> {
+pub struct OuterIterator<'iter, N: 'iter, I: 'iter> {
     inner_len: usize,
-    indptr_iter: Windows<'iter, usize>,
-    indices: &'iter [usize],
+    indptr_iter: Windows<'iter, I>,
+    indices: &'iter [I],
     data: &'iter [N],
 }
 
 /// Iterator on the matrix' outer dimension, permuted
 /// Implemented over an iterator on the indptr array
-pub struct OuterIteratorPerm<'iter, 'perm: 'iter, N: 'iter> {
+pub struct OuterIteratorPerm<'iter, 'perm: 'iter, N: 'iter, I: 'iter + SpIndex> {
     inner_len: usize,
     outer_ind_iter: Range<usize>,
-    indptr: &'iter [usize],
-    indices: &'iter [usize],
+    indptr: &'iter [I],
+    indices: &'iter [I],
     data: &'iter [N],
-    perm: PermView<'perm>,
+    perm: PermViewI<'perm, I>,
 }
 
 
 /// Outer iteration on a compressed matrix yields
 /// a tuple consisting of the outer index and of a sparse vector
 /// containing the associated inner dimension
-impl <'iter, N: 'iter>
+impl <'iter, N: 'iter, I: 'iter + SpIndex>
 Iterator
-for OuterIterator<'iter, N> {
-    type Item = CsVec<N, &'iter[usize], &'iter[N]>;
+for OuterIterator<'iter, N, I> {
+    type Item = CsVecBase<N, I, &'iter[I], &'iter[N]>;
     #[inline]
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
         match self.indptr_iter.next() {
             None => None,
             Some(window) => {
-                let inner_start = window[0];
-                let inner_end = window[1];
+                let inner_start = window[0].index();
+                let inner_end = window[1].index();
                 let indices = &self.indices[inner_start..inner_end];
                 let data = &self.data[inner_start..inner_end];
                 // safety derives from the structure checks in the constructors
                 unsafe {
-                    let vec = CsVec::new_view_raw(self.inner_len,
-                                                  indices.len(),
-                                                  indices.as_ptr(),
-                                                  data.as_ptr());
+                    let vec = CsVecBase::new_view_raw(self.inner_len,
+                                                      indices.len(),
+                                                      indices.as_ptr(),
+                                                      data.as_ptr());
                     Some(vec)
                 }
             }
@@ -134,26 +141,26 @@ for OuterIterator<'iter, N> {
 /// Permuted outer iteration on a compressed matrix yields
 /// a tuple consisting of the outer index and of a sparse vector
 /// containing the associated inner dimension
-impl <'iter, 'perm: 'iter, N: 'iter>
+impl <'iter, 'perm: 'iter, N: 'iter, I: 'iter + SpIndex>
 Iterator
-for OuterIteratorPerm<'iter, 'perm, N> {
-    type Item = (usize, CsVec<N, &'iter[usize], &'iter[N]>);
+for OuterIteratorPerm<'iter, 'perm, N, I> {
+    type Item = (usize, CsVecBase<N, I, &'iter[I], &'iter[N]>);
     #[inline]
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
         match self.outer_ind_iter.next() {
             None => None,
             Some(outer_ind) => {
                 let outer_ind_perm = self.perm.at(outer_ind);
-                let inner_start = self.indptr[outer_ind_perm];
-                let inner_end = self.indptr[outer_ind_perm + 1];
+                let inner_start = self.indptr[outer_ind_perm].index();
+                let inner_end = self.indptr[outer_ind_perm + 1].index();
                 let indices = &self.indices[inner_start..inner_end];
                 let data = &self.data[inner_start..inner_end];
                 // safety derives from the structure checks in the constructors
                 unsafe {
-                    let vec = CsVec::new_view_raw(self.inner_len,
-                                                  indices.len(),
-                                                  indices.as_ptr(),
-                                                  data.as_ptr());
+                    let vec = CsVecBase::new_view_raw(self.inner_len,
+                                                      indices.len(),
+                                                      indices.as_ptr(),
+                                                      data.as_ptr());
                     Some((outer_ind_perm, vec))
                 }
             }
@@ -172,24 +179,24 @@ for OuterIteratorPerm<'iter, 'perm, N> {
 /// Only the outer dimension iteration is reverted. If you wish to also
 /// revert the inner dimension, you should call rev() again when iterating
 /// the vector.
-impl <'iter, N: 'iter>
+impl <'iter, N: 'iter, I: 'iter + SpIndex>
 DoubleEndedIterator
-for OuterIterator<'iter, N> {
+for OuterIterator<'iter, N, I> {
     #[inline]
     fn next_back(&mut self) -> Option<<Self as Iterator>::Item> {
         match self.indptr_iter.next_back() {
             None => None,
             Some(window) => {
-                let inner_start = window[0];
-                let inner_end = window[1];
+                let inner_start = window[0].index();
+                let inner_end = window[1].index();
                 let indices = &self.indices[inner_start..inner_end];
                 let data = &self.data[inner_start..inner_end];
                 // safety derives from the structure checks in the constructors
                 unsafe {
-                    let vec = CsVec::new_view_raw(self.inner_len,
-                                                  indices.len(),
-                                                  indices.as_ptr(),
-                                                  data.as_ptr());
+                    let vec = CsVecBase::new_view_raw(self.inner_len,
+                                                      indices.len(),
+                                                      indices.as_ptr(),
+                                                      data.as_ptr());
                     Some(vec)
                 }
             }
@@ -197,14 +204,15 @@ for OuterIterator<'iter, N> {
     }
 }
 
-impl <'iter, N: 'iter> ExactSizeIterator for OuterIterator<'iter, N> {
+impl <'iter, N: 'iter, I: 'iter + SpIndex> ExactSizeIterator
+for OuterIterator<'iter, N, I> {
     fn len(&self) -> usize {
         self.indptr_iter.len()
     }
 }
 
 
-impl<'a, N:'a> CsMat<N, Vec<usize>, &'a [usize], &'a [N]> {
+impl<'a, N:'a, I: 'a + SpIndex> CsMatBase<N, I, Vec<I>, &'a [I], &'a [N]> {
     /// Create a borrowed row or column CsMat matrix from raw data,
     /// without checking their validity
     ///
@@ -214,11 +222,11 @@ impl<'a, N:'a> CsMat<N, Vec<usize>, &'a [usize], &'a [N]> {
     /// perform unchecked slice access.
     pub unsafe fn new_vecview_raw(
         storage: CompressedStorage, nrows : usize, ncols: usize,
-        indptr : Vec<usize>, indices : *const usize, data : *const N
+        indptr : Vec<I>, indices : *const I, data : *const N
         )
-    -> CsMatVecView<'a, N> {
-        let nnz = indptr[1];
-        CsMat {
+    -> CsMatVecViewI<'a, N, I> {
+        let nnz = indptr[1].index();
+        CsMatBase {
             storage: storage,
             nrows : nrows,
             ncols: ncols,
@@ -229,23 +237,15 @@ impl<'a, N:'a> CsMat<N, Vec<usize>, &'a [usize], &'a [N]> {
     }
 }
 
-impl<'a, N:'a> CsMat<N, &'a [usize], &'a [usize], &'a [N]> {
+impl<'a, N:'a, I: 'a + SpIndex> CsMatBase<N, I, &'a [I], &'a [I], &'a [N]> {
     /// Create a borrowed CsMat matrix from sliced data,
     /// checking their validity
     pub fn new_view(
         storage: CompressedStorage, shape: Shape,
-        indptr : &'a[usize], indices : &'a[usize], data : &'a[N]
+        indptr : &'a[I], indices : &'a[I], data : &'a[N]
         )
-    -> Result<CsMatView<'a, N>, SprsError> {
-        let m = CsMat {
-            storage: storage,
-            nrows : shape.0,
-            ncols: shape.1,
-            indptr : indptr,
-            indices : indices,
-            data : data,
-        };
-        m.check_compressed_structure().and(Ok(m))
+    -> Result<CsMatViewI<'a, N, I>, SprsError> {
+        checked_from_parts(storage, shape, indptr, indices, data)
     }
 
     /// Create a borrowed CsMat matrix from raw data,
@@ -257,17 +257,17 @@ impl<'a, N:'a> CsMat<N, &'a [usize], &'a [usize], &'a [N]> {
     /// perform unchecked slice access.
     pub unsafe fn new_view_raw(
         storage: CompressedStorage, shape: Shape,
-        indptr : *const usize, indices : *const usize, data : *const N
+        indptr : *const I, indices : *const I, data : *const N
         )
-    -> CsMatView<'a, N> {
+    -> CsMatViewI<'a, N, I> {
         let (nrows, ncols) = shape;
         let outer = match storage {
             CSR => nrows,
             CSC => ncols,
         };
         let indptr = slice::from_raw_parts(indptr, outer + 1);
-        let nnz = *indptr.get_unchecked(outer);
-        CsMat {
+        let nnz = indptr.get_unchecked(outer).index();
+        CsMatBase {
             storage: storage,
             nrows : nrows,
             ncols: ncols,
@@ -282,7 +282,7 @@ impl<'a, N:'a> CsMat<N, &'a [usize], &'a [usize], &'a [N]> {
     /// eg this gets the rows from i to i + count in a CSR matrix
     pub fn middle_outer_views(&self,
                               i: usize, count: usize
-                             ) -> CsMatView<'a, N> {
+                             ) -> CsMatViewI<'a, N, I> {
         if count == 0 {
             panic!("Empty view");
         }
@@ -290,10 +290,14 @@ impl<'a, N:'a> CsMat<N, &'a [usize], &'a [usize], &'a [N]> {
         if i >= self.outer_dims() || iend > self.outer_dims() {
             panic!("Out of bounds index");
         }
-        CsMat {
+        let (nrows, ncols) = match self.storage {
+            CSR => (count, self.cols()),
+            CSC => (self.rows(), count),
+        };
+        CsMatBase {
             storage: self.storage,
-            nrows: count,
-            ncols: self.cols(),
+            nrows: nrows,
+            ncols: ncols,
             indptr: &self.indptr[i..(iend+1)],
             indices: &self.indices[..],
             data: &self.data[..],
@@ -302,19 +306,19 @@ impl<'a, N:'a> CsMat<N, &'a [usize], &'a [usize], &'a [N]> {
 
 }
 
-impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
+impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
     /// Create an empty CsMat for building purposes
     pub fn empty(storage: CompressedStorage, inner_size: usize
-                ) -> CsMatOwned<N> {
+                ) -> CsMatI<N, I> {
         let (nrows, ncols) = match storage {
             CSR => (0, inner_size),
             CSC => (inner_size, 0)
         };
-        CsMat {
+        CsMatBase {
             storage: storage,
             nrows: nrows,
             ncols: ncols,
-            indptr: vec![0; 1],
+            indptr: vec![I::zero(); 1],
             indices: Vec::new(),
             data: Vec::new(),
         }
@@ -322,13 +326,13 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
 
     /// Create a new CsMat representing the zero matrix.
     /// Hence it has no non-zero elements.
-    pub fn zero(shape: Shape) -> CsMatOwned<N> {
+    pub fn zero(shape: Shape) -> CsMatI<N, I> {
         let (rows, cols) = shape;
-        CsMat {
+        CsMatBase {
             storage: CSR,
             nrows: rows,
             ncols: cols,
-            indptr: vec![0; rows + 1],
+            indptr: vec![I::zero(); rows + 1],
             indices: Vec::new(),
             data: Vec::new(),
         }
@@ -376,13 +380,13 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
     /// - if `indices` contains values greater or equal to the number of
     ///   columns.
     pub fn new(shape: Shape,
-               indptr : Vec<usize>,
-               indices : Vec<usize>,
+               indptr : Vec<I>,
+               indices : Vec<I>,
                data : Vec<N>
-              ) -> CsMatOwned<N>
+              ) -> CsMatI<N, I>
     where N: Copy
     {
-        CsMat::new_(CSR, shape, indptr, indices, data).unwrap()
+        CsMatBase::new_(CSR, shape, indptr, indices, data).unwrap()
     }
 
     /// Create an owned CSC matrix from moved data.
@@ -405,24 +409,24 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
     /// - if `indices` contains values greater or equal to the number of
     ///   columns.
     pub fn new_csc(shape: Shape,
-                   indptr : Vec<usize>,
-                   indices : Vec<usize>,
+                   indptr : Vec<I>,
+                   indices : Vec<I>,
                    data : Vec<N>
-                  ) -> CsMatOwned<N>
+                  ) -> CsMatI<N, I>
     where N: Copy
     {
-        CsMat::new_(CSC, shape, indptr, indices, data).unwrap()
+        CsMatBase::new_(CSC, shape, indptr, indices, data).unwrap()
     }
 
     fn new_(storage: CompressedStorage,
             shape: Shape,
-            indptr : Vec<usize>,
-            indices : Vec<usize>,
+            indptr : Vec<I>,
+            indices : Vec<I>,
             data : Vec<N>
-           ) -> Result<CsMatOwned<N>, SprsError>
+           ) -> Result<CsMatI<N, I>, SprsError>
     where N: Copy
     {
-        let mut m = CsMat {
+        let mut m = CsMatBase {
             storage: storage,
             nrows : shape.0,
             ncols: shape.1,
@@ -439,8 +443,8 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
     {
         let mut buf = Vec::new();
         for start_stop in self.indptr.windows(2) {
-            let start = start_stop[0];
-            let stop = start_stop[1];
+            let start = start_stop[0].index();
+            let stop = start_stop[1].index();
             let indices = &mut self.indices[start..stop];
             let data = &mut self.data[start..stop];
             let len = stop - start;
@@ -456,7 +460,7 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
         let mut nnz = self.nnz();
         for (inner_ind, val) in data.iter().enumerate() {
             if *val != N::zero() {
-                self.indices.push(inner_ind);
+                self.indices.push(I::from_usize(inner_ind));
                 self.data.push(val.clone());
                 nnz += 1;
             }
@@ -465,12 +469,12 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
             CSR => self.nrows += 1,
             CSC => self.ncols += 1
         }
-        self.indptr.push(nnz);
+        self.indptr.push(I::from_usize(nnz));
         self
     }
 
     /// Append an outer dim to an existing matrix, provided by a sparse vector
-    pub fn append_outer_csvec(mut self, vec: CsVec<N,&[usize],&[N]>) -> Self
+    pub fn append_outer_csvec(mut self, vec: CsVecViewI<N, I>) -> Self
     where N: Clone
     {
         assert_eq!(self.inner_dims(), vec.dim());
@@ -482,13 +486,222 @@ impl<N> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
             CSR => self.nrows += 1,
             CSC => self.ncols += 1
         }
-        let nnz = self.indptr.last().unwrap() + vec.nnz();
+        let nnz = *self.indptr.last().unwrap() + I::from_usize(vec.nnz());
         self.indptr.push(nnz);
         self
     }
+
+    /// Insert an element at (row, col), shifting the storage as needed.
+    /// If an element is already stored at this location, its value is
+    /// replaced.
+    ///
+    /// Inserting is linear in the number of non-zeros stored after the
+    /// insertion location. Building a matrix by repeated insertions is
+    /// thus best done in storage order, otherwise a `TripletMat` should
+    /// be preferred.
+    ///
+    /// # Panics
+    ///
+    /// - on out-of-bounds access
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsMatOwned};
+    /// let mut mat: CsMatOwned<f64> = CsMat::eye(3);
+    /// mat.insert(0, 2, 5.);
+    /// mat.insert(1, 1, 4.);
+    /// assert_eq!(mat.get(0, 2), Some(&5.));
+    /// assert_eq!(mat.get(1, 1), Some(&4.));
+    /// assert_eq!(mat.nnz(), 4);
+    /// ```
+    pub fn insert(&mut self, row: usize, col: usize, val: N) {
+        if row >= self.rows() || col >= self.cols() {
+            panic!("Out of bounds index");
+        }
+        let outer = outer_dimension(self.storage, row, col);
+        let inner = I::from_usize(inner_dimension(self.storage, row, col));
+        let start = self.indptr[outer].index();
+        let stop = self.indptr[outer + 1].index();
+        match self.indices[start..stop].binary_search(&inner) {
+            Ok(pos) => self.data[start + pos] = val,
+            Err(pos) => {
+                self.indices.insert(start + pos, inner);
+                self.data.insert(start + pos, val);
+                for ptr in &mut self.indptr[outer + 1..] {
+                    *ptr = *ptr + I::one();
+                }
+            }
+        }
+    }
+
+    /// Remove the element stored at (row, col), returning its value,
+    /// or None if no element is stored at this location.
+    ///
+    /// # Panics
+    ///
+    /// - on out-of-bounds access
+    pub fn remove(&mut self, row: usize, col: usize) -> Option<N> {
+        if row >= self.rows() || col >= self.cols() {
+            panic!("Out of bounds index");
+        }
+        let outer = outer_dimension(self.storage, row, col);
+        let inner = I::from_usize(inner_dimension(self.storage, row, col));
+        let start = self.indptr[outer].index();
+        let stop = self.indptr[outer + 1].index();
+        match self.indices[start..stop].binary_search(&inner) {
+            Ok(pos) => {
+                self.indices.remove(start + pos);
+                for ptr in &mut self.indptr[outer + 1..] {
+                    *ptr = *ptr - I::one();
+                }
+                Some(self.data.remove(start + pos))
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Remove the stored elements for which `pred` returns true.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMatOwned;
+    /// let mut mat = CsMatOwned::new((2, 2),
+    ///                               vec![0, 2, 3],
+    ///                               vec![0, 1, 1],
+    ///                               vec![1., 1e-14, -2.]);
+    /// mat.prune(|&x: &f64| x.abs() < 1e-12);
+    /// assert_eq!(mat.nnz(), 2);
+    /// assert_eq!(mat.get(0, 1), None);
+    /// ```
+    pub fn prune<F>(&mut self, mut pred: F)
+    where F: FnMut(&N) -> bool
+    {
+        self.compact(None, |inner, val| {
+            if pred(val) { None } else { Some(inner) }
+        });
+    }
+
+    /// Remove the explicitly stored zeros.
+    pub fn eliminate_zeros(&mut self)
+    where N: Num
+    {
+        self.prune(|val| val.is_zero());
+    }
+
+    /// Remove the rows whose indices are in `rows`. The remaining rows
+    /// are shifted up.
+    ///
+    /// The indices need not be sorted, and may be repeated.
+    ///
+    /// # Panics
+    ///
+    /// - if a row index is out of bounds
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsMatOwned};
+    /// let mut mat: CsMatOwned<f64> = CsMat::eye(4);
+    /// mat.remove_rows(&[2, 0]);
+    /// assert_eq!(mat.shape(), (2, 4));
+    /// assert_eq!(mat.indices(), &[1, 3]);
+    /// ```
+    pub fn remove_rows(&mut self, rows: &[usize]) {
+        match self.storage {
+            CSR => self.remove_outer_dims(rows),
+            CSC => self.remove_inner_dims(rows),
+        }
+    }
+
+    /// Remove the columns whose indices are in `cols`. The remaining columns
+    /// are shifted left.
+    ///
+    /// The indices need not be sorted, and may be repeated.
+    ///
+    /// # Panics
+    ///
+    /// - if a column index is out of bounds
+    pub fn remove_cols(&mut self, cols: &[usize]) {
+        match self.storage {
+            CSR => self.remove_inner_dims(cols),
+            CSC => self.remove_outer_dims(cols),
+        }
+    }
+
+    fn remove_outer_dims(&mut self, outer_inds: &[usize]) {
+        let mut removed = vec![false; self.outer_dims()];
+        for &i in outer_inds {
+            if i >= removed.len() {
+                panic!("Out of bounds index");
+            }
+            removed[i] = true;
+        }
+        self.compact(Some(&removed), |inner, _| Some(inner));
+    }
+
+    fn remove_inner_dims(&mut self, inner_inds: &[usize]) {
+        let inner_dims = self.inner_dims();
+        let mut new_inds = vec![Some(I::zero()); inner_dims];
+        for &i in inner_inds {
+            if i >= inner_dims {
+                panic!("Out of bounds index");
+            }
+            new_inds[i] = None;
+        }
+        let mut count = 0;
+        for new_ind in &mut new_inds {
+            if new_ind.is_some() {
+                *new_ind = Some(I::from_usize(count));
+                count += 1;
+            }
+        }
+        self.compact(None, |inner, _| new_inds[inner.index()]);
+        match self.storage {
+            CSR => self.ncols = count,
+            CSC => self.nrows = count,
+        }
+    }
+
+    /// Keep the elements for which `keep` returns an inner index, moving
+    /// them to this new inner index, in place. The outer dimensions marked
+    /// in `removed_outer` are removed along with their elements.
+    ///
+    /// `keep` must preserve the order of the inner indices it keeps.
+    fn compact<F>(&mut self, removed_outer: Option<&[bool]>, mut keep: F)
+    where F: FnMut(I, &N) -> Option<I>
+    {
+        let mut indptr = Vec::with_capacity(self.indptr.len());
+        indptr.push(I::zero());
+        let mut nnz = 0;
+        for outer_ind in 0..self.outer_dims() {
+            if removed_outer.map_or(false, |removed| removed[outer_ind]) {
+                continue;
+            }
+            let start = self.indptr[outer_ind].index();
+            let stop = self.indptr[outer_ind + 1].index();
+            for k in start..stop {
+                if let Some(inner) = keep(self.indices[k], &self.data[k]) {
+                    self.indices[nnz] = inner;
+                    self.data.swap(nnz, k);
+                    nnz += 1;
+                }
+            }
+            indptr.push(I::from_usize(nnz));
+        }
+        self.indices.truncate(nnz);
+        self.data.truncate(nnz);
+        let outer_dims = indptr.len() - 1;
+        self.indptr = indptr;
+        match self.storage {
+            CSR => self.nrows = outer_dims,
+            CSC => self.ncols = outer_dims,
+        }
+    }
 }
 
-impl<N: Num> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
+impl<N: Num, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
     /// Identity matrix, stored as a CSR matrix.
     ///
     /// ```rust
@@ -499,14 +712,14 @@ impl<N: Num> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
     /// let y = &eye * &x;
     /// assert_eq!(x, y);
     /// ```
-    pub fn eye(dim: usize) -> CsMatOwned<N>
+    pub fn eye(dim: usize) -> CsMatI<N, I>
     where N: Clone
     {
         let n = dim;
-        let indptr = (0..n+1).collect();
-        let indices = (0..n).collect();
+        let indptr = (0..n+1).map(I::from_usize).collect();
+        let indices = (0..n).map(I::from_usize).collect();
         let data = vec![N::one(); n];
-        CsMat {
+        CsMatBase {
             storage: CSR,
             nrows: n,
             ncols: n,
@@ -526,14 +739,14 @@ impl<N: Num> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
     /// let y = &eye * &x;
     /// assert_eq!(x, y);
     /// ```
-    pub fn eye_csc(dim: usize) -> CsMatOwned<N>
+    pub fn eye_csc(dim: usize) -> CsMatI<N, I>
     where N: Clone
     {
         let n = dim;
-        let indptr = (0..n+1).collect();
-        let indices = (0..n).collect();
+        let indptr = (0..n+1).map(I::from_usize).collect();
+        let indices = (0..n).map(I::from_usize).collect();
         let data = vec![N::one(); n];
-        CsMat {
+        CsMatBase {
             storage: CSC,
             nrows: n,
             ncols: n,
@@ -545,10 +758,11 @@ impl<N: Num> CsMat<N, Vec<usize>, Vec<usize>, Vec<N>> {
 
 }
 
-impl<N, IptrStorage, IndStorage, DataStorage>
-CsMat<N, IptrStorage, IndStorage, DataStorage>
-where IptrStorage: Deref<Target=[usize]>,
-      IndStorage: Deref<Target=[usize]>,
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
       DataStorage: Deref<Target=[N]> {
 
     /// Return an outer iterator for the matrix
@@ -565,7 +779,7 @@ where IptrStorage: Deref<Target=[usize]>,
     ///     assert_eq!(val, 1.);
     /// }
     /// ```
-    pub fn outer_iterator<'a>(&'a self) -> OuterIterator<'a, N> {
+    pub fn outer_iterator<'a>(&'a self) -> OuterIterator<'a, N, I> {
         let inner_len = match self.storage {
             CSR => self.ncols,
             CSC => self.nrows
@@ -582,8 +796,8 @@ where IptrStorage: Deref<Target=[usize]>,
     /// for iterating over the inner dimension of P*A*P^T
     /// Unstable
     pub fn outer_iterator_perm<'a, 'perm: 'a>(
-        &'a self, perm: PermView<'perm>)
-    -> OuterIteratorPerm<'a, 'perm, N> {
+        &'a self, perm: PermViewI<'perm, I>)
+    -> OuterIteratorPerm<'a, 'perm, N, I> {
         let (inner_len, oriented_perm) = match self.storage {
             CSR => (self.ncols, perm.reborrow()),
             CSC => (self.nrows, perm.reborrow_inv())
@@ -624,7 +838,7 @@ where IptrStorage: Deref<Target=[usize]>,
     /// This is often relevant for the complexity of most sparse matrix
     /// algorithms, which are often linear in the number of non-zeros.
     pub fn nnz(&self) -> usize {
-        *self.indptr.last().unwrap()
+        self.indptr.last().unwrap().index()
     }
 
     /// Number of outer dimensions, that ie equal to self.rows() for a CSR
@@ -657,25 +871,25 @@ where IptrStorage: Deref<Target=[usize]>,
     }
 
     /// Get a view into the i-th outer dimension (eg i-th row for a CSR matrix)
-    pub fn outer_view(&self, i: usize) -> Option<CsVecView<N>> {
+    pub fn outer_view(&self, i: usize) -> Option<CsVecViewI<N, I>> {
         if i >= self.outer_dims() {
             return None;
         }
-        let start = self.indptr[i];
-        let stop = self.indptr[i+1];
+        let start = self.indptr[i].index();
+        let stop = self.indptr[i+1].index();
         // safety derives from the structure checks in the constructors
         unsafe {
-            Some(CsVec::new_view_raw(self.inner_dims(),
-                                     self.indices[start..stop].len(),
-                                     self.indices[start..stop].as_ptr(),
-                                     self.data[start..stop].as_ptr()))
+            Some(CsVecBase::new_view_raw(self.inner_dims(),
+                                         self.indices[start..stop].len(),
+                                         self.indices[start..stop].as_ptr(),
+                                         self.data[start..stop].as_ptr()))
         }
     }
 
     /// Iteration on outer blocks of size block_size
     pub fn outer_block_iter(&self, block_size: usize
-                           ) -> ChunkOuterBlocks<N> {
-        let m = CsMatView {
+                           ) -> ChunkOuterBlocks<N, I> {
+        let m = CsMatBase {
             storage: self.storage,
             nrows: self.rows(),
             ncols: self.cols(),
@@ -690,6 +904,263 @@ where IptrStorage: Deref<Target=[usize]>,
         }
     }
 
+    /// View of the contiguous range of outer dimensions `range`, eg a range
+    /// of rows for a CSR matrix. This view does not copy any data.
+    ///
+    /// Contrary to `middle_outer_views`, the range may be empty.
+    ///
+    /// # Panics
+    ///
+    /// * if the range is out of bounds or decreasing
+    pub fn slice_outer(&self, range: Range<usize>) -> CsMatViewI<N, I> {
+        if range.start > range.end || range.end > self.outer_dims() {
+            panic!("Out of bounds index");
+        }
+        let count = range.end - range.start;
+        let (nrows, ncols) = match self.storage {
+            CSR => (count, self.cols()),
+            CSC => (self.rows(), count),
+        };
+        CsMatBase {
+            storage: self.storage,
+            nrows: nrows,
+            ncols: ncols,
+            indptr: &self.indptr[range.start..(range.end + 1)],
+            indices: &self.indices[..],
+            data: &self.data[..],
+        }
+    }
+
+    /// Get the k-th diagonal of this matrix as a sparse vector, holding
+    /// the stored elements at positions (i, i + k). The main diagonal is
+    /// given by `k = 0`, upper diagonals by positive `k` and lower diagonals
+    /// by negative `k`.
+    ///
+    /// The vector has the length of the diagonal, which is zero when `k`
+    /// lies outside of the matrix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMatOwned;
+    /// // | 1 0 2 |
+    /// // | 0 3 0 |
+    /// let a = CsMatOwned::new((2, 3),
+    ///                         vec![0, 2, 3],
+    ///                         vec![0, 2, 1],
+    ///                         vec![1, 2, 3]);
+    /// let d = a.diag(0);
+    /// assert_eq!(d.dim(), 2);
+    /// assert_eq!(d.data(), &[1, 3]);
+    /// assert_eq!(a.diag(2).data(), &[2]);
+    /// assert_eq!(a.diag(-1).nnz(), 0);
+    /// ```
+    pub fn diag(&self, k: isize) -> CsVecI<N, I>
+    where N: Copy
+    {
+        let (rows, cols) = (self.rows() as isize, self.cols() as isize);
+        // a diagonal starts at (row_start, col_start)
+        let (row_start, col_start) = if k >= 0 { (0, k) } else { (-k, 0) };
+        let len = cmp::max(0, cmp::min(rows - row_start, cols - col_start));
+        let len = len as usize;
+        let (outer_start, inner_start) = match self.storage {
+            CSR => (row_start as usize, col_start as usize),
+            CSC => (col_start as usize, row_start as usize),
+        };
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for pos in 0..len {
+            let vec = self.outer_view(outer_start + pos).unwrap();
+            if let Some(val) = vec.get(inner_start + pos) {
+                indices.push(I::from_usize(pos));
+                data.push(*val);
+            }
+        }
+        CsVecBase::new(len, indices, data)
+    }
+
+    /// Get the k-th diagonal of this matrix as a dense array, see `diag`.
+    pub fn diag_dense(&self, k: isize) -> Array<N, Ix>
+    where N: Num + Copy
+    {
+        let diag = self.diag(k);
+        let mut res = vec![N::zero(); diag.dim()];
+        diag.scatter(&mut res);
+        Array::from_vec(res)
+    }
+
+    /// Get an owned copy of the submatrix of the rows in `rows` and of the
+    /// columns in `cols`. The result has the storage of this matrix.
+    ///
+    /// When only the outer dimension needs to be sliced, `slice_outer`
+    /// gives a view without copying.
+    ///
+    /// # Panics
+    ///
+    /// * if a range is out of bounds or decreasing
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMatOwned;
+    /// // | 1 0 2 |
+    /// // | 0 3 0 |
+    /// // | 4 0 5 |
+    /// let a = CsMatOwned::new((3, 3),
+    ///                         vec![0, 2, 3, 5],
+    ///                         vec![0, 2, 1, 0, 2],
+    ///                         vec![1, 2, 3, 4, 5]);
+    /// let b = a.slice(1..3, 0..2);
+    /// assert_eq!(b.shape(), (2, 2));
+    /// assert_eq!(b.get(0, 1), Some(&3));
+    /// assert_eq!(b.get(1, 0), Some(&4));
+    /// assert_eq!(b.nnz(), 2);
+    /// ```
+    pub fn slice(&self, rows: Range<usize>, cols: Range<usize>)
+                 -> CsMatI<N, I>
+    where N: Clone
+    {
+        if rows.start > rows.end || rows.end > self.rows()
+           || cols.start > cols.end || cols.end > self.cols() {
+            panic!("Out of bounds index");
+        }
+        let (outer, inner) = match self.storage {
+            CSR => (rows, cols),
+            CSC => (cols, rows),
+        };
+        let mut indptr = Vec::with_capacity(outer.end - outer.start + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(I::zero());
+        for outer_ind in outer.clone() {
+            let start = self.indptr[outer_ind].index();
+            let stop = self.indptr[outer_ind + 1].index();
+            let vec_indices = &self.indices[start..stop];
+            let vec_data = &self.data[start..stop];
+            // indices are sorted, the slice is found by bisection
+            let inner_start = I::from_usize(inner.start);
+            let inner_end = I::from_usize(inner.end);
+            let lo = match vec_indices.binary_search(&inner_start) {
+                Ok(pos) | Err(pos) => pos,
+            };
+            let hi = lo + match vec_indices[lo..].binary_search(&inner_end) {
+                Ok(pos) | Err(pos) => pos,
+            };
+            indices.extend(vec_indices[lo..hi]
+                               .iter()
+                               .map(|&i| i - inner_start));
+            data.extend_from_slice(&vec_data[lo..hi]);
+            indptr.push(I::from_usize(indices.len()));
+        }
+        let (nrows, ncols) = match self.storage {
+            CSR => (outer.end - outer.start, inner.end - inner.start),
+            CSC => (inner.end - inner.start, outer.end - outer.start),
+        };
+        CsMatBase {
+            storage: self.storage,
+            nrows: nrows,
+            ncols: ncols,
+            indptr: indptr,
+            indices: indices,
+            data: data,
+        }
+    }
+
+    /// Get an owned copy of the submatrix formed by gathering the rows
+    /// `rows` and the columns `cols` of this matrix, ie the entry `(i, j)`
+    /// of the result is the entry `(rows[i], cols[j])` of this matrix.
+    ///
+    /// The indices need not be sorted, and may be repeated. The result has
+    /// the storage of this matrix.
+    ///
+    /// # Panics
+    ///
+    /// * if an index is out of bounds
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMatOwned;
+    /// // | 1 0 2 |
+    /// // | 0 3 0 |
+    /// // | 4 0 5 |
+    /// let a = CsMatOwned::new((3, 3),
+    ///                         vec![0, 2, 3, 5],
+    ///                         vec![0, 2, 1, 0, 2],
+    ///                         vec![1, 2, 3, 4, 5]);
+    /// // the corners of a
+    /// let b = a.select(&[0, 2], &[0, 2]);
+    /// assert_eq!(b.data(), &[1, 2, 4, 5]);
+    /// ```
+    pub fn select(&self, rows: &[usize], cols: &[usize]) -> CsMatI<N, I>
+    where N: Clone
+    {
+        let (outer_sel, inner_sel) = match self.storage {
+            CSR => (rows, cols),
+            CSC => (cols, rows),
+        };
+        // bucket the selected inner indices by their original value, so
+        // that the positions of inner index j in the result are
+        // inner_pos[inner_start[j]..inner_start[j + 1]]
+        let inner_dims = self.inner_dims();
+        let mut inner_start = vec![0; inner_dims + 1];
+        for &j in inner_sel {
+            if j >= inner_dims {
+                panic!("Out of bounds index");
+            }
+            inner_start[j + 1] += 1;
+        }
+        for j in 0..inner_dims {
+            inner_start[j + 1] += inner_start[j];
+        }
+        let mut next = inner_start.clone();
+        let mut inner_pos = vec![0; inner_sel.len()];
+        for (new_ind, &j) in inner_sel.iter().enumerate() {
+            inner_pos[next[j]] = new_ind;
+            next[j] += 1;
+        }
+
+        let mut indptr = Vec::with_capacity(outer_sel.len() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        let mut buf = Vec::new();
+        indptr.push(I::zero());
+        let outer_dims = self.outer_dims();
+        for &outer_ind in outer_sel {
+            if outer_ind >= outer_dims {
+                panic!("Out of bounds index");
+            }
+            let start = self.indptr[outer_ind].index();
+            let stop = self.indptr[outer_ind + 1].index();
+            // buf holds the new inner index and the position of the value
+            buf.clear();
+            for nnz_ind in start..stop {
+                let j = self.indices[nnz_ind].index();
+                for &new_ind in &inner_pos[inner_start[j]..inner_start[j + 1]] {
+                    buf.push((new_ind, nnz_ind));
+                }
+            }
+            buf.sort_by_key(|&(new_ind, _)| new_ind);
+            for &(new_ind, nnz_ind) in &buf {
+                indices.push(I::from_usize(new_ind));
+                data.push(self.data[nnz_ind].clone());
+            }
+            indptr.push(I::from_usize(indices.len()));
+        }
+        let (nrows, ncols) = match self.storage {
+            CSR => (outer_sel.len(), inner_sel.len()),
+            CSC => (inner_sel.len(), outer_sel.len()),
+        };
+        CsMatBase {
+            storage: self.storage,
+            nrows: nrows,
+            ncols: ncols,
+            indptr: indptr,
+            indices: indices,
+            data: data,
+        }
+    }
+
     /// The array of offsets in the indices() and data() slices.
     /// The elements of the slice at outer dimension i
     /// are available between the elements indptr[i] and indptr[i+1]
@@ -709,13 +1180,13 @@ where IptrStorage: Deref<Target=[usize]>,
     /// assert_eq!(eye.indices()[loc], 3);
     /// assert_eq!(eye.data()[loc], 1.);
     /// ```
-    pub fn indptr(&self) -> &[usize] {
+    pub fn indptr(&self) -> &[I] {
         &self.indptr[..]
     }
 
     /// The inner dimension location for each non-zero value. See
     /// the documentation of indptr() for more explanations.
-    pub fn indices(&self) -> &[usize] {
+    pub fn indices(&self) -> &[I] {
         &self.indices[..]
     }
 
@@ -751,8 +1222,8 @@ where IptrStorage: Deref<Target=[usize]>,
 
     /// Transposed view of this matrix
     /// No allocation required (this is simply a storage order change)
-    pub fn transpose_view(&self) -> CsMatView<N> {
-        CsMatView {
+    pub fn transpose_view(&self) -> CsMatViewI<N, I> {
+        CsMatBase {
             storage: self.storage.other_storage(),
             nrows: self.ncols,
             ncols: self.nrows,
@@ -764,10 +1235,10 @@ where IptrStorage: Deref<Target=[usize]>,
 
     /// Get an owned version of this matrix. If the matrix was already
     /// owned, this will make a deep copy.
-    pub fn to_owned(&self) -> CsMatOwned<N>
+    pub fn to_owned(&self) -> CsMatI<N, I>
     where N: Clone
     {
-        CsMatOwned {
+        CsMatBase {
             storage: self.storage,
             nrows: self.nrows,
             ncols: self.ncols,
@@ -777,7 +1248,38 @@ where IptrStorage: Deref<Target=[usize]>,
         }
     }
 
-    pub fn map<F>(&self, f: F) -> CsMatOwned<N>
+    /// Get an owned version of this matrix, using another index type.
+    ///
+    /// # Errors
+    ///
+    /// `IndexOverflow` if an index or an indptr value does not fit in the
+    /// requested index type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsMatOwned, CsMatI};
+    /// let eye : CsMatOwned<f64> = CsMat::eye(5);
+    /// let eye_u16: CsMatI<f64, u16> = eye.to_other_index().unwrap();
+    /// assert_eq!(eye_u16.indices(), &[0, 1, 2, 3, 4]);
+    /// ```
+    pub fn to_other_index<I2>(&self) -> Result<CsMatI<N, I2>, SprsError>
+    where N: Clone,
+          I2: SpIndex
+    {
+        let indptr = try!(convert_indices(&self.indptr[..]));
+        let indices = try!(convert_indices(&self.indices[..]));
+        Ok(CsMatBase {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: indptr,
+            indices: indices,
+            data: self.data.to_vec(),
+        })
+    }
+
+    pub fn map<F>(&self, f: F) -> CsMatI<N, I>
     where F: FnMut(&N) -> N,
           N: Clone
     {
@@ -786,6 +1288,46 @@ where IptrStorage: Deref<Target=[usize]>,
         res
     }
 
+    /// Get the complex conjugate of this matrix, in the same storage.
+    ///
+    /// This is a copy of the matrix for real scalars.
+    pub fn conj(&self) -> CsMatI<N, I>
+    where N: Conjugate
+    {
+        self.map(|x| x.conj())
+    }
+
+    /// Get the conjugate transpose of this matrix.
+    ///
+    /// Like `transpose_view`, this only requires a storage order change:
+    /// the result has the other storage of this matrix. Use `to_csr` or
+    /// `to_csc` afterwards to obtain a specific storage.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate num_complex;
+    /// # extern crate sprs;
+    /// # fn main() {
+    /// use num_complex::Complex64;
+    /// use sprs::CsMatOwned;
+    /// let c = Complex64::new;
+    /// let a = CsMatOwned::new((2, 2),
+    ///                         vec![0, 1, 2],
+    ///                         vec![1, 0],
+    ///                         vec![c(1., 2.), c(3., -1.)]);
+    /// let a_h = a.conj_transpose();
+    /// assert!(a_h.is_csc());
+    /// assert_eq!(a_h.get(0, 1), Some(&c(3., 1.)));
+    /// assert_eq!(a_h.get(1, 0), Some(&c(1., -2.)));
+    /// # }
+    /// ```
+    pub fn conj_transpose(&self) -> CsMatI<N, I>
+    where N: Conjugate
+    {
+        self.transpose_view().conj()
+    }
+
     /// Access an element given its outer_ind and inner_ind.
     /// Will return None if there is no non-zero element at this location.
     ///
@@ -825,7 +1367,7 @@ where IptrStorage: Deref<Target=[usize]>,
         if outer_ind >= self.outer_dims() {
             return None;
         }
-        let offset = self.indptr[outer_ind];
+        let offset = self.indptr[outer_ind].index();
         self.outer_view(outer_ind)
             .and_then(|vec| vec.nnz_index(inner_ind))
             .map(|vec::NnzIndex(ind)| NnzIndex(ind + offset))
@@ -840,30 +1382,34 @@ where IptrStorage: Deref<Target=[usize]>,
     ///   indices and indptr would take more space than the addressable memory
     /// * indices is sorted for each outer slice
     /// * indices are lower than inner_dims()
+    ///
+    /// Inconsistent lengths are reported as `BadStorageLength` and out of
+    /// bounds indices as `OutOfBoundsIndex`, so malformed data can be
+    /// rejected without panicking.
     pub fn check_compressed_structure(&self) -> Result<(), SprsError> {
         let outer = self.outer_dims();
 
         if self.indptr.len() != outer + 1 {
-            panic!("Indptr length does not match dimension");
+            return Err(SprsError::BadStorageLength);
         }
         if self.indices.len() != self.data.len() {
-            panic!("Indices and data lengths do not match");
+            return Err(SprsError::BadStorageLength);
         }
         let nnz = self.indices.len();
         if nnz != self.nnz() {
-            panic!("Indices length and inpdtr's nnz do not match");
+            return Err(SprsError::BadStorageLength);
         }
         if let Some(&max_indptr) = self.indptr.iter().max() {
-            if max_indptr > nnz {
-                panic!("An indptr value is out of bounds");
+            if max_indptr.index() > nnz {
+                return Err(SprsError::UnsortedIndptr);
             }
-            if max_indptr > usize::max_value() / 2 {
+            if max_indptr.index() > usize::max_value() / 2 {
                 // We do not allow indptr values to be larger than half
                 // the maximum value of an usize, as that would clearly exhaust
                 // all available memory
                 // This means we could have an isize, but in practice it's
                 // easier to work with usize for indexing.
-                panic!("An indptr value is larger than allowed");
+                return Err(SprsError::IndexOverflow);
             }
         }
         else {
@@ -883,8 +1429,8 @@ where IptrStorage: Deref<Target=[usize]>,
     }
 
     /// Return a view into the current matrix
-    pub fn view(&self) -> CsMatView<N> {
-        CsMat {
+    pub fn view(&self) -> CsMatViewI<N, I> {
+        CsMatBase {
             storage: self.storage,
             nrows: self.nrows,
             ncols: self.ncols,
@@ -903,24 +1449,62 @@ where IptrStorage: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IptrStorage, IndStorage, DataStorage>
-CsMat<N, IptrStorage, IndStorage, DataStorage>
+/// Build a matrix from any kind of compressed storage, checking its
+/// structure as `CsMat::new_view` does.
+pub fn checked_from_parts<N, I, IptrStorage, IndStorage, DataStorage>(
+    storage: CompressedStorage,
+    shape: Shape,
+    indptr: IptrStorage,
+    indices: IndStorage,
+    data: DataStorage)
+    -> Result<CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>, SprsError>
+where I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
+      DataStorage: Deref<Target=[N]>
+{
+    let mat = CsMatBase {
+        storage: storage,
+        nrows: shape.0,
+        ncols: shape.1,
+        indptr: indptr,
+        indices: indices,
+        data: data,
+    };
+    mat.check_compressed_structure().and(Ok(mat))
+}
+
+/// Convert a slice of indices into another index type, failing if a value
+/// does not fit in the new type.
+pub fn convert_indices<I, I2>(indices: &[I]) -> Result<Vec<I2>, SprsError>
+where I: SpIndex,
+      I2: SpIndex
+{
+    indices.iter()
+           .map(|&i| I2::try_from_usize(i.index()))
+           .collect::<Option<Vec<_>>>()
+           .ok_or(SprsError::IndexOverflow)
+}
+
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
 where N: Default,
-      IptrStorage: Deref<Target=[usize]>,
-      IndStorage: Deref<Target=[usize]>,
+      I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
       DataStorage: Deref<Target=[N]> {
 
     /// Create a matrix mathematically equal to this one, but with the
     /// opposed storage (a CSC matrix will be converted to CSR, and vice versa)
-    pub fn to_other_storage(&self) -> CsMatOwned<N>
+    pub fn to_other_storage(&self) -> CsMatI<N, I>
     where N: Clone
     {
-        let mut indptr = vec![0; self.inner_dims() + 1];
-        let mut indices = vec![0; self.nnz()];
+        let mut indptr = vec![I::zero(); self.inner_dims() + 1];
+        let mut indices = vec![I::zero(); self.nnz()];
         let mut data = vec![N::default(); self.nnz()];
         raw::convert_mat_storage(self.view(),
                                  &mut indptr, &mut indices, &mut data);
-        CsMat {
+        CsMatBase {
             storage: self.storage().other_storage(),
             nrows: self.nrows,
             ncols: self.ncols,
@@ -932,7 +1516,7 @@ where N: Default,
 
     /// Create a new CSC matrix equivalent to this one.
     /// A new matrix will be created even if this matrix was already CSC.
-    pub fn to_csc(&self) -> CsMatOwned<N>
+    pub fn to_csc(&self) -> CsMatI<N, I>
     where N: Clone
     {
         match self.storage {
@@ -943,7 +1527,7 @@ where N: Default,
 
     /// Create a new CSR matrix equivalent to this one.
     /// A new matrix will be created even if this matrix was already CSR.
-    pub fn to_csr(&self) -> CsMatOwned<N>
+    pub fn to_csr(&self) -> CsMatI<N, I>
     where N: Clone
     {
         match self.storage {
@@ -954,11 +1538,12 @@ where N: Default,
 
 }
 
-impl<N, IptrStorage, IndStorage, DataStorage>
-CsMat<N, IptrStorage, IndStorage, DataStorage>
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
 where
-IptrStorage: Deref<Target=[usize]>,
-IndStorage: Deref<Target=[usize]>,
+I: SpIndex,
+IptrStorage: Deref<Target=[I]>,
+IndStorage: Deref<Target=[I]>,
 DataStorage: DerefMut<Target=[N]> {
 
     /// Mutable access to the non zero values
@@ -973,20 +1558,82 @@ DataStorage: DerefMut<Target=[N]> {
         }
     }
 
+    /// Multiply this matrix in place by a diagonal matrix on the left,
+    /// ie compute `D * A` where `D` has the diagonal `diag`. This scales
+    /// the row `i` by `diag[i]`.
+    ///
+    /// # Panics
+    ///
+    /// - if `diag` does not have one element per row
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsMatOwned};
+    /// let mut a: CsMatOwned<f64> = CsMat::eye(3);
+    /// a.scale_rows(&[1., 2., 3.]);
+    /// assert_eq!(a.data(), &[1., 2., 3.]);
+    /// ```
+    pub fn scale_rows(&mut self, diag: &[N])
+    where N: Num + Copy
+    {
+        if diag.len() != self.rows() {
+            panic!("Dimension mismatch");
+        }
+        self.scale_lines(diag, self.is_csr());
+    }
+
+    /// Multiply this matrix in place by a diagonal matrix on the right,
+    /// ie compute `A * D` where `D` has the diagonal `diag`. This scales
+    /// the column `j` by `diag[j]`.
+    ///
+    /// # Panics
+    ///
+    /// - if `diag` does not have one element per column
+    pub fn scale_cols(&mut self, diag: &[N])
+    where N: Num + Copy
+    {
+        if diag.len() != self.cols() {
+            panic!("Dimension mismatch");
+        }
+        self.scale_lines(diag, self.is_csc());
+    }
+
+    fn scale_lines(&mut self, diag: &[N], lines_are_outer: bool)
+    where N: Num + Copy
+    {
+        for outer_ind in 0..self.outer_dims() {
+            let start = self.indptr[outer_ind].index();
+            let stop = self.indptr[outer_ind + 1].index();
+            let indices = &self.indices[start..stop];
+            let data = &mut self.data[start..stop];
+            for (&inner_ind, val) in indices.iter().zip(data.iter_mut()) {
+                let factor = if lines_are_outer {
+                    diag[outer_ind]
+                } else {
+                    diag[inner_ind.index()]
+                };
+                *val = *val * factor;
+            }
+        }
+    }
+
     /// Get a mutable view into the i-th outer dimension
     /// (eg i-th row for a CSR matrix)
-    pub fn outer_view_mut(&mut self, i: usize) -> Option<CsVecViewMut<N>> {
+    pub fn outer_view_mut(&mut self, i: usize)
+                          -> Option<CsVecViewMutI<N, I>> {
         if i >= self.outer_dims() {
             return None;
         }
-        let start = self.indptr[i];
-        let stop = self.indptr[i+1];
+        let start = self.indptr[i].index();
+        let stop = self.indptr[i+1].index();
         // safety derives from the structure checks in the constructors
         unsafe {
-            Some(CsVec::new_view_mut_raw(self.inner_dims(),
-                                         self.indices[start..stop].len(),
-                                         self.indices[start..stop].as_ptr(),
-                                         self.data[start..stop].as_mut_ptr()))
+            Some(CsVecBase::new_view_mut_raw(
+                self.inner_dims(),
+                self.indices[start..stop].len(),
+                self.indices[start..stop].as_ptr(),
+                self.data[start..stop].as_mut_ptr()))
         }
     }
 
@@ -1071,14 +1718,17 @@ pub mod raw {
     ///
     /// Panics if the output slices don't match the input matrices'
     /// corresponding slices.
-    pub fn convert_storage<N: Clone>(in_storage: super::CompressedStorage,
-                                     shape: Shape,
-                                     in_indtpr: &[usize],
-                                     in_indices: &[usize],
-                                     in_data: &[N],
-                                     indptr: &mut [usize],
-                                     indices: &mut[usize],
-                                     data: &mut [N]) {
+    pub fn convert_storage<N, I>(in_storage: super::CompressedStorage,
+                                 shape: Shape,
+                                 in_indtpr: &[I],
+                                 in_indices: &[I],
+                                 in_data: &[N],
+                                 indptr: &mut [I],
+                                 indices: &mut[I],
+                                 data: &mut [N])
+    where N: Clone,
+          I: SpIndex
+    {
         // we're building a csmat even though the indices are not sorted,
         // but it's not a problem since we don't rely on this property.
         // FIXME: this would be better with an explicit unsorted matrix type
@@ -1098,61 +1748,65 @@ pub mod raw {
     ///
     /// Panics if the output slices don't match the input matrices'
     /// corresponding slices.
-    pub fn convert_mat_storage<N: Clone>(mat: CsMatView<N>,
-                                        indptr: &mut [usize],
-                                        indices: &mut[usize],
-                                        data: &mut [N]) {
+    pub fn convert_mat_storage<N, I>(mat: CsMatViewI<N, I>,
+                                     indptr: &mut [I],
+                                     indices: &mut[I],
+                                     data: &mut [N])
+    where N: Clone,
+          I: SpIndex
+    {
         assert_eq!(indptr.len(), mat.inner_dims() + 1);
         assert_eq!(indices.len(), mat.indices().len());
         assert_eq!(data.len(), mat.data().len());
 
-        assert!(indptr.iter().all(|x| *x == 0));
+        assert!(indptr.iter().all(|x| x.is_zero()));
 
         for vec in mat.outer_iterator() {
             for (inner_dim, _) in vec.iter() {
-                indptr[inner_dim] += 1;
+                indptr[inner_dim] = indptr[inner_dim] + I::one();
             }
         }
 
-        let mut cumsum = 0;
+        let mut cumsum = I::zero();
         for iptr in indptr.iter_mut() {
             let tmp = *iptr;
             *iptr = cumsum;
-            cumsum += tmp;
+            cumsum = cumsum + tmp;
         }
         if let Some(last_iptr) = indptr.last() {
-            assert_eq!(*last_iptr, mat.nnz());
+            assert_eq!(last_iptr.index(), mat.nnz());
         }
 
         for (outer_dim, vec) in mat.outer_iterator().enumerate() {
             for (inner_dim, val) in vec.iter() {
-                let dest = indptr[inner_dim];
+                let dest = indptr[inner_dim].index();
                 data[dest] = val.clone();
-                indices[dest] = outer_dim;
-                indptr[inner_dim] += 1;
+                indices[dest] = I::from_usize(outer_dim);
+                indptr[inner_dim] = indptr[inner_dim] + I::one();
             }
         }
 
-        let mut last = 0;
+        let mut last = I::zero();
         for iptr in indptr.iter_mut() {
             swap(iptr, &mut last);
         }
     }
 }
 
-impl<'a, 'b, N, IpStorage, IStorage, DStorage, IpS2, IS2, DS2>
-Add<&'b CsMat<N, IpS2, IS2, DS2>>
-for &'a CsMat<N, IpStorage, IStorage, DStorage>
+impl<'a, 'b, N, I, IpStorage, IStorage, DStorage, IpS2, IS2, DS2>
+Add<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
+for &'a CsMatBase<N, I, IpStorage, IStorage, DStorage>
 where N: 'a + Copy + Num + Default,
-      IpStorage: 'a + Deref<Target=[usize]>,
-      IStorage: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IpStorage: 'a + Deref<Target=[I]>,
+      IStorage: 'a + Deref<Target=[I]>,
       DStorage: 'a + Deref<Target=[N]>,
-      IpS2: 'a + Deref<Target=[usize]>,
-      IS2: 'a + Deref<Target=[usize]>,
+      IpS2: 'a + Deref<Target=[I]>,
+      IS2: 'a + Deref<Target=[I]>,
       DS2: 'a + Deref<Target=[N]> {
-    type Output = CsMatOwned<N>;
+    type Output = CsMatI<N, I>;
 
-    fn add(self, rhs: &'b CsMat<N, IpS2, IS2, DS2>) -> CsMatOwned<N> {
+    fn add(self, rhs: &'b CsMatBase<N, I, IpS2, IS2, DS2>) -> CsMatI<N, I> {
         if self.storage() != rhs.view().storage() {
             return binop::add_mat_same_storage(
                 self, &rhs.view().to_other_storage())
@@ -1161,16 +1815,17 @@ where N: 'a + Copy + Num + Default,
     }
 }
 
-impl<'a, 'b, N, IpStorage, IStorage, DStorage, Mat> Sub<&'b Mat>
-for &'a CsMat<N, IpStorage, IStorage, DStorage>
+impl<'a, 'b, N, I, IpStorage, IStorage, DStorage, Mat> Sub<&'b Mat>
+for &'a CsMatBase<N, I, IpStorage, IStorage, DStorage>
 where N: 'a + Copy + Num + Default,
-      IpStorage: 'a + Deref<Target=[usize]>,
-      IStorage: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IpStorage: 'a + Deref<Target=[I]>,
+      IStorage: 'a + Deref<Target=[I]>,
       DStorage: 'a + Deref<Target=[N]>,
-      Mat: SpMatView<N> {
-    type Output = CsMatOwned<N>;
+      Mat: SpMatView<N, I> {
+    type Output = CsMatI<N, I>;
 
-    fn sub(self, rhs: &'b Mat) -> CsMatOwned<N> {
+    fn sub(self, rhs: &'b Mat) -> CsMatI<N, I> {
         if self.storage() != rhs.view().storage() {
             return binop::sub_mat_same_storage(
                 self, &rhs.view().to_other_storage())
@@ -1181,14 +1836,15 @@ where N: 'a + Copy + Num + Default,
 
 macro_rules! sparse_scalar_mul {
     ($scalar: ident) => (
-        impl<'a, IpStorage, IStorage, DStorage> Mul<$scalar>
-        for &'a CsMat<$scalar, IpStorage, IStorage, DStorage>
-        where IpStorage: 'a + Deref<Target=[usize]>,
-              IStorage: 'a + Deref<Target=[usize]>,
+        impl<'a, I, IpStorage, IStorage, DStorage> Mul<$scalar>
+        for &'a CsMatBase<$scalar, I, IpStorage, IStorage, DStorage>
+        where I: 'a + SpIndex,
+              IpStorage: 'a + Deref<Target=[I]>,
+              IStorage: 'a + Deref<Target=[I]>,
               DStorage: 'a + Deref<Target=[$scalar]> {
-            type Output = CsMatOwned<$scalar>;
+            type Output = CsMatI<$scalar, I>;
 
-            fn mul(self, rhs: $scalar) -> CsMatOwned<$scalar> {
+            fn mul(self, rhs: $scalar) -> CsMatI<$scalar, I> {
                 binop::scalar_mul_mat(self, rhs)
             }
         }
@@ -1204,19 +1860,20 @@ sparse_scalar_mul!(usize);
 sparse_scalar_mul!(f32);
 sparse_scalar_mul!(f64);
 
-impl<'a, 'b, N, IpS1, IS1, DS1, IpS2, IS2, DS2>
-Mul<&'b CsMat<N, IpS2, IS2, DS2>>
-for &'a CsMat<N, IpS1, IS1, DS1>
+impl<'a, 'b, N, I, IpS1, IS1, DS1, IpS2, IS2, DS2>
+Mul<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
+for &'a CsMatBase<N, I, IpS1, IS1, DS1>
 where N: 'a + Copy + Num + Default,
-      IpS1: 'a + Deref<Target=[usize]>,
-      IS1: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IpS1: 'a + Deref<Target=[I]>,
+      IS1: 'a + Deref<Target=[I]>,
       DS1: 'a + Deref<Target=[N]>,
-      IpS2: 'b + Deref<Target=[usize]>,
-      IS2: 'b + Deref<Target=[usize]>,
+      IpS2: 'b + Deref<Target=[I]>,
+      IS2: 'b + Deref<Target=[I]>,
       DS2: 'b + Deref<Target=[N]> {
-    type Output = CsMatOwned<N>;
+    type Output = CsMatI<N, I>;
 
-    fn mul(self, rhs: &'b CsMat<N, IpS2, IS2, DS2>) -> CsMatOwned<N> {
+    fn mul(self, rhs: &'b CsMatBase<N, I, IpS2, IS2, DS2>) -> CsMatI<N, I> {
         match (self.storage(), rhs.storage()) {
             (CSR, CSR) => {
                 let mut workspace = prod::workspace_csr(self, rhs);
@@ -1241,12 +1898,13 @@ where N: 'a + Copy + Num + Default,
     }
 }
 
-impl<'a, 'b, N, IpS, IS, DS, DS2>
+impl<'a, 'b, N, I, IpS, IS, DS, DS2>
 Add<&'b ArrayBase<DS2, (Ix, Ix)>>
-for &'a CsMat<N, IpS, IS, DS>
+for &'a CsMatBase<N, I, IpS, IS, DS>
 where N: 'a + Copy + Num + Default,
-      IpS: 'a + Deref<Target=[usize]>,
-      IS: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IpS: 'a + Deref<Target=[I]>,
+      IS: 'a + Deref<Target=[I]>,
       DS: 'a + Deref<Target=[N]>,
       DS2: 'b + ndarray::Data<Elem=N> {
     type Output = Array<N, (Ix, Ix)>;
@@ -1287,12 +1945,13 @@ where N: 'a + Copy + Num + Default,
     }
 }
 
-impl<'a, 'b, N, IpS, IS, DS, DS2>
+impl<'a, 'b, N, I, IpS, IS, DS, DS2>
 Mul<&'b ArrayBase<DS2, (Ix, Ix)>>
-for &'a CsMat<N, IpS, IS, DS>
+for &'a CsMatBase<N, I, IpS, IS, DS>
 where N: 'a + Copy + Num + Default,
-      IpS: 'a + Deref<Target=[usize]>,
-      IS: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IpS: 'a + Deref<Target=[I]>,
+      IS: 'a + Deref<Target=[I]>,
       DS: 'a + Deref<Target=[N]>,
       DS2: 'b + ndarray::Data<Elem=N> {
     type Output = Array<N, (Ix, Ix)>;
@@ -1337,9 +1996,10 @@ where N: 'a + Copy + Num + Default,
     }
 }
 
-impl<N, IpS, IS, DS> Index<[usize; 2]> for CsMat<N, IpS, IS, DS>
-where IpS: Deref<Target=[usize]>,
-      IS: Deref<Target=[usize]>,
+impl<N, I, IpS, IS, DS> Index<[usize; 2]> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
       DS: Deref<Target=[N]>
 {
     type Output = N;
@@ -1351,9 +2011,10 @@ where IpS: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IpS, IS, DS> IndexMut<[usize; 2]> for CsMat<N, IpS, IS, DS>
-where IpS: Deref<Target=[usize]>,
-      IS: Deref<Target=[usize]>,
+impl<N, I, IpS, IS, DS> IndexMut<[usize; 2]> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
       DS: DerefMut<Target=[N]>
 {
     fn index_mut(&mut self, index: [usize; 2]) -> &mut N {
@@ -1364,9 +2025,10 @@ where IpS: Deref<Target=[usize]>,
 }
 
 
-impl<N, IpS, IS, DS> Index<NnzIndex> for CsMat<N, IpS, IS, DS>
-where IpS: Deref<Target=[usize]>,
-      IS: Deref<Target=[usize]>,
+impl<N, I, IpS, IS, DS> Index<NnzIndex> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
       DS: Deref<Target=[N]>
 {
     type Output = N;
@@ -1377,9 +2039,10 @@ where IpS: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IpS, IS, DS> IndexMut<NnzIndex> for CsMat<N, IpS, IS, DS>
-where IpS: Deref<Target=[usize]>,
-      IS: Deref<Target=[usize]>,
+impl<N, I, IpS, IS, DS> IndexMut<NnzIndex> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
       DS: DerefMut<Target=[N]>
 {
     fn index_mut(&mut self, index: NnzIndex) -> &mut N {
@@ -1390,18 +2053,19 @@ where IpS: Deref<Target=[usize]>,
 
 /// An iterator over non-overlapping blocks of a matrix,
 /// along the least-varying dimension
-pub struct ChunkOuterBlocks<'a, N: 'a> {
-    mat: CsMatView<'a, N>,
+pub struct ChunkOuterBlocks<'a, N: 'a, I: 'a + SpIndex> {
+    mat: CsMatViewI<'a, N, I>,
     dims_in_bloc: usize,
     bloc_count: usize,
 }
 
-impl<'a, N: 'a> Iterator for ChunkOuterBlocks<'a, N> {
-    type Item = CsMatView<'a, N>;
+impl<'a, N: 'a, I: 'a + SpIndex> Iterator for ChunkOuterBlocks<'a, N, I> {
+    type Item = CsMatViewI<'a, N, I>;
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
         let cur_dim = self.dims_in_bloc * self.bloc_count;
         let end_dim = self.dims_in_bloc + cur_dim;
-        let count = if self.dims_in_bloc == 0 {
+        let count = if self.dims_in_bloc == 0
+                       || cur_dim >= self.mat.outer_dims() {
             return None;
         }
         else if end_dim > self.mat.outer_dims() {
@@ -1419,9 +2083,52 @@ impl<'a, N: 'a> Iterator for ChunkOuterBlocks<'a, N> {
 }
 
 
+/// Deserialized form of a CsMatBase, whose structure has not been checked
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub struct CsMatBaseShadow<N, I, IptrStorage, IndStorage, DataStorage>
+where I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
+      DataStorage: Deref<Target=[N]> {
+    storage: CompressedStorage,
+    nrows: usize,
+    ncols: usize,
+    indptr: IptrStorage,
+    indices: IndStorage,
+    data: DataStorage,
+    #[serde(skip)]
+    marker: PhantomData<(N, I)>,
+}
+
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+TryFrom<CsMatBaseShadow<N, I, IptrStorage, IndStorage, DataStorage>>
+for CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
+      DataStorage: Deref<Target=[N]> {
+    type Error = SprsError;
+
+    fn try_from(shadow: CsMatBaseShadow<N, I, IptrStorage, IndStorage,
+                                        DataStorage>)
+                -> Result<Self, SprsError> {
+        checked_from_parts(shadow.storage,
+                           (shadow.nrows, shadow.ncols),
+                           shadow.indptr,
+                           shadow.indices,
+                           shadow.data)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use sparse::{CsMat, CsMatOwned};
+    use ndarray::arr1;
+    use num_complex::Complex64;
+    use sparse::{CsMat, CsMatOwned, CsMatI};
     use super::CompressedStorage::{CSC, CSR};
     use errors::SprsError;
     use test_data::{mat1, mat1_csc, mat1_times_2};
@@ -1529,6 +2236,19 @@ mod test {
                    Err(SprsError::UnsortedIndptr));
     }
 
+    #[test]
+    fn test_new_view_malformed_errors() {
+        let indptr: &[usize] = &[0, 1, 2, 3];
+        let indices: &[usize] = &[0, 1, 2];
+        let data: &[f64] = &[1., 1., 1.];
+        assert_eq!(CsMat::new_view(CSR, (2, 3), indptr, indices, data),
+                   Err(SprsError::BadStorageLength));
+        assert_eq!(CsMat::new_view(CSR, (3, 3), indptr, indices, &data[..2]),
+                   Err(SprsError::BadStorageLength));
+        assert_eq!(CsMat::new_view(CSR, (3, 3), indptr, &[0, 1, 3], data),
+                   Err(SprsError::OutOfBoundsIndex));
+    }
+
     #[test]
     fn test_new_csr_fail_indices_ordering() {
         let indptr: &[usize] = &[0, 2, 4, 5, 6, 7];
@@ -1641,6 +2361,277 @@ mod test {
         block_iter.next().unwrap();
         block_iter.next().unwrap();
         assert_eq!(block_iter.next(), None);
+
+        let mat : CsMatOwned<f64> = CsMat::eye(12);
+        assert_eq!(mat.outer_block_iter(4).count(), 3);
+
+        let mat = mat1_csc();
+        let view = mat.view();
+        let mut block_iter = view.outer_block_iter(2);
+        for j_start in &[0, 2] {
+            let block = block_iter.next().unwrap();
+            assert_eq!(block.shape(), (5, 2));
+            for i in 0..5 {
+                for j in 0..2 {
+                    assert_eq!(block.get(i, j), mat.get(i, j_start + j));
+                }
+            }
+        }
+        let block = block_iter.next().unwrap();
+        assert_eq!(block.shape(), (5, 1));
+        assert_eq!(block.get(2, 0), mat.get(2, 4));
+        assert_eq!(block_iter.next(), None);
+    }
+
+    #[test]
+    fn middle_outer_views_csc() {
+        let mat = mat1_csc();
+        let view = mat.view();
+        let block = view.middle_outer_views(1, 3);
+        assert_eq!(block.shape(), (5, 3));
+        for i in 0..5 {
+            for j in 0..3 {
+                assert_eq!(block.get(i, j), mat.get(i, j + 1));
+            }
+        }
+        let block = view.middle_outer_views(0, 5);
+        assert_eq!(block, view);
+    }
+
+    #[test]
+    fn slice_outer() {
+        let mat = mat1();
+        let view = mat.slice_outer(1..4);
+        assert_eq!(view.shape(), (3, 5));
+        for i in 0..3 {
+            for j in 0..5 {
+                assert_eq!(view.get(i, j), mat.get(i + 1, j));
+            }
+        }
+        assert_eq!(mat.slice_outer(0..5), mat.view());
+        let empty = mat.slice_outer(2..2);
+        assert_eq!(empty.shape(), (0, 5));
+        assert_eq!(empty.outer_iterator().count(), 0);
+
+        let mat = mat1_csc();
+        let view = mat.slice_outer(3..5);
+        assert_eq!(view.shape(), (5, 2));
+        assert_eq!(view.get(4, 0), mat.get(4, 3));
+    }
+
+    #[test]
+    fn slice() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        let mat = mat1();
+        let expected = CsMatOwned::new((3, 3),
+                                       vec![0, 2, 3, 3],
+                                       vec![1, 2, 0],
+                                       vec![2., 5., 5.]);
+        assert_eq!(mat.slice(1..4, 2..5), expected);
+        assert_eq!(mat1_csc().slice(1..4, 2..5), expected.to_csc());
+        assert_eq!(mat.slice(0..5, 0..5), mat);
+        assert_eq!(mat.slice(0..5, 0..1).nnz(), 0);
+
+        let empty = mat1_csc().slice(2..2, 1..4);
+        assert_eq!(empty.shape(), (0, 3));
+        assert_eq!(empty.nnz(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_out_of_bounds() {
+        mat1().slice(0..6, 0..2);
+    }
+
+    #[test]
+    fn select() {
+        let mat = mat1();
+        let expected = CsMatOwned::new((3, 3),
+                                       vec![0, 2, 5, 8],
+                                       vec![0, 2, 0, 1, 2, 0, 1, 2],
+                                       vec![7., 7., 4., 3., 4., 4., 3., 4.]);
+        let res = mat.select(&[4, 0, 0], &[3, 2, 3]);
+        assert_eq!(res, expected);
+        let res = mat1_csc().select(&[4, 0, 0], &[3, 2, 3]);
+        assert_eq!(res, expected.to_csc());
+
+        // selecting with ranges is slicing
+        let rows: Vec<_> = (1..4).collect();
+        let cols: Vec<_> = (2..5).collect();
+        assert_eq!(mat.select(&rows, &cols), mat.slice(1..4, 2..5));
+
+        let empty = mat.select(&[], &[1, 2]);
+        assert_eq!(empty.shape(), (0, 2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn select_out_of_bounds() {
+        mat1().select(&[0, 1], &[5]);
+    }
+
+    #[test]
+    fn diag() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        for mat in &[mat1(), mat1_csc()] {
+            let d = mat.diag(0);
+            assert_eq!(d.dim(), 5);
+            assert_eq!(d.indices(), &[2]);
+            assert_eq!(d.data(), &[5.]);
+            assert_eq!(mat.diag(1).nnz(), 0);
+            let d = mat.diag(2);
+            assert_eq!(d.dim(), 3);
+            assert_eq!(d.indices(), &[0, 1]);
+            assert_eq!(d.data(), &[3., 2.]);
+            assert_eq!(mat.diag_dense(-1), arr1(&[0., 0., 0., 7.]));
+            assert_eq!(mat.diag_dense(-2), arr1(&[0., 8., 0.]));
+            assert_eq!(mat.diag(4).dim(), 1);
+            assert_eq!(mat.diag(5).dim(), 0);
+            assert_eq!(mat.diag(-7).dim(), 0);
+        }
+        let rect = mat1().slice(0..2, 0..5);
+        assert_eq!(rect.diag_dense(3), arr1(&[4., 5.]));
+        assert_eq!(rect.diag_dense(-1), arr1(&[0.]));
+    }
+
+    #[test]
+    fn scale_rows_cols() {
+        let d_rows = [1., 2., 3., 4., 5.];
+        let d_cols = [-1., 1., 0.5, 2., 3.];
+        for mat in &[mat1(), mat1_csc()] {
+            let mut res = mat.to_owned();
+            res.scale_rows(&d_rows);
+            let mut expected = mat.to_dense();
+            for (i, mut row) in expected.outer_iter_mut().enumerate() {
+                for x in row.iter_mut() {
+                    *x *= d_rows[i];
+                }
+            }
+            assert_eq!(res.to_dense(), expected);
+
+            res.scale_cols(&d_cols);
+            for mut row in expected.outer_iter_mut() {
+                for (j, x) in row.iter_mut().enumerate() {
+                    *x *= d_cols[j];
+                }
+            }
+            assert_eq!(res.to_dense(), expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn scale_rows_dim_mismatch() {
+        mat1().scale_rows(&[1., 2.]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn scale_cols_dim_mismatch() {
+        mat1().scale_cols(&[1., 2.]);
+    }
+
+    #[test]
+    fn insert_remove() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        for &csr in &[true, false] {
+            let mut mat = if csr { mat1() } else { mat1_csc() };
+            mat.insert(1, 0, 1.);
+            mat.insert(4, 4, 9.);
+            mat.insert(0, 3, 6.);
+            assert_eq!(mat.nnz(), 9);
+            assert_eq!(mat.get(1, 0), Some(&1.));
+            assert_eq!(mat.get(4, 4), Some(&9.));
+            assert_eq!(mat.get(0, 3), Some(&6.));
+            assert!(mat.check_compressed_structure().is_ok());
+
+            assert_eq!(mat.remove(1, 0), Some(1.));
+            assert_eq!(mat.remove(4, 4), Some(9.));
+            assert_eq!(mat.remove(4, 4), None);
+            mat.insert(0, 3, 4.);
+            let expected = if csr { mat1() } else { mat1_csc() };
+            assert_eq!(mat, expected);
+
+            assert_eq!(mat.remove(3, 1), Some(8.));
+            assert_eq!(mat.nnz(), 6);
+            assert_eq!(mat.outer_view(if csr { 3 } else { 1 }).unwrap().nnz(),
+                       0);
+            assert!(mat.check_compressed_structure().is_ok());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut mat = mat1();
+        mat.insert(5, 0, 1.);
+    }
+
+    #[test]
+    fn prune() {
+        let mut mat = CsMatOwned::new((3, 3),
+                                      vec![0, 2, 4, 5],
+                                      vec![0, 2, 0, 1, 2],
+                                      vec![1., 0., 1e-14, 0., 3.]);
+        let mut mat2 = mat.to_owned();
+        mat.eliminate_zeros();
+        let expected = CsMatOwned::new((3, 3),
+                                       vec![0, 1, 2, 3],
+                                       vec![0, 0, 2],
+                                       vec![1., 1e-14, 3.]);
+        assert_eq!(mat, expected);
+        mat2.prune(|&x: &f64| x.abs() < 1e-12);
+        let expected = CsMatOwned::new((3, 3),
+                                       vec![0, 1, 1, 2],
+                                       vec![0, 2],
+                                       vec![1., 3.]);
+        assert_eq!(mat2, expected);
+    }
+
+    #[test]
+    fn remove_rows_cols() {
+        // removing rows 1 and 3, and columns 0 and 4 of mat1 gives
+        // | 0 3 4 |
+        // | 0 5 0 |
+        // | 0 0 7 |
+        let expected = CsMatOwned::new((3, 3),
+                                       vec![0, 2, 3, 4],
+                                       vec![1, 2, 1, 2],
+                                       vec![3., 4., 5., 7.]);
+        for &csr in &[true, false] {
+            let mut mat = if csr { mat1() } else { mat1_csc() };
+            mat.remove_rows(&[3, 1, 3]);
+            mat.remove_cols(&[4, 0]);
+            assert_eq!(mat.shape(), (3, 3));
+            assert!(mat.check_compressed_structure().is_ok());
+            assert_eq!(mat.to_csr(), expected);
+
+            mat.remove_cols(&[0, 2]);
+            assert_eq!(mat.shape(), (3, 1));
+            assert_eq!(mat.to_csr().data(), &[3., 5.]);
+            mat.remove_rows(&[0, 1, 2]);
+            assert_eq!(mat.shape(), (0, 1));
+            assert_eq!(mat.nnz(), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_rows_out_of_bounds() {
+        let mut mat = mat1_csc();
+        mat.remove_rows(&[5]);
     }
 
     #[test]
@@ -1726,4 +2717,108 @@ mod test {
         res.map_inplace(|&x| x / 3.);
         assert_eq!(res, mat);
     }
+
+    #[test]
+    fn conj_transpose() {
+        // | 1+i  0  |
+        // | 2-i  3i |
+        let c = Complex64::new;
+        let mat = CsMatOwned::new((2, 2),
+                                  vec![0, 1, 3],
+                                  vec![0, 0, 1],
+                                  vec![c(1., 1.), c(2., -1.), c(0., 3.)]);
+        let conj = mat.conj();
+        assert!(conj.is_csr());
+        assert_eq!(conj.data(), &[c(1., -1.), c(2., 1.), c(0., -3.)]);
+
+        let mat_h = mat.conj_transpose();
+        let expected = CsMatOwned::new((2, 2),
+                                       vec![0, 2, 3],
+                                       vec![0, 1, 1],
+                                       vec![c(1., -1.), c(2., 1.), c(0., -3.)]);
+        assert!(mat_h.is_csc());
+        assert_eq!(mat_h.to_csr(), expected);
+        assert_eq!(mat_h.conj_transpose(), mat);
+
+        // real matrices are simply transposed
+        let mat = mat1();
+        assert_eq!(mat.conj_transpose().view(), mat.transpose_view());
+    }
+
+    #[test]
+    fn smaller_index_types() {
+        let a: CsMatI<f64, u32> = mat1().to_other_index().unwrap();
+        let b: CsMatI<f64, u16> = mat1_csc().to_other_index().unwrap();
+        assert_eq!(a.indptr(), &[0, 2, 4, 5, 6, 7]);
+        assert_eq!(a.get(1, 4), Some(&5.));
+        assert_eq!(b.get(1, 4), Some(&5.));
+        assert_eq!(b.to_csr().to_other_index(), Ok(mat1()));
+
+        let a_times_2 = &a * 2.;
+        assert_eq!(a_times_2.to_other_index(), Ok(mat1_times_2()));
+        let sum = &a + &a;
+        assert_eq!(sum, a_times_2);
+        let prod = &a * &a;
+        let expected: CsMatI<f64, u32> = (&mat1() * &mat1())
+            .to_other_index().unwrap();
+        assert_eq!(prod, expected);
+    }
+
+    #[test]
+    fn to_other_index_overflow() {
+        let eye: CsMatOwned<f64> = CsMat::eye(70000);
+        let res: Result<CsMatI<f64, u16>, _> = eye.to_other_index();
+        assert_eq!(res, Err(SprsError::IndexOverflow));
+        let res: Result<CsMatI<f64, u32>, _> = eye.to_other_index();
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        use serde_json;
+        let mat = mat1();
+        let json = serde_json::to_string(&mat).unwrap();
+        let mat2: CsMatOwned<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(mat, mat2);
+
+        let mat = mat1_csc().to_other_index::<u32>().unwrap();
+        let json = serde_json::to_string(&mat.view()).unwrap();
+        let mat2: CsMatI<f64, u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(mat, mat2);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_corrupt() {
+        use serde_json;
+        fn parse(json: &str) -> Result<CsMatOwned<f64>, serde_json::Error> {
+            serde_json::from_str(json)
+        }
+        let ok = r#"{"storage":"CSR","nrows":2,"ncols":3,
+                     "indptr":[0,2,3],"indices":[0,2,1],"data":[1,2,3]}"#;
+        assert!(parse(ok).is_ok());
+        let corrupt = [
+            // unsorted indices
+            r#"{"storage":"CSR","nrows":2,"ncols":3,
+                "indptr":[0,2,3],"indices":[2,0,1],"data":[1,2,3]}"#,
+            // out of bounds index
+            r#"{"storage":"CSR","nrows":2,"ncols":3,
+                "indptr":[0,2,3],"indices":[0,3,1],"data":[1,2,3]}"#,
+            // unsorted indptr
+            r#"{"storage":"CSR","nrows":2,"ncols":3,
+                "indptr":[0,4,3],"indices":[0,2,1],"data":[1,2,3]}"#,
+            // bad indptr length
+            r#"{"storage":"CSC","nrows":2,"ncols":3,
+                "indptr":[0,2,3],"indices":[0,2,1],"data":[1,2,3]}"#,
+            // bad nnz
+            r#"{"storage":"CSR","nrows":2,"ncols":3,
+                "indptr":[0,2,3],"indices":[0,2,1],"data":[1,2]}"#,
+            r#"{"storage":"CSR","nrows":2,"ncols":3,
+                "indptr":[0,2,2],"indices":[0,2,1],"data":[1,2,3]}"#,
+        ];
+        for json in corrupt.iter() {
+            assert!(parse(json).is_err());
+        }
+    }
 }
@@ -1,10 +1,36 @@
 ///! Data structures to work with elimination trees (etree).
 ///! etrees arise when considering cholesky factorization, QR factorization, ...
+///!
+///! The elimination tree of a symmetric matrix is computed by
+///! `Parents::from_symmetric`, and the column elimination tree of a
+///! rectangular matrix, ie the elimination tree of `A^T A`, by
+///! `Parents::from_ata`. Once computed, a tree can be traversed in postorder,
+///! and gives the row and column counts of the cholesky factor without
+///! computing it, through `Parents::cholesky_counts`.
 
 use std::ops::{Deref, DerefMut};
 
+use indexing::SpIndex;
+use sparse::CsMatBase;
+use sparse::permutation::PermutationBase;
+
 pub type Parent = Option<usize>;
 
+/// The number of non-zero entries in each row and in each column of a
+/// cholesky factor L, including its diagonal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactorCounts {
+    pub row_counts: Vec<usize>,
+    pub col_counts: Vec<usize>,
+}
+
+impl FactorCounts {
+    /// The number of non-zero entries in L, including its diagonal
+    pub fn nnz(&self) -> usize {
+        self.col_counts.iter().fold(0, |acc, &c| acc + c)
+    }
+}
+
 /// Store an etree as the parent information of each node.
 /// This reflects the fact that etrees can in fact have multiple roots.
 #[derive(Debug)]
@@ -47,6 +73,210 @@ impl<S: Deref<Target=[Parent]>> Parents<S> {
             parents: &self.parents[..]
         }
     }
+
+    /// Compute a postordering of the tree, ie an ordering where the nodes
+    /// of each subtree are numbered consecutively, the root of the subtree
+    /// coming last. The returned vector holds the node of each position.
+    ///
+    /// The children of a node are visited by increasing index, and the
+    /// trees of a forest by increasing root index.
+    pub fn postorder(&self) -> Vec<usize> {
+        let n = self.nb_nodes();
+        // linked lists of the children of each node
+        let mut head: Vec<Option<usize>> = vec![None; n];
+        let mut next: Vec<Option<usize>> = vec![None; n];
+        for node in (0..n).rev() {
+            if let Some(parent) = self.get_parent(node) {
+                next[node] = head[parent];
+                head[parent] = Some(node);
+            }
+        }
+        let mut postorder = Vec::with_capacity(n);
+        let mut stack = Vec::new();
+        for root in (0..n).filter(|&node| self.is_root(node)) {
+            stack.push(root);
+            while let Some(&node) = stack.last() {
+                match head[node] {
+                    Some(child) => {
+                        head[node] = next[child];
+                        stack.push(child);
+                    }
+                    None => {
+                        stack.pop();
+                        postorder.push(node);
+                    }
+                }
+            }
+        }
+        postorder
+    }
+
+    /// Compute the first descendant of each node, ie the smallest position
+    /// in `postorder` of a node of its subtree.
+    ///
+    /// # Panics
+    ///
+    /// * if postorder is not a postordering of this tree
+    pub fn first_descendants(&self, postorder: &[usize]) -> Vec<usize> {
+        let n = self.nb_nodes();
+        assert_eq!(postorder.len(), n, "Dimension mismatch");
+        let mut first: Vec<Option<usize>> = vec![None; n];
+        for (pos, &node) in postorder.iter().enumerate() {
+            let mut node = Some(node);
+            while let Some(i) = node {
+                if first[i].is_some() {
+                    break;
+                }
+                first[i] = Some(pos);
+                node = self.get_parent(i);
+            }
+        }
+        first.into_iter()
+             .map(|f| f.expect("postorder visits all nodes"))
+             .collect()
+    }
+
+    /// Compute the level of each node, ie its distance to the root of
+    /// its tree.
+    pub fn levels(&self) -> Vec<usize> {
+        let n = self.nb_nodes();
+        let mut levels: Vec<Option<usize>> = vec![None; n];
+        let mut path = Vec::new();
+        for node in 0..n {
+            // walk up to a node of known level, then go back down
+            let mut cur = node;
+            let mut level = loop {
+                if let Some(level) = levels[cur] {
+                    break level;
+                }
+                match self.get_parent(cur) {
+                    Some(parent) => {
+                        path.push(cur);
+                        cur = parent;
+                    }
+                    None => {
+                        levels[cur] = Some(0);
+                        break 0;
+                    }
+                }
+            };
+            while let Some(i) = path.pop() {
+                level += 1;
+                levels[i] = Some(level);
+            }
+        }
+        levels.into_iter().map(|l| l.expect("all nodes visited")).collect()
+    }
+
+    /// Group the nodes by level. The nodes of a level set are not
+    /// ancestors of each other, so they can be eliminated independently
+    /// once the nodes of the deeper level sets have been eliminated.
+    ///
+    /// The nodes of each level set are sorted.
+    pub fn level_sets(&self) -> Vec<Vec<usize>> {
+        let levels = self.levels();
+        let nb_levels = levels.iter().max().map_or(0, |&l| l + 1);
+        let mut sets = vec![Vec::new(); nb_levels];
+        for (node, &level) in levels.iter().enumerate() {
+            sets[level].push(node);
+        }
+        sets
+    }
+
+    /// Compute the row and column counts of the cholesky factor L of
+    /// `P A P^T`, where this tree is the elimination tree of `P A P^T`, with
+    /// the algorithm of Gilbert, Ng and Peyton.
+    ///
+    /// The full symmetric non-zero pattern of A should be stored.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square, or does not have the size of the tree
+    /// * if the permutation does not have the size of the tree
+    pub fn cholesky_counts<N, I, IpS, IS, DS, PS>(
+        &self,
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        perm: &PermutationBase<I, PS>)
+        -> FactorCounts
+    where I: SpIndex,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>,
+          PS: Deref<Target = [I]>
+    {
+        let n = self.nb_nodes();
+        assert!(mat.rows() == n && mat.cols() == n, "Dimension mismatch");
+        if let Some(dim) = perm.dim() {
+            assert_eq!(dim, n, "Dimension mismatch");
+        }
+        let postorder = self.postorder();
+        let first = self.first_descendants(&postorder);
+        let levels = self.levels();
+
+        // A node j is a leaf of the row subtree of i if A_ij is non-zero
+        // and no other node of the subtree of j was a leaf of the row
+        // subtree of i. The column counts are accumulated as differences
+        // along the tree, the least common ancestor of consecutive leaves
+        // being found with a disjoint set structure.
+        let mut col_deltas = vec![0isize; n];
+        for (pos, &node) in postorder.iter().enumerate() {
+            // leaves are their own first descendant
+            if first[node] == pos {
+                col_deltas[node] = 1;
+            }
+        }
+        let mut row_counts = vec![1; n];
+        let mut max_first: Vec<Option<usize>> = vec![None; n];
+        let mut prev_leaf: Vec<Option<usize>> = vec![None; n];
+        let mut ancestors: Vec<usize> = (0..n).collect();
+        for &j in &postorder {
+            if let Some(parent) = self.get_parent(j) {
+                col_deltas[parent] -= 1;
+            }
+            let vec = mat.outer_view(perm.at(j)).expect("j is in bounds");
+            for i in vec.indices().iter().map(|i| perm.at_inv(i.index())) {
+                if i <= j || max_first[i].map_or(false, |f| first[j] <= f) {
+                    continue;
+                }
+                // j is a leaf of the row subtree of i
+                max_first[i] = Some(first[j]);
+                col_deltas[j] += 1;
+                let lca = match prev_leaf[i] {
+                    None => i,
+                    Some(prev) => {
+                        let mut root = prev;
+                        while ancestors[root] != root {
+                            root = ancestors[root];
+                        }
+                        // path compression
+                        let mut node = prev;
+                        while node != root {
+                            let next = ancestors[node];
+                            ancestors[node] = root;
+                            node = next;
+                        }
+                        col_deltas[root] -= 1;
+                        root
+                    }
+                };
+                prev_leaf[i] = Some(j);
+                row_counts[i] += levels[j] - levels[lca];
+            }
+            if let Some(parent) = self.get_parent(j) {
+                ancestors[j] = parent;
+            }
+        }
+        let mut col_counts = col_deltas;
+        for &j in &postorder {
+            if let Some(parent) = self.get_parent(j) {
+                col_counts[parent] += col_counts[j];
+            }
+        }
+        FactorCounts {
+            row_counts: row_counts,
+            col_counts: col_counts.into_iter().map(|c| c as usize).collect(),
+        }
+    }
 }
 
 impl<S: DerefMut<Target=[Parent]>> Parents<S> {
@@ -98,4 +328,311 @@ impl Parents<Vec<Parent>> {
             parents: vec![None; nb_nodes],
         }
     }
+
+    /// Compute the elimination tree of `P A P^T`, where A is a symmetric
+    /// matrix and P a permutation matrix. The node `k` of the tree is the
+    /// row and column `perm.at(k)` of A.
+    ///
+    /// The full symmetric non-zero pattern of A should be stored, but its
+    /// symmetry is not checked.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if the permutation does not have the size of mat
+    pub fn from_symmetric<N, I, IpS, IS, DS, PS>(
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        perm: &PermutationBase<I, PS>)
+        -> ParentsOwned
+    where I: SpIndex,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>,
+          PS: Deref<Target = [I]>
+    {
+        let n = mat.rows();
+        assert!(mat.cols() == n, "matrix should be square");
+        if let Some(dim) = perm.dim() {
+            assert_eq!(dim, n, "Dimension mismatch");
+        }
+        // Liu's algorithm, with path compression through the ancestors
+        let mut parents = ParentsOwned::new(n);
+        let mut ancestors: Vec<Option<usize>> = vec![None; n];
+        for k in 0..n {
+            let vec = mat.outer_view(perm.at(k)).expect("k is in bounds");
+            for inner in vec.indices() {
+                let mut node = Some(perm.at_inv(inner.index()));
+                while let Some(i) = node {
+                    if i >= k {
+                        break;
+                    }
+                    node = ancestors[i];
+                    ancestors[i] = Some(k);
+                    if node.is_none() {
+                        parents.set_parent(i, k);
+                    }
+                }
+            }
+        }
+        parents
+    }
+
+    /// Compute the column elimination tree of a matrix A, ie the
+    /// elimination tree of `A^T A`, without forming `A^T A`.
+    ///
+    /// The node `k` of the tree is the column `col_perm.at(k)` of A, which
+    /// gives the tree of the R factor of the QR factorization of `A P^T`.
+    ///
+    /// # Panics
+    ///
+    /// * if the permutation does not have the number of columns of A
+    pub fn from_ata<N, I, IpS, IS, DS, PS>(
+        mat: &CsMatBase<N, I, IpS, IS, DS>,
+        col_perm: &PermutationBase<I, PS>)
+        -> ParentsOwned
+    where I: SpIndex,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>,
+          PS: Deref<Target = [I]>
+    {
+        let (m, n) = mat.shape();
+        if let Some(dim) = col_perm.dim() {
+            assert_eq!(dim, n, "Dimension mismatch");
+        }
+        let transposed;
+        let (indptr, indices) = if mat.is_csc() {
+            (mat.indptr(), mat.indices())
+        } else {
+            transposed = transposed_pattern(mat);
+            (&transposed.0[..], &transposed.1[..])
+        };
+
+        // Liu's algorithm, where the rows of column k are connected to the
+        // last column in which they appeared. The ancestors are the roots of
+        // the partial trees, with path compression.
+        let mut parents = ParentsOwned::new(n);
+        let mut ancestors: Vec<Option<usize>> = vec![None; n];
+        let mut prev: Vec<Option<usize>> = vec![None; m];
+        for k in 0..n {
+            let col = col_perm.at(k);
+            let start = indptr[col].index();
+            let stop = indptr[col + 1].index();
+            for row in indices[start..stop].iter().map(|i| i.index()) {
+                let mut node = prev[row];
+                while let Some(i) = node {
+                    if i >= k {
+                        break;
+                    }
+                    node = ancestors[i];
+                    ancestors[i] = Some(k);
+                    if node.is_none() {
+                        parents.set_parent(i, k);
+                    }
+                }
+                prev[row] = Some(k);
+            }
+        }
+        parents
+    }
+}
+
+/// The column pointers and row indices of the pattern of a CSR matrix
+fn transposed_pattern<N, I, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                                         -> (Vec<I>, Vec<I>)
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>
+{
+    let mut counts = vec![0; mat.inner_dims() + 1];
+    for &inner in mat.indices() {
+        counts[inner.index() + 1] += 1;
+    }
+    for i in 0..mat.inner_dims() {
+        counts[i + 1] += counts[i];
+    }
+    let indptr = counts.iter().map(|&c| I::from_usize(c)).collect();
+    let mut indices = vec![I::zero(); mat.nnz()];
+    for (outer, vec) in mat.outer_iterator().enumerate() {
+        for &inner in vec.indices() {
+            let pos = &mut counts[inner.index()];
+            indices[*pos] = I::from_usize(outer);
+            *pos += 1;
+        }
+    }
+    (indptr, indices)
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::CsMatOwned;
+    use sparse::construct::{from_diags, kronsum};
+    use sparse::permutation::{Permutation, PermOwned, permute_sym};
+    use super::{FactorCounts, ParentsOwned};
+    use ::CSR;
+
+    fn parents_vec(parents: &ParentsOwned) -> Vec<Option<usize>> {
+        (0..parents.nb_nodes()).map(|i| parents.get_parent(i)).collect()
+    }
+
+    #[test]
+    fn from_ata() {
+        // | 1 1 0 |
+        // | 0 1 1 |
+        // | 0 0 1 |
+        // | 1 0 0 |
+        let a = CsMatOwned::new((4, 3), vec![0, 2, 4, 5, 6],
+                                vec![0, 1, 1, 2, 2, 0],
+                                vec![1.; 6]);
+        let identity = PermOwned::identity();
+        let parents = ParentsOwned::from_ata(&a, &identity);
+        assert_eq!(parents_vec(&parents), vec![Some(1), Some(2), None]);
+        let parents = ParentsOwned::from_ata(&a.to_csc(), &identity);
+        assert_eq!(parents_vec(&parents), vec![Some(1), Some(2), None]);
+
+        // | 1 0 1 |
+        // | 0 1 0 |
+        let a = CsMatOwned::new((2, 3), vec![0, 2, 3], vec![0, 2, 1],
+                                vec![1.; 3]);
+        let parents = ParentsOwned::from_ata(&a, &identity);
+        assert_eq!(parents_vec(&parents), vec![Some(2), None, None]);
+        let perm = Permutation::new(vec![2, 1, 0]);
+        let parents = ParentsOwned::from_ata(&a, &perm);
+        assert_eq!(parents_vec(&parents), vec![Some(2), None, None]);
+        let perm = Permutation::new(vec![0, 1, 2]);
+        let parents = ParentsOwned::from_ata(&a.to_csc(), &perm);
+        assert_eq!(parents_vec(&parents), vec![Some(2), None, None]);
+    }
+
+    fn tree(parents: &[Option<usize>]) -> ParentsOwned {
+        let mut tree = ParentsOwned::new(parents.len());
+        for (node, parent) in parents.iter().enumerate() {
+            if let Some(parent) = *parent {
+                tree.set_parent(node, parent);
+            }
+        }
+        tree
+    }
+
+    /// Compute the elimination tree and the counts of the cholesky factor
+    /// by performing the symbolic elimination on a dense pattern
+    fn dense_symbolic(mat: &CsMatOwned<f64>)
+                      -> (Vec<Option<usize>>, FactorCounts) {
+        let n = mat.rows();
+        let mut pattern = vec![vec![false; n]; n];
+        for (outer, vec) in mat.outer_iterator().enumerate() {
+            for (inner, _) in vec.iter() {
+                pattern[outer][inner] = true;
+            }
+        }
+        for k in 0..n {
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    if pattern[i][k] && pattern[j][k] {
+                        pattern[i][j] = true;
+                    }
+                }
+            }
+        }
+        let parents = (0..n).map(|j| (j + 1..n).find(|&i| pattern[i][j]))
+                            .collect();
+        let row_counts = (0..n).map(|i| {
+            (0..i).filter(|&j| pattern[i][j]).count() + 1
+        }).collect();
+        let col_counts = (0..n).map(|j| {
+            (j + 1..n).filter(|&i| pattern[i][j]).count() + 1
+        }).collect();
+        (parents, FactorCounts {
+            row_counts: row_counts,
+            col_counts: col_counts,
+        })
+    }
+
+    fn test_matrices() -> Vec<CsMatOwned<f64>> {
+        let lap_1d: CsMatOwned<f64> = from_diags((4, 4),
+                                                 &[&[-1.; 3], &[2.; 4],
+                                                   &[-1.; 3]],
+                                                 &[-1, 0, 1]);
+        let lap_2d = kronsum(lap_1d.view(), lap_1d.view(), CSR);
+        // | x . x . . x |
+        // | . x . x . . |
+        // | x . x . x . |
+        // | . x . x x . |
+        // | . . x x x . |
+        // | x . . . . x |
+        let arbitrary = CsMatOwned::new((6, 6),
+                                        vec![0, 3, 5, 8, 11, 14, 16],
+                                        vec![0, 2, 5, 1, 3, 0, 2, 4,
+                                             1, 3, 4, 2, 3, 4, 0, 5],
+                                        vec![1.; 16]);
+        // a forest: two independent blocks
+        let forest = CsMatOwned::new((4, 4),
+                                     vec![0, 2, 4, 6, 8],
+                                     vec![0, 2, 1, 3, 0, 2, 1, 3],
+                                     vec![1.; 8]);
+        vec![lap_1d, lap_2d, arbitrary, forest]
+    }
+
+    #[test]
+    fn postorder() {
+        let parents = tree(&[Some(3), Some(2), Some(4), Some(4), None]);
+        let post = parents.postorder();
+        assert_eq!(post, vec![1, 2, 0, 3, 4]);
+        assert_eq!(parents.first_descendants(&post), vec![2, 0, 0, 2, 0]);
+
+        let parents = tree(&[Some(2), Some(2), Some(4), Some(4), None, None]);
+        let post = parents.postorder();
+        assert_eq!(post, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(parents.first_descendants(&post), vec![0, 1, 0, 3, 0, 5]);
+    }
+
+    #[test]
+    fn levels() {
+        let parents = tree(&[Some(3), Some(2), Some(4), Some(4), None]);
+        assert_eq!(parents.levels(), vec![2, 2, 1, 1, 0]);
+        assert_eq!(parents.level_sets(),
+                   vec![vec![4], vec![2, 3], vec![0, 1]]);
+
+        let parents = tree(&[Some(2), Some(2), Some(4), Some(4), None, None]);
+        assert_eq!(parents.levels(), vec![2, 2, 1, 1, 0, 0]);
+        assert_eq!(parents.level_sets(),
+                   vec![vec![4, 5], vec![2, 3], vec![0, 1]]);
+
+        let parents = ParentsOwned::new(0);
+        assert!(parents.postorder().is_empty());
+        assert!(parents.level_sets().is_empty());
+    }
+
+    #[test]
+    fn from_symmetric_and_counts() {
+        let identity = PermOwned::identity();
+        for mat in test_matrices() {
+            let (expected_parents, expected_counts) = dense_symbolic(&mat);
+            let parents = ParentsOwned::from_symmetric(&mat, &identity);
+            assert_eq!(parents_vec(&parents), expected_parents);
+            let counts = parents.cholesky_counts(&mat, &identity);
+            assert_eq!(counts, expected_counts);
+            let csc = mat.to_csc();
+            let parents = ParentsOwned::from_symmetric(&csc, &identity);
+            assert_eq!(parents_vec(&parents), expected_parents);
+            assert_eq!(parents.cholesky_counts(&csc, &identity), counts);
+        }
+    }
+
+    #[test]
+    fn from_symmetric_and_counts_perm() {
+        for mat in test_matrices() {
+            let n = mat.rows();
+            let perm = Permutation::new((0..n).rev().collect());
+            let permuted = permute_sym(&mat, &perm);
+            let (expected_parents, expected_counts) =
+                dense_symbolic(&permuted);
+            let parents = ParentsOwned::from_symmetric(&mat, &perm);
+            assert_eq!(parents_vec(&parents), expected_parents);
+            let counts = parents.cholesky_counts(&mat, &perm);
+            assert_eq!(counts, expected_counts);
+            assert_eq!(counts.nnz(), counts.row_counts.iter().sum::<usize>());
+        }
+    }
 }
@@ -0,0 +1,624 @@
+///! Incomplete factorizations
+///!
+///! An incomplete factorization computes approximate triangular factors of
+///! a sparse matrix, limiting their fill-in either to the sparsity pattern
+///! of the matrix (IC0, ILU0) or through a drop tolerance and a maximum
+///! number of entries per row (ILUT). Their triangular solves are cheap
+///! approximations of the inverse of the matrix, which makes them good
+///! preconditioners for iterative solvers.
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use num_traits::Float;
+
+use indexing::SpIndex;
+use sparse::{CsMatBase, CsMatI, CsMatViewI};
+use sparse::linalg::iterative::Preconditioner;
+use sparse::linalg::trisolve;
+use errors::SprsError;
+
+/// Sentinel for columns absent from the current row
+const NONE: usize = ::std::usize::MAX;
+
+/// Dot product of two sparse rows with sorted indices
+fn sparse_dot<N: Float, I: SpIndex>(indices1: &[I],
+                                    data1: &[N],
+                                    indices2: &[I],
+                                    data2: &[N])
+                                    -> N {
+    let mut res = N::zero();
+    let (mut p1, mut p2) = (0, 0);
+    while p1 < indices1.len() && p2 < indices2.len() {
+        let (j1, j2) = (indices1[p1].index(), indices2[p2].index());
+        if j1 == j2 {
+            res = res + data1[p1] * data2[p2];
+            p1 += 1;
+            p2 += 1;
+        } else if j1 < j2 {
+            p1 += 1;
+        } else {
+            p2 += 1;
+        }
+    }
+    res
+}
+
+/// Solve `L U x = rhs` in place, for csr factors
+fn lu_solve_in_place<N, I>(l: CsMatViewI<N, I>,
+                           u: CsMatViewI<N, I>,
+                           x: &mut [N])
+where N: Float,
+      I: SpIndex
+{
+    trisolve::lsolve_csr_dense_rhs(l, x).expect("L has a non-zero diagonal");
+    trisolve::usolve_csr_dense_rhs(u, x).expect("U has a non-zero diagonal");
+}
+
+/// Zero fill-in incomplete Cholesky factorization.
+///
+/// Approximates a symmetric positive definite matrix `A` by `L L^T`, where
+/// `L` is lower triangular and has the sparsity pattern of the lower
+/// triangular part of `A`.
+#[derive(Debug)]
+pub struct Ic0<N, I: SpIndex = usize> {
+    l: CsMatI<N, I>,
+    l_t: CsMatI<N, I>,
+}
+
+impl<N: Float + Default, I: SpIndex> Ic0<N, I> {
+    /// Compute the incomplete Cholesky factorization of `mat`.
+    ///
+    /// Only the lower triangular part of `mat` is read, the matrix is
+    /// assumed to be symmetric.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    ///
+    /// # Errors
+    ///
+    /// `NotPositiveDefinite(col)` if a non-positive pivot is encountered in
+    /// the column `col`. This can happen for some positive definite
+    /// matrices, as the dropped fill-in can make the incomplete
+    /// factorization break down.
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                            -> Result<Ic0<N, I>, SprsError>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        assert!(mat.rows() == mat.cols(), "Non square matrix");
+        let n = mat.rows();
+        let mat = mat.to_csr();
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(I::from_usize(0));
+        for (i, row) in mat.outer_iterator().enumerate() {
+            let start = indices.len();
+            let mut diag = N::zero();
+            for (j, &val) in row.iter() {
+                if j < i {
+                    indices.push(I::from_usize(j));
+                    data.push(val);
+                } else if j == i {
+                    diag = val;
+                }
+            }
+            // l_ik = (a_ik - sum_{j < k} l_ij l_kj) / l_kk, the row of L
+            // being sorted, the l_ij are known when computing l_ik
+            for pos in start..indices.len() {
+                let k = indices[pos].index();
+                let k_start = indptr[k].index();
+                let k_diag = indptr[k + 1].index() - 1;
+                let dot = sparse_dot(&indices[start..pos],
+                                     &data[start..pos],
+                                     &indices[k_start..k_diag],
+                                     &data[k_start..k_diag]);
+                data[pos] = (data[pos] - dot) / data[k_diag];
+            }
+            let pivot = data[start..]
+                .iter()
+                .fold(diag, |acc, &l_ij| acc - l_ij * l_ij);
+            if !(pivot > N::zero()) {
+                return Err(SprsError::NotPositiveDefinite(i));
+            }
+            indices.push(I::from_usize(i));
+            data.push(pivot.sqrt());
+            indptr.push(I::from_usize(indices.len()));
+        }
+        let l = CsMatI::new((n, n), indptr, indices, data);
+        let l_t = l.to_csc().transpose_into();
+        Ok(Ic0 { l: l, l_t: l_t })
+    }
+
+    /// The lower triangular factor L, in CSR storage
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+}
+
+impl<N: Float, I: SpIndex> Preconditioner<N> for Ic0<N, I> {
+    /// Solve `L L^T z = r`
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        z.copy_from_slice(r);
+        lu_solve_in_place(self.l.view(), self.l_t.view(), z);
+    }
+}
+
+/// Split the rows of a factored matrix into a unit lower triangular matrix
+/// and an upper triangular matrix, both in CSR storage.
+fn split_lu<N, I>(n: usize,
+                  indptr: &[I],
+                  indices: &[I],
+                  data: &[N])
+                  -> (CsMatI<N, I>, CsMatI<N, I>)
+where N: Float,
+      I: SpIndex
+{
+    let mut l_indptr = Vec::with_capacity(n + 1);
+    let mut l_indices = Vec::new();
+    let mut l_data = Vec::new();
+    let mut u_indptr = Vec::with_capacity(n + 1);
+    let mut u_indices = Vec::new();
+    let mut u_data = Vec::new();
+    l_indptr.push(I::from_usize(0));
+    u_indptr.push(I::from_usize(0));
+    for i in 0..n {
+        for pos in indptr[i].index()..indptr[i + 1].index() {
+            if indices[pos].index() < i {
+                l_indices.push(indices[pos]);
+                l_data.push(data[pos]);
+            } else {
+                u_indices.push(indices[pos]);
+                u_data.push(data[pos]);
+            }
+        }
+        l_indices.push(I::from_usize(i));
+        l_data.push(N::one());
+        l_indptr.push(I::from_usize(l_indices.len()));
+        u_indptr.push(I::from_usize(u_indices.len()));
+    }
+    (CsMatI::new((n, n), l_indptr, l_indices, l_data),
+     CsMatI::new((n, n), u_indptr, u_indices, u_data))
+}
+
+/// Zero fill-in incomplete LU factorization.
+///
+/// Approximates a square matrix `A` by `L U`, where `L` is unit lower
+/// triangular and `U` is upper triangular, and `L + U` has the sparsity
+/// pattern of `A`. No pivoting is performed.
+#[derive(Debug)]
+pub struct Ilu0<N, I: SpIndex = usize> {
+    l: CsMatI<N, I>,
+    u: CsMatI<N, I>,
+}
+
+impl<N: Float + Default, I: SpIndex> Ilu0<N, I> {
+    /// Compute the incomplete LU factorization of `mat`.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if a zero pivot is encountered, in particular if
+    /// a diagonal element is absent from the sparsity pattern of `mat`.
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                            -> Result<Ilu0<N, I>, SprsError>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        assert!(mat.rows() == mat.cols(), "Non square matrix");
+        let n = mat.rows();
+        let mat = mat.to_csr();
+        let indptr = mat.indptr();
+        let indices = mat.indices();
+        let mut data = mat.data().to_vec();
+        let mut diag_pos = vec![NONE; n];
+        let mut row_pos = vec![NONE; n];
+        for i in 0..n {
+            let (start, end) = (indptr[i].index(), indptr[i + 1].index());
+            for pos in start..end {
+                row_pos[indices[pos].index()] = pos;
+            }
+            // IKJ variant of gaussian elimination, restricted to the
+            // pattern of the row
+            for pos in start..end {
+                let k = indices[pos].index();
+                if k >= i {
+                    break;
+                }
+                let l_ik = data[pos] / data[diag_pos[k]];
+                data[pos] = l_ik;
+                for k_pos in diag_pos[k] + 1..indptr[k + 1].index() {
+                    let j_pos = row_pos[indices[k_pos].index()];
+                    if j_pos != NONE {
+                        data[j_pos] = data[j_pos] - l_ik * data[k_pos];
+                    }
+                }
+            }
+            diag_pos[i] = row_pos[i];
+            if diag_pos[i] == NONE || data[diag_pos[i]] == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            for pos in start..end {
+                row_pos[indices[pos].index()] = NONE;
+            }
+        }
+        let (l, u) = split_lu(n, indptr, indices, &data);
+        Ok(Ilu0 { l: l, u: u })
+    }
+
+    /// The unit lower triangular factor L, in CSR storage
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor U, in CSR storage
+    pub fn u(&self) -> CsMatViewI<N, I> {
+        self.u.view()
+    }
+}
+
+impl<N: Float, I: SpIndex> Preconditioner<N> for Ilu0<N, I> {
+    /// Solve `L U z = r`
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        z.copy_from_slice(r);
+        lu_solve_in_place(self.l.view(), self.u.view(), z);
+    }
+}
+
+/// Incomplete LU factorization with threshold dropping.
+///
+/// Approximates a square matrix `A` by `L U`, where `L` is unit lower
+/// triangular and `U` is upper triangular. During the elimination of row
+/// `i`, entries smaller than `drop_tol` times the norm of the i-th row of
+/// `A` are dropped, and only the `fill` largest entries of the rows of
+/// `L` and `U` are kept, not counting the diagonal. No pivoting is
+/// performed.
+///
+/// A zero `drop_tol` with `fill` at least the size of the matrix gives
+/// the complete LU factorization.
+#[derive(Debug)]
+pub struct Ilut<N, I: SpIndex = usize> {
+    l: CsMatI<N, I>,
+    u: CsMatI<N, I>,
+}
+
+/// Keep the `fill` entries of largest magnitude, sorted by column
+fn keep_largest<N: Float>(entries: &mut Vec<(usize, N)>, fill: usize) {
+    if entries.len() > fill {
+        entries.sort_by(|&(_, x), &(_, y)| {
+            y.abs().partial_cmp(&x.abs()).unwrap_or(Ordering::Equal)
+        });
+        entries.truncate(fill);
+    }
+    entries.sort_by_key(|&(j, _)| j);
+}
+
+impl<N: Float + Default, I: SpIndex> Ilut<N, I> {
+    /// Compute the thresholded incomplete LU factorization of `mat`.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if drop_tol is negative
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if a zero pivot is encountered, which happens for
+    /// rows of `mat` that are entirely zero.
+    pub fn new<IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                            drop_tol: N,
+                            fill: usize)
+                            -> Result<Ilut<N, I>, SprsError>
+    where IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        assert!(mat.rows() == mat.cols(), "Non square matrix");
+        assert!(drop_tol >= N::zero(), "Negative drop tolerance");
+        let n = mat.rows();
+        let mat = mat.to_csr();
+        let mut l_indptr = Vec::with_capacity(n + 1);
+        let mut l_indices = Vec::new();
+        let mut l_data = Vec::new();
+        let mut u_indptr = Vec::with_capacity(n + 1);
+        let mut u_indices: Vec<I> = Vec::new();
+        let mut u_data = Vec::new();
+        l_indptr.push(I::from_usize(0));
+        u_indptr.push(I::from_usize(0));
+
+        // dense work row, with the list of its non-zero locations
+        let mut work = vec![N::zero(); n];
+        let mut in_pattern = vec![false; n];
+        let mut pattern = Vec::new();
+        let mut lower = BTreeSet::new();
+        for (i, row) in mat.outer_iterator().enumerate() {
+            let row_norm = row.iter()
+                .fold(N::zero(), |acc, (_, &val)| acc + val * val)
+                .sqrt();
+            let tol = drop_tol * row_norm;
+            for (j, &val) in row.iter() {
+                work[j] = val;
+                in_pattern[j] = true;
+                pattern.push(j);
+                if j < i {
+                    lower.insert(j);
+                }
+            }
+            // eliminate the lower part in increasing column order, fill-in
+            // can add columns to eliminate
+            loop {
+                let k = match lower.iter().next() {
+                    Some(&k) => k,
+                    None => break,
+                };
+                lower.remove(&k);
+                let u_start = u_indptr[k].index();
+                let u_end = u_indptr[k + 1].index();
+                // the diagonal is the first entry of each row of U
+                let l_ik = work[k] / u_data[u_start];
+                if l_ik.abs() <= tol {
+                    work[k] = N::zero();
+                    continue;
+                }
+                work[k] = l_ik;
+                for u_pos in u_start + 1..u_end {
+                    let j = u_indices[u_pos].index();
+                    if !in_pattern[j] {
+                        in_pattern[j] = true;
+                        pattern.push(j);
+                        if j < i {
+                            lower.insert(j);
+                        }
+                    }
+                    work[j] = work[j] - l_ik * u_data[u_pos];
+                }
+            }
+
+            let mut l_row = Vec::new();
+            let mut u_row = Vec::new();
+            for &j in &pattern {
+                if work[j].abs() > tol {
+                    if j < i {
+                        l_row.push((j, work[j]));
+                    } else if j > i {
+                        u_row.push((j, work[j]));
+                    }
+                }
+            }
+            let diag = work[i];
+            for &j in &pattern {
+                work[j] = N::zero();
+                in_pattern[j] = false;
+            }
+            pattern.clear();
+            if diag == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            keep_largest(&mut l_row, fill);
+            keep_largest(&mut u_row, fill);
+
+            for (j, val) in l_row {
+                l_indices.push(I::from_usize(j));
+                l_data.push(val);
+            }
+            l_indices.push(I::from_usize(i));
+            l_data.push(N::one());
+            l_indptr.push(I::from_usize(l_indices.len()));
+            u_indices.push(I::from_usize(i));
+            u_data.push(diag);
+            for (j, val) in u_row {
+                u_indices.push(I::from_usize(j));
+                u_data.push(val);
+            }
+            u_indptr.push(I::from_usize(u_indices.len()));
+        }
+        Ok(Ilut {
+            l: CsMatI::new((n, n), l_indptr, l_indices, l_data),
+            u: CsMatI::new((n, n), u_indptr, u_indices, u_data),
+        })
+    }
+
+    /// The unit lower triangular factor L, in CSR storage
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor U, in CSR storage
+    pub fn u(&self) -> CsMatViewI<N, I> {
+        self.u.view()
+    }
+}
+
+impl<N: Float, I: SpIndex> Preconditioner<N> for Ilut<N, I> {
+    /// Solve `L U z = r`
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        z.copy_from_slice(r);
+        lu_solve_in_place(self.l.view(), self.u.view(), z);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::{CsMat, CsMatOwned, CsMatView};
+    use sparse::triplet::TripletMat;
+    use sparse::linalg::iterative::{gmres, pcg, Jacobi, LinearOperator};
+    use sparse::linalg::iterative::{PrecondSide, Preconditioner};
+    use sparse::linalg::iterative::StoppingCriterion::RelativeResidual;
+    use errors::SprsError;
+    use super::{Ic0, Ilu0, Ilut};
+
+    /// Five points discretization of `-laplacian(u) + c * grad(u)` on a
+    /// square grid, symmetric when c is 0
+    fn convection_diffusion_2d(n: usize, c: f64) -> CsMatOwned<f64> {
+        let mut triplets = TripletMat::new((n * n, n * n));
+        for i in 0..n {
+            for j in 0..n {
+                let ind = i * n + j;
+                triplets.add_triplet(ind, ind, 4.);
+                if i > 0 {
+                    triplets.add_triplet(ind, ind - n, -1. - c);
+                }
+                if i + 1 < n {
+                    triplets.add_triplet(ind, ind + n, -1. + c);
+                }
+                if j > 0 {
+                    triplets.add_triplet(ind, ind - 1, -1. - c);
+                }
+                if j + 1 < n {
+                    triplets.add_triplet(ind, ind + 1, -1. + c);
+                }
+            }
+        }
+        triplets.to_csr()
+    }
+
+    /// Check that `L U` matches `mat` on the sparsity pattern of `mat`
+    fn check_pattern_product(mat: &CsMatOwned<f64>,
+                             l: CsMatView<f64>,
+                             u: CsMatView<f64>) {
+        let prod = &l * &u;
+        for (i, row) in mat.outer_iterator().enumerate() {
+            for (j, &val) in row.iter() {
+                let prod_val = *prod.get(i, j).unwrap_or(&0.);
+                assert!((val - prod_val).abs() < 1e-12);
+            }
+        }
+    }
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-10)
+    }
+
+    #[test]
+    fn ic0_pattern() {
+        let mat = convection_diffusion_2d(6, 0.);
+        let ic = Ic0::new(&mat).unwrap();
+        let l = ic.l();
+        for (i, row) in l.outer_iterator().enumerate() {
+            for (j, _) in row.iter() {
+                assert!(j <= i);
+                assert!(mat.get(i, j).is_some());
+            }
+        }
+        let l_t = l.to_csc();
+        check_pattern_product(&mat, l, l_t.transpose_view());
+    }
+
+    #[test]
+    fn ic0_exact_on_tridiagonal() {
+        // no fill-in occurs, so the factorization is complete
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 5, 7],
+                             vec![0, 1, 0, 1, 2, 1, 2],
+                             vec![4., 1., 1., 4., 1., 1., 4.]);
+        let ic = Ic0::new(&mat).unwrap();
+        let x0 = [1., 2., 3.];
+        let mut b = [0.; 3];
+        mat.apply(&x0, &mut b);
+        let mut x = [0.; 3];
+        ic.apply(&b, &mut x);
+        assert!(close(&x, &x0));
+    }
+
+    #[test]
+    fn ic0_not_positive_definite() {
+        let mat = CsMat::new((2, 2),
+                             vec![0, 2, 4],
+                             vec![0, 1, 0, 1],
+                             vec![1., 2., 2., 1.]);
+        assert_eq!(Ic0::<f64>::new(&mat).err(),
+                   Some(SprsError::NotPositiveDefinite(1)));
+    }
+
+    #[test]
+    fn ic0_pcg() {
+        let mat = convection_diffusion_2d(12, 0.);
+        let b: Vec<f64> = (0..144).map(|i| (i as f64).sin()).collect();
+        let jacobi = Jacobi::new(&mat).unwrap();
+        let ic = Ic0::new(&mat.to_csc()).unwrap();
+        let sol_jacobi = pcg(&mat, &b, &jacobi, 1e-10, 200).unwrap();
+        let sol_ic = pcg(&mat, &b, &ic, 1e-10, 200).unwrap();
+        assert!(sol_jacobi.converged());
+        assert!(sol_ic.converged());
+        assert!(sol_ic.iterations() < sol_jacobi.iterations());
+    }
+
+    #[test]
+    fn ilu0_pattern() {
+        let mat = convection_diffusion_2d(6, 0.3);
+        let ilu = Ilu0::new(&mat).unwrap();
+        assert_eq!(ilu.l().nnz() + ilu.u().nnz(), mat.nnz() + 36);
+        check_pattern_product(&mat, ilu.l(), ilu.u());
+    }
+
+    #[test]
+    fn ilu0_missing_diagonal() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 1.]);
+        assert_eq!(Ilu0::<f64>::new(&mat).err(),
+                   Some(SprsError::SingularMatrix));
+    }
+
+    #[test]
+    fn ilut_complete() {
+        let mat = convection_diffusion_2d(5, 0.3);
+        let n = mat.rows();
+        let ilut = Ilut::new(&mat, 0., n).unwrap();
+        let x0: Vec<f64> = (0..n).map(|i| i as f64).collect();
+        let mut b = vec![0.; n];
+        mat.apply(&x0, &mut b);
+        let mut x = vec![0.; n];
+        ilut.apply(&b, &mut x);
+        assert!(close(&x, &x0));
+    }
+
+    #[test]
+    fn ilut_fill_limit() {
+        let mat = convection_diffusion_2d(6, 0.3);
+        let ilut = Ilut::new(&mat, 1e-4, 2).unwrap();
+        for (l_row, u_row) in ilut.l().outer_iterator()
+                                  .zip(ilut.u().outer_iterator()) {
+            assert!(l_row.nnz() <= 3);
+            assert!(u_row.nnz() <= 3);
+        }
+        // a larger drop tolerance gives sparser factors
+        let ilut_fine = Ilut::new(&mat, 1e-4, 6).unwrap();
+        let ilut_coarse = Ilut::new(&mat, 1e-1, 6).unwrap();
+        assert!(ilut_coarse.l().nnz() + ilut_coarse.u().nnz() <
+                ilut_fine.l().nnz() + ilut_fine.u().nnz());
+    }
+
+    #[test]
+    fn ilu_gmres() {
+        let mat = convection_diffusion_2d(12, 0.4);
+        let n = mat.rows();
+        let b: Vec<f64> = (0..n).map(|i| 1. + (i as f64).cos()).collect();
+        let jacobi = Jacobi::new(&mat).unwrap();
+        let ilu0 = Ilu0::new(&mat).unwrap();
+        let ilut = Ilut::new(&mat, 1e-3, 10).unwrap();
+        let solve = |precond: &Preconditioner<f64>| {
+            let sol = gmres(&mat, &b, precond, PrecondSide::Right, 30,
+                            RelativeResidual(1e-10), 500).unwrap();
+            assert!(sol.converged());
+            sol.iterations()
+        };
+        let iter_jacobi = solve(&jacobi);
+        let iter_ilu0 = solve(&ilu0);
+        let iter_ilut = solve(&ilut);
+        assert!(iter_ilu0 < iter_jacobi);
+        assert!(iter_ilut <= iter_ilu0);
+    }
+
+    #[test]
+    fn ilut_zero_row() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 1], vec![0], vec![1.]);
+        assert_eq!(Ilut::<f64>::new(&mat, 1e-3, 5).err(),
+                   Some(SprsError::SingularMatrix));
+    }
+}
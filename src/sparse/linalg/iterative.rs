@@ -0,0 +1,941 @@
+///! Iterative solvers for sparse linear systems
+///!
+///! Iterative solvers only need to compute products between the system
+///! matrix and dense vectors, which makes them suitable for systems too
+///! large to be factorized. The system matrix can be any type implementing
+///! the `LinearOperator` trait, such as sparse matrices.
+///!
+///! The convergence of iterative solvers can be accelerated by using a
+///! preconditioner, ie an approximation of the inverse of the system
+///! matrix, described by the `Preconditioner` trait.
+
+use std::cell::RefCell;
+use std::ops::Deref;
+use num_traits::{Float, Num};
+
+use indexing::SpIndex;
+use sparse::CsMatBase;
+use sparse::prod;
+use errors::SprsError;
+
+/// A linear operator, which can be multiplied with dense vectors
+pub trait LinearOperator<N> {
+    /// The number of rows and columns of this operator
+    fn shape(&self) -> (usize, usize);
+
+    /// Compute `y = A x`, overwriting the contents of y.
+    ///
+    /// # Panics
+    ///
+    /// * if the length of x is not the number of columns of the operator
+    /// * if the length of y is not the number of rows of the operator
+    fn apply(&self, x: &[N], y: &mut [N]);
+}
+
+impl<N, I, IpS, IS, DS> LinearOperator<N> for CsMatBase<N, I, IpS, IS, DS>
+where N: Num + Copy,
+      I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>
+{
+    fn shape(&self) -> (usize, usize) {
+        (self.rows(), self.cols())
+    }
+
+    fn apply(&self, x: &[N], y: &mut [N]) {
+        for val in y.iter_mut() {
+            *val = N::zero();
+        }
+        if self.is_csr() {
+            prod::mul_acc_mat_vec_csr(self.view(), x, y);
+        } else {
+            prod::mul_acc_mat_vec_csc(self.view(), x, y);
+        }
+    }
+}
+
+/// A preconditioner, ie an approximation `M` of a linear operator `A`
+/// whose inverse can be cheaply applied.
+pub trait Preconditioner<N> {
+    /// Compute `z = M^-1 r`, overwriting the contents of z.
+    fn apply(&self, r: &[N], z: &mut [N]);
+}
+
+/// The identity preconditioner, which does nothing
+#[derive(Debug, Clone, Copy)]
+pub struct NoPreconditioner;
+
+impl<N: Copy> Preconditioner<N> for NoPreconditioner {
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        z.copy_from_slice(r);
+    }
+}
+
+/// The Jacobi preconditioner, which approximates a matrix by its diagonal
+#[derive(Debug, Clone)]
+pub struct Jacobi<N> {
+    inv_diag: Vec<N>,
+}
+
+impl<N: Float> Jacobi<N> {
+    /// Create a Jacobi preconditioner from the diagonal of a square matrix.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if a diagonal entry is zero or missing.
+    pub fn new<I, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                               -> Result<Jacobi<N>, SprsError>
+    where I: SpIndex,
+          IpS: Deref<Target = [I]>,
+          IS: Deref<Target = [I]>,
+          DS: Deref<Target = [N]>
+    {
+        assert!(mat.rows() == mat.cols(), "matrix should be square");
+        let diag = (0..mat.rows()).map(|i| {
+            mat.get(i, i).cloned().unwrap_or(N::zero())
+        }).collect();
+        Jacobi::from_diag(diag)
+    }
+
+    /// Create a Jacobi preconditioner from the diagonal of a matrix.
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if a diagonal entry is zero.
+    pub fn from_diag(diag: Vec<N>) -> Result<Jacobi<N>, SprsError> {
+        if diag.iter().any(|&d| d == N::zero()) {
+            return Err(SprsError::SingularMatrix);
+        }
+        let inv_diag = diag.into_iter().map(|d| d.recip()).collect();
+        Ok(Jacobi { inv_diag: inv_diag })
+    }
+}
+
+impl<N: Float> Preconditioner<N> for Jacobi<N> {
+    fn apply(&self, r: &[N], z: &mut [N]) {
+        assert!(r.len() == self.inv_diag.len() && z.len() == r.len(),
+                "Dimension mismatch");
+        for ((zi, &ri), &di) in z.iter_mut().zip(r).zip(&self.inv_diag) {
+            *zi = ri * di;
+        }
+    }
+}
+
+/// The outcome of an iterative solve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvergenceStatus {
+    /// The stopping criterion was reached
+    Converged,
+    /// The maximum number of iterations was reached before the stopping
+    /// criterion
+    MaxIterReached,
+}
+
+/// The result of an iterative solve
+#[derive(Debug, Clone)]
+pub struct IterativeSolution<N> {
+    /// The approximate solution
+    pub x: Vec<N>,
+    /// The euclidean norm of the residual `b - A x` at the start of the
+    /// solve, then after each iteration
+    pub residuals: Vec<N>,
+    /// Whether the solve converged
+    pub status: ConvergenceStatus,
+}
+
+impl<N> IterativeSolution<N> {
+    /// The number of iterations performed by the solver
+    pub fn iterations(&self) -> usize {
+        self.residuals.len() - 1
+    }
+
+    /// Whether the solve converged
+    pub fn converged(&self) -> bool {
+        self.status == ConvergenceStatus::Converged
+    }
+}
+
+fn dot<N: Float>(x: &[N], y: &[N]) -> N {
+    x.iter().zip(y).fold(N::zero(), |acc, (&xi, &yi)| acc + xi * yi)
+}
+
+fn norm2<N: Float>(x: &[N]) -> N {
+    dot(x, x).sqrt()
+}
+
+/// Compute `y = y + alpha * x`
+fn axpy<N: Float>(alpha: N, x: &[N], y: &mut [N]) {
+    for (yi, &xi) in y.iter_mut().zip(x) {
+        *yi = *yi + alpha * xi;
+    }
+}
+
+fn check_system_dims<N, Op>(op: &Op, rhs: &[N])
+where Op: LinearOperator<N> + ?Sized
+{
+    let (rows, cols) = op.shape();
+    if rows != cols {
+        panic!("Non square matrix passed to solver");
+    }
+    if rhs.len() != rows {
+        panic!("Dimension mismatch");
+    }
+}
+
+/// Solve the symmetric positive definite system `A x = b` using the
+/// conjugate gradient method, starting from a zero initial guess.
+///
+/// The solve stops when the norm of the residual `b - A x` drops below
+/// `tol * norm(b)`, or after `max_iter` iterations.
+///
+/// # Panics
+///
+/// * if op is not square
+/// * if rhs does not have the dimension of op
+///
+/// # Errors
+///
+/// `SolverBreakdown` if a search direction `p` with `p^T A p <= 0` is
+/// encountered, which means the operator is not positive definite.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// use sprs::linalg::iterative::cg;
+/// // |4 1|     |1|
+/// // |1 3| x = |2|
+/// let a = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+///                    vec![4., 1., 1., 3f64]);
+/// let sol = cg(&a, &[1., 2.], 1e-10, 10).unwrap();
+/// assert!(sol.converged());
+/// assert!((sol.x[0] - 1. / 11.).abs() < 1e-10);
+/// assert!((sol.x[1] - 7. / 11.).abs() < 1e-10);
+/// ```
+pub fn cg<N, Op>(op: &Op,
+                 rhs: &[N],
+                 tol: N,
+                 max_iter: usize)
+                 -> Result<IterativeSolution<N>, SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized
+{
+    pcg(op, rhs, &NoPreconditioner, tol, max_iter)
+}
+
+/// Solve the symmetric positive definite system `A x = b` using the
+/// preconditioned conjugate gradient method, starting from a zero initial
+/// guess.
+///
+/// The preconditioner should be symmetric positive definite as well, eg a
+/// `Jacobi` preconditioner or the LDLT factorization of an approximation
+/// of A.
+///
+/// The solve stops when the norm of the residual `b - A x` drops below
+/// `tol * norm(b)`, or after `max_iter` iterations.
+///
+/// # Panics
+///
+/// * if op is not square
+/// * if rhs does not have the dimension of op
+///
+/// # Errors
+///
+/// `SolverBreakdown` if a search direction `p` with `p^T A p <= 0` is
+/// encountered, which means the operator is not positive definite.
+pub fn pcg<N, Op, P>(op: &Op,
+                     rhs: &[N],
+                     precond: &P,
+                     tol: N,
+                     max_iter: usize)
+                     -> Result<IterativeSolution<N>, SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized,
+      P: Preconditioner<N> + ?Sized
+{
+    check_system_dims(op, rhs);
+    let n = rhs.len();
+    let mut x = vec![N::zero(); n];
+    let mut r = rhs.to_vec();
+    let mut z = vec![N::zero(); n];
+    let mut q = vec![N::zero(); n];
+    let threshold = tol * norm2(rhs);
+
+    let mut res_norm = norm2(&r);
+    let mut residuals = vec![res_norm];
+    if res_norm <= threshold {
+        return Ok(IterativeSolution {
+            x: x,
+            residuals: residuals,
+            status: ConvergenceStatus::Converged,
+        });
+    }
+    precond.apply(&r, &mut z);
+    let mut p = z.clone();
+    let mut rz = dot(&r, &z);
+
+    for _ in 0..max_iter {
+        op.apply(&p, &mut q);
+        let pq = dot(&p, &q);
+        if !(pq > N::zero()) {
+            return Err(SprsError::SolverBreakdown);
+        }
+        let alpha = rz / pq;
+        axpy(alpha, &p, &mut x);
+        axpy(-alpha, &q, &mut r);
+        res_norm = norm2(&r);
+        residuals.push(res_norm);
+        if res_norm <= threshold {
+            return Ok(IterativeSolution {
+                x: x,
+                residuals: residuals,
+                status: ConvergenceStatus::Converged,
+            });
+        }
+        precond.apply(&r, &mut z);
+        let rz_next = dot(&r, &z);
+        let beta = rz_next / rz;
+        rz = rz_next;
+        for (pi, &zi) in p.iter_mut().zip(&z) {
+            *pi = zi + beta * *pi;
+        }
+    }
+    Ok(IterativeSolution {
+        x: x,
+        residuals: residuals,
+        status: ConvergenceStatus::MaxIterReached,
+    })
+}
+
+/// The side on which a preconditioner M is applied to the system `A x = b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecondSide {
+    /// Solve `M^-1 A x = M^-1 b`. The residuals monitored by the solver
+    /// are the preconditioned residuals `M^-1 (b - A x)`.
+    Left,
+    /// Solve `A M^-1 u = b`, then compute `x = M^-1 u`. The residuals
+    /// monitored by the solver are the true residuals `b - A x`.
+    Right,
+}
+
+/// When to stop an iterative solver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoppingCriterion<N> {
+    /// Stop when the norm of the residual drops below `tol * norm(b)`
+    RelativeResidual(N),
+    /// Stop when the norm of the residual drops below `tol`
+    AbsoluteResidual(N),
+}
+
+impl<N: Float> StoppingCriterion<N> {
+    /// The residual norm below which the solver stops, for a system whose
+    /// right hand side has the given norm.
+    pub fn threshold(&self, rhs_norm: N) -> N {
+        match *self {
+            StoppingCriterion::RelativeResidual(tol) => tol * rhs_norm,
+            StoppingCriterion::AbsoluteResidual(tol) => tol,
+        }
+    }
+}
+
+/// The operator `M^-1 A`, holding the workspace for the intermediate
+/// product, as it is applied at each iteration of a solver.
+struct LeftPreconditioned<'a, N, Op: 'a + ?Sized, P: 'a + ?Sized> {
+    op: &'a Op,
+    precond: &'a P,
+    workspace: RefCell<Vec<N>>,
+}
+
+impl<'a, N, Op, P> LeftPreconditioned<'a, N, Op, P>
+where N: Float,
+      Op: 'a + LinearOperator<N> + ?Sized,
+      P: 'a + Preconditioner<N> + ?Sized
+{
+    fn new(op: &'a Op, precond: &'a P) -> Self {
+        LeftPreconditioned {
+            op: op,
+            precond: precond,
+            workspace: RefCell::new(vec![N::zero(); op.shape().0]),
+        }
+    }
+}
+
+impl<'a, N, Op, P> LinearOperator<N> for LeftPreconditioned<'a, N, Op, P>
+where N: Float,
+      Op: 'a + LinearOperator<N> + ?Sized,
+      P: 'a + Preconditioner<N> + ?Sized
+{
+    fn shape(&self) -> (usize, usize) {
+        self.op.shape()
+    }
+
+    fn apply(&self, x: &[N], y: &mut [N]) {
+        let mut tmp = self.workspace.borrow_mut();
+        self.op.apply(x, &mut tmp);
+        self.precond.apply(&tmp, y);
+    }
+}
+
+/// The operator `A M^-1`, see `LeftPreconditioned`
+struct RightPreconditioned<'a, N, Op: 'a + ?Sized, P: 'a + ?Sized> {
+    op: &'a Op,
+    precond: &'a P,
+    workspace: RefCell<Vec<N>>,
+}
+
+impl<'a, N, Op, P> RightPreconditioned<'a, N, Op, P>
+where N: Float,
+      Op: 'a + LinearOperator<N> + ?Sized,
+      P: 'a + Preconditioner<N> + ?Sized
+{
+    fn new(op: &'a Op, precond: &'a P) -> Self {
+        RightPreconditioned {
+            op: op,
+            precond: precond,
+            workspace: RefCell::new(vec![N::zero(); op.shape().1]),
+        }
+    }
+}
+
+impl<'a, N, Op, P> LinearOperator<N> for RightPreconditioned<'a, N, Op, P>
+where N: Float,
+      Op: 'a + LinearOperator<N> + ?Sized,
+      P: 'a + Preconditioner<N> + ?Sized
+{
+    fn shape(&self) -> (usize, usize) {
+        self.op.shape()
+    }
+
+    fn apply(&self, x: &[N], y: &mut [N]) {
+        let mut tmp = self.workspace.borrow_mut();
+        self.precond.apply(x, &mut tmp);
+        self.op.apply(&tmp, y);
+    }
+}
+
+/// Run an unpreconditioned solver on the system preconditioned on the
+/// given side.
+fn solve_preconditioned<N, Op, P, F>(op: &Op,
+                                     rhs: &[N],
+                                     precond: &P,
+                                     side: PrecondSide,
+                                     criterion: StoppingCriterion<N>,
+                                     solver: F)
+                                     -> Result<IterativeSolution<N>,
+                                               SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized,
+      P: Preconditioner<N> + ?Sized,
+      F: Fn(&LinearOperator<N>, &[N], N)
+            -> Result<IterativeSolution<N>, SprsError>
+{
+    check_system_dims(op, rhs);
+    match side {
+        PrecondSide::Left => {
+            let mut precond_rhs = vec![N::zero(); rhs.len()];
+            precond.apply(rhs, &mut precond_rhs);
+            let threshold = criterion.threshold(norm2(&precond_rhs));
+            let left = LeftPreconditioned::new(op, precond);
+            solver(&left, &precond_rhs, threshold)
+        }
+        PrecondSide::Right => {
+            let threshold = criterion.threshold(norm2(rhs));
+            let right = RightPreconditioned::new(op, precond);
+            let mut sol = try!(solver(&right, rhs, threshold));
+            let mut x = vec![N::zero(); rhs.len()];
+            precond.apply(&sol.x, &mut x);
+            sol.x = x;
+            Ok(sol)
+        }
+    }
+}
+
+/// Solve the system `A x = b` using the restarted GMRES(m) method, starting
+/// from a zero initial guess.
+///
+/// The Krylov basis is rebuilt from the current residual every `restart`
+/// iterations, which bounds the memory usage to `restart + 1` vectors.
+/// `max_iter` bounds the total number of iterations, including restarts.
+///
+/// The residual history holds the residual norms estimated by the
+/// Hessenberg least squares problem, which match the norms of the
+/// (preconditioned, for left preconditioning) residuals in exact
+/// arithmetic.
+///
+/// # Panics
+///
+/// * if op is not square
+/// * if rhs does not have the dimension of op
+/// * if restart is 0
+///
+/// # Errors
+///
+/// `SolverBreakdown` if the Krylov space becomes invariant without
+/// containing the solution, which happens for singular operators.
+pub fn gmres<N, Op, P>(op: &Op,
+                       rhs: &[N],
+                       precond: &P,
+                       side: PrecondSide,
+                       restart: usize,
+                       criterion: StoppingCriterion<N>,
+                       max_iter: usize)
+                       -> Result<IterativeSolution<N>, SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized,
+      P: Preconditioner<N> + ?Sized
+{
+    assert!(restart > 0, "restart should be positive");
+    solve_preconditioned(op, rhs, precond, side, criterion,
+                         |op, rhs, threshold| {
+                             gmres_impl(op, rhs, restart, threshold, max_iter)
+                         })
+}
+
+fn gmres_impl<N, Op>(op: &Op,
+                     rhs: &[N],
+                     restart: usize,
+                     threshold: N,
+                     max_iter: usize)
+                     -> Result<IterativeSolution<N>, SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized
+{
+    let n = rhs.len();
+    let mut x = vec![N::zero(); n];
+    let mut residuals = Vec::new();
+    let mut r = vec![N::zero(); n];
+    let mut iterations = 0;
+    loop {
+        // residual of the current iterate
+        op.apply(&x, &mut r);
+        for (ri, &bi) in r.iter_mut().zip(rhs) {
+            *ri = bi - *ri;
+        }
+        let beta = norm2(&r);
+        if residuals.is_empty() {
+            residuals.push(beta);
+        }
+        if beta <= threshold {
+            return Ok(IterativeSolution {
+                x: x,
+                residuals: residuals,
+                status: ConvergenceStatus::Converged,
+            });
+        }
+        if iterations == max_iter {
+            return Ok(IterativeSolution {
+                x: x,
+                residuals: residuals,
+                status: ConvergenceStatus::MaxIterReached,
+            });
+        }
+
+        // Arnoldi process, with the Hessenberg matrix reduced to upper
+        // triangular form by Givens rotations as it is built
+        let mut basis: Vec<Vec<N>> = vec![r.iter().map(|&ri| ri / beta)
+                                           .collect()];
+        let mut hessenberg: Vec<Vec<N>> = Vec::with_capacity(restart);
+        let mut rotations: Vec<(N, N)> = Vec::with_capacity(restart);
+        let mut g = vec![beta];
+        let mut converged = false;
+        while hessenberg.len() < restart && iterations < max_iter {
+            let j = hessenberg.len();
+            let mut w = vec![N::zero(); n];
+            op.apply(&basis[j], &mut w);
+            let mut h = Vec::with_capacity(j + 2);
+            for v in &basis {
+                let hij = dot(&w, v);
+                axpy(-hij, v, &mut w);
+                h.push(hij);
+            }
+            let h_next = norm2(&w);
+            h.push(h_next);
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let (hi, hi1) = (h[i], h[i + 1]);
+                h[i] = c * hi + s * hi1;
+                h[i + 1] = c * hi1 - s * hi;
+            }
+            let rho = h[j].hypot(h[j + 1]);
+            let (c, s) = if rho == N::zero() {
+                (N::one(), N::zero())
+            } else {
+                (h[j] / rho, h[j + 1] / rho)
+            };
+            h[j] = rho;
+            h.pop();
+            rotations.push((c, s));
+            let gj = g[j];
+            g[j] = c * gj;
+            g.push(-s * gj);
+            hessenberg.push(h);
+            iterations += 1;
+            let res_estimate = g[j + 1].abs();
+            residuals.push(res_estimate);
+            if res_estimate <= threshold {
+                converged = true;
+                break;
+            }
+            if h_next == N::zero() {
+                // the Krylov space is invariant: the least squares
+                // solution is exact, unless the operator is singular,
+                // which is detected by the triangular solve below
+                break;
+            }
+            basis.push(w.iter().map(|&wi| wi / h_next).collect());
+        }
+
+        // solve the triangular least squares system and update x
+        let k = hessenberg.len();
+        let mut y = g[..k].to_vec();
+        for i in (0..k).rev() {
+            let mut yi = y[i];
+            for l in i + 1..k {
+                yi = yi - hessenberg[l][i] * y[l];
+            }
+            if hessenberg[i][i] == N::zero() {
+                return Err(SprsError::SolverBreakdown);
+            }
+            y[i] = yi / hessenberg[i][i];
+        }
+        for (v, &yi) in basis.iter().zip(&y) {
+            axpy(yi, v, &mut x);
+        }
+        if converged {
+            return Ok(IterativeSolution {
+                x: x,
+                residuals: residuals,
+                status: ConvergenceStatus::Converged,
+            });
+        }
+    }
+}
+
+/// Solve the system `A x = b` using the stabilized bi-conjugate gradient
+/// method (BiCGSTAB), starting from a zero initial guess.
+///
+/// # Panics
+///
+/// * if op is not square
+/// * if rhs does not have the dimension of op
+///
+/// # Errors
+///
+/// `SolverBreakdown` if one of the scalar quantities the method divides by
+/// vanishes, in which case the method cannot make progress.
+pub fn bicgstab<N, Op, P>(op: &Op,
+                          rhs: &[N],
+                          precond: &P,
+                          side: PrecondSide,
+                          criterion: StoppingCriterion<N>,
+                          max_iter: usize)
+                          -> Result<IterativeSolution<N>, SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized,
+      P: Preconditioner<N> + ?Sized
+{
+    solve_preconditioned(op, rhs, precond, side, criterion,
+                         |op, rhs, threshold| {
+                             bicgstab_impl(op, rhs, threshold, max_iter)
+                         })
+}
+
+fn bicgstab_impl<N, Op>(op: &Op,
+                        rhs: &[N],
+                        threshold: N,
+                        max_iter: usize)
+                        -> Result<IterativeSolution<N>, SprsError>
+where N: Float,
+      Op: LinearOperator<N> + ?Sized
+{
+    let n = rhs.len();
+    let mut x = vec![N::zero(); n];
+    let mut r = rhs.to_vec();
+    let r_hat = rhs.to_vec();
+    let mut p = vec![N::zero(); n];
+    let mut v = vec![N::zero(); n];
+    let mut s = vec![N::zero(); n];
+    let mut t = vec![N::zero(); n];
+    let (mut rho_prev, mut alpha, mut omega) = (N::one(), N::one(), N::one());
+
+    let mut residuals = vec![norm2(&r)];
+    let mut status = ConvergenceStatus::MaxIterReached;
+    if residuals[0] <= threshold {
+        status = ConvergenceStatus::Converged;
+    }
+    let mut iter = 0;
+    while status != ConvergenceStatus::Converged && iter < max_iter {
+        iter += 1;
+        let rho = dot(&r_hat, &r);
+        if rho == N::zero() {
+            return Err(SprsError::SolverBreakdown);
+        }
+        let beta = (rho / rho_prev) * (alpha / omega);
+        for ((pi, &ri), &vi) in p.iter_mut().zip(&r).zip(&v) {
+            *pi = ri + beta * (*pi - omega * vi);
+        }
+        op.apply(&p, &mut v);
+        let r_hat_v = dot(&r_hat, &v);
+        if r_hat_v == N::zero() {
+            return Err(SprsError::SolverBreakdown);
+        }
+        alpha = rho / r_hat_v;
+        for ((si, &ri), &vi) in s.iter_mut().zip(&r).zip(&v) {
+            *si = ri - alpha * vi;
+        }
+        let s_norm = norm2(&s);
+        if s_norm <= threshold {
+            axpy(alpha, &p, &mut x);
+            residuals.push(s_norm);
+            status = ConvergenceStatus::Converged;
+            break;
+        }
+        op.apply(&s, &mut t);
+        let tt = dot(&t, &t);
+        if tt == N::zero() {
+            return Err(SprsError::SolverBreakdown);
+        }
+        omega = dot(&t, &s) / tt;
+        if omega == N::zero() {
+            return Err(SprsError::SolverBreakdown);
+        }
+        axpy(alpha, &p, &mut x);
+        axpy(omega, &s, &mut x);
+        for ((ri, &si), &ti) in r.iter_mut().zip(&s).zip(&t) {
+            *ri = si - omega * ti;
+        }
+        let res_norm = norm2(&r);
+        residuals.push(res_norm);
+        if res_norm <= threshold {
+            status = ConvergenceStatus::Converged;
+        }
+        rho_prev = rho;
+    }
+    Ok(IterativeSolution {
+        x: x,
+        residuals: residuals,
+        status: status,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::{CsMat, CsMatOwned};
+    use errors::SprsError;
+    use super::{cg, pcg, ConvergenceStatus, Jacobi, LinearOperator};
+    use super::{gmres, bicgstab, NoPreconditioner, PrecondSide};
+    use super::StoppingCriterion::{AbsoluteResidual, RelativeResidual};
+
+    /// The 1D laplacian of size n, scaled row and column wise by scales
+    fn laplacian(n: usize, scales: &[f64]) -> CsMatOwned<f64> {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for i in 0..n {
+            if i > 0 {
+                indices.push(i - 1);
+                data.push(-1. * scales[i] * scales[i - 1]);
+            }
+            indices.push(i);
+            data.push(2. * scales[i] * scales[i]);
+            if i + 1 < n {
+                indices.push(i + 1);
+                data.push(-1. * scales[i] * scales[i + 1]);
+            }
+            indptr.push(indices.len());
+        }
+        CsMat::new((n, n), indptr, indices, data)
+    }
+
+    fn residual_norm(mat: &CsMatOwned<f64>, x: &[f64], b: &[f64]) -> f64 {
+        let mut ax = vec![0.; b.len()];
+        mat.apply(x, &mut ax);
+        ax.iter().zip(b).map(|(y, b)| (y - b) * (y - b)).sum::<f64>().sqrt()
+    }
+
+    #[test]
+    fn operator_csr_csc() {
+        let mat = laplacian(4, &[1.; 4]);
+        let x = [1., 2., 3., 4.];
+        let mut y = [1.; 4];
+        mat.apply(&x, &mut y);
+        assert_eq!(y, [0., 0., 0., 5.]);
+        let mut y = [1.; 4];
+        mat.to_csc().apply(&x, &mut y);
+        assert_eq!(y, [0., 0., 0., 5.]);
+    }
+
+    #[test]
+    fn cg_laplacian() {
+        let n = 50;
+        let mat = laplacian(n, &vec![1.; n]);
+        let b: Vec<f64> = (0..n).map(|i| (i as f64).sin()).collect();
+        let sol = cg(&mat, &b, 1e-10, 100).unwrap();
+        assert_eq!(sol.status, ConvergenceStatus::Converged);
+        // in exact arithmetic, CG converges in at most n iterations
+        assert!(sol.iterations() <= n);
+        assert_eq!(sol.residuals.len(), sol.iterations() + 1);
+        assert!(residual_norm(&mat, &sol.x, &b) < 1e-9);
+        assert!(*sol.residuals.last().unwrap() < 1e-10 * sol.residuals[0]);
+    }
+
+    #[test]
+    fn cg_max_iter() {
+        let n = 50;
+        let mat = laplacian(n, &vec![1.; n]);
+        let b = vec![1.; n];
+        let sol = cg(&mat, &b, 1e-10, 5).unwrap();
+        assert_eq!(sol.status, ConvergenceStatus::MaxIterReached);
+        assert!(!sol.converged());
+        assert_eq!(sol.iterations(), 5);
+    }
+
+    #[test]
+    fn cg_zero_rhs() {
+        let mat = laplacian(5, &[1.; 5]);
+        let sol = cg(&mat, &[0.; 5], 1e-10, 10).unwrap();
+        assert!(sol.converged());
+        assert_eq!(sol.iterations(), 0);
+        assert_eq!(sol.x, vec![0.; 5]);
+    }
+
+    #[test]
+    fn cg_breakdown() {
+        // indefinite matrix
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., -1.]);
+        let res = cg(&mat, &[1., 1.], 1e-10, 10);
+        assert_eq!(res.err(), Some(SprsError::SolverBreakdown));
+    }
+
+    #[test]
+    fn jacobi_pcg() {
+        // a badly scaled laplacian converges faster with a jacobi
+        // preconditioner
+        let n = 40;
+        let scales: Vec<f64> = (0..n).map(|i| 1. + (i % 7) as f64 * 10.)
+                                     .collect();
+        let mat = laplacian(n, &scales);
+        let b = vec![1.; n];
+        let jacobi = Jacobi::new(&mat).unwrap();
+        let plain = cg(&mat, &b, 1e-8, 1000).unwrap();
+        let precond = pcg(&mat, &b, &jacobi, 1e-8, 1000).unwrap();
+        assert!(plain.converged() && precond.converged());
+        assert!(precond.iterations() < plain.iterations());
+        assert!(residual_norm(&mat, &precond.x, &b) < 1e-7 * 40f64.sqrt());
+    }
+
+    #[test]
+    fn jacobi_singular() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 1], vec![1., 1.]);
+        assert_eq!(Jacobi::new(&mat).err(), Some(SprsError::SingularMatrix));
+    }
+
+    /// A convection-diffusion operator, which is not symmetric
+    fn convection_diffusion(n: usize, convection: f64) -> CsMatOwned<f64> {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for i in 0..n {
+            if i > 0 {
+                indices.push(i - 1);
+                data.push(-1. - convection);
+            }
+            indices.push(i);
+            data.push(2. + (i % 3) as f64);
+            if i + 1 < n {
+                indices.push(i + 1);
+                data.push(-1. + convection);
+            }
+            indptr.push(indices.len());
+        }
+        CsMat::new((n, n), indptr, indices, data)
+    }
+
+    #[test]
+    fn gmres_unsymmetric() {
+        let n = 40;
+        let mat = convection_diffusion(n, 0.4);
+        let b: Vec<f64> = (0..n).map(|i| 1. + (i as f64).cos()).collect();
+        let jacobi = Jacobi::new(&mat).unwrap();
+        for &restart in &[5, 50] {
+            let sol = gmres(&mat, &b, &NoPreconditioner, PrecondSide::Right,
+                            restart, RelativeResidual(1e-10), 500).unwrap();
+            assert!(sol.converged());
+            assert!(residual_norm(&mat, &sol.x, &b) < 1e-9 * 40.);
+            for &side in &[PrecondSide::Left, PrecondSide::Right] {
+                let sol = gmres(&mat, &b, &jacobi, side, restart,
+                                RelativeResidual(1e-10), 500).unwrap();
+                assert!(sol.converged());
+                assert_eq!(sol.residuals.len(), sol.iterations() + 1);
+                assert!(residual_norm(&mat, &sol.x, &b) < 1e-8 * 40.);
+            }
+        }
+        // without restarts, GMRES converges in at most n iterations
+        let sol = gmres(&mat, &b, &NoPreconditioner, PrecondSide::Right,
+                        n, RelativeResidual(1e-12), n).unwrap();
+        assert!(sol.converged());
+    }
+
+    #[test]
+    fn gmres_criterion_and_max_iter() {
+        let n = 40;
+        let mat = convection_diffusion(n, 0.4);
+        let b = vec![1.; n];
+        let sol = gmres(&mat, &b, &NoPreconditioner, PrecondSide::Right,
+                        10, AbsoluteResidual(1e-3), 500).unwrap();
+        assert!(sol.converged());
+        assert!(*sol.residuals.last().unwrap() <= 1e-3);
+        assert!(residual_norm(&mat, &sol.x, &b) <= 1.1e-3);
+
+        let sol = gmres(&mat, &b, &NoPreconditioner, PrecondSide::Right,
+                        3, RelativeResidual(1e-12), 7).unwrap();
+        assert_eq!(sol.status, ConvergenceStatus::MaxIterReached);
+        assert_eq!(sol.iterations(), 7);
+    }
+
+    #[test]
+    fn gmres_singular() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 1], vec![0], vec![1.]);
+        let res = gmres(&mat, &[0., 1.], &NoPreconditioner,
+                        PrecondSide::Right, 2, RelativeResidual(1e-10), 10);
+        assert_eq!(res.err(), Some(SprsError::SolverBreakdown));
+    }
+
+    #[test]
+    fn bicgstab_unsymmetric() {
+        let n = 40;
+        let mat = convection_diffusion(n, 0.4);
+        let b: Vec<f64> = (0..n).map(|i| 1. + (i as f64).cos()).collect();
+        let sol = bicgstab(&mat, &b, &NoPreconditioner, PrecondSide::Right,
+                           RelativeResidual(1e-10), 500).unwrap();
+        assert!(sol.converged());
+        assert!(residual_norm(&mat, &sol.x, &b) < 1e-9 * 40.);
+        let jacobi = Jacobi::new(&mat).unwrap();
+        for &side in &[PrecondSide::Left, PrecondSide::Right] {
+            let sol = bicgstab(&mat, &b, &jacobi, side,
+                               RelativeResidual(1e-10), 500).unwrap();
+            assert!(sol.converged());
+            assert_eq!(sol.residuals.len(), sol.iterations() + 1);
+            assert!(residual_norm(&mat, &sol.x, &b) < 1e-8 * 40.);
+        }
+
+        let sol = bicgstab(&mat, &b, &NoPreconditioner, PrecondSide::Right,
+                           RelativeResidual(1e-14), 2).unwrap();
+        assert_eq!(sol.status, ConvergenceStatus::MaxIterReached);
+        assert_eq!(sol.iterations(), 2);
+    }
+
+    #[test]
+    fn bicgstab_breakdown() {
+        // |0 1|
+        // |1 0|, for which the first step gives r_hat^T A p = 0
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 1.]);
+        let res = bicgstab(&mat, &[1., 0.], &NoPreconditioner,
+                           PrecondSide::Right, RelativeResidual(1e-10), 10);
+        assert_eq!(res.err(), Some(SprsError::SolverBreakdown));
+    }
+}
@@ -1,7 +1,9 @@
 ///! Sparse linear algebra
 ///!
 ///! This module contains solvers for sparse linear systems. Currently
-///! there are solver for sparse triangular systems and symmetric systems.
+///! there are solver for sparse triangular systems and symmetric systems,
+///! as well as iterative solvers and incomplete factorizations to
+///! precondition them.
 
 
 use num_traits::Num;
@@ -9,6 +11,9 @@ use std::iter::IntoIterator;
 
 pub mod trisolve;
 pub mod etree;
+pub mod ordering;
+pub mod iterative;
+pub mod incomplete;
 
 /// Diagonal solve
 pub fn diag_solve<'a, N, I1, I2>(diag: I1, x: I2)
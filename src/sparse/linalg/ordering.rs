@@ -0,0 +1,694 @@
+///! Orderings of sparse matrices
+///!
+///! The non-zero count of the factors of a sparse factorization depends
+///! heavily on the ordering of the rows and columns of the factorized
+///! matrix. This module computes permutations which reduce this fill-in,
+///! and can be passed to eg `LdlSymbolic::new_perm` in the `sprs-ldl` crate.
+///!
+///! It also computes bandwidth reducing orderings, which are useful for
+///! banded solvers and for the memory locality of matrix vector products.
+
+use std::collections::BTreeSet;
+use std::ops::Deref;
+
+use indexing::SpIndex;
+use sparse::CsMatBase;
+use sparse::permutation::{PermutationBase, PermOwnedI};
+
+/// Statistics about the fill-in of the cholesky factorization of a
+/// symmetric matrix, for a given ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillStats {
+    /// The number of non-zero entries in the strictly lower part of L
+    pub nnz_l: usize,
+    /// The number of multiply-add operations required by the numeric
+    /// factorization, ie the sum of the squared column counts of L
+    pub flops: usize,
+}
+
+/// Compute the adjacency lists of the graph of `A + A^T`, without
+/// self loops. Each list is sorted.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn symmetric_adjacency<N, I, IpS, IS, DS>(mat: &CsMatBase<N, I,
+                                                              IpS, IS, DS>)
+                                              -> Vec<Vec<usize>>
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    let mut adj = vec![Vec::new(); n];
+    for (outer_ind, vec) in mat.outer_iterator().enumerate() {
+        for (inner_ind, _) in vec.iter() {
+            if inner_ind != outer_ind {
+                adj[outer_ind].push(inner_ind);
+                adj[inner_ind].push(outer_ind);
+            }
+        }
+    }
+    for neighbours in adj.iter_mut() {
+        neighbours.sort();
+        neighbours.dedup();
+    }
+    adj
+}
+
+/// Compute the fill-in statistics of the cholesky factorization of
+/// `P (A + A^T) P^T`, where only the non-zero pattern of A is considered.
+///
+/// Comparing the statistics of an ordering with those of the identity
+/// permutation shows how much fill-in it saves.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn fill_stats<N, I, IpS, IS, DS, PS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                         perm: &PermutationBase<I, PS>)
+                                         -> FillStats
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS: Deref<Target = [I]>
+{
+    let adj = symmetric_adjacency(mat);
+    let n = adj.len();
+    // row counts of L, computed by walking up the elimination tree as
+    // in the symbolic LDL factorization
+    let mut parents: Vec<Option<usize>> = vec![None; n];
+    let mut flags = vec![0; n];
+    let mut col_counts = vec![0; n];
+    for k in 0..n {
+        flags[k] = k;
+        for &orig_ind in &adj[perm.at(k)] {
+            let mut i = perm.at_inv(orig_ind);
+            if i > k {
+                continue;
+            }
+            while flags[i] != k {
+                if parents[i].is_none() {
+                    parents[i] = Some(k);
+                }
+                col_counts[i] += 1;
+                flags[i] = k;
+                i = parents[i].expect("parent set above");
+            }
+        }
+    }
+    FillStats {
+        nnz_l: col_counts.iter().sum(),
+        flops: col_counts.iter().map(|&c| c * c).sum(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum NodeStatus {
+    Variable,
+    Element,
+    Dead,
+}
+
+/// Compute an approximate minimum degree ordering of the symmetric
+/// non-zero pattern of `A + A^T`.
+///
+/// The returned permutation P is such that the cholesky factorization of
+/// `P A P^T` has a small fill-in. It can be passed directly to
+/// `LdlSymbolic::new_perm`.
+///
+/// The ordering is computed on the quotient graph of the elimination,
+/// using the approximate external degrees, element absorption and
+/// supervariable detection of the AMD algorithm by Amestoy, Davis and Duff.
+/// Ties between nodes of equal degree are broken by choosing the node
+/// with the smallest index.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn amd<N, I, IpS, IS, DS>(mat: &CsMatBase<N, I, IpS, IS, DS>)
+                              -> PermOwnedI<I>
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>
+{
+    let mut adj = symmetric_adjacency(mat);
+    let n = adj.len();
+
+    let mut status = vec![NodeStatus::Variable; n];
+    // weight of each supervariable, 0 for variables merged into another
+    let mut weights = vec![1; n];
+    let mut members: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    // elements adjacent to each variable, and variables of each element
+    let mut elements: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut element_vars: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut degrees: Vec<usize> = adj.iter().map(|a| a.len()).collect();
+    let mut queue: BTreeSet<(usize, usize)> = degrees.iter()
+                                                     .cloned()
+                                                     .zip(0..n)
+                                                     .collect();
+    // stamp based markers, to avoid clearing workspaces at each step
+    let mut marks = vec![0; n];
+    let mut stamp = 0;
+    // |Le \ Lp| for the elements adjacent to the current pivot element
+    let mut external = vec![0; n];
+    let mut external_stamps = vec![0; n];
+    let mut remaining = n;
+
+    let mut order = Vec::with_capacity(n);
+
+    while let Some(&(_, pivot)) = queue.iter().next() {
+        queue.remove(&(degrees[pivot], pivot));
+        remaining -= weights[pivot];
+        order.extend(members[pivot].drain(..));
+        stamp += 1;
+
+        // form the new element, absorbing the elements adjacent to pivot
+        let mut pivot_vars = Vec::new();
+        marks[pivot] = stamp;
+        for &var in &adj[pivot] {
+            if status[var] == NodeStatus::Variable && marks[var] != stamp {
+                marks[var] = stamp;
+                pivot_vars.push(var);
+            }
+        }
+        for &elt in &elements[pivot] {
+            for &var in &element_vars[elt] {
+                if status[var] == NodeStatus::Variable &&
+                   marks[var] != stamp {
+                    marks[var] = stamp;
+                    pivot_vars.push(var);
+                }
+            }
+            status[elt] = NodeStatus::Dead;
+            element_vars[elt].clear();
+        }
+        status[pivot] = NodeStatus::Element;
+        adj[pivot].clear();
+        elements[pivot].clear();
+
+        // the variables of the new element no longer need their edges
+        // to other variables of the element
+        for &var in &pivot_vars {
+            elements[var].retain(|&elt| status[elt] == NodeStatus::Element);
+            elements[var].push(pivot);
+            adj[var].retain(|&other| {
+                status[other] == NodeStatus::Variable && marks[other] != stamp
+            });
+        }
+
+        // merge indistinguishable variables into supervariables
+        let mut hashed: Vec<(usize, usize)> = pivot_vars.iter()
+            .map(|&var| {
+                let hash = adj[var].iter().chain(elements[var].iter())
+                                   .fold(0usize, |h, &x| h.wrapping_add(x));
+                (hash, var)
+            })
+            .collect();
+        hashed.sort();
+        for &var in &pivot_vars {
+            adj[var].sort();
+            elements[var].sort();
+        }
+        for i in 0..hashed.len() {
+            let (hash, var) = hashed[i];
+            if weights[var] == 0 {
+                continue;
+            }
+            for &(other_hash, other) in &hashed[i + 1..] {
+                if other_hash != hash {
+                    break;
+                }
+                if weights[other] != 0 && adj[var] == adj[other] &&
+                   elements[var] == elements[other] {
+                    queue.remove(&(degrees[other], other));
+                    weights[var] += weights[other];
+                    weights[other] = 0;
+                    status[other] = NodeStatus::Dead;
+                    let merged = members[other].split_off(0);
+                    members[var].extend(merged);
+                    adj[other].clear();
+                    elements[other].clear();
+                }
+            }
+        }
+        pivot_vars.retain(|&var| weights[var] != 0);
+        let pivot_weight: usize = pivot_vars.iter().map(|&v| weights[v]).sum();
+        element_vars[pivot] = pivot_vars.clone();
+
+        // compute |Le \ Lp| for the elements adjacent to the new element
+        for &var in &pivot_vars {
+            for &elt in &elements[var] {
+                if elt == pivot {
+                    continue;
+                }
+                if external_stamps[elt] != stamp {
+                    external_stamps[elt] = stamp;
+                    element_vars[elt].retain(|&v| {
+                        status[v] == NodeStatus::Variable
+                    });
+                    external[elt] = element_vars[elt].iter()
+                                                     .map(|&v| weights[v])
+                                                     .sum();
+                }
+                external[elt] -= weights[var];
+            }
+        }
+
+        // update the approximate degrees, absorbing the elements which
+        // are included in the new element
+        for &var in &pivot_vars {
+            let mut absorbed = false;
+            for &elt in &elements[var] {
+                if elt != pivot && external[elt] == 0 {
+                    status[elt] = NodeStatus::Dead;
+                    absorbed = true;
+                }
+            }
+            if absorbed {
+                elements[var].retain(|&elt| {
+                    status[elt] == NodeStatus::Element
+                });
+            }
+            let var_weight = weights[var];
+            let adj_degree: usize = adj[var].iter().map(|&v| weights[v]).sum();
+            let elt_degree: usize = elements[var].iter()
+                                                 .filter(|&&elt| elt != pivot)
+                                                 .map(|&elt| external[elt])
+                                                 .sum();
+            let bound = adj_degree + pivot_weight - var_weight + elt_degree;
+            let degree = bound.min(degrees[var] + pivot_weight - var_weight)
+                              .min(remaining - var_weight);
+            queue.remove(&(degrees[var], var));
+            degrees[var] = degree;
+            queue.insert((degree, var));
+        }
+    }
+
+    let order = order.into_iter().map(I::from_usize).collect();
+    PermutationBase::new(order)
+}
+
+/// Compute the bandwidth of `P A P^T`, ie the largest distance between
+/// the diagonal and a non-zero entry.
+///
+/// Using the identity permutation gives the bandwidth of A.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn bandwidth<N, I, IpS, IS, DS, PS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                        perm: &PermutationBase<I, PS>)
+                                        -> usize
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS: Deref<Target = [I]>
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    let mut res = 0;
+    for (outer_ind, vec) in mat.outer_iterator().enumerate() {
+        let outer_ind = perm.at_inv(outer_ind);
+        for (inner_ind, _) in vec.iter() {
+            let inner_ind = perm.at_inv(inner_ind);
+            let dist = if inner_ind > outer_ind {
+                inner_ind - outer_ind
+            } else {
+                outer_ind - inner_ind
+            };
+            res = res.max(dist);
+        }
+    }
+    res
+}
+
+/// Compute the profile of the symmetric pattern `P (A + A^T) P^T`, ie the
+/// sum over all rows of the distance between the first non-zero entry of
+/// the row and the diagonal.
+///
+/// The profile is the number of entries that a skyline (or envelope)
+/// storage of the lower triangular part of the matrix has to store in
+/// addition to the diagonal. Using the identity permutation gives the
+/// profile of A.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn profile<N, I, IpS, IS, DS, PS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                      perm: &PermutationBase<I, PS>)
+                                      -> usize
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS: Deref<Target = [I]>
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    let n = mat.rows();
+    // first non-zero column of each row of the permuted matrix
+    let mut first_nnz: Vec<usize> = (0..n).collect();
+    for (outer_ind, vec) in mat.outer_iterator().enumerate() {
+        let outer_ind = perm.at_inv(outer_ind);
+        for (inner_ind, _) in vec.iter() {
+            let inner_ind = perm.at_inv(inner_ind);
+            if inner_ind < outer_ind {
+                first_nnz[outer_ind] = first_nnz[outer_ind].min(inner_ind);
+            } else {
+                first_nnz[inner_ind] = first_nnz[inner_ind].min(outer_ind);
+            }
+        }
+    }
+    first_nnz.iter().enumerate().map(|(row, &first)| row - first).sum()
+}
+
+/// Compute the level structure of the connected component of root,
+/// visiting the nodes in increasing degree order inside each level.
+///
+/// Visited nodes are marked with stamp.
+fn level_structure(adj: &[Vec<usize>],
+                   root: usize,
+                   marks: &mut [usize],
+                   stamp: usize)
+                   -> Vec<Vec<usize>> {
+    let mut levels = vec![vec![root]];
+    marks[root] = stamp;
+    loop {
+        let mut next_level = Vec::new();
+        for &node in levels.last().expect("levels is not empty") {
+            let start = next_level.len();
+            for &neighbour in &adj[node] {
+                if marks[neighbour] != stamp {
+                    marks[neighbour] = stamp;
+                    next_level.push(neighbour);
+                }
+            }
+            next_level[start..].sort_by_key(|&node| (adj[node].len(), node));
+        }
+        if next_level.is_empty() {
+            return levels;
+        }
+        levels.push(next_level);
+    }
+}
+
+/// Compute a reverse Cuthill-McKee ordering of the symmetric non-zero
+/// pattern of `A + A^T`.
+///
+/// The returned permutation P is such that `P A P^T` has a small bandwidth
+/// and profile, which is useful for banded solvers and to improve the
+/// memory locality of matrix vector products. The effect of the ordering
+/// can be measured using `bandwidth` and `profile`.
+///
+/// Each connected component of the graph is ordered in turn, starting
+/// from a pseudo-peripheral node found with the algorithm of George and Liu.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn reverse_cuthill_mckee<N, I, IpS, IS, DS>(
+    mat: &CsMatBase<N, I, IpS, IS, DS>) -> PermOwnedI<I>
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>
+{
+    let adj = symmetric_adjacency(mat);
+    let n = adj.len();
+    let degree = |node: usize| adj[node].len();
+    // nodes already ordered are marked with 1, the other stamps are used
+    // for the traversals looking for a pseudo-peripheral node
+    let mut marks = vec![0; n];
+    let mut stamp = 1;
+    let mut order = Vec::with_capacity(n);
+    let mut by_degree: Vec<usize> = (0..n).collect();
+    by_degree.sort_by_key(|&node| (degree(node), node));
+
+    for &start in &by_degree {
+        if marks[start] == 1 {
+            continue;
+        }
+        // find a pseudo-peripheral node of the component of start
+        let mut root = start;
+        stamp += 1;
+        let mut levels = level_structure(&adj, root, &mut marks, stamp);
+        loop {
+            let candidate = *levels.last()
+                                   .expect("levels is not empty")
+                                   .iter()
+                                   .min_by_key(|&&node| (degree(node), node))
+                                   .expect("levels are not empty");
+            stamp += 1;
+            let candidate_levels = level_structure(&adj,
+                                                   candidate,
+                                                   &mut marks,
+                                                   stamp);
+            if candidate_levels.len() <= levels.len() {
+                break;
+            }
+            root = candidate;
+            levels = candidate_levels;
+        }
+        debug_assert!(levels[0][0] == root);
+        // the Cuthill-McKee ordering of the component is its level
+        // structure from the pseudo-peripheral node
+        for level in &levels {
+            for &node in level {
+                marks[node] = 1;
+                order.push(node);
+            }
+        }
+    }
+    order.reverse();
+    let order = order.into_iter().map(I::from_usize).collect();
+    PermutationBase::new(order)
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::{CsMat, CsMatOwned};
+    use sparse::permutation::PermOwned;
+    use sparse::triplet::TripletMat;
+    use super::{amd, fill_stats, symmetric_adjacency, FillStats};
+    use super::{bandwidth, profile, reverse_cuthill_mckee};
+
+    /// The 2D laplacian on a grid of size n * n
+    fn grid_laplacian(n: usize) -> CsMatOwned<f64> {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                let ind = i * n + j;
+                if i > 0 {
+                    indices.push(ind - n);
+                    data.push(-1.);
+                }
+                if j > 0 {
+                    indices.push(ind - 1);
+                    data.push(-1.);
+                }
+                indices.push(ind);
+                data.push(4.);
+                if j + 1 < n {
+                    indices.push(ind + 1);
+                    data.push(-1.);
+                }
+                if i + 1 < n {
+                    indices.push(ind + n);
+                    data.push(-1.);
+                }
+                indptr.push(indices.len());
+            }
+        }
+        CsMat::new((n * n, n * n), indptr, indices, data)
+    }
+
+    fn assert_is_perm(perm: &PermOwned, n: usize) {
+        let mut seen = vec![false; n];
+        for i in 0..n {
+            assert!(!seen[perm.at(i)]);
+            seen[perm.at(i)] = true;
+            assert_eq!(perm.at_inv(perm.at(i)), i);
+        }
+    }
+
+    #[test]
+    fn adjacency_is_symmetrized() {
+        // |1 1 0|
+        // |0 1 0|
+        // |1 0 1|
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 3, 5],
+                             vec![0, 1, 1, 0, 2],
+                             vec![1, 1, 1, 1, 1]);
+        let adj = symmetric_adjacency(&mat);
+        assert_eq!(adj, vec![vec![1, 2], vec![0], vec![0]]);
+    }
+
+    #[test]
+    fn fill_stats_arrow() {
+        // arrow matrix with a dense first row and column
+        let n = 6;
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        for i in 0..n {
+            if i == 0 {
+                indices.extend(0..n);
+            } else {
+                indices.push(0);
+                indices.push(i);
+            }
+            indptr.push(indices.len());
+        }
+        let data = vec![1.; indices.len()];
+        let mat = CsMat::new((n, n), indptr, indices, data);
+
+        let natural = fill_stats(&mat, &PermOwned::identity());
+        assert_eq!(natural,
+                   FillStats {
+                       nnz_l: 15,
+                       flops: 5 * 5 + 4 * 4 + 3 * 3 + 2 * 2 + 1,
+                   });
+
+        let perm = amd(&mat);
+        assert_is_perm(&perm, n);
+        // the dense node should be eliminated once it has become sparse
+        assert!(perm.at_inv(0) >= n - 2);
+        let stats = fill_stats(&mat, &perm);
+        assert_eq!(stats, FillStats { nnz_l: 5, flops: 5 });
+    }
+
+    #[test]
+    fn amd_grid() {
+        let mat = grid_laplacian(10);
+        let perm = amd(&mat);
+        assert_is_perm(&perm, 100);
+        let natural = fill_stats(&mat, &PermOwned::identity());
+        let stats = fill_stats(&mat, &perm);
+        assert_eq!(natural.nnz_l, 909);
+        assert!(stats.nnz_l < 600);
+        assert!(stats.flops < natural.flops / 2);
+    }
+
+    #[test]
+    fn amd_disconnected_and_empty() {
+        // diagonal matrix: no fill whatever the ordering
+        let mat: CsMatOwned<f64> = CsMat::eye(4);
+        let perm = amd(&mat);
+        assert_is_perm(&perm, 4);
+        assert_eq!(fill_stats(&mat, &perm), FillStats { nnz_l: 0, flops: 0 });
+
+        let mat: CsMatOwned<f64> = CsMat::zero((0, 0));
+        let perm = amd(&mat);
+        assert_eq!(fill_stats(&mat, &perm), FillStats { nnz_l: 0, flops: 0 });
+    }
+
+    /// A path graph, whose nodes are numbered using the given order
+    fn shuffled_path(order: &[usize]) -> CsMatOwned<f64> {
+        let n = order.len();
+        let mut triplets = TripletMat::new((n, n));
+        for i in 0..n {
+            triplets.add_triplet(order[i], order[i], 2.);
+            if i + 1 < n {
+                triplets.add_triplet(order[i], order[i + 1], -1.);
+                triplets.add_triplet(order[i + 1], order[i], -1.);
+            }
+        }
+        triplets.to_csr()
+    }
+
+    #[test]
+    fn bandwidth_and_profile() {
+        // |1 0 0 1|
+        // |0 1 1 0|
+        // |0 1 1 0|
+        // |1 0 0 1|
+        let mat = CsMat::new((4, 4),
+                             vec![0, 2, 4, 6, 8],
+                             vec![0, 3, 1, 2, 1, 2, 0, 3],
+                             vec![1; 8]);
+        let identity = PermOwned::identity();
+        assert_eq!(bandwidth(&mat, &identity), 3);
+        assert_eq!(profile(&mat, &identity), 4);
+        let perm = PermOwned::new(vec![0, 3, 1, 2]);
+        assert_eq!(bandwidth(&mat, &perm), 1);
+        assert_eq!(profile(&mat, &perm), 2);
+        assert_eq!(bandwidth(&mat.to_csc(), &perm), 1);
+        assert_eq!(profile(&mat.to_csc(), &perm), 2);
+
+        // the profile only looks at the symmetrized pattern
+        let upper = CsMat::new((3, 3),
+                               vec![0, 2, 3, 3],
+                               vec![0, 2, 1],
+                               vec![1; 3]);
+        assert_eq!(profile(&upper, &PermOwned::identity()), 2);
+        assert_eq!(bandwidth(&upper, &PermOwned::identity()), 2);
+    }
+
+    #[test]
+    fn rcm_path() {
+        let order = vec![3, 7, 0, 5, 1, 8, 2, 6, 4, 9];
+        let mat = shuffled_path(&order);
+        assert!(bandwidth(&mat, &PermOwned::identity()) > 1);
+        let perm = reverse_cuthill_mckee(&mat);
+        assert_is_perm(&perm, 10);
+        assert_eq!(bandwidth(&mat, &perm), 1);
+        assert_eq!(profile(&mat, &perm), 9);
+        // an end of the path is a peripheral node
+        assert!(perm.at(0) == 3 || perm.at(0) == 9);
+    }
+
+    #[test]
+    fn rcm_disconnected() {
+        // two paths and an isolated node
+        let order = vec![3, 7, 0, 5, 1, 8, 2, 6, 4, 9];
+        let path = shuffled_path(&order);
+        let mut triplets = TripletMat::new((11, 11));
+        for (outer, vec) in path.outer_iterator().enumerate() {
+            for (inner, &val) in vec.iter() {
+                // cut the path between its 5th and 6th nodes
+                if (outer == 1 && inner == 8) || (outer == 8 && inner == 1) {
+                    continue;
+                }
+                triplets.add_triplet(outer, inner, val);
+            }
+        }
+        triplets.add_triplet(10, 10, 1.);
+        let mat = triplets.to_csr();
+        let perm = reverse_cuthill_mckee(&mat);
+        assert_is_perm(&perm, 11);
+        assert_eq!(bandwidth(&mat, &perm), 1);
+        assert_eq!(profile(&mat, &perm), 8);
+    }
+
+    #[test]
+    fn rcm_grid() {
+        let n = 8;
+        let mat = grid_laplacian(n);
+        let natural = PermOwned::identity();
+        assert_eq!(bandwidth(&mat, &natural), n);
+        let perm = reverse_cuthill_mckee(&mat);
+        assert_is_perm(&perm, n * n);
+        assert!(bandwidth(&mat, &perm) <= n + 1);
+        assert!(profile(&mat, &perm) <= profile(&mat, &natural));
+
+        // ordering a randomly numbered grid recovers a small bandwidth
+        let shuffle: Vec<_> = (0..n * n).map(|i| (i * 37) % (n * n)).collect();
+        let shuffle = PermOwned::new(shuffle);
+        let mut triplets = TripletMat::new((n * n, n * n));
+        for (outer, vec) in mat.outer_iterator().enumerate() {
+            for (inner, &val) in vec.iter() {
+                triplets.add_triplet(shuffle.at(outer), shuffle.at(inner), val);
+            }
+        }
+        let shuffled = triplets.to_csc();
+        assert!(bandwidth(&shuffled, &natural) > 2 * n);
+        let perm = reverse_cuthill_mckee(&shuffled);
+        assert!(bandwidth(&shuffled, &perm) <= n + 1);
+    }
+}
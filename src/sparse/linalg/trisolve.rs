@@ -2,13 +2,16 @@
 
 use std::ops::IndexMut;
 use num_traits::Num;
-use sparse::CsMatView;
+use sparse::CsMatViewI;
+use indexing::SpIndex;
 use sparse::vec;
 use errors::SprsError;
 use stack::{self, StackVal, DStack};
 
-fn check_solver_dimensions<N, V: ?Sized>(lower_tri_mat: &CsMatView<N>, rhs: &V)
+fn check_solver_dimensions<N, I, V: ?Sized>(lower_tri_mat: &CsMatViewI<N, I>,
+                                            rhs: &V)
 where N: Copy + Num,
+      I: SpIndex,
       V: vec::VecDim<N>
 {
     let (cols, rows) = (lower_tri_mat.cols(), lower_tri_mat.rows());
@@ -27,10 +30,11 @@ where N: Copy + Num,
 ///
 /// This solve does not assume the input matrix to actually be
 /// triangular, instead it ignores the upper triangular part.
-pub fn lsolve_csr_dense_rhs<N, V: ?Sized>(lower_tri_mat: CsMatView<N>,
-                                          rhs: &mut V)
+pub fn lsolve_csr_dense_rhs<N, I, V: ?Sized>(lower_tri_mat: CsMatViewI<N, I>,
+                                             rhs: &mut V)
                                           -> Result<(), SprsError>
 where N: Copy + Num,
+      I: SpIndex,
       V: IndexMut<usize, Output = N> + vec::VecDim<N>
 {
     check_solver_dimensions(&lower_tri_mat, rhs);
@@ -77,10 +81,11 @@ where N: Copy + Num,
 /// is the diagonal element (thus actual sorted lower triangular matrices work
 /// best). Otherwise, logarithmic search for the diagonal element
 /// has to be performed for each column.
-pub fn lsolve_csc_dense_rhs<N, V: ?Sized>(lower_tri_mat: CsMatView<N>,
-                                          rhs: &mut V)
+pub fn lsolve_csc_dense_rhs<N, I, V: ?Sized>(lower_tri_mat: CsMatViewI<N, I>,
+                                             rhs: &mut V)
                                           -> Result<(), SprsError>
 where N: Copy + Num,
+      I: SpIndex,
       V: IndexMut<usize, Output = N> + vec::VecDim<N>
 {
     check_solver_dimensions(&lower_tri_mat, rhs);
@@ -102,8 +107,8 @@ where N: Copy + Num,
     Ok(())
 }
 
-fn lspsolve_csc_process_col<N: Copy + Num, V: ?Sized>
-                                                      (col: vec::CsVecView<N>,
+fn lspsolve_csc_process_col<N: Copy + Num, I: SpIndex, V: ?Sized>
+                                                      (col: vec::CsVecViewI<N, I>,
                                                        col_ind: usize,
                                                        rhs: &mut V)
                                                        -> Result<(), SprsError>
@@ -139,10 +144,11 @@ where V: vec::VecDim<N> + IndexMut<usize, Output = N>
 /// is the diagonal element (thus actual sorted lower triangular matrices work
 /// best). Otherwise, logarithmic search for the diagonal element
 /// has to be performed for each column.
-pub fn usolve_csc_dense_rhs<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
-                                          rhs: &mut V)
+pub fn usolve_csc_dense_rhs<N, I, V: ?Sized>(upper_tri_mat: CsMatViewI<N, I>,
+                                             rhs: &mut V)
                                           -> Result<(), SprsError>
 where N: Copy + Num,
+      I: SpIndex,
       V: IndexMut<usize, Output = N> + vec::VecDim<N>
 {
     check_solver_dimensions(&upper_tri_mat, rhs);
@@ -188,10 +194,11 @@ where N: Copy + Num,
 ///
 /// This solve does not assume the input matrix to actually be
 /// triangular, instead it ignores the upper triangular part.
-pub fn usolve_csr_dense_rhs<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
-                                          rhs: &mut V)
+pub fn usolve_csr_dense_rhs<N, I, V: ?Sized>(upper_tri_mat: CsMatViewI<N, I>,
+                                             rhs: &mut V)
                                           -> Result<(), SprsError>
 where N: Copy + Num,
+      I: SpIndex,
       V: IndexMut<usize, Output = N> + vec::VecDim<N>
 {
     check_solver_dimensions(&upper_tri_mat, rhs);
@@ -248,13 +255,14 @@ where N: Copy + Num,
 /// * if dstack is not empty
 /// * if w_workspace is not of length n
 ///
-pub fn lsolve_csc_sparse_rhs<N>(lower_tri_mat: CsMatView<N>,
-                                rhs: vec::CsVecView<N>,
-                                dstack: &mut DStack<StackVal<usize>>,
-                                x_workspace: &mut [N],
-                                visited: &mut [bool]
-                               ) -> Result<(), SprsError>
-where N: Copy + Num
+pub fn lsolve_csc_sparse_rhs<N, I>(lower_tri_mat: CsMatViewI<N, I>,
+                                   rhs: vec::CsVecViewI<N, I>,
+                                   dstack: &mut DStack<StackVal<usize>>,
+                                   x_workspace: &mut [N],
+                                   visited: &mut [bool]
+                                  ) -> Result<(), SprsError>
+where N: Copy + Num,
+      I: SpIndex
 {
     if !lower_tri_mat.is_csc() {
         panic!("Storage mismatch");
@@ -277,7 +285,89 @@ where N: Copy + Num
     // |      f       7| | w |     | c |     w = c / l7
 
     // compute the non-zero elements of the result by dfs traversal
-    for (root_ind, _) in rhs.iter() {
+    sparse_reach(lower_tri_mat.view(),
+                 rhs.indices(),
+                 |ind| Some(ind),
+                 dstack,
+                 visited);
+
+    // solve for the non-zero values into dense workspace
+    rhs.scatter(x_workspace);
+    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+        let col = lower_tri_mat.outer_view(ind).expect("ind not in bounds");
+        try!(lspsolve_csc_process_col(col, ind, x_workspace));
+    }
+    Ok(())
+}
+
+/// Compute the non-zero pattern of the solution of a sparse triangular
+/// system, using a depth first search in the graph of the matrix.
+///
+/// The nodes of the graph are the indices of the solution. The outgoing
+/// edges of a node are given by the inner indices of a column of `tri_mat`,
+/// which column is obtained through `node_to_outer`. Nodes mapped to `None`
+/// have no outgoing edges. This makes it possible to traverse triangular
+/// matrices whose rows have been permuted, or whose columns are not all
+/// known yet, as happens during a left-looking LU factorization.
+///
+/// The traversal starts from the nodes in `rhs_pattern`. Once it is done,
+/// the right stack of `dstack` holds the reached nodes in topological
+/// order, wrapped in `StackVal::Enter`, and these nodes are marked in
+/// `visited`. Nodes already marked in `visited` are not traversed.
+///
+/// # Panics
+///
+/// * if dstack is not empty
+/// * if dstack is too small to hold the traversal. A capacity of
+///   `n + nnz(rhs_pattern) + nnz(tri_mat)` is always sufficient.
+pub fn sparse_reach<N, I, F>(tri_mat: CsMatViewI<N, I>,
+                             rhs_pattern: &[I],
+                             node_to_outer: F,
+                             dstack: &mut DStack<StackVal<usize>>,
+                             visited: &mut [bool])
+where I: SpIndex,
+      F: Fn(usize) -> Option<usize>
+{
+    let indptr = tri_mat.indptr();
+    let indices = tri_mat.indices();
+    sparse_reach_structure(rhs_pattern,
+                           |ind| match node_to_outer(ind) {
+                               Some(outer) => {
+                                   let start = indptr[outer].index();
+                                   let stop = indptr[outer + 1].index();
+                                   &indices[start..stop]
+                               }
+                               None => &[],
+                           },
+                           dstack,
+                           visited);
+}
+
+/// Compute the non-zero pattern of the solution of a sparse triangular
+/// system, given only the structure of the matrix.
+///
+/// This is `sparse_reach` for a graph whose outgoing edges are given by
+/// `children`, eg as a slice of the inner indices of a column. As the
+/// traversal only needs the structure, these indices do not have to be
+/// sorted, nor to be part of a valid matrix, which allows traversing a
+/// matrix under construction.
+///
+/// # Panics
+///
+/// * if dstack is not empty
+/// * if dstack is too small to hold the traversal. A capacity of
+///   `n + nnz(rhs_pattern)` plus the number of edges of the graph is always
+///   sufficient.
+pub fn sparse_reach_structure<'a, I, F>(rhs_pattern: &[I],
+                                        children: F,
+                                        dstack: &mut DStack<StackVal<usize>>,
+                                        visited: &mut [bool])
+where I: 'a + SpIndex,
+      F: Fn(usize) -> &'a [I]
+{
+    assert!(dstack.is_left_empty() && dstack.is_right_empty(),
+            "dstack should be empty");
+    for root_ind in rhs_pattern.iter().map(|i| i.index()) {
         if visited[root_ind] {
             continue;
         }
@@ -290,12 +380,8 @@ where N: Copy + Num
                     }
                     visited[ind] = true;
                     dstack.push_left(StackVal::Exit(ind));
-                    if let Some(column) = lower_tri_mat.outer_view(ind) {
-                        for (child_ind, _) in column.iter() {
-                            dstack.push_left(StackVal::Enter(child_ind));
-                        }
-                    } else {
-                        unreachable!();
+                    for child_ind in children(ind) {
+                        dstack.push_left(StackVal::Enter(child_ind.index()));
                     }
                 }
                 StackVal::Exit(ind) => {
@@ -304,20 +390,12 @@ where N: Copy + Num
             }
         }
     }
-
-    // solve for the non-zero values into dense workspace
-    rhs.scatter(x_workspace);
-    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
-        println!("ind: {}", ind);
-        let col = lower_tri_mat.outer_view(ind).expect("ind not in bounds");
-        try!(lspsolve_csc_process_col(col, ind, x_workspace));
-    }
-    Ok(())
 }
 
 #[cfg(test)]
 mod test {
 
+    use num_complex::Complex64;
     use sparse::{CsMatOwned, vec};
     use stack::{self, DStack};
     use std::collections::HashSet;
@@ -386,6 +464,40 @@ mod test {
         assert_eq!(x, vec![3, 1, 1]);
     }
 
+    #[test]
+    fn complex_dense_rhs() {
+        // |1+i        | | 1 |   | 1+i |
+        // |2    i     | | i | = | 1   |
+        // |     1-i  2| |1+i|   | 3+3i|
+        let c = Complex64::new;
+        let l = CsMatOwned::new((3, 3),
+                                vec![0, 1, 3, 5],
+                                vec![0, 0, 1, 1, 2],
+                                vec![c(1., 1.), c(2., 0.), c(0., 1.),
+                                     c(1., -1.), c(2., 0.)]);
+        let x0 = vec![c(1., 0.), c(0., 1.), c(1., 1.)];
+
+        let mut x = vec![c(1., 1.), c(1., 0.), c(3., 3.)];
+        super::lsolve_csr_dense_rhs(l.view(), &mut x).unwrap();
+        assert_eq!(x, x0);
+
+        let mut x = vec![c(1., 1.), c(1., 0.), c(3., 3.)];
+        super::lsolve_csc_dense_rhs(l.to_csc().view(), &mut x).unwrap();
+        assert_eq!(x, x0);
+
+        // |1-i  2     | | 1 |   | 1+i |
+        // |    -i  1+i| | i | = | 1+2i|
+        // |         2 | |1+i|   | 2+2i|
+        let u = l.conj_transpose();
+        let mut x = vec![c(1., 1.), c(1., 2.), c(2., 2.)];
+        super::usolve_csc_dense_rhs(u.view(), &mut x).unwrap();
+        assert_eq!(x, x0);
+
+        let mut x = vec![c(1., 1.), c(1., 2.), c(2., 2.)];
+        super::usolve_csr_dense_rhs(u.to_csr().view(), &mut x).unwrap();
+        assert_eq!(x, x0);
+    }
+
     #[test]
     fn lspsolve_csc() {
         // |1        | | |   | |
@@ -458,4 +570,68 @@ mod test {
 
         assert_eq!(x, expected_output);
     }
+
+    #[test]
+    fn sparse_reach_partial_graph() {
+        // graph where node 2 has its edges in column 0, node 0 in column 1,
+        // and the other nodes have no outgoing edges
+        let mat = CsMatOwned::new_csc((4, 2),
+                                      vec![0, 3, 5],
+                                      vec![1, 2, 3, 0, 2],
+                                      vec![1, 1, 1, 1, 1]);
+        let node_to_outer = |ind| match ind {
+            2 => Some(0),
+            0 => Some(1),
+            _ => None,
+        };
+        let mut dstack = DStack::with_capacity(4 + 1 + 5);
+        let mut visited = vec![false; 4];
+        super::sparse_reach(mat.view(),
+                            &[0],
+                            &node_to_outer,
+                            &mut dstack,
+                            &mut visited);
+        let reached: Vec<_> = dstack.iter_right()
+                                    .map(stack::extract_stack_val)
+                                    .cloned()
+                                    .collect();
+        assert_eq!(reached.len(), 4);
+        let pos = |node| reached.iter().position(|&i| i == node).unwrap();
+        assert!(pos(0) < pos(2));
+        assert!(pos(2) < pos(1));
+        assert!(pos(2) < pos(3));
+        assert_eq!(visited, vec![true; 4]);
+
+        let mut dstack = DStack::with_capacity(4 + 1 + 5);
+        let mut visited = vec![false; 4];
+        super::sparse_reach(mat.view(),
+                            &[1],
+                            &node_to_outer,
+                            &mut dstack,
+                            &mut visited);
+        assert_eq!(dstack.iter_right().collect::<Vec<_>>(),
+                   vec![&stack::StackVal::Enter(1)]);
+
+        // the structure alone can be traversed, even with unsorted indices
+        let mut dstack = DStack::with_capacity(4 + 1 + 5);
+        let mut visited = vec![false; 4];
+        let indices = [3usize, 1, 2, 2, 0];
+        super::sparse_reach_structure(&[0],
+                                      |ind| match ind {
+                                          2 => &indices[0..3],
+                                          0 => &indices[3..5],
+                                          _ => &[],
+                                      },
+                                      &mut dstack,
+                                      &mut visited);
+        let reached: Vec<_> = dstack.iter_right()
+                                    .map(stack::extract_stack_val)
+                                    .cloned()
+                                    .collect();
+        assert_eq!(reached.len(), 4);
+        let pos = |node| reached.iter().position(|&i| i == node).unwrap();
+        assert!(pos(0) < pos(2));
+        assert!(pos(2) < pos(1));
+        assert!(pos(2) < pos(3));
+    }
 }
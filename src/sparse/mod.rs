@@ -1,17 +1,33 @@
 use std::ops::Deref;
 
+use indexing::SpIndex;
+
 pub use self::csmat::{CompressedStorage};
 
-pub use self::vec::{CsVec,
+pub use self::vec::{CsVecBase,
+                    CsVec,
+                    CsVecI,
                     CsVecOwned,
+                    CsVecViewI,
                     CsVecView,
+                    CsVecViewMutI,
+                    CsVecViewMut,
 };
 
-/// Compressed matrix in the CSR or CSC format.
+/// Compressed matrix in the CSR or CSC format, generic over its index type
+/// and over the storage of its components.
+///
+/// With the `serde` feature, owned matrices can be serialized and
+/// deserialized. Deserialized matrices go through the same structure
+/// checks as the constructors.
 #[derive(PartialEq, Debug)]
-pub struct CsMat<N, IptrStorage, IndStorage, DataStorage>
-where IptrStorage: Deref<Target=[usize]>,
-      IndStorage: Deref<Target=[usize]>,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde",
+           serde(try_from = "csmat::CsMatBaseShadow<N, I, IptrStorage, IndStorage, DataStorage>"))]
+pub struct CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
       DataStorage: Deref<Target=[N]> {
     storage: CompressedStorage,
     nrows : usize,
@@ -21,26 +37,38 @@ where IptrStorage: Deref<Target=[usize]>,
     data : DataStorage
 }
 
-pub type CsMatOwned<N> = CsMat<N, Vec<usize>, Vec<usize>, Vec<N>>;
-pub type CsMatView<'a, N> = CsMat<N, &'a [usize], &'a [usize], &'a [N]>;
-pub type CsMatViewMut<'a, N> = CsMat<N, &'a [usize], &'a [usize], &'a mut [N]>;
+/// Compressed matrix in the CSR or CSC format, using `usize` indices.
+pub type CsMat<N, IptrStorage, IndStorage, DataStorage> =
+    CsMatBase<N, usize, IptrStorage, IndStorage, DataStorage>;
+
+pub type CsMatI<N, I> = CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>>;
+pub type CsMatViewI<'a, N, I> = CsMatBase<N, I, &'a [I], &'a [I], &'a [N]>;
+pub type CsMatViewMutI<'a, N, I> =
+    CsMatBase<N, I, &'a [I], &'a [I], &'a mut [N]>;
 // FIXME: a fixed size array would be better, but no Deref impl
-pub type CsMatVecView<'a, N> = CsMat<N, Vec<usize>, &'a [usize], &'a [N]>;
+pub type CsMatVecViewI<'a, N, I> = CsMatBase<N, I, Vec<I>, &'a [I], &'a [N]>;
+
+pub type CsMatOwned<N> = CsMatI<N, usize>;
+pub type CsMatView<'a, N> = CsMatViewI<'a, N, usize>;
+pub type CsMatViewMut<'a, N> = CsMatViewMutI<'a, N, usize>;
+pub type CsMatVecView<'a, N> = CsMatVecViewI<'a, N, usize>;
 
 mod prelude {
     pub use super::{
-        CsMat,
-        CsMatView,
-        CsMatViewMut,
+        CsMatBase,
+        CsMatI,
+        CsMatViewI,
+        CsMatVecViewI,
         CsMatOwned,
-        CsMatVecView,
     };
+    pub use indexing::SpIndex;
 }
 
 mod utils {
-    pub fn sort_indices_data_slices<N: Copy>(indices: &mut [usize],
-                                             data: &mut [N],
-                                             buf: &mut Vec<(usize, N)>) {
+    pub fn sort_indices_data_slices<N: Copy, I: Copy + Ord>(
+        indices: &mut [I],
+        data: &mut [N],
+        buf: &mut Vec<(I, N)>) {
         let len = indices.len();
         assert_eq!(len, data.len());
         let indices = &mut indices[..len];
@@ -69,5 +97,7 @@ pub mod binop;
 pub mod construct;
 pub mod linalg;
 pub mod symmetric;
+pub mod complex;
+mod reductions;
 pub mod compressed;
 pub mod to_dense;
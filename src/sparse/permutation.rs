@@ -3,10 +3,17 @@
 /// Both the permutation matrices and its inverse are stored
 
 use std::ops::{Deref, Mul};
+use std::marker::PhantomData;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use sparse::prelude::*;
+use sparse::CompressedStorage::{CSR, CSC};
+#[cfg(feature = "serde")]
+use errors::SprsError;
 
 #[derive(Debug)]
-pub enum Permutation<IndStorage>
-where IndStorage: Deref<Target=[usize]> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum PermStorage<IndStorage> {
     Identity,
     FinitePerm {
         perm: IndStorage,
@@ -14,115 +21,197 @@ where IndStorage: Deref<Target=[usize]> {
     }
 }
 
-pub type PermOwned = Permutation<Vec<usize>>;
-pub type PermView<'a> = Permutation<&'a [usize]>;
+use self::PermStorage::*;
 
-use self::Permutation::*;
+/// A permutation, storing both the permutation and its inverse.
+///
+/// With the `serde` feature, owned permutations can be serialized and
+/// deserialized. Deserialization checks that the stored arrays are inverse
+/// permutations.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde",
+           serde(try_from = "PermutationShadow<I, IndStorage>"))]
+pub struct PermutationBase<I, IndStorage>
+where I: SpIndex,
+      IndStorage: Deref<Target=[I]> {
+    storage: PermStorage<IndStorage>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    index_type: PhantomData<I>,
+}
 
-impl Permutation<Vec<usize>> {
+/// Deserialized form of a PermutationBase, which has not been checked
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub struct PermutationShadow<I, IndStorage>
+where I: SpIndex,
+      IndStorage: Deref<Target=[I]> {
+    storage: PermStorage<IndStorage>,
+    #[serde(skip)]
+    index_type: PhantomData<I>,
+}
+
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+impl<I, IndStorage> TryFrom<PermutationShadow<I, IndStorage>>
+for PermutationBase<I, IndStorage>
+where I: SpIndex,
+      IndStorage: Deref<Target=[I]> {
+    type Error = SprsError;
 
-    pub fn new(perm: Vec<usize>) -> Permutation<Vec<usize>> {
+    fn try_from(shadow: PermutationShadow<I, IndStorage>)
+                -> Result<Self, SprsError> {
+        if let FinitePerm { ref perm, ref perm_inv } = shadow.storage {
+            if perm.len() != perm_inv.len() {
+                return Err(SprsError::BadStorageLength);
+            }
+            let n = perm.len();
+            // perm o perm_inv being the identity on a finite set, both are
+            // bijections
+            for (i, &p_inv) in perm_inv.iter().enumerate() {
+                if p_inv.index() >= n || perm[p_inv.index()].index() != i {
+                    return Err(SprsError::BadPermutation);
+                }
+            }
+        }
+        Ok(PermutationBase::from_storage(shadow.storage))
+    }
+}
+
+pub type Permutation<IndStorage> = PermutationBase<usize, IndStorage>;
+pub type PermOwnedI<I> = PermutationBase<I, Vec<I>>;
+pub type PermViewI<'a, I> = PermutationBase<I, &'a [I]>;
+pub type PermOwned = PermOwnedI<usize>;
+pub type PermView<'a> = PermViewI<'a, usize>;
+
+impl<I, IndStorage> PermutationBase<I, IndStorage>
+where I: SpIndex,
+      IndStorage: Deref<Target=[I]> {
+    fn from_storage(storage: PermStorage<IndStorage>)
+                    -> PermutationBase<I, IndStorage> {
+        PermutationBase {
+            storage: storage,
+            index_type: PhantomData,
+        }
+    }
+}
+
+impl<I: SpIndex> PermutationBase<I, Vec<I>> {
+
+    pub fn new(perm: Vec<I>) -> PermOwnedI<I> {
         let mut perm_inv = perm.clone();
         for (ind, val) in perm.iter().enumerate() {
-            perm_inv[*val] = ind;
+            perm_inv[val.index()] = I::from_usize(ind);
         }
-        FinitePerm {
+        PermutationBase::from_storage(FinitePerm {
             perm: perm,
             perm_inv: perm_inv
-        }
+        })
     }
 }
 
-impl<'a> Permutation<&'a [usize]> {
-    pub fn reborrow(&self) -> PermView<'a> {
-        match self {
-            &Identity => Identity,
-            &FinitePerm {
+impl<'a, I: SpIndex> PermutationBase<I, &'a [I]> {
+    pub fn reborrow(&self) -> PermViewI<'a, I> {
+        PermutationBase::from_storage(match self.storage {
+            Identity => Identity,
+            FinitePerm {
                 perm: ref p, perm_inv: ref p_
             } => FinitePerm { perm: &p[..], perm_inv: &p_[..] }
-        }
+        })
     }
 
-    pub fn reborrow_inv(&self) -> PermView<'a> {
-        match self {
-            &Identity => Identity,
-            &FinitePerm {
+    pub fn reborrow_inv(&self) -> PermViewI<'a, I> {
+        PermutationBase::from_storage(match self.storage {
+            Identity => Identity,
+            FinitePerm {
                 perm: ref p, perm_inv: ref p_
             } => FinitePerm { perm: &p_[..], perm_inv: &p[..] }
-        }
+        })
     }
 }
 
-impl<IndStorage> Permutation<IndStorage>
-where IndStorage: Deref<Target=[usize]> {
+impl<I, IndStorage> PermutationBase<I, IndStorage>
+where I: SpIndex,
+      IndStorage: Deref<Target=[I]> {
 
-    pub fn identity() -> Permutation<IndStorage> {
-        Identity
+    pub fn identity() -> PermutationBase<I, IndStorage> {
+        PermutationBase::from_storage(Identity)
     }
 
-    pub fn inv(&self) -> PermView {
-        match self {
-            &Identity => Identity,
-            &FinitePerm {
+    pub fn inv(&self) -> PermViewI<I> {
+        PermutationBase::from_storage(match self.storage {
+            Identity => Identity,
+            FinitePerm {
                 perm: ref p, perm_inv: ref p_
             } => FinitePerm { perm: &p_[..], perm_inv: &p[..] }
-        }
+        })
     }
 
     // TODO: either the trait Deref or Borrow should be implemnted for this
-    pub fn view(&self) -> PermView {
-        match self {
-            &Identity => Identity,
-            &FinitePerm {
+    pub fn view(&self) -> PermViewI<I> {
+        PermutationBase::from_storage(match self.storage {
+            Identity => Identity,
+            FinitePerm {
                 perm: ref p, perm_inv: ref p_
             } => FinitePerm { perm: &p[..], perm_inv: &p_[..] }
-        }
+        })
     }
 
-    pub fn owned_clone(&self) -> PermOwned {
-        match self {
-            &Identity => Identity,
-            &FinitePerm {
+    pub fn owned_clone(&self) -> PermOwnedI<I> {
+        PermutationBase::from_storage(match self.storage {
+            Identity => Identity,
+            FinitePerm {
                 perm: ref p, perm_inv: ref p_
             } => FinitePerm {
                 perm: p.iter().cloned().collect(),
                 perm_inv: p_.iter().cloned().collect()
             }
+        })
+    }
+
+    /// The dimension of this permutation, or None for the identity
+    /// permutation which can be applied to any dimension.
+    pub fn dim(&self) -> Option<usize> {
+        match self.storage {
+            Identity => None,
+            FinitePerm { perm: ref p, perm_inv: _ } => Some(p.len()),
         }
     }
 
     pub fn at(&self, index: usize) -> usize {
-        match self {
-            &Identity => index,
-            &FinitePerm {
-                perm: ref p, perm_inv: _ } => p[index]
+        match self.storage {
+            Identity => index,
+            FinitePerm {
+                perm: ref p, perm_inv: _ } => p[index].index()
         }
     }
 
     pub fn at_inv(&self, index: usize) -> usize {
-        match self {
-            &Identity => index,
-            &FinitePerm {
-                perm: _, perm_inv: ref p_ } => p_[index]
+        match self.storage {
+            Identity => index,
+            FinitePerm {
+                perm: _, perm_inv: ref p_ } => p_[index].index()
         }
     }
 }
 
-impl<'a, 'b, N, IndStorage> Mul<&'a [N]> for &'b Permutation<IndStorage>
-where IndStorage: 'b + Deref<Target=[usize]>,
+impl<'a, 'b, N, I, IndStorage> Mul<&'a [N]> for &'b PermutationBase<I, IndStorage>
+where I: 'b + SpIndex,
+      IndStorage: 'b + Deref<Target=[I]>,
       N: 'a + Copy
 {
     type Output = Vec<N>;
     fn mul(self, rhs: &'a [N]) -> Vec<N> {
         let mut res = rhs.to_vec();
-        match self {
-            &Identity => res,
-            &FinitePerm {
+        match self.storage {
+            Identity => res,
+            FinitePerm {
                 perm: ref p,
                 perm_inv: _,
             } => {
-                for (&pi, r) in p.iter().zip(res.iter_mut()) {
-                    *r = rhs[pi];
+                for (pi, r) in p.iter().zip(res.iter_mut()) {
+                    *r = rhs[pi.index()];
                 }
                 res
             }
@@ -131,7 +220,173 @@ where IndStorage: 'b + Deref<Target=[usize]>,
 }
 
 
+// With the matrix products below, `&perm * &vec` no longer coerces `&Vec<N>`
+// into `&[N]`, this impl keeps such code compiling.
+impl<'a, 'b, N, I, IndStorage> Mul<&'a Vec<N>> for &'b PermutationBase<I, IndStorage>
+where I: 'b + SpIndex,
+      IndStorage: 'b + Deref<Target=[I]>,
+      N: 'a + Copy
+{
+    type Output = Vec<N>;
+    fn mul(self, rhs: &'a Vec<N>) -> Vec<N> {
+        self * &rhs[..]
+    }
+}
+
+/// Compute `P A Q^T`, where P and Q are the permutation matrices of
+/// `row_perm` and `col_perm`. In other words, the entry `(i, j)` of the
+/// result is the entry `(row_perm.at(i), col_perm.at(j))` of A.
+///
+/// This is the `P A Q` of sparse factorizations, where the column
+/// ordering is given as the list of columns of A to consider in turn.
+///
+/// The result has the same storage as A, and sorted indices.
+///
+/// # Panics
+///
+/// * if the permutations do not have the dimensions of the matrix
+pub fn permute<N, I, IpS, IS, DS, PS1, PS2>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                            row_perm: &PermutationBase<I, PS1>,
+                                            col_perm: &PermutationBase<I, PS2>)
+                                            -> CsMatI<N, I>
+where N: Copy,
+      I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS1: Deref<Target = [I]>,
+      PS2: Deref<Target = [I]>
+{
+    assert!(row_perm.dim().map_or(true, |n| n == mat.rows()),
+            "Dimension mismatch");
+    assert!(col_perm.dim().map_or(true, |n| n == mat.cols()),
+            "Dimension mismatch");
+    let (outer_perm, inner_perm) = match mat.storage() {
+        CSR => (row_perm.view(), col_perm.view()),
+        CSC => (col_perm.view(), row_perm.view()),
+    };
+    let mut indptr = Vec::with_capacity(mat.outer_dims() + 1);
+    let mut indices = Vec::with_capacity(mat.nnz());
+    let mut data = Vec::with_capacity(mat.nnz());
+    indptr.push(I::zero());
+    for outer_ind in 0..mat.outer_dims() {
+        let vec = mat.outer_view(outer_perm.at(outer_ind))
+                     .expect("outer index in bounds");
+        for (inner_ind, &val) in vec.iter() {
+            indices.push(I::from_usize(inner_perm.at_inv(inner_ind)));
+            data.push(val);
+        }
+        indptr.push(I::from_usize(indices.len()));
+    }
+    // the constructors sort the indices
+    match mat.storage() {
+        CSR => CsMatI::new(mat.shape(), indptr, indices, data),
+        CSC => CsMatI::new_csc(mat.shape(), indptr, indices, data),
+    }
+}
+
+/// Compute `P A`, ie the row `i` of the result is the row `perm.at(i)`
+/// of A.
+///
+/// The result has the same storage as A, and sorted indices.
+///
+/// # Panics
+///
+/// * if the permutation does not have the number of rows of the matrix
+pub fn permute_rows<N, I, IpS, IS, DS, PS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                           perm: &PermutationBase<I, PS>)
+                                           -> CsMatI<N, I>
+where N: Copy,
+      I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS: Deref<Target = [I]>
+{
+    let identity: PermViewI<I> = PermutationBase::identity();
+    permute(mat, perm, &identity)
+}
+
+/// Compute `A P^T`, ie the column `j` of the result is the column
+/// `perm.at(j)` of A.
+///
+/// The result has the same storage as A, and sorted indices.
+///
+/// # Panics
+///
+/// * if the permutation does not have the number of columns of the matrix
+pub fn permute_cols<N, I, IpS, IS, DS, PS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                           perm: &PermutationBase<I, PS>)
+                                           -> CsMatI<N, I>
+where N: Copy,
+      I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS: Deref<Target = [I]>
+{
+    let identity: PermViewI<I> = PermutationBase::identity();
+    permute(mat, &identity, perm)
+}
+
+/// Compute the symmetric permutation `P A P^T`, ie the entry `(i, j)` of
+/// the result is the entry `(perm.at(i), perm.at(j))` of A.
+///
+/// The result has the same storage as A, and sorted indices.
+///
+/// # Panics
+///
+/// * if the matrix is not square
+/// * if the permutation does not have the dimension of the matrix
+pub fn permute_sym<N, I, IpS, IS, DS, PS>(mat: &CsMatBase<N, I, IpS, IS, DS>,
+                                          perm: &PermutationBase<I, PS>)
+                                          -> CsMatI<N, I>
+where N: Copy,
+      I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      IS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      PS: Deref<Target = [I]>
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    permute(mat, perm, perm)
+}
+
+impl<'a, 'b, N, I, IpS, IS, DS, PS> Mul<&'a CsMatBase<N, I, IpS, IS, DS>>
+for &'b PermutationBase<I, PS>
+where N: 'a + Copy,
+      I: 'a + 'b + SpIndex,
+      IpS: 'a + Deref<Target = [I]>,
+      IS: 'a + Deref<Target = [I]>,
+      DS: 'a + Deref<Target = [N]>,
+      PS: 'b + Deref<Target = [I]>
+{
+    type Output = CsMatI<N, I>;
+    fn mul(self, rhs: &'a CsMatBase<N, I, IpS, IS, DS>) -> CsMatI<N, I> {
+        permute_rows(rhs, self)
+    }
+}
+
+impl<'a, 'b, N, I, IpS, IS, DS, PS> Mul<&'b PermutationBase<I, PS>>
+for &'a CsMatBase<N, I, IpS, IS, DS>
+where N: 'a + Copy,
+      I: 'a + 'b + SpIndex,
+      IpS: 'a + Deref<Target = [I]>,
+      IS: 'a + Deref<Target = [I]>,
+      DS: 'a + Deref<Target = [N]>,
+      PS: 'b + Deref<Target = [I]>
+{
+    type Output = CsMatI<N, I>;
+    fn mul(self, rhs: &'b PermutationBase<I, PS>) -> CsMatI<N, I> {
+        // column j of A Q is column rhs.at_inv(j) of A
+        permute_cols(self, &rhs.inv())
+    }
+}
+
+#[cfg(test)]
 mod test {
+    use sparse::CsMat;
+    use super::{PermOwned, permute, permute_rows, permute_cols, permute_sym};
 
     #[test]
     fn perm_mul() {
@@ -141,9 +396,104 @@ mod test {
         // |1 0 0 0 0| |3|   |5|
         // |0 0 0 0 1| |4|   |4|
         let x = vec![5, 1, 2, 3, 4];
-        let p = super::PermOwned::new(vec![2, 1, 3, 0, 4]);
+        let p = PermOwned::new(vec![2, 1, 3, 0, 4]);
 
         let y = &p * &x;
         assert_eq!(&y, &[2, 1, 3, 5, 4]);
     }
+
+    #[test]
+    fn permute_mat() {
+        // |1 2 0|
+        // |0 3 4|
+        // |5 0 6|
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 4, 6],
+                             vec![0, 1, 1, 2, 0, 2],
+                             vec![1, 2, 3, 4, 5, 6]);
+        let p = PermOwned::new(vec![2, 0, 1]);
+        let q = PermOwned::new(vec![1, 2, 0]);
+
+        // |5 0 6|
+        // |1 2 0|
+        // |0 3 4|
+        let expected_rows = CsMat::new((3, 3),
+                                       vec![0, 2, 4, 6],
+                                       vec![0, 2, 0, 1, 1, 2],
+                                       vec![5, 6, 1, 2, 3, 4]);
+        // |2 0 1|
+        // |3 4 0|
+        // |0 6 5|
+        let expected_cols = CsMat::new((3, 3),
+                                       vec![0, 2, 4, 6],
+                                       vec![0, 2, 0, 1, 1, 2],
+                                       vec![2, 1, 3, 4, 6, 5]);
+        // |0 6 5|
+        // |2 0 1|
+        // |3 4 0|
+        let expected_both = CsMat::new((3, 3),
+                                       vec![0, 2, 4, 6],
+                                       vec![1, 2, 0, 2, 0, 1],
+                                       vec![6, 5, 2, 1, 3, 4]);
+        // |6 5 0|
+        // |0 1 2|
+        // |4 0 3|
+        let expected_sym = CsMat::new((3, 3),
+                                      vec![0, 2, 4, 6],
+                                      vec![0, 1, 1, 2, 0, 2],
+                                      vec![6, 5, 1, 2, 4, 3]);
+        for mat in &[mat.to_csr(), mat.to_csc()] {
+            assert_eq!(permute_rows(mat, &p).to_csr(), expected_rows);
+            assert_eq!(permute_cols(mat, &q).to_csr(), expected_cols);
+            assert_eq!(permute(mat, &p, &q).to_csr(), expected_both);
+            assert_eq!(permute_sym(mat, &p).to_csr(), expected_sym);
+            assert_eq!(permute_rows(mat, &p).storage(), mat.storage());
+
+            // operators follow the matrix product semantics
+            assert_eq!((&p * mat).to_csr(), expected_rows);
+            assert_eq!((mat * &q.inv()).to_csr(), expected_cols);
+            assert_eq!((&(&p * mat) * &q.inv()).to_csr(), expected_both);
+            assert_eq!((&(&p * mat) * &p.inv()).to_csr(), expected_sym);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn permute_dim_mismatch() {
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 4, 6],
+                             vec![0, 1, 1, 2, 0, 2],
+                             vec![1, 2, 3, 4, 5, 6]);
+        let p = PermOwned::new(vec![1, 0]);
+        permute_rows(&mat, &p);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        use serde_json;
+        let perm = PermOwned::new(vec![2, 0, 3, 1]);
+        let json = serde_json::to_string(&perm).unwrap();
+        let perm2: PermOwned = serde_json::from_str(&json).unwrap();
+        for i in 0..4 {
+            assert_eq!(perm2.at(i), perm.at(i));
+            assert_eq!(perm2.at_inv(i), perm.at_inv(i));
+        }
+
+        let id = PermOwned::identity();
+        let json = serde_json::to_string(&id).unwrap();
+        let id2: PermOwned = serde_json::from_str(&json).unwrap();
+        assert_eq!(id2.dim(), None);
+
+        let parse = |json| serde_json::from_str::<PermOwned>(json);
+        let not_inverse = r#"{"storage":{"FinitePerm":
+                              {"perm":[1,0,2],"perm_inv":[0,1,2]}}}"#;
+        assert!(parse(not_inverse).is_err());
+        let not_perm = r#"{"storage":{"FinitePerm":
+                           {"perm":[0,0,2],"perm_inv":[0,1,2]}}}"#;
+        assert!(parse(not_perm).is_err());
+        let out_of_bounds = r#"{"storage":{"FinitePerm":
+                                {"perm":[0,1],"perm_inv":[0,2]}}}"#;
+        assert!(parse(out_of_bounds).is_err());
+    }
 }
@@ -1,18 +1,28 @@
 ///! Sparse matrix product
 
+use std::ops::{Deref, DerefMut};
 use sparse::prelude::*;
-use sparse::vec::{CsVecView, CsVecOwned};
+use sparse::vec::{CsVecViewI, CsVecI};
 use num_traits::Num;
 use sparse::compressed::SpMatView;
 use ndarray::{ArrayView, ArrayViewMut, Axis};
 use ::Ix2;
+#[cfg(feature = "multi_thread")]
+use std::cmp;
+#[cfg(feature = "multi_thread")]
+use ndarray::Array;
+#[cfg(feature = "multi_thread")]
+use rayon;
+#[cfg(feature = "multi_thread")]
+use rayon::prelude::*;
 
 /// Multiply a sparse CSC matrix with a dense vector and accumulate the result
 /// into another dense vector
-pub fn mul_acc_mat_vec_csc<N>(mat: CsMatView<N>,
-                              in_vec: &[N],
-                              res_vec: &mut[N])
-where N: Num + Copy {
+pub fn mul_acc_mat_vec_csc<N, I>(mat: CsMatViewI<N, I>,
+                                 in_vec: &[N],
+                                 res_vec: &mut[N])
+where N: Num + Copy,
+      I: SpIndex {
     let mat = mat.view();
     if mat.cols() != in_vec.len() || mat.rows() != res_vec.len() {
         panic!("Dimension mismatch");
@@ -33,10 +43,11 @@ where N: Num + Copy {
 
 /// Multiply a sparse CSR matrix with a dense vector and accumulate the result
 /// into another dense vector
-pub fn mul_acc_mat_vec_csr<N>(mat: CsMatView<N>,
-                              in_vec: &[N],
-                              res_vec: &mut[N])
-where N: Num + Copy {
+pub fn mul_acc_mat_vec_csr<N, I>(mat: CsMatViewI<N, I>,
+                                 in_vec: &[N],
+                                 res_vec: &mut[N])
+where N: Num + Copy,
+      I: SpIndex {
     if mat.cols() != in_vec.len() || mat.rows() != res_vec.len() {
         panic!("Dimension mismatch");
     }
@@ -64,14 +75,15 @@ where N: Num + Copy {
 /// rhs: right hand size matrix
 /// workspace: used to accumulate the line values. Should be of length
 ///            rhs.cols()
-pub fn csr_mul_csr<N, Mat1, Mat2>(lhs: &Mat1,
-                                  rhs: &Mat2,
-                                  workspace: &mut[N]
-                                 ) -> CsMatOwned<N>
+pub fn csr_mul_csr<N, I, Mat1, Mat2>(lhs: &Mat1,
+                                     rhs: &Mat2,
+                                     workspace: &mut[N]
+                                    ) -> CsMatI<N, I>
 where
 N: Num + Copy,
-Mat1: SpMatView<N>,
-Mat2: SpMatView<N> {
+I: SpIndex,
+Mat1: SpMatView<N, I>,
+Mat2: SpMatView<N, I> {
     csr_mul_csr_impl(lhs.view(), rhs.view(), workspace)
 }
 
@@ -84,44 +96,48 @@ Mat2: SpMatView<N> {
 /// rhs: right hand size matrix
 /// workspace: used to accumulate the line values. Should be of length
 ///            lhs.lines()
-pub fn csc_mul_csc<N, Mat1, Mat2>(lhs: &Mat1,
-                                  rhs: &Mat2,
-                                  workspace: &mut[N]
-                                 ) -> CsMatOwned<N>
+pub fn csc_mul_csc<N, I, Mat1, Mat2>(lhs: &Mat1,
+                                     rhs: &Mat2,
+                                     workspace: &mut[N]
+                                    ) -> CsMatI<N, I>
 where
 N: Num + Copy,
-Mat1: SpMatView<N>,
-Mat2: SpMatView<N> {
+I: SpIndex,
+Mat1: SpMatView<N, I>,
+Mat2: SpMatView<N, I> {
     csr_mul_csr_impl(rhs.transpose_view(),
                      lhs.transpose_view(),
                      workspace).transpose_into()
 }
 
 /// Allocate the appropriate workspace for a CSR-CSR product
-pub fn workspace_csr<N, Mat1, Mat2>(_: &Mat1, rhs: &Mat2) -> Vec<N>
+pub fn workspace_csr<N, I, Mat1, Mat2>(_: &Mat1, rhs: &Mat2) -> Vec<N>
 where N: Copy + Num,
-      Mat1: SpMatView<N>,
-      Mat2: SpMatView<N> {
+      I: SpIndex,
+      Mat1: SpMatView<N, I>,
+      Mat2: SpMatView<N, I> {
     let len = rhs.view().cols();
     vec![N::zero(); len]
 }
 
 /// Allocate the appropriate workspace for a CSC-CSC product
-pub fn workspace_csc<N, Mat1, Mat2>(lhs: &Mat1, _: &Mat2) -> Vec<N>
+pub fn workspace_csc<N, I, Mat1, Mat2>(lhs: &Mat1, _: &Mat2) -> Vec<N>
 where N: Copy + Num,
-      Mat1: SpMatView<N>,
-      Mat2: SpMatView<N> {
+      I: SpIndex,
+      Mat1: SpMatView<N, I>,
+      Mat2: SpMatView<N, I> {
     let len = lhs.view().rows();
     vec![N::zero(); len]
 }
 
 /// Actual implementation of CSR-CSR multiplication
 /// All other matrix products are implemented in terms of this one.
-pub fn csr_mul_csr_impl<N>(lhs: CsMatView<N>,
-                           rhs: CsMatView<N>,
-                           workspace: &mut[N]
-                          ) -> CsMatOwned<N>
-where N: Num + Copy {
+pub fn csr_mul_csr_impl<N, I>(lhs: CsMatViewI<N, I>,
+                              rhs: CsMatViewI<N, I>,
+                              workspace: &mut[N]
+                             ) -> CsMatI<N, I>
+where N: Num + Copy,
+      I: SpIndex {
     let res_rows = lhs.rows();
     let res_cols = rhs.cols();
     if lhs.cols() != rhs.rows() {
@@ -134,7 +150,7 @@ where N: Num + Copy {
         panic!("Storage mismatch");
     }
 
-    let mut res = CsMatOwned::empty(lhs.storage(), res_cols);
+    let mut res = CsMatI::empty(lhs.storage(), res_cols);
     res.reserve_nnz_exact(lhs.nnz() + rhs.nnz());
     for lvec in lhs.outer_iterator() {
         // reset the accumulators
@@ -161,14 +177,236 @@ where N: Num + Copy {
     res
 }
 
+/// Sentinel for columns absent from the current row
+const NONE: usize = ::std::usize::MAX;
+
+fn check_csr_prod_operands<N, I>(lhs: &CsMatViewI<N, I>,
+                                 rhs: &CsMatViewI<N, I>)
+where I: SpIndex {
+    if lhs.cols() != rhs.rows() {
+        panic!("Dimension mismatch");
+    }
+    if !lhs.is_csr() || !rhs.is_csr() {
+        panic!("Storage mismatch");
+    }
+}
+
+/// Symbolic phase of a CSR-CSR product.
+///
+/// Computes the sparsity pattern of `lhs * rhs`, and returns a CSR matrix
+/// with this pattern and zero values, whose values can be computed by
+/// `csr_mul_csr_numeric`. The pattern is structural: entries which cancel
+/// numerically are kept.
+///
+/// CSC matrices can be handled by computing the pattern of the transposed
+/// product `rhs^T * lhs^T`, using transposed views.
+///
+/// # Panics
+///
+/// * if lhs.cols() != rhs.rows()
+/// * if lhs or rhs are not CSR
+pub fn csr_mul_csr_symbolic<N, I>(lhs: CsMatViewI<N, I>,
+                                  rhs: CsMatViewI<N, I>
+                                 ) -> CsMatI<N, I>
+where N: Num + Copy,
+      I: SpIndex {
+    check_csr_prod_operands(&lhs, &rhs);
+    let mut indptr = Vec::with_capacity(lhs.rows() + 1);
+    let mut indices = Vec::new();
+    indptr.push(I::zero());
+    // marker[col] holds the last row having a non zero at col
+    let mut marker = vec![NONE; rhs.cols()];
+    for (row_ind, lvec) in lhs.outer_iterator().enumerate() {
+        let start = indices.len();
+        for (lcol, _) in lvec.iter() {
+            let rvec = rhs.outer_view(lcol).unwrap();
+            for (rcol, _) in rvec.iter() {
+                if marker[rcol] != row_ind {
+                    marker[rcol] = row_ind;
+                    indices.push(I::from_usize(rcol));
+                }
+            }
+        }
+        indices[start..].sort();
+        indptr.push(I::from_usize(indices.len()));
+    }
+    let data = vec![N::zero(); indices.len()];
+    CsMatI::new((lhs.rows(), rhs.cols()), indptr, indices, data)
+}
+
+/// Compute the values of the rows of a CSR-CSR product, given the pattern
+/// of these rows. `lhs` holds the corresponding rows of the left operand,
+/// and `res_data` the values of these rows only.
+fn csr_mul_csr_numeric_rows<N, I>(lhs: &CsMatViewI<N, I>,
+                                  rhs: &CsMatViewI<N, I>,
+                                  res_indptr: &[I],
+                                  res_indices: &[I],
+                                  res_data: &mut [N])
+where N: Num + Copy,
+      I: SpIndex {
+    let offset = res_indptr[0].index();
+    // position in res_data of each column of the current row
+    let mut positions = vec![NONE; rhs.cols()];
+    for (row, range) in res_indptr.windows(2).enumerate() {
+        let (start, end) = (range[0].index(), range[1].index());
+        for pos in start..end {
+            positions[res_indices[pos].index()] = pos - offset;
+            res_data[pos - offset] = N::zero();
+        }
+        let lvec = lhs.outer_view(row).unwrap();
+        for (lcol, &lval) in lvec.iter() {
+            let rvec = rhs.outer_view(lcol).unwrap();
+            for (rcol, &rval) in rvec.iter() {
+                let pos = positions[rcol];
+                if pos != NONE {
+                    let prod = lval * rval;
+                    res_data[pos] = res_data[pos] + prod;
+                }
+            }
+        }
+        for pos in start..end {
+            positions[res_indices[pos].index()] = NONE;
+        }
+    }
+}
+
+fn check_csr_prod_result<N, I, IpS, IS, DS>(
+    lhs: &CsMatViewI<N, I>,
+    rhs: &CsMatViewI<N, I>,
+    res: &CsMatBase<N, I, IpS, IS, DS>)
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]> {
+    check_csr_prod_operands(lhs, rhs);
+    if res.rows() != lhs.rows() || res.cols() != rhs.cols() {
+        panic!("Dimension mismatch");
+    }
+    if !res.is_csr() {
+        panic!("Storage mismatch");
+    }
+}
+
+/// Numeric phase of a CSR-CSR product.
+///
+/// Computes the values of `lhs * rhs` at the non zero locations of `res`,
+/// overwriting its values. Contributions outside the pattern of `res` are
+/// ignored, so `res` can hold the pattern computed by
+/// `csr_mul_csr_symbolic`, to refill the product of matrices whose values
+/// changed but not their patterns, or any other pattern to compute a
+/// masked product.
+///
+/// The computed values are exactly the ones computed by `csr_mul_csr`.
+///
+/// # Panics
+///
+/// * if lhs.cols() != rhs.rows()
+/// * if res does not have the shape of the product
+/// * if lhs, rhs or res are not CSR
+pub fn csr_mul_csr_numeric<N, I, IpS, IS, DS>(
+    lhs: CsMatViewI<N, I>,
+    rhs: CsMatViewI<N, I>,
+    res: &mut CsMatBase<N, I, IpS, IS, DS>)
+where N: Num + Copy,
+      I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: DerefMut<Target=[N]> {
+    check_csr_prod_result(&lhs, &rhs, res);
+    let offset = res.indptr[0].index();
+    let nnz = res.indptr[res.rows()].index() - offset;
+    csr_mul_csr_numeric_rows(&lhs,
+                             &rhs,
+                             &res.indptr[..],
+                             &res.indices[..],
+                             &mut res.data[offset..offset + nnz]);
+}
+
+/// Multithreaded version of `csr_mul_csr_numeric`.
+///
+/// The rows of lhs and res are split in blocks, which are handled by
+/// different threads. The result is exactly the one of
+/// `csr_mul_csr_numeric`.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_csr_mul_csr_numeric<N, I, IpS, IS, DS>(
+    lhs: CsMatViewI<N, I>,
+    rhs: CsMatViewI<N, I>,
+    res: &mut CsMatBase<N, I, IpS, IS, DS>)
+where N: Num + Copy + Send + Sync,
+      I: SpIndex + Sync,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: DerefMut<Target=[N]> {
+    check_csr_prod_result(&lhs, &rhs, res);
+    let indptr = &res.indptr[..];
+    let indices = &res.indices[..];
+    let block_size = par_block_size(lhs.rows());
+    let mut blocks = Vec::new();
+    let mut rest = &mut res.data[indptr[0].index()..];
+    let mut start = 0;
+    for lblock in lhs.outer_block_iter(block_size) {
+        let end = start + lblock.rows();
+        let res_indptr = &indptr[start..end + 1];
+        let len = indptr[end].index() - indptr[start].index();
+        let (data_block, tail) = {rest}.split_at_mut(len);
+        blocks.push((lblock, res_indptr, data_block));
+        rest = tail;
+        start = end;
+    }
+    blocks.into_par_iter().for_each(|(lblock, res_indptr, data_block)| {
+        csr_mul_csr_numeric_rows(&lblock,
+                                 &rhs,
+                                 res_indptr,
+                                 indices,
+                                 data_block);
+    });
+}
+
+/// Masked CSR-CSR product.
+///
+/// Computes the entries of `lhs * rhs` located at the non zeros of `mask`,
+/// whose values are ignored. The result has the pattern of `mask`, with
+/// zero values where the product has no non zero.
+///
+/// # Panics
+///
+/// * if lhs.cols() != rhs.rows()
+/// * if mask does not have the shape of the product
+/// * if lhs, rhs or mask are not CSR
+pub fn csr_mul_csr_masked<N, M, I>(lhs: CsMatViewI<N, I>,
+                                   rhs: CsMatViewI<N, I>,
+                                   mask: CsMatViewI<M, I>
+                                  ) -> CsMatI<N, I>
+where N: Num + Copy,
+      I: SpIndex {
+    if mask.rows() != lhs.rows() || mask.cols() != rhs.cols() {
+        panic!("Dimension mismatch");
+    }
+    if !mask.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let mask_indptr = mask.indptr();
+    let start = mask_indptr[0];
+    let end = mask_indptr[mask.rows()];
+    let indptr = mask_indptr.iter().map(|&i| i - start).collect();
+    let indices = mask.indices()[start.index()..end.index()].to_vec();
+    let data = vec![N::zero(); indices.len()];
+    let mut res = CsMatI::new(mask.shape(), indptr, indices, data);
+    csr_mul_csr_numeric(lhs, rhs, &mut res);
+    res
+}
+
 /// CSR-vector multiplication
-pub fn csr_mul_csvec<N>(lhs: CsMatView<N>,
-                        rhs: CsVecView<N>) -> CsVecOwned<N>
-where N: Copy + Num {
+pub fn csr_mul_csvec<N, I>(lhs: CsMatViewI<N, I>,
+                           rhs: CsVecViewI<N, I>) -> CsVecI<N, I>
+where N: Copy + Num,
+      I: SpIndex {
     if lhs.cols() != rhs.dim() {
         panic!("Dimension mismatch");
     }
-    let mut res = CsVecOwned::empty(lhs.rows());
+    let mut res = CsVecI::empty(lhs.rows());
     for (row_ind, lvec) in lhs.outer_iterator().enumerate() {
         let val = lvec.dot(&rhs);
         if val != N::zero() {
@@ -181,11 +419,12 @@ where N: Copy + Num {
 /// CSR-dense rowmaj multiplication
 ///
 /// Performs better if out is rowmaj.
-pub fn csr_mulacc_dense_rowmaj<'a, N>(lhs: CsMatView<N>,
-                                      rhs: ArrayView<N, Ix2>,
-                                      mut out: ArrayViewMut<'a, N, Ix2>
-                                     )
-where N: 'a + Num + Copy
+pub fn csr_mulacc_dense_rowmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                         rhs: ArrayView<N, Ix2>,
+                                         mut out: ArrayViewMut<'a, N, Ix2>
+                                        )
+where N: 'a + Num + Copy,
+      I: 'a + SpIndex
 {
     if lhs.cols() != rhs.shape()[0] {
         panic!("Dimension mismatch");
@@ -238,11 +477,12 @@ where N: 'a + Num + Copy
 /// CSC-dense rowmaj multiplication
 ///
 /// Performs better if out is rowmaj
-pub fn csc_mulacc_dense_rowmaj<'a, N>(lhs: CsMatView<N>,
-                                      rhs: ArrayView<N, Ix2>,
-                                      mut out: ArrayViewMut<'a, N, Ix2>
-                                     )
-where N: 'a + Num + Copy
+pub fn csc_mulacc_dense_rowmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                         rhs: ArrayView<N, Ix2>,
+                                         mut out: ArrayViewMut<'a, N, Ix2>
+                                        )
+where N: 'a + Num + Copy,
+      I: 'a + SpIndex
 {
     if lhs.cols() != rhs.shape()[0] {
         panic!("Dimension mismatch");
@@ -274,11 +514,12 @@ where N: 'a + Num + Copy
 /// CSC-dense colmaj multiplication
 ///
 /// Performs better if out is colmaj
-pub fn csc_mulacc_dense_colmaj<'a, N>(lhs: CsMatView<N>,
-                                      rhs: ArrayView<N, Ix2>,
-                                      mut out: ArrayViewMut<'a, N, Ix2>
-                                     )
-where N: 'a + Num + Copy
+pub fn csc_mulacc_dense_colmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                         rhs: ArrayView<N, Ix2>,
+                                         mut out: ArrayViewMut<'a, N, Ix2>
+                                        )
+where N: 'a + Num + Copy,
+      I: 'a + SpIndex
 {
     if lhs.cols() != rhs.shape()[0] {
         panic!("Dimension mismatch");
@@ -312,11 +553,12 @@ where N: 'a + Num + Copy
 /// CSR-dense colmaj multiplication
 ///
 /// Performs better if out is colmaj
-pub fn csr_mulacc_dense_colmaj<'a, N>(lhs: CsMatView<N>,
-                                      rhs: ArrayView<N, Ix2>,
-                                      mut out: ArrayViewMut<'a, N, Ix2>
-                                     )
-where N: 'a + Num + Copy
+pub fn csr_mulacc_dense_colmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                         rhs: ArrayView<N, Ix2>,
+                                         mut out: ArrayViewMut<'a, N, Ix2>
+                                        )
+where N: 'a + Num + Copy,
+      I: 'a + SpIndex
 {
     if lhs.cols() != rhs.shape()[0] {
         panic!("Dimension mismatch");
@@ -346,8 +588,258 @@ where N: 'a + Num + Copy
     }
 }
 
+/// Number of outer dimensions in the blocks of a parallel product.
+///
+/// The blocks are built by `outer_block_iter`, several of them per thread,
+/// so that rayon can balance between its threads blocks holding different
+/// numbers of non zeros.
+#[cfg(feature = "multi_thread")]
+fn par_block_size(outer_dims: usize) -> usize {
+    let nb_blocks = 8 * rayon::current_num_threads();
+    cmp::max(1, (outer_dims + nb_blocks - 1) / nb_blocks)
+}
+
+#[cfg(feature = "multi_thread")]
+fn check_dense_prod_dims<N, I>(lhs: &CsMatViewI<N, I>,
+                               rhs: &ArrayView<N, Ix2>,
+                               out: &ArrayViewMut<N, Ix2>)
+where I: SpIndex
+{
+    if lhs.cols() != rhs.shape()[0] {
+        panic!("Dimension mismatch");
+    }
+    if lhs.rows() != out.shape()[0] {
+        panic!("Dimension mismatch");
+    }
+    if rhs.shape()[1] != out.shape()[1] {
+        panic!("Dimension mismatch");
+    }
+}
+
+/// Multithreaded version of `mul_acc_mat_vec_csr`.
+///
+/// The rows of the matrix are split in blocks, which are handled by
+/// different threads. The result is exactly the one of
+/// `mul_acc_mat_vec_csr`.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_mul_acc_mat_vec_csr<N, I>(mat: CsMatViewI<N, I>,
+                                     in_vec: &[N],
+                                     res_vec: &mut[N])
+where N: Num + Copy + Send + Sync,
+      I: SpIndex + Sync {
+    if mat.cols() != in_vec.len() || mat.rows() != res_vec.len() {
+        panic!("Dimension mismatch");
+    }
+    if !mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let block_size = par_block_size(mat.rows());
+    let blocks: Vec<_> = res_vec.chunks_mut(block_size)
+                                .zip(mat.outer_block_iter(block_size))
+                                .collect();
+    blocks.into_par_iter().for_each(|(res_block, block)| {
+        mul_acc_mat_vec_csr(block, in_vec, res_block);
+    });
+}
+
+/// Multithreaded version of `mul_acc_mat_vec_csc`.
+///
+/// The columns of the matrix are split in blocks, each thread accumulating
+/// the product of its blocks into its own vector. These vectors are then
+/// summed into the result, which can therefore differ from the one of
+/// `mul_acc_mat_vec_csc` by rounding errors.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_mul_acc_mat_vec_csc<N, I>(mat: CsMatViewI<N, I>,
+                                     in_vec: &[N],
+                                     res_vec: &mut[N])
+where N: Num + Copy + Send + Sync,
+      I: SpIndex + Sync {
+    if mat.cols() != in_vec.len() || mat.rows() != res_vec.len() {
+        panic!("Dimension mismatch");
+    }
+    if !mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+    let rows = mat.rows();
+    let block_size = par_block_size(mat.cols());
+    let blocks: Vec<_> = in_vec.chunks(block_size)
+                               .zip(mat.outer_block_iter(block_size))
+                               .collect();
+    let acc = blocks.into_par_iter()
+                    .fold(|| vec![N::zero(); rows],
+                          |mut acc, (in_block, block)| {
+        mul_acc_mat_vec_csc(block, in_block, &mut acc);
+        acc
+    }).reduce_with(|mut acc, partial| {
+        for (aval, &pval) in acc.iter_mut().zip(partial.iter()) {
+            *aval = *aval + pval;
+        }
+        acc
+    });
+    if let Some(acc) = acc {
+        for (res, &aval) in res_vec.iter_mut().zip(acc.iter()) {
+            *res = *res + aval;
+        }
+    }
+}
+
+/// Multithreaded version of `csr_mulacc_dense_rowmaj`.
+///
+/// The rows of lhs are split in blocks, which are handled by different
+/// threads. The result is exactly the one of `csr_mulacc_dense_rowmaj`.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_csr_mulacc_dense_rowmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                             rhs: ArrayView<N, Ix2>,
+                                             mut out: ArrayViewMut<'a, N, Ix2>
+                                            )
+where N: 'a + Num + Copy + Send + Sync,
+      I: 'a + SpIndex + Sync
+{
+    check_dense_prod_dims(&lhs, &rhs, &out);
+    if !lhs.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let block_size = par_block_size(lhs.rows());
+    let blocks: Vec<_> = out.axis_chunks_iter_mut(Axis(0), block_size)
+                            .zip(lhs.outer_block_iter(block_size))
+                            .collect();
+    blocks.into_par_iter().for_each(|(oblock, lblock)| {
+        csr_mulacc_dense_rowmaj(lblock, rhs.view(), oblock);
+    });
+}
+
+/// Multithreaded version of `csr_mulacc_dense_colmaj`.
+///
+/// The rows of lhs are split in blocks, which are handled by different
+/// threads. The result is exactly the one of `csr_mulacc_dense_colmaj`.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_csr_mulacc_dense_colmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                             rhs: ArrayView<N, Ix2>,
+                                             mut out: ArrayViewMut<'a, N, Ix2>
+                                            )
+where N: 'a + Num + Copy + Send + Sync,
+      I: 'a + SpIndex + Sync
+{
+    check_dense_prod_dims(&lhs, &rhs, &out);
+    if !lhs.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let block_size = par_block_size(lhs.rows());
+    let blocks: Vec<_> = out.axis_chunks_iter_mut(Axis(0), block_size)
+                            .zip(lhs.outer_block_iter(block_size))
+                            .collect();
+    blocks.into_par_iter().for_each(|(oblock, lblock)| {
+        csr_mulacc_dense_colmaj(lblock, rhs.view(), oblock);
+    });
+}
+
+/// CSC-dense multiplication using per-thread accumulators
+#[cfg(feature = "multi_thread")]
+fn par_csc_mulacc_dense<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                  rhs: ArrayView<N, Ix2>,
+                                  mut out: ArrayViewMut<'a, N, Ix2>
+                                 )
+where N: 'a + Num + Copy + Send + Sync,
+      I: 'a + SpIndex + Sync
+{
+    let axis0 = Axis(0);
+    let shape = (lhs.rows(), rhs.shape()[1]);
+    let block_size = par_block_size(lhs.cols());
+    let blocks: Vec<_> = rhs.axis_chunks_iter(axis0, block_size)
+                            .zip(lhs.outer_block_iter(block_size))
+                            .collect();
+    let acc = blocks.into_par_iter()
+                    .fold(|| Array::zeros(shape),
+                          |mut acc, (rblock, lblock)| {
+        for (lcol, rline) in lblock.outer_iterator()
+                                   .zip(rblock.axis_iter(axis0)) {
+            for (orow, &lval) in lcol.iter() {
+                let mut oline = acc.row_mut(orow);
+                for (oval, &rval) in oline.iter_mut().zip(rline.iter()) {
+                    let prev = *oval;
+                    *oval = prev + lval * rval;
+                }
+            }
+        }
+        acc
+    }).reduce_with(|mut acc, partial| {
+        acc.zip_mut_with(&partial, |aval, &pval| {
+            let prev = *aval;
+            *aval = prev + pval;
+        });
+        acc
+    });
+    if let Some(acc) = acc {
+        out.zip_mut_with(&acc, |oval, &aval| {
+            let prev = *oval;
+            *oval = prev + aval;
+        });
+    }
+}
+
+/// Multithreaded version of `csc_mulacc_dense_rowmaj`.
+///
+/// The columns of lhs are split in blocks, each thread accumulating the
+/// product of its blocks into its own dense matrix. These matrices are
+/// then summed into the result, which can therefore differ from the one of
+/// `csc_mulacc_dense_rowmaj` by rounding errors.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_csc_mulacc_dense_rowmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                             rhs: ArrayView<N, Ix2>,
+                                             out: ArrayViewMut<'a, N, Ix2>
+                                            )
+where N: 'a + Num + Copy + Send + Sync,
+      I: 'a + SpIndex + Sync
+{
+    check_dense_prod_dims(&lhs, &rhs, &out);
+    if !lhs.is_csc() {
+        panic!("Storage mismatch");
+    }
+    if !rhs.is_standard_layout() {
+        panic!("Storage mismatch");
+    }
+    par_csc_mulacc_dense(lhs, rhs, out);
+}
+
+/// Multithreaded version of `csc_mulacc_dense_colmaj`.
+///
+/// The columns of lhs are split in blocks, each thread accumulating the
+/// product of its blocks into its own dense matrix. These matrices are
+/// then summed into the result, which can therefore differ from the one of
+/// `csc_mulacc_dense_colmaj` by rounding errors.
+///
+/// Requires the `multi_thread` feature.
+#[cfg(feature = "multi_thread")]
+pub fn par_csc_mulacc_dense_colmaj<'a, N, I>(lhs: CsMatViewI<N, I>,
+                                             rhs: ArrayView<N, Ix2>,
+                                             out: ArrayViewMut<'a, N, Ix2>
+                                            )
+where N: 'a + Num + Copy + Send + Sync,
+      I: 'a + SpIndex + Sync
+{
+    check_dense_prod_dims(&lhs, &rhs, &out);
+    if !lhs.is_csc() {
+        panic!("Storage mismatch");
+    }
+    if rhs.is_standard_layout() {
+        panic!("Storage mismatch");
+    }
+    par_csc_mulacc_dense(lhs, rhs, out);
+}
+
 #[cfg(test)]
 mod test {
+    use num_complex::Complex64;
     use sparse::{CsMat, CsMatOwned};
     use sparse::vec::{CsVec};
     use sparse::csmat::CompressedStorage::{CSC, CSR};
@@ -530,6 +1022,40 @@ mod test {
                    .all(|(&x, &y)| (x - y).abs() <= eps));
     }
 
+    #[test]
+    fn mul_complex() {
+        // | 1+i  0 | | i  1  |   | -1+i  1+i |
+        // | 2   -i | | 0  1-i| = |  2i   1-i |
+        let c = Complex64::new;
+        let a = CsMatOwned::new((2, 2),
+                                vec![0, 1, 3],
+                                vec![0, 0, 1],
+                                vec![c(1., 1.), c(2., 0.), c(0., -1.)]);
+        let b = CsMatOwned::new((2, 2),
+                                vec![0, 2, 3],
+                                vec![0, 1, 1],
+                                vec![c(0., 1.), c(1., 0.), c(1., -1.)]);
+        let expected = CsMatOwned::new((2, 2),
+                                       vec![0, 2, 4],
+                                       vec![0, 1, 0, 1],
+                                       vec![c(-1., 1.), c(1., 1.),
+                                            c(0., 2.), c(1., -1.)]);
+        assert_eq!(&a * &b, expected);
+        assert_eq!(&a.to_csc() * &b.to_csc(), expected.to_csc());
+
+        let x = CsVec::new(2, vec![0, 1], vec![c(1., 0.), c(0., 1.)]);
+        let expected_ax = CsVec::new(2, vec![0, 1], vec![c(1., 1.), c(3., 0.)]);
+        assert_eq!(&a * &x, expected_ax);
+        assert_eq!(&a.to_csc() * &x, expected_ax);
+
+        let b_dense = arr2(&[[c(0., 1.), c(1., 0.)],
+                             [c(0., 0.), c(1., -1.)]]);
+        let expected_dense = arr2(&[[c(-1., 1.), c(1., 1.)],
+                                    [c(0., 2.), c(1., -1.)]]);
+        assert_eq!(&a * &b_dense, expected_dense);
+        assert_eq!(&a.to_csc() * &b_dense, expected_dense);
+    }
+
     #[test]
     fn mul_csc_dense_rowmaj() {
         let a = mat1_csc();
@@ -590,4 +1116,159 @@ mod test {
         let c = &a * &b;
         assert_eq!(c, expected_output);
     }
+
+    /// A larger matrix, with rows of varying density
+    #[cfg(feature = "multi_thread")]
+    fn mat_rows_varying_density(rows: usize, cols: usize) -> CsMatOwned<f64> {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for i in 0..rows {
+            let step = 1 + (i * 7) % 23;
+            for j in (i % step..cols).filter(|j| j % step == 0) {
+                indices.push(j);
+                data.push(((i * cols + j) as f64).sin());
+            }
+            indptr.push(indices.len());
+        }
+        CsMat::new((rows, cols), indptr, indices, data)
+    }
+
+    #[test]
+    #[cfg(feature = "multi_thread")]
+    fn par_mul_mat_vec() {
+        let mat = mat_rows_varying_density(300, 200);
+        let x: Vec<f64> = (0..200).map(|i| (i as f64).cos()).collect();
+        let mut expected = vec![1.; 300];
+        mul_acc_mat_vec_csr(mat.view(), &x, &mut expected);
+        let mut res = vec![1.; 300];
+        super::par_mul_acc_mat_vec_csr(mat.view(), &x, &mut res);
+        assert_eq!(res, expected);
+
+        let mat_csc = mat.to_csc();
+        let mut res = vec![1.; 300];
+        super::par_mul_acc_mat_vec_csc(mat_csc.view(), &x, &mut res);
+        assert!(res.iter().zip(expected.iter())
+                   .all(|(&x, &y)| (x - y).abs() <= 1e-12));
+    }
+
+    #[test]
+    #[cfg(feature = "multi_thread")]
+    fn par_mul_dense() {
+        let mat = mat_rows_varying_density(300, 200);
+        let mat_csc = mat.to_csc();
+        let rhs = Array::from_shape_fn((200, 5), |(i, j)| {
+            ((i * 5 + j) as f64).cos()
+        });
+        let rhs_colmaj = Array::from_shape_fn((200, 5).f(), |(i, j)| {
+            ((i * 5 + j) as f64).cos()
+        });
+
+        let mut expected = Array::from_elem((300, 5), 1.);
+        super::csr_mulacc_dense_rowmaj(mat.view(), rhs.view(),
+                                       expected.view_mut());
+        let mut res = Array::from_elem((300, 5), 1.);
+        super::par_csr_mulacc_dense_rowmaj(mat.view(), rhs.view(),
+                                           res.view_mut());
+        assert_eq!(res, expected);
+
+        let mut expected_colmaj = Array::from_elem((300, 5).f(), 1.);
+        super::csr_mulacc_dense_colmaj(mat.view(), rhs_colmaj.view(),
+                                       expected_colmaj.view_mut());
+        let mut res = Array::from_elem((300, 5).f(), 1.);
+        super::par_csr_mulacc_dense_colmaj(mat.view(), rhs_colmaj.view(),
+                                           res.view_mut());
+        assert_eq!(res, expected_colmaj);
+
+        let mut res = Array::from_elem((300, 5), 1.);
+        super::par_csc_mulacc_dense_rowmaj(mat_csc.view(), rhs.view(),
+                                           res.view_mut());
+        assert!(res.iter().zip(expected.iter())
+                   .all(|(&x, &y)| (x - y).abs() <= 1e-12));
+
+        let mut res = Array::from_elem((300, 5).f(), 1.);
+        super::par_csc_mulacc_dense_colmaj(mat_csc.view(),
+                                           rhs_colmaj.view(),
+                                           res.view_mut());
+        assert!(res.iter().zip(expected_colmaj.iter())
+                   .all(|(&x, &y)| (x - y).abs() <= 1e-12));
+    }
+
+    #[test]
+    fn mul_csr_csr_symbolic_numeric() {
+        let a = mat1();
+        let mut res = super::csr_mul_csr_symbolic(a.view(), a.view());
+        assert_eq!(res.data().iter().filter(|&&x| x != 0.).count(), 0);
+        super::csr_mul_csr_numeric(a.view(), a.view(), &mut res);
+        assert_eq!(res, mat1_self_matprod());
+
+        // refill the product with new values
+        let b = a.map(|&x| 2. * x - 1.);
+        super::csr_mul_csr_numeric(b.view(), b.view(), &mut res);
+        let mut workspace = [0.; 5];
+        let expected = csr_mul_csr(&b, &b, &mut workspace);
+        assert_eq!(res.indptr(), expected.indptr());
+        assert_eq!(res.indices(), expected.indices());
+        assert_eq!(res.data(), expected.data());
+
+        let c = mat2();
+        let mut res = super::csr_mul_csr_symbolic(a.view(), c.view());
+        super::csr_mul_csr_numeric(a.view(), c.view(), &mut res);
+        assert_eq!(res, mat1_matprod_mat2());
+    }
+
+    #[test]
+    fn mul_csr_csr_symbolic_cancellation() {
+        // |1 1| |1|   |0|
+        // |0 1| |-1| = |-1|
+        let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                           vec![1., 1., 1.]);
+        let b = CsMat::new((2, 1), vec![0, 1, 2], vec![0, 0], vec![1., -1.]);
+        let mut res = super::csr_mul_csr_symbolic(a.view(), b.view());
+        super::csr_mul_csr_numeric(a.view(), b.view(), &mut res);
+        assert_eq!(res.nnz(), 2);
+        assert_eq!(res.data(), &[0., -1.]);
+    }
+
+    #[test]
+    fn mul_csr_csr_masked() {
+        let a = mat1();
+        let mask: CsMatOwned<bool> = CsMat::new(
+            (5, 5), vec![0, 2, 2, 3, 4, 5], vec![0, 4, 1, 3, 2],
+            vec![true; 5]);
+        let res = super::csr_mul_csr_masked(a.view(), a.view(), mask.view());
+        assert_eq!(res.indptr(), mask.indptr());
+        assert_eq!(res.indices(), mask.indices());
+        let full = mat1_self_matprod();
+        for (row_ind, row) in res.outer_iterator().enumerate() {
+            for (col_ind, &val) in row.iter() {
+                let expected = *full.get(row_ind, col_ind).unwrap_or(&0.);
+                assert_eq!(val, expected);
+            }
+        }
+
+        // masking with a view on a block of rows
+        let mask_block = mask.view().middle_outer_views(2, 2);
+        let a_block = a.view().middle_outer_views(2, 2);
+        let res = super::csr_mul_csr_masked(a_block, a.view(), mask_block);
+        assert_eq!(res.shape(), (2, 5));
+        assert_eq!(res.indptr(), &[0, 1, 2]);
+        assert_eq!(res.indices(), &[1, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "multi_thread")]
+    fn par_mul_csr_csr_numeric() {
+        let a = mat_rows_varying_density(300, 200);
+        let b = mat_rows_varying_density(200, 250);
+        let mut res = super::csr_mul_csr_symbolic(a.view(), b.view());
+        let mut par_res = super::csr_mul_csr_symbolic(a.view(), b.view());
+        super::csr_mul_csr_numeric(a.view(), b.view(), &mut res);
+        super::par_csr_mul_csr_numeric(a.view(), b.view(), &mut par_res);
+        assert_eq!(res, par_res);
+        let mut workspace = vec![0.; 250];
+        let expected = csr_mul_csr(&a, &b, &mut workspace);
+        assert_eq!(res.nnz(), expected.nnz());
+        assert_eq!(res.data(), expected.data());
+    }
 }
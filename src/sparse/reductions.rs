@@ -0,0 +1,377 @@
+//! Reductions of sparse matrices along their axes, norms and trace
+//!
+//! Reductions along an axis follow the ndarray convention: reducing along
+//! `Axis(0)` collapses the rows, giving one value per column, and reducing
+//! along `Axis(1)` gives one value per row. They work on both storages,
+//! iterating the outer dimension and scattering into the inner one, without
+//! converting the matrix.
+
+use std::ops::Deref;
+
+use ndarray::{Array, Axis, Ix};
+use num_traits::{Float, Num, NumCast};
+
+use sparse::prelude::*;
+
+impl<N, I, IpS, IS, DS> CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target = [I]>,
+      DS: Deref<Target = [N]>,
+      IS: Deref<Target = [I]>
+{
+    /// The number of lines and their length when reducing along axis,
+    /// and whether the lines are the outer dimensions.
+    fn axis_lines(&self, axis: Axis) -> (usize, usize, bool) {
+        let lines_are_cols = match axis {
+            Axis(0) => true,
+            Axis(1) => false,
+            _ => panic!("Invalid axis for a matrix"),
+        };
+        let (nb_lines, line_len) = if lines_are_cols {
+            (self.cols(), self.rows())
+        } else {
+            (self.rows(), self.cols())
+        };
+        (nb_lines, line_len, lines_are_cols == self.is_csc())
+    }
+
+    /// Fold the non-zero elements of each line along axis, in increasing
+    /// order of their position in the line.
+    fn fold_axis<B, F>(&self, axis: Axis, init: B, mut f: F) -> Vec<B>
+    where B: Clone,
+          F: FnMut(&mut B, usize, &N)
+    {
+        let (nb_lines, _, lines_are_outer) = self.axis_lines(axis);
+        let mut acc = vec![init; nb_lines];
+        for (outer_ind, vec) in self.outer_iterator().enumerate() {
+            for (inner_ind, val) in vec.iter() {
+                if lines_are_outer {
+                    f(&mut acc[outer_ind], inner_ind, val);
+                } else {
+                    f(&mut acc[inner_ind], outer_ind, val);
+                }
+            }
+        }
+        acc
+    }
+
+    /// Fold the elements of each line along axis into the best one
+    /// according to `better`, taking implicit zeros into account.
+    ///
+    /// Ties are resolved in favor of the smallest position.
+    fn arg_best_axis<F>(&self, axis: Axis, better: F) -> Vec<(N, usize)>
+    where N: Num + Copy,
+          F: Fn(&N, &N) -> bool
+    {
+        let (_, line_len, _) = self.axis_lines(axis);
+        // positions being visited in increasing order, the first implicit
+        // zero of a line is found by tracking the next expected position
+        let init: (Option<(N, usize)>, usize) = (None, 0);
+        let lines = self.fold_axis(axis, init, |acc, pos, &val| {
+            let replace = match acc.0 {
+                None => true,
+                Some((best, _)) => better(&val, &best),
+            };
+            if replace {
+                acc.0 = Some((val, pos));
+            }
+            if acc.1 == pos {
+                acc.1 += 1;
+            }
+        });
+        lines.into_iter()
+             .map(|(best, first_zero)| {
+                 let zero = (N::zero(), first_zero);
+                 match best {
+                     _ if line_len == 0 => panic!("Empty line along axis"),
+                     None => zero,
+                     Some(best) if first_zero == line_len => best,
+                     Some((val, pos)) => {
+                         let zero_better = better(&N::zero(), &val);
+                         let tie = !zero_better && !better(&val, &N::zero());
+                         if zero_better || (tie && first_zero < pos) {
+                             zero
+                         } else {
+                             (val, pos)
+                         }
+                     }
+                 }
+             })
+             .collect()
+    }
+
+    /// Sum of the elements along an axis.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ndarray;
+    /// # extern crate sprs;
+    /// # fn main() {
+    /// use ndarray::{arr1, Axis};
+    /// use sprs::CsMatOwned;
+    /// // | 1 0 2 |
+    /// // | 0 3 0 |
+    /// let a = CsMatOwned::new((2, 3),
+    ///                         vec![0, 2, 3],
+    ///                         vec![0, 2, 1],
+    ///                         vec![1, 2, 3]);
+    /// assert_eq!(a.sum_axis(Axis(0)), arr1(&[1, 3, 2]));
+    /// assert_eq!(a.sum_axis(Axis(1)), arr1(&[3, 3]));
+    /// # }
+    /// ```
+    pub fn sum_axis(&self, axis: Axis) -> Array<N, Ix>
+    where N: Num + Copy
+    {
+        let sums = self.fold_axis(axis, N::zero(), |acc, _, &val| {
+            *acc = *acc + val;
+        });
+        Array::from_vec(sums)
+    }
+
+    /// Mean of the elements along an axis, implicit zeros included.
+    ///
+    /// # Panics
+    ///
+    /// - if the axis has length 0
+    pub fn mean_axis(&self, axis: Axis) -> Array<N, Ix>
+    where N: Num + Copy + NumCast
+    {
+        let (_, line_len, _) = self.axis_lines(axis);
+        if line_len == 0 {
+            panic!("Empty line along axis");
+        }
+        let len = N::from(line_len).expect("line length overflows");
+        let mut res = self.sum_axis(axis);
+        for x in res.iter_mut() {
+            *x = *x / len;
+        }
+        res
+    }
+
+    /// Minimum of the elements along an axis, implicit zeros included.
+    ///
+    /// # Panics
+    ///
+    /// - if the axis has length 0
+    pub fn min_axis(&self, axis: Axis) -> Array<N, Ix>
+    where N: Num + Copy + PartialOrd
+    {
+        let lines = self.arg_best_axis(axis, |x, y| x < y);
+        Array::from_iter(lines.into_iter().map(|(val, _)| val))
+    }
+
+    /// Maximum of the elements along an axis, implicit zeros included.
+    ///
+    /// # Panics
+    ///
+    /// - if the axis has length 0
+    pub fn max_axis(&self, axis: Axis) -> Array<N, Ix>
+    where N: Num + Copy + PartialOrd
+    {
+        let lines = self.arg_best_axis(axis, |x, y| x > y);
+        Array::from_iter(lines.into_iter().map(|(val, _)| val))
+    }
+
+    /// Position of the minimum element along an axis, implicit zeros
+    /// included. Ties are resolved in favor of the smallest position.
+    ///
+    /// # Panics
+    ///
+    /// - if the axis has length 0
+    pub fn argmin_axis(&self, axis: Axis) -> Array<usize, Ix>
+    where N: Num + Copy + PartialOrd
+    {
+        let lines = self.arg_best_axis(axis, |x, y| x < y);
+        Array::from_iter(lines.into_iter().map(|(_, pos)| pos))
+    }
+
+    /// Position of the maximum element along an axis, implicit zeros
+    /// included. Ties are resolved in favor of the smallest position.
+    ///
+    /// # Panics
+    ///
+    /// - if the axis has length 0
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ndarray;
+    /// # extern crate sprs;
+    /// # fn main() {
+    /// use ndarray::{arr1, Axis};
+    /// use sprs::CsMatOwned;
+    /// // |  1 0 2 |
+    /// // | -1 0 0 |
+    /// let a = CsMatOwned::new((2, 3),
+    ///                         vec![0, 2, 3],
+    ///                         vec![0, 2, 0],
+    ///                         vec![1, 2, -1]);
+    /// assert_eq!(a.argmax_axis(Axis(1)), arr1(&[2, 1]));
+    /// # }
+    /// ```
+    pub fn argmax_axis(&self, axis: Axis) -> Array<usize, Ix>
+    where N: Num + Copy + PartialOrd
+    {
+        let lines = self.arg_best_axis(axis, |x, y| x > y);
+        Array::from_iter(lines.into_iter().map(|(_, pos)| pos))
+    }
+
+    /// Number of stored elements along an axis.
+    pub fn nnz_axis(&self, axis: Axis) -> Array<usize, Ix> {
+        Array::from_vec(self.fold_axis(axis, 0, |acc, _, _| *acc += 1))
+    }
+
+    /// The 1-norm of this matrix, ie its maximum absolute column sum.
+    pub fn norm_one(&self) -> N
+    where N: Float
+    {
+        self.fold_axis(Axis(0), N::zero(), |acc, _, &val| {
+                *acc = *acc + val.abs();
+            })
+            .into_iter()
+            .fold(N::zero(), N::max)
+    }
+
+    /// The infinity-norm of this matrix, ie its maximum absolute row sum.
+    pub fn norm_inf(&self) -> N
+    where N: Float
+    {
+        self.fold_axis(Axis(1), N::zero(), |acc, _, &val| {
+                *acc = *acc + val.abs();
+            })
+            .into_iter()
+            .fold(N::zero(), N::max)
+    }
+
+    /// The Frobenius norm of this matrix, ie the square root of the sum of
+    /// its squared elements.
+    pub fn norm_frobenius(&self) -> N
+    where N: Float
+    {
+        self.outer_iterator()
+            .fold(N::zero(), |acc, vec| {
+                vec.data().iter().fold(acc, |acc, &val| acc + val * val)
+            })
+            .sqrt()
+    }
+
+    /// The max norm of this matrix, ie the maximum absolute value of its
+    /// elements.
+    pub fn norm_max(&self) -> N
+    where N: Float
+    {
+        self.outer_iterator().fold(N::zero(), |acc, vec| {
+            vec.data().iter().fold(acc, |acc, &val| acc.max(val.abs()))
+        })
+    }
+
+    /// The sum of the diagonal elements of this matrix. Rectangular
+    /// matrices are supported, their diagonal starting at (0, 0).
+    pub fn trace(&self) -> N
+    where N: Num + Copy
+    {
+        self.outer_iterator()
+            .enumerate()
+            .filter_map(|(ind, vec)| vec.get(ind).cloned())
+            .fold(N::zero(), |acc, val| acc + val)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{arr1, Axis};
+    use sparse::CsMatOwned;
+    use test_data::{mat1, mat1_csc};
+
+    #[test]
+    fn sum_mean_nnz_axis() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        for mat in &[mat1(), mat1_csc()] {
+            assert_eq!(mat.sum_axis(Axis(0)), arr1(&[0., 8., 8., 13., 5.]));
+            assert_eq!(mat.sum_axis(Axis(1)), arr1(&[7., 7., 5., 8., 7.]));
+            assert_eq!(mat.mean_axis(Axis(1)),
+                       arr1(&[1.4, 1.4, 1., 1.6, 1.4]));
+            assert_eq!(mat.nnz_axis(Axis(0)), arr1(&[0, 1, 2, 3, 1]));
+            assert_eq!(mat.nnz_axis(Axis(1)), arr1(&[2, 2, 1, 1, 1]));
+        }
+    }
+
+    #[test]
+    fn min_max_axis() {
+        // | -1  0  2 |
+        // |  0  0  0 |
+        // |  3 -2  4 |
+        // |  5  6  7 |
+        let mat = CsMatOwned::new((4, 3),
+                                  vec![0, 2, 2, 5, 8],
+                                  vec![0, 2, 0, 1, 2, 0, 1, 2],
+                                  vec![-1, 2, 3, -2, 4, 5, 6, 7]);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            assert_eq!(mat.max_axis(Axis(1)), arr1(&[2, 0, 4, 7]));
+            assert_eq!(mat.argmax_axis(Axis(1)), arr1(&[2, 0, 2, 2]));
+            assert_eq!(mat.min_axis(Axis(1)), arr1(&[-1, 0, -2, 5]));
+            assert_eq!(mat.argmin_axis(Axis(1)), arr1(&[0, 0, 1, 0]));
+            assert_eq!(mat.max_axis(Axis(0)), arr1(&[5, 6, 7]));
+            assert_eq!(mat.argmax_axis(Axis(0)), arr1(&[3, 3, 3]));
+            assert_eq!(mat.min_axis(Axis(0)), arr1(&[-1, -2, 0]));
+            // the first implicit zero of the last column is on row 1
+            assert_eq!(mat.argmin_axis(Axis(0)), arr1(&[0, 2, 1]));
+        }
+
+        // ties between explicit and implicit zeros go to the smallest
+        // position
+        let mat = CsMatOwned::new((1, 3),
+                                  vec![0, 2],
+                                  vec![1, 2],
+                                  vec![0, -1]);
+        assert_eq!(mat.argmax_axis(Axis(1)), arr1(&[0]));
+        let mat = CsMatOwned::new((1, 3),
+                                  vec![0, 2],
+                                  vec![0, 2],
+                                  vec![0, -1]);
+        assert_eq!(mat.argmax_axis(Axis(1)), arr1(&[0]));
+        assert_eq!(mat.max_axis(Axis(1)), arr1(&[0]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn max_axis_empty() {
+        let mat: CsMatOwned<f64> = CsMatOwned::new((2, 0),
+                                                   vec![0, 0, 0],
+                                                   vec![],
+                                                   vec![]);
+        mat.max_axis(Axis(1));
+    }
+
+    #[test]
+    fn norms_and_trace() {
+        let mut mat = mat1();
+        mat.insert(1, 0, -9.);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            assert_eq!(mat.norm_one(), 13.);
+            assert_eq!(mat.norm_inf(), 16.);
+            assert_eq!(mat.norm_max(), 9.);
+            let squares: f64 = 9. + 16. + 81. + 4. + 25. + 25. + 64. + 49.;
+            assert_eq!(mat.norm_frobenius(), squares.sqrt());
+            assert_eq!(mat.trace(), 5.);
+        }
+        let rect = mat1().slice(0..3, 0..5);
+        assert_eq!(rect.trace(), 5.);
+        assert_eq!(rect.transpose_view().trace(), 5.);
+
+        // outer views share the storage of their parent matrix
+        let diag = CsMatOwned::new((2, 2), vec![0, 1, 2], vec![0, 1],
+                                   vec![1., 9.]);
+        let first_row = diag.slice_outer(0..1);
+        assert_eq!(first_row.norm_max(), 1.);
+        assert_eq!(first_row.norm_frobenius(), 1.);
+        let last_row = diag.view().middle_outer_views(1, 1);
+        assert_eq!(last_row.norm_max(), 9.);
+        assert_eq!(last_row.norm_frobenius(), 9.);
+    }
+}
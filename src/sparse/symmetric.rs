@@ -3,13 +3,15 @@
 use std::ops::{Deref};
 
 use sparse::prelude::*;
+use sparse::complex::Conjugate;
 
-pub fn is_symmetric<N, IpStorage, IStorage, DStorage>(
-    mat: &CsMat<N, IpStorage, IStorage, DStorage>) -> bool
+pub fn is_symmetric<N, I, IpStorage, IStorage, DStorage>(
+    mat: &CsMatBase<N, I, IpStorage, IStorage, DStorage>) -> bool
 where
 N: Copy + PartialEq,
-IpStorage: Deref<Target=[usize]>,
-IStorage: Deref<Target=[usize]>,
+I: SpIndex,
+IpStorage: Deref<Target=[I]>,
+IStorage: Deref<Target=[I]>,
 DStorage: Deref<Target=[N]> {
     if mat.rows() != mat.cols() {
         return false;
@@ -27,11 +29,39 @@ DStorage: Deref<Target=[N]> {
     true
 }
 
+/// Check whether a matrix is hermitian, ie equal to its conjugate transpose.
+///
+/// For real scalars this is the same as `is_symmetric`.
+pub fn is_hermitian<N, I, IpStorage, IStorage, DStorage>(
+    mat: &CsMatBase<N, I, IpStorage, IStorage, DStorage>) -> bool
+where
+N: Conjugate + PartialEq,
+I: SpIndex,
+IpStorage: Deref<Target=[I]>,
+IStorage: Deref<Target=[I]>,
+DStorage: Deref<Target=[N]> {
+    if mat.rows() != mat.cols() {
+        return false;
+    }
+    for (outer_ind, vec) in mat.outer_iterator().enumerate() {
+        for (inner_ind, &value) in vec.iter() {
+            match mat.get_outer_inner(inner_ind, outer_ind) {
+                None => return false,
+                Some(&transposed_val) => if transposed_val != value.conj() {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod test {
     use sparse::CsMat;
     use sparse::csmat::CompressedStorage::{CSR};
-    use super::is_symmetric;
+    use num_complex::Complex64;
+    use super::{is_symmetric, is_hermitian};
 
     #[test]
     fn is_symmetric_simple() {
@@ -64,5 +94,34 @@ mod test {
         assert!(is_symmetric(&a));
     }
 
+    #[test]
+    fn is_hermitian_complex() {
+        // | 2    1-i |
+        // | 1+i  3   |
+        let c = Complex64::new;
+        let indptr: &[usize] = &[0, 2, 4];
+        let indices: &[usize] = &[0, 1, 0, 1];
+        let data = [c(2., 0.), c(1., -1.), c(1., 1.), c(3., 0.)];
+        let a = CsMat::new_view(CSR, (2, 2), indptr, indices, &data).unwrap();
+        assert!(is_hermitian(&a));
+        assert!(!is_symmetric(&a));
+
+        // a non real diagonal breaks hermitian symmetry
+        let data = [c(2., 1.), c(1., -1.), c(1., 1.), c(3., 0.)];
+        let a = CsMat::new_view(CSR, (2, 2), indptr, indices, &data).unwrap();
+        assert!(!is_hermitian(&a));
+
+        // complex symmetric matrices are not hermitian
+        let data = [c(2., 0.), c(1., 1.), c(1., 1.), c(3., 0.)];
+        let a = CsMat::new_view(CSR, (2, 2), indptr, indices, &data).unwrap();
+        assert!(is_symmetric(&a));
+        assert!(!is_hermitian(&a));
+
+        // for real matrices, hermitian and symmetric are equivalent
+        let data = [2., 1., 1., 3.];
+        let a = CsMat::new_view(CSR, (2, 2), indptr, indices, &data).unwrap();
+        assert!(is_hermitian(&a));
+    }
+
     // TODO: symmetry test on A^T*A products
 }
@@ -1,17 +1,20 @@
 ///! Utilities for sparse-to-dense conversion
 
 use ndarray::{ArrayViewMut, Axis};
-use ::CsMatView;
+use ::CsMatViewI;
 use ::Ix2;
+use indexing::SpIndex;
 
 /// Assign a sparse matrix into a dense matrix
 ///
 /// The dense matrix will not be zeroed prior to assignment,
 /// so existing values not corresponding to non-zeroes will be preserved.
-pub fn assign_to_dense<N>(mut array: ArrayViewMut<N, Ix2>, spmat: CsMatView<N>)
-where N: Clone
+pub fn assign_to_dense<N, I>(mut array: ArrayViewMut<N, Ix2>,
+                             spmat: CsMatViewI<N, I>)
+where N: Clone,
+      I: SpIndex
 {
-    if spmat.cols() != array.shape()[0] {
+    if spmat.cols() != array.shape()[1] {
         panic!("Dimension mismatch");
     }
     if spmat.rows() != array.shape()[0] {
@@ -58,4 +61,16 @@ mod test {
                               [0., 0., 0., 7., 0.]]);
         assert_eq!(expected, res);
     }
+
+    #[test]
+    fn to_dense_non_square() {
+        let expected = arr2(&[[0., 2., 0.],
+                              [1., 0., 3.]]);
+        let csr = CsMatOwned::new((2, 3),
+                                  vec![0, 1, 3],
+                                  vec![1, 0, 2],
+                                  vec![2., 1., 3.]);
+        assert_eq!(csr.to_dense(), expected);
+        assert_eq!(csr.to_csc().to_dense(), expected);
+    }
 }
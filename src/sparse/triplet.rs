@@ -9,26 +9,43 @@
 ///! entries. By convention, duplicate locations are summed up when converting
 ///! into CsMatOwned.
 
-use sparse::{csmat, CsMatOwned};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+use sparse::{csmat, CsMatI};
+use indexing::SpIndex;
 use num_traits::Num;
+#[cfg(feature = "serde")]
+use errors::SprsError;
 
 /// Indexing type into a Triplet
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct TripletIndex(pub usize);
 
 /// Triplet matrix owning its data
-pub struct TripletMat<N> {
+///
+/// With the `serde` feature, triplet matrices can be serialized and
+/// deserialized. Deserialization checks that the arrays have the same length
+/// and that the indices are in bounds.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde",
+           serde(try_from = "TripletMatShadow<N, I>"))]
+pub struct TripletMatI<N, I: SpIndex> {
     rows: usize,
     cols: usize,
-    row_inds: Vec<usize>,
-    col_inds: Vec<usize>,
+    row_inds: Vec<I>,
+    col_inds: Vec<I>,
     data: Vec<N>,
 }
 
-impl<N> TripletMat<N> {
+pub type TripletMat<N> = TripletMatI<N, usize>;
+pub type TripletMatView<'a, N> = TripletMatViewI<'a, N, usize>;
+pub type TripletMatViewMut<'a, N> = TripletMatViewMutI<'a, N, usize>;
+
+impl<N, I: SpIndex> TripletMatI<N, I> {
     /// Create a new triplet matrix of shape `(nb_rows, nb_cols)`
-    pub fn new(shape: (usize, usize)) -> TripletMat<N> {
-        TripletMat {
+    pub fn new(shape: (usize, usize)) -> TripletMatI<N, I> {
+        TripletMatI {
             rows: shape.0,
             cols: shape.1,
             row_inds: Vec::new(),
@@ -39,8 +56,10 @@ impl<N> TripletMat<N> {
 
     /// Create a new triplet matrix of shape `(nb_rows, nb_cols)`, and
     /// pre-allocate `cap` elements on the backing storage
-    pub fn with_capacity(shape: (usize, usize), cap: usize) -> TripletMat<N> {
-        TripletMat {
+    pub fn with_capacity(shape: (usize, usize),
+                         cap: usize)
+                         -> TripletMatI<N, I> {
+        TripletMatI {
             rows: shape.0,
             cols: shape.1,
             row_inds: Vec::with_capacity(cap),
@@ -57,21 +76,21 @@ impl<N> TripletMat<N> {
     /// - if the arrays don't have the same length
     /// - if either the row or column indices are out of bounds.
     pub fn from_triplets(shape: (usize, usize),
-                         row_inds: Vec<usize>,
-                         col_inds: Vec<usize>,
+                         row_inds: Vec<I>,
+                         col_inds: Vec<I>,
                          data: Vec<N>)
-                         -> TripletMat<N> {
+                         -> TripletMatI<N, I> {
         assert!(row_inds.len() == col_inds.len(),
                 "all inputs should have the same length");
         assert!(data.len() == col_inds.len(),
                 "all inputs should have the same length");
         assert!(row_inds.len() == data.len(),
                 "all inputs should have the same length");
-        assert!(row_inds.iter().all(|&i| i < shape.0),
+        assert!(row_inds.iter().all(|&i| i.index() < shape.0),
                 "row indices should be within shape");
-        assert!(col_inds.iter().all(|&j| j < shape.1),
+        assert!(col_inds.iter().all(|&j| j.index() < shape.1),
                 "col indices should be within shape");
-        TripletMat {
+        TripletMatI {
             rows: shape.0,
             cols: shape.1,
             row_inds: row_inds,
@@ -101,12 +120,12 @@ impl<N> TripletMat<N> {
     }
 
     /// The non-zero row indices
-    pub fn row_inds(&self) -> &[usize] {
+    pub fn row_inds(&self) -> &[I] {
         self.borrowed().row_inds()
     }
 
     /// The non-zero column indices
-    pub fn col_inds(&self) -> &[usize] {
+    pub fn col_inds(&self) -> &[I] {
         self.borrowed().col_inds()
     }
 
@@ -121,8 +140,8 @@ impl<N> TripletMat<N> {
     }
 
     /// Return a view of this matrix
-    pub fn borrowed(&self) -> TripletMatView<N> {
-        TripletMatView {
+    pub fn borrowed(&self) -> TripletMatViewI<N, I> {
+        TripletMatViewI {
             rows: self.rows,
             cols: self.cols,
             row_inds: &self.row_inds[..],
@@ -143,8 +162,8 @@ impl<N> TripletMat<N> {
     }
 
     /// Get a mutable view into this matrix.
-    pub fn borrowed_mut(&mut self) -> TripletMatViewMut<N> {
-        TripletMatViewMut {
+    pub fn borrowed_mut(&mut self) -> TripletMatViewMutI<N, I> {
+        TripletMatViewMutI {
             rows: self.rows,
             cols: self.cols,
             row_inds: &mut self.row_inds[..],
@@ -154,7 +173,7 @@ impl<N> TripletMat<N> {
     }
 
     /// Get a transposed view of this matrix
-    pub fn transpose_view(&self) -> TripletMatView<N> {
+    pub fn transpose_view(&self) -> TripletMatViewI<N, I> {
         self.borrowed().transpose_view()
     }
 
@@ -162,8 +181,8 @@ impl<N> TripletMat<N> {
     pub fn add_triplet(&mut self, row: usize, col: usize, val: N) {
         assert!(row < self.rows);
         assert!(col < self.cols);
-        self.row_inds.push(row);
-        self.col_inds.push(col);
+        self.row_inds.push(I::from_usize(row));
+        self.col_inds.push(I::from_usize(col));
         self.data.push(val);
     }
 
@@ -182,14 +201,14 @@ impl<N> TripletMat<N> {
     }
 
     /// Create a CSC matrix from this triplet matrix
-    pub fn to_csc(&self) -> CsMatOwned<N>
+    pub fn to_csc(&self) -> CsMatI<N, I>
     where N: Clone + Num
     {
         self.borrowed().to_csc()
     }
 
     /// Create a CSR matrix from this triplet matrix
-    pub fn to_csr(&self) -> CsMatOwned<N>
+    pub fn to_csr(&self) -> CsMatI<N, I>
     where N: Clone + Num
     {
         self.borrowed().to_csr()
@@ -197,15 +216,15 @@ impl<N> TripletMat<N> {
 }
 
 /// Triplet matrix view
-pub struct TripletMatView<'a, N: 'a> {
+pub struct TripletMatViewI<'a, N: 'a, I: 'a + SpIndex> {
     rows: usize,
     cols: usize,
-    row_inds: &'a [usize],
-    col_inds: &'a [usize],
+    row_inds: &'a [I],
+    col_inds: &'a [I],
     data: &'a [N],
 }
 
-impl<'a, N> TripletMatView<'a, N> {
+impl<'a, N, I: SpIndex> TripletMatViewI<'a, N, I> {
     /// The number of rows of the matrix
     pub fn rows(&self) -> usize {
         self.rows
@@ -227,12 +246,12 @@ impl<'a, N> TripletMatView<'a, N> {
     }
 
     /// The non-zero row indices
-    pub fn row_inds(&self) -> &'a [usize] {
+    pub fn row_inds(&self) -> &'a [I] {
         self.row_inds
     }
 
     /// The non-zero column indices
-    pub fn col_inds(&self) -> &'a [usize] {
+    pub fn col_inds(&self) -> &'a [I] {
         self.col_inds
     }
 
@@ -247,14 +266,14 @@ impl<'a, N> TripletMatView<'a, N> {
             .iter()
             .zip(self.col_inds.iter())
             .enumerate()
-            .filter(|&(_, (&i, &j))| i == row && j == col)
+            .filter(|&(_, (&i, &j))| i.index() == row && j.index() == col)
             .map(|(ind, _)| TripletIndex(ind))
             .collect()
     }
 
     /// Get a transposed view of this matrix
-    pub fn transpose_view(&self) -> TripletMatView<'a, N> {
-        TripletMatView {
+    pub fn transpose_view(&self) -> TripletMatViewI<'a, N, I> {
+        TripletMatViewI {
             rows: self.cols,
             cols: self.rows,
             row_inds: self.col_inds,
@@ -264,12 +283,12 @@ impl<'a, N> TripletMatView<'a, N> {
     }
 
     /// Create a CSC matrix from this triplet matrix
-    pub fn to_csc(&self) -> CsMatOwned<N>
+    pub fn to_csc(&self) -> CsMatI<N, I>
     where N: Clone + Num
     {
         let mut row_counts = vec![0; self.rows() + 1];
-        for &i in self.row_inds.iter() {
-            row_counts[i + 1] += 1;
+        for i in self.row_inds.iter() {
+            row_counts[i.index() + 1] += 1;
         }
         let mut indptr = row_counts.clone();
         // cum sum
@@ -277,7 +296,7 @@ impl<'a, N> TripletMatView<'a, N> {
             indptr[i] += indptr[i - 1];
         }
         let nnz_max = indptr[self.rows()];
-        let mut indices = vec![0; nnz_max];
+        let mut indices = vec![I::zero(); nnz_max];
         let mut data = vec![N::zero(); nnz_max];
 
         // reset row counts to 0
@@ -285,11 +304,12 @@ impl<'a, N> TripletMatView<'a, N> {
             *count = 0;
         }
 
-        for (val, (&i, &j)) in self.data
+        for (val, (i, &j)) in self.data
                                    .iter()
                                    .zip(self.row_inds
                                             .iter()
                                             .zip(self.col_inds.iter())) {
+            let i = i.index();
             let start = indptr[i];
             let stop = start + row_counts[i];
             let col_exists = {
@@ -332,8 +352,9 @@ impl<'a, N> TripletMatView<'a, N> {
         // at this point we have a CSR matrix with unsorted columns
         // transposing it will yield the desired CSC matrix with sorted rows
         let nnz = indptr[self.rows()];
-        let mut out_indptr = vec![0; self.cols() + 1];
-        let mut out_indices = vec![0; nnz];
+        let indptr: Vec<I> = indptr.into_iter().map(I::from_usize).collect();
+        let mut out_indptr = vec![I::zero(); self.cols() + 1];
+        let mut out_indices = vec![I::zero(); nnz];
         let mut out_data = vec![N::zero(); nnz];
         csmat::raw::convert_storage(csmat::CompressedStorage::CSR,
                                     self.shape(),
@@ -343,7 +364,7 @@ impl<'a, N> TripletMatView<'a, N> {
                                     &mut out_indptr,
                                     &mut out_indices,
                                     &mut out_data);
-        CsMatOwned {
+        CsMatI {
             storage: csmat::CompressedStorage::CSC,
             nrows: self.rows,
             ncols: self.cols,
@@ -354,7 +375,7 @@ impl<'a, N> TripletMatView<'a, N> {
     }
 
     /// Create a CSR matrix from this triplet matrix
-    pub fn to_csr(&self) -> CsMatOwned<N>
+    pub fn to_csr(&self) -> CsMatI<N, I>
     where N: Clone + Num
     {
         let res = self.transpose_view().to_csc();
@@ -364,15 +385,15 @@ impl<'a, N> TripletMatView<'a, N> {
 
 
 /// Triplet matrix mutable view
-pub struct TripletMatViewMut<'a, N: 'a> {
+pub struct TripletMatViewMutI<'a, N: 'a, I: 'a + SpIndex> {
     rows: usize,
     cols: usize,
-    row_inds: &'a mut [usize],
-    col_inds: &'a mut [usize],
+    row_inds: &'a mut [I],
+    col_inds: &'a mut [I],
     data: &'a mut [N],
 }
 
-impl<'a, N> TripletMatViewMut<'a, N> {
+impl<'a, N, I: SpIndex> TripletMatViewMutI<'a, N, I> {
     /// The number of rows of the matrix
     pub fn rows(&self) -> usize {
         self.borrowed().rows()
@@ -394,12 +415,12 @@ impl<'a, N> TripletMatViewMut<'a, N> {
     }
 
     /// The non-zero row indices
-    pub fn row_inds(&self) -> &[usize] {
+    pub fn row_inds(&self) -> &[I] {
         self.borrowed().row_inds()
     }
 
     /// The non-zero column indices
-    pub fn col_inds(&self) -> &[usize] {
+    pub fn col_inds(&self) -> &[I] {
         self.borrowed().col_inds()
     }
 
@@ -409,8 +430,8 @@ impl<'a, N> TripletMatViewMut<'a, N> {
     }
 
     /// Return a view of this matrix
-    pub fn borrowed(&self) -> TripletMatView<N> {
-        TripletMatView {
+    pub fn borrowed(&self) -> TripletMatViewI<N, I> {
+        TripletMatViewI {
             rows: self.rows,
             cols: self.cols,
             row_inds: &self.row_inds[..],
@@ -420,7 +441,7 @@ impl<'a, N> TripletMatViewMut<'a, N> {
     }
 
     /// Get a transposed view of this matrix
-    pub fn transpose_view(&self) -> TripletMatView<N> {
+    pub fn transpose_view(&self) -> TripletMatViewI<N, I> {
         self.borrowed().transpose_view()
     }
 
@@ -431,31 +452,67 @@ impl<'a, N> TripletMatViewMut<'a, N> {
                        row: usize,
                        col: usize,
                        val: N) {
-        self.row_inds[triplet_ind] = row;
-        self.col_inds[triplet_ind] = col;
+        self.row_inds[triplet_ind] = I::from_usize(row);
+        self.col_inds[triplet_ind] = I::from_usize(col);
         self.data[triplet_ind] = val;
     }
 
     /// Create a CSC matrix from this triplet matrix
-    pub fn to_csc(&self) -> CsMatOwned<N>
+    pub fn to_csc(&self) -> CsMatI<N, I>
     where N: Clone + Num
     {
         self.borrowed().to_csc()
     }
 
     /// Create a CSR matrix from this triplet matrix
-    pub fn to_csr(&self) -> CsMatOwned<N>
+    pub fn to_csr(&self) -> CsMatI<N, I>
     where N: Clone + Num
     {
         self.borrowed().to_csr()
     }
 }
 
+/// Deserialized form of a TripletMatI, whose structure has not been checked
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub struct TripletMatShadow<N, I: SpIndex> {
+    rows: usize,
+    cols: usize,
+    row_inds: Vec<I>,
+    col_inds: Vec<I>,
+    data: Vec<N>,
+}
+
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+impl<N, I: SpIndex> TryFrom<TripletMatShadow<N, I>> for TripletMatI<N, I> {
+    type Error = SprsError;
+
+    fn try_from(shadow: TripletMatShadow<N, I>) -> Result<Self, SprsError> {
+        let nnz = shadow.data.len();
+        if shadow.row_inds.len() != nnz || shadow.col_inds.len() != nnz {
+            return Err(SprsError::BadStorageLength);
+        }
+        if shadow.row_inds.iter().any(|i| i.index() >= shadow.rows) ||
+           shadow.col_inds.iter().any(|j| j.index() >= shadow.cols) {
+            return Err(SprsError::OutOfBoundsIndex);
+        }
+        Ok(TripletMatI {
+            rows: shadow.rows,
+            cols: shadow.cols,
+            row_inds: shadow.row_inds,
+            col_inds: shadow.col_inds,
+            data: shadow.data,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
 
-    use super::TripletMat;
-    use sparse::CsMatOwned;
+    use super::{TripletMat, TripletMatI};
+    use sparse::{CsMatOwned, CsMatI};
 
     #[test]
     fn triplet_incremental() {
@@ -663,4 +720,48 @@ mod test {
 
         assert_eq!(csr, expected.to_csr());
     }
+
+    #[test]
+    fn triplet_u32_indices() {
+        let mut triplet_mat: TripletMatI<f64, u32> =
+            TripletMatI::with_capacity((4, 4), 6);
+        triplet_mat.add_triplet(0, 0, 1.);
+        triplet_mat.add_triplet(0, 1, 2.);
+        triplet_mat.add_triplet(1, 0, 3.);
+        triplet_mat.add_triplet(2, 3, 4.);
+        triplet_mat.add_triplet(3, 2, 5.);
+        triplet_mat.add_triplet(3, 3, 6.);
+        assert_eq!(triplet_mat.row_inds(), &[0, 0, 1, 2, 3, 3]);
+
+        let csc = triplet_mat.to_csc();
+        let expected = CsMatI::new_csc((4, 4),
+                                       vec![0u32, 2, 3, 4, 6],
+                                       vec![0, 1, 0, 3, 2, 3],
+                                       vec![1., 3., 2., 5., 4., 6.]);
+        assert_eq!(csc, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        use serde_json;
+        let mut triplets = TripletMat::new((3, 4));
+        triplets.add_triplet(0, 3, 1.);
+        triplets.add_triplet(2, 1, 2.);
+        triplets.add_triplet(0, 3, 3.);
+        let json = serde_json::to_string(&triplets).unwrap();
+        let triplets2: TripletMat<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(triplets.shape(), triplets2.shape());
+        assert_eq!(triplets.row_inds(), triplets2.row_inds());
+        assert_eq!(triplets.col_inds(), triplets2.col_inds());
+        assert_eq!(triplets.data(), triplets2.data());
+
+        let parse = |json| serde_json::from_str::<TripletMat<f64>>(json);
+        let bad_length = r#"{"rows":3,"cols":4,"row_inds":[0,2],
+                             "col_inds":[3],"data":[1,2]}"#;
+        assert!(parse(bad_length).is_err());
+        let out_of_bounds = r#"{"rows":3,"cols":4,"row_inds":[0,3],
+                                "col_inds":[3,1],"data":[1,2]}"#;
+        assert!(parse(out_of_bounds).is_err());
+    }
 }
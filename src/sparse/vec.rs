@@ -19,6 +19,8 @@
 use std::iter::{Zip, Peekable, FilterMap, IntoIterator, Enumerate};
 use std::ops::{Deref, DerefMut, Mul, Add, Sub, Index, IndexMut};
 use std::convert::AsRef;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 use std::cmp;
 use std::slice::{self, Iter};
 use std::collections::HashSet;
@@ -28,18 +30,29 @@ use ndarray::{self, ArrayBase, Ix};
 
 use num_traits::Num;
 
-use sparse::permutation::PermView;
+use indexing::SpIndex;
+use sparse::permutation::PermViewI;
 use sparse::{prod, binop};
 use sparse::utils;
 use sparse::prelude::*;
 use sparse::csmat::CompressedStorage::{CSR, CSC};
+use sparse::csmat::convert_indices;
 use errors::SprsError;
 
 /// A sparse vector, storing the indices of its non-zero data.
 /// The indices should be sorted.
+///
+/// With the `serde` feature, owned vectors can be serialized and
+/// deserialized. Deserialized vectors go through the same structure checks
+/// as the constructors.
 #[derive(PartialEq, Debug)]
-pub struct CsVec<N, IStorage, DStorage>
-where DStorage: Deref<Target=[N]> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde",
+           serde(try_from = "CsVecBaseShadow<N, I, IStorage, DStorage>"))]
+pub struct CsVecBase<N, I, IStorage, DStorage>
+where I: SpIndex,
+      IStorage: Deref<Target=[I]>,
+      DStorage: Deref<Target=[N]> {
     dim: usize,
     indices : IStorage,
     data : DStorage
@@ -52,9 +65,14 @@ where DStorage: Deref<Target=[N]> {
 /// time.
 pub struct NnzIndex(pub usize);
 
-pub type CsVecView<'a, N> = CsVec<N, &'a [usize], &'a [N]>;
-pub type CsVecViewMut<'a, N> = CsVec<N, &'a [usize], &'a mut [N]>;
-pub type CsVecOwned<N> = CsVec<N, Vec<usize>, Vec<N>>;
+pub type CsVec<N, IStorage, DStorage> = CsVecBase<N, usize, IStorage, DStorage>;
+pub type CsVecI<N, I> = CsVecBase<N, I, Vec<I>, Vec<N>>;
+pub type CsVecViewI<'a, N, I> = CsVecBase<N, I, &'a [I], &'a [N]>;
+pub type CsVecViewMutI<'a, N, I> = CsVecBase<N, I, &'a [I], &'a mut [N]>;
+
+pub type CsVecView<'a, N> = CsVecViewI<'a, N, usize>;
+pub type CsVecViewMut<'a, N> = CsVecViewMutI<'a, N, usize>;
+pub type CsVecOwned<N> = CsVecI<N, usize>;
 
 /// A trait to represent types which can be interpreted as vectors
 /// of a given dimension.
@@ -63,7 +81,10 @@ pub trait VecDim<N> {
     fn dim(&self) -> usize;
 }
 
-impl<N, IS, DS: Deref<Target=[N]>> VecDim<N> for CsVec<N, IS, DS> {
+impl<N, I, IS, DS> VecDim<N> for CsVecBase<N, I, IS, DS>
+where I: SpIndex,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]> {
     fn dim(&self) -> usize {
         self.dim
     }
@@ -77,25 +98,25 @@ impl<N, T: ?Sized> VecDim<N> for T where T: AsRef<[N]> {
 
 
 /// An iterator over the non-zero elements of a sparse vector
-pub struct VectorIterator<'a, N: 'a> {
-    ind_data: Zip<Iter<'a,usize>, Iter<'a,N>>,
+pub struct VectorIterator<'a, N: 'a, I: 'a> {
+    ind_data: Zip<Iter<'a,I>, Iter<'a,N>>,
 }
 
-pub struct VectorIteratorPerm<'a, N: 'a> {
-    ind_data: Zip<Iter<'a,usize>, Iter<'a,N>>,
-    perm: PermView<'a>,
+pub struct VectorIteratorPerm<'a, N: 'a, I: 'a + SpIndex> {
+    ind_data: Zip<Iter<'a,I>, Iter<'a,N>>,
+    perm: PermViewI<'a, I>,
 }
 
 
-impl <'a, N: 'a>
+impl <'a, N: 'a, I: 'a + SpIndex>
 Iterator
-for VectorIterator<'a, N> {
+for VectorIterator<'a, N, I> {
     type Item = (usize, &'a N);
 
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
         match self.ind_data.next() {
             None => None,
-            Some((inner_ind, data)) => Some((*inner_ind, data))
+            Some((inner_ind, data)) => Some((inner_ind.index(), data))
         }
     }
 
@@ -104,16 +125,16 @@ for VectorIterator<'a, N> {
     }
 }
 
-impl <'a, N: 'a>
+impl <'a, N: 'a, I: 'a + SpIndex>
 Iterator
-for VectorIteratorPerm<'a, N> {
+for VectorIteratorPerm<'a, N, I> {
     type Item = (usize, &'a N);
 
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
         match self.ind_data.next() {
             None => None,
             Some((inner_ind, data)) => Some(
-                (self.perm.at(*inner_ind), data))
+                (self.perm.at(inner_ind.index()), data))
         }
     }
 
@@ -184,7 +205,7 @@ pub trait SparseIterTools: Iterator {
 impl<T: Iterator> SparseIterTools for Enumerate<T> {
 }
 
-impl<'a, N: 'a> SparseIterTools for VectorIterator<'a, N> {
+impl<'a, N: 'a, I: 'a + SpIndex> SparseIterTools for VectorIterator<'a, N, I> {
 }
 
 /// Trait for types that can be iterated as sparse vectors
@@ -202,29 +223,32 @@ pub trait IntoSparseVecIter<N> {
     fn dim(&self) -> usize;
 }
 
-impl<'a, N: 'a> IntoSparseVecIter<&'a N> for CsVecView<'a, N> {
-    type IterType = VectorIterator<'a, N>;
+impl<'a, N: 'a, I: 'a + SpIndex> IntoSparseVecIter<&'a N>
+for CsVecViewI<'a, N, I> {
+    type IterType = VectorIterator<'a, N, I>;
 
     fn dim(&self) -> usize {
         self.dim()
     }
 
-    fn into_sparse_vec_iter(self) -> VectorIterator<'a, N> {
+    fn into_sparse_vec_iter(self) -> VectorIterator<'a, N, I> {
         self.iter_()
     }
 }
 
-impl<'a, N: 'a, IS, DS> IntoSparseVecIter<&'a N> for &'a CsVec<N, IS, DS>
-where IS: Deref<Target=[usize]>,
+impl<'a, N: 'a, I: 'a, IS, DS> IntoSparseVecIter<&'a N>
+for &'a CsVecBase<N, I, IS, DS>
+where I: SpIndex,
+      IS: Deref<Target=[I]>,
       DS: Deref<Target=[N]>
 {
-    type IterType = VectorIterator<'a, N>;
+    type IterType = VectorIterator<'a, N, I>;
 
     fn dim(&self) -> usize {
         (*self).dim()
     }
 
-    fn into_sparse_vec_iter(self) -> VectorIterator<'a, N> {
+    fn into_sparse_vec_iter(self) -> VectorIterator<'a, N, I> {
         self.iter()
     }
 }
@@ -342,15 +366,15 @@ where Ite1: Iterator<Item=(usize, &'a N1)>,
     }
 }
 
-impl<'a, N: 'a> CsVecView<'a, N> {
+impl<'a, N: 'a, I: 'a + SpIndex> CsVecBase<N, I, &'a [I], &'a [N]> {
 
     /// Create a borrowed CsVec over slice data.
     pub fn new_view(
         n: usize,
-        indices: &'a [usize],
+        indices: &'a [I],
         data: &'a [N])
-    -> Result<CsVec<N, &'a[usize], &'a[N]>, SprsError> {
-        let v = CsVec {
+    -> Result<CsVecViewI<'a, N, I>, SprsError> {
+        let v = CsVecBase {
             dim: n,
             indices: indices,
             data: data,
@@ -368,7 +392,7 @@ impl<'a, N: 'a> CsVecView<'a, N> {
     }
 
     /// Re-borrowing version of `iter()`
-    fn iter_(&self) -> VectorIterator<'a, N> {
+    fn iter_(&self) -> VectorIterator<'a, N, I> {
         VectorIterator {
             ind_data: self.indices.iter().zip(self.data.iter()),
         }
@@ -381,10 +405,10 @@ impl<'a, N: 'a> CsVecView<'a, N> {
     /// perform unchecked slice access.
     pub unsafe fn new_view_raw(n: usize,
                                nnz: usize,
-                               indices: *const usize,
+                               indices: *const I,
                                data: *const N,
-                              ) -> CsVec<N, &'a[usize], &'a[N]> {
-        CsVec {
+                              ) -> CsVecViewI<'a, N, I> {
+        CsVecBase {
             dim: n,
             indices: slice::from_raw_parts(indices, nnz),
             data: slice::from_raw_parts(data, nnz),
@@ -392,7 +416,7 @@ impl<'a, N: 'a> CsVecView<'a, N> {
     }
 }
 
-impl<N> CsVec<N, Vec<usize>, Vec<N>> {
+impl<N, I: SpIndex> CsVecBase<N, I, Vec<I>, Vec<N>> {
     /// Create an owning CsVec from vector data.
     ///
     /// # Panics
@@ -400,16 +424,16 @@ impl<N> CsVec<N, Vec<usize>, Vec<N>> {
     /// - if `indices` and `data` lengths differ
     /// - if the vector contains out of bounds indices
     pub fn new(n: usize,
-               mut indices: Vec<usize>,
+               mut indices: Vec<I>,
                mut data: Vec<N>
-              ) -> CsVec<N, Vec<usize>, Vec<N>>
+              ) -> CsVecI<N, I>
     where N: Copy
     {
         let mut buf = Vec::with_capacity(indices.len());
         utils::sort_indices_data_slices(&mut indices[..],
                                         &mut data[..],
                                         &mut buf);
-        let v = CsVec {
+        let v = CsVecBase {
             dim: n,
             indices: indices,
             data: data
@@ -418,8 +442,8 @@ impl<N> CsVec<N, Vec<usize>, Vec<N>> {
     }
 
     /// Create an empty CsVec, which can be used for incremental construction
-    pub fn empty(dim: usize) -> CsVec<N, Vec<usize>, Vec<N>> {
-        CsVec {
+    pub fn empty(dim: usize) -> CsVecI<N, I> {
+        CsVecBase {
             dim: dim,
             indices: Vec::new(),
             data: Vec::new(),
@@ -439,10 +463,11 @@ impl<N> CsVec<N, Vec<usize>, Vec<N>> {
     pub fn append(&mut self, ind: usize, val: N) {
         match self.indices.last() {
             None => (),
-            Some(&last_ind) => assert!(ind > last_ind, "unsorted append")
+            Some(&last_ind) => assert!(ind > last_ind.index(),
+                                       "unsorted append")
         }
         assert!(ind <= self.dim, "out of bounds index");
-        self.indices.push(ind);
+        self.indices.push(I::from_usize(ind));
         self.data.push(val);
     }
 
@@ -465,13 +490,14 @@ impl<N> CsVec<N, Vec<usize>, Vec<N>> {
     }
 }
 
-impl<N, IStorage, DStorage> CsVec<N, IStorage, DStorage>
-where IStorage: Deref<Target=[usize]>,
+impl<N, I, IStorage, DStorage> CsVecBase<N, I, IStorage, DStorage>
+where I: SpIndex,
+      IStorage: Deref<Target=[I]>,
       DStorage: Deref<Target=[N]> {
 
     /// Get a view of this vector.
-    pub fn view(&self) -> CsVecView<N> {
-        CsVec {
+    pub fn view(&self) -> CsVecViewI<N, I> {
+        CsVecBase {
             dim: self.dim,
             indices: &self.indices[..],
             data: &self.data[..],
@@ -479,8 +505,9 @@ where IStorage: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IStorage, DStorage> CsVec<N, IStorage, DStorage>
-where IStorage: Deref<Target=[usize]>,
+impl<N, I, IStorage, DStorage> CsVecBase<N, I, IStorage, DStorage>
+where I: SpIndex,
+      IStorage: Deref<Target=[I]>,
       DStorage: Deref<Target=[N]> {
 
     /// Iterate over the non zero values.
@@ -496,7 +523,7 @@ where IStorage: Deref<Target=[usize]>,
     /// assert_eq!(iter.next(), Some((4, &3.)));
     /// assert_eq!(iter.next(), None);
     /// ```
-    pub fn iter(&self) -> VectorIterator<N> {
+    pub fn iter(&self) -> VectorIterator<N, I> {
         VectorIterator {
             ind_data: self.indices.iter().zip(self.data.iter()),
         }
@@ -505,8 +532,8 @@ where IStorage: Deref<Target=[usize]>,
     /// Permuted iteration. Not finished
     #[doc(hidden)]
     pub fn iter_perm<'a, 'perm: 'a>(&'a self,
-                                    perm: PermView<'perm>)
-    -> VectorIteratorPerm<'a, N>
+                                    perm: PermViewI<'perm, I>)
+    -> VectorIteratorPerm<'a, N, I>
     where N: 'a
     {
         VectorIteratorPerm {
@@ -516,7 +543,7 @@ where IStorage: Deref<Target=[usize]>,
     }
 
     /// The underlying indices.
-    pub fn indices(&self) -> &[usize] {
+    pub fn indices(&self) -> &[I] {
         &self.indices
     }
 
@@ -543,31 +570,49 @@ where IStorage: Deref<Target=[usize]>,
             return Err(SprsError::NonSortedIndices);
         }
 
-        if self.indices.iter().max().unwrap_or(&0) >= &self.dim {
-            panic!("Out of bounds index");
+        let max_ind = self.indices.iter().max().map(|i| i.index());
+        if max_ind.map_or(false, |max_ind| max_ind >= self.dim) {
+            return Err(SprsError::OutOfBoundsIndex);
         }
 
         Ok(())
     }
 
     /// Allocate a new vector equal to this one.
-    pub fn to_owned(&self) -> CsVecOwned<N>
+    pub fn to_owned(&self) -> CsVecI<N, I>
     where N: Clone
     {
-        CsVec {
+        CsVecBase {
             dim: self.dim,
             indices: self.indices.to_vec(),
             data: self.data.to_vec(),
         }
     }
 
+    /// Allocate a new vector equal to this one, using another index type.
+    ///
+    /// # Errors
+    ///
+    /// `IndexOverflow` if an index does not fit in the requested index type.
+    pub fn to_other_index<I2>(&self) -> Result<CsVecI<N, I2>, SprsError>
+    where N: Clone,
+          I2: SpIndex
+    {
+        let indices = try!(convert_indices(&self.indices[..]));
+        Ok(CsVecBase {
+            dim: self.dim,
+            indices: indices,
+            data: self.data.to_vec(),
+        })
+    }
+
     /// View this vector as a matrix with only one row.
-    pub fn row_view(&self) -> CsMatVecView<N> {
+    pub fn row_view(&self) -> CsMatVecViewI<N, I> {
         // Safe because we're taking a view into a vector that has
         // necessarily been checked
-        let indptr = vec![0, self.indices.len()];
+        let indptr = vec![I::zero(), I::from_usize(self.indices.len())];
         unsafe {
-            CsMatVecView::new_vecview_raw(CSR, 1, self.dim,
+            CsMatBase::new_vecview_raw(CSR, 1, self.dim,
                                           indptr,
                                           self.indices.as_ptr(),
                                           self.data.as_ptr())
@@ -575,12 +620,12 @@ where IStorage: Deref<Target=[usize]>,
     }
 
     /// View this vector as a matrix with only one column.
-    pub fn col_view(&self) -> CsMatVecView<N> {
+    pub fn col_view(&self) -> CsMatVecViewI<N, I> {
         // Safe because we're taking a view into a vector that has
         // necessarily been checked
-        let indptr = vec![0, self.indices.len()];
+        let indptr = vec![I::zero(), I::from_usize(self.indices.len())];
         unsafe {
-            CsMatVecView::new_vecview_raw(CSC, self.dim, 1,
+            CsMatBase::new_vecview_raw(CSC, self.dim, 1,
                                           indptr,
                                           self.indices.as_ptr(),
                                           self.data.as_ptr())
@@ -599,7 +644,9 @@ where IStorage: Deref<Target=[usize]>,
     /// once it is available, the NnzIndex enables retrieving the data with
     /// O(1) complexity.
     pub fn nnz_index(&self, index: usize) -> Option<NnzIndex> {
-        self.indices.binary_search(&index).map(|i| NnzIndex(i)).ok()
+        I::try_from_usize(index).and_then(|index| {
+            self.indices.binary_search(&index).map(|i| NnzIndex(i)).ok()
+        })
     }
 
     /// Sparse vector dot product. The right-hand-side can be any type
@@ -641,12 +688,15 @@ where IStorage: Deref<Target=[usize]>,
     /// Transform this vector into a set of (index, value) tuples
     pub fn to_set(self) -> HashSet<(usize, N)>
     where N: Hash + Eq + Clone {
-        self.indices().iter().cloned().zip(self.data.iter().cloned()).collect()
+        self.indices().iter()
+                      .map(|i| i.index())
+                      .zip(self.data.iter().cloned())
+                      .collect()
     }
 
     /// Apply a function to each non-zero element, yielding a new matrix
     /// with the same sparsity structure.
-    pub fn map<F>(&self, f: F) -> CsVecOwned<N>
+    pub fn map<F>(&self, f: F) -> CsVecI<N, I>
     where F: FnMut(&N) -> N,
           N: Clone
     {
@@ -656,9 +706,10 @@ where IStorage: Deref<Target=[usize]>,
     }
 }
 
-impl<'a, N, IStorage, DStorage> CsVec<N, IStorage, DStorage>
+impl<'a, N, I, IStorage, DStorage> CsVecBase<N, I, IStorage, DStorage>
 where N: 'a,
-      IStorage: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IStorage: 'a + Deref<Target=[I]>,
       DStorage: DerefMut<Target=[N]> {
 
     /// The underlying non zero values as a mutable slice.
@@ -666,8 +717,8 @@ where N: 'a,
         &mut self.data[..]
     }
 
-    pub fn view_mut(&mut self) -> CsVecViewMut<N> {
-        CsVec {
+    pub fn view_mut(&mut self) -> CsVecViewMutI<N, I> {
+        CsVecBase {
             dim: self.dim,
             indices: &self.indices[..],
             data: &mut self.data[..],
@@ -695,8 +746,9 @@ where N: 'a,
 
 }
 
-impl<'a, N> CsVecViewMut<'a, N>
-where N: 'a {
+impl<'a, N, I> CsVecViewMutI<'a, N, I>
+where N: 'a,
+      I: 'a + SpIndex {
 
     /// Create a borrowed CsVec over slice data without checking the structure
     /// This is unsafe because algorithms are free to assume
@@ -705,10 +757,10 @@ where N: 'a {
     /// perform unchecked slice access.
     pub unsafe fn new_view_mut_raw(n: usize,
                                    nnz: usize,
-                                   indices: *const usize,
+                                   indices: *const I,
                                    data: *mut N,
-                                  ) -> CsVecViewMut<'a, N> {
-        CsVec {
+                                  ) -> CsVecViewMutI<'a, N, I> {
+        CsVecBase {
             dim: n,
             indices: slice::from_raw_parts(indices, nnz),
             data: slice::from_raw_parts_mut(data, nnz),
@@ -716,34 +768,37 @@ where N: 'a {
     }
 }
 
-impl<'a, 'b, N, IS1, DS1, IpS2, IS2, DS2> Mul<&'b CsMat<N, IpS2, IS2, DS2>>
-for &'a CsVec<N, IS1, DS1>
+impl<'a, 'b, N, I, IS1, DS1, IpS2, IS2, DS2>
+Mul<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
+for &'a CsVecBase<N, I, IS1, DS1>
 where N: 'a + Copy + Num + Default,
-      IS1: 'a + Deref<Target=[usize]>,
+      I: 'a + SpIndex,
+      IS1: 'a + Deref<Target=[I]>,
       DS1: 'a + Deref<Target=[N]>,
-      IpS2: 'b + Deref<Target=[usize]>,
-      IS2: 'b + Deref<Target=[usize]>,
+      IpS2: 'b + Deref<Target=[I]>,
+      IS2: 'b + Deref<Target=[I]>,
       DS2: 'b + Deref<Target=[N]> {
 
-    type Output = CsVecOwned<N>;
+    type Output = CsVecI<N, I>;
 
-    fn mul(self, rhs: &CsMat<N, IpS2, IS2, DS2>) -> CsVecOwned<N> {
+    fn mul(self, rhs: &CsMatBase<N, I, IpS2, IS2, DS2>) -> CsVecI<N, I> {
         (&self.row_view() * rhs).outer_view(0).unwrap().to_owned()
     }
 }
 
-impl<'a, 'b, N, IpS1, IS1, DS1, IS2, DS2> Mul<&'b CsVec<N, IS2, DS2>>
-for &'a CsMat<N, IpS1, IS1, DS1>
+impl<'a, 'b, N, I, IpS1, IS1, DS1, IS2, DS2> Mul<&'b CsVecBase<N, I, IS2, DS2>>
+for &'a CsMatBase<N, I, IpS1, IS1, DS1>
 where N: Copy + Num + Default,
-      IpS1: Deref<Target=[usize]>,
-      IS1: Deref<Target=[usize]>,
+      I: SpIndex,
+      IpS1: Deref<Target=[I]>,
+      IS1: Deref<Target=[I]>,
       DS1: Deref<Target=[N]>,
-      IS2: Deref<Target=[usize]>,
+      IS2: Deref<Target=[I]>,
       DS2: Deref<Target=[N]> {
 
-    type Output = CsVecOwned<N>;
+    type Output = CsVecI<N, I>;
 
-    fn mul(self, rhs: &CsVec<N, IS2, DS2>) -> CsVecOwned<N> {
+    fn mul(self, rhs: &CsVecBase<N, I, IS2, DS2>) -> CsVecI<N, I> {
         if self.is_csr() {
             prod::csr_mul_csvec(self.view(), rhs.view())
         }
@@ -753,17 +808,18 @@ where N: Copy + Num + Default,
     }
 }
 
-impl<'a, 'b, N, IS1, DS1, IS2, DS2> Add<&'b CsVec<N, IS2, DS2>>
-for &'a CsVec<N, IS1, DS1>
+impl<'a, 'b, N, I, IS1, DS1, IS2, DS2> Add<&'b CsVecBase<N, I, IS2, DS2>>
+for &'a CsVecBase<N, I, IS1, DS1>
 where N: Copy + Num,
-      IS1: Deref<Target=[usize]>,
+      I: SpIndex,
+      IS1: Deref<Target=[I]>,
       DS1: Deref<Target=[N]>,
-      IS2: Deref<Target=[usize]>,
+      IS2: Deref<Target=[I]>,
       DS2: Deref<Target=[N]> {
 
-    type Output = CsVecOwned<N>;
+    type Output = CsVecI<N, I>;
 
-    fn add(self, rhs: &CsVec<N, IS2, DS2>) -> CsVecOwned<N> {
+    fn add(self, rhs: &CsVecBase<N, I, IS2, DS2>) -> CsVecI<N, I> {
         binop::csvec_binop(self.view(),
                            rhs.view(),
                            |&x, &y| x + y
@@ -771,17 +827,18 @@ where N: Copy + Num,
     }
 }
 
-impl<'a, 'b, N, IS1, DS1, IS2, DS2> Sub<&'b CsVec<N, IS2, DS2>>
-for &'a CsVec<N, IS1, DS1>
+impl<'a, 'b, N, I, IS1, DS1, IS2, DS2> Sub<&'b CsVecBase<N, I, IS2, DS2>>
+for &'a CsVecBase<N, I, IS1, DS1>
 where N: Copy + Num,
-      IS1: Deref<Target=[usize]>,
+      I: SpIndex,
+      IS1: Deref<Target=[I]>,
       DS1: Deref<Target=[N]>,
-      IS2: Deref<Target=[usize]>,
+      IS2: Deref<Target=[I]>,
       DS2: Deref<Target=[N]> {
 
-    type Output = CsVecOwned<N>;
+    type Output = CsVecI<N, I>;
 
-    fn sub(self, rhs: &CsVec<N, IS2, DS2>) -> CsVecOwned<N> {
+    fn sub(self, rhs: &CsVecBase<N, I, IS2, DS2>) -> CsVecI<N, I> {
         binop::csvec_binop(self.view(),
                            rhs.view(),
                            |&x, &y| x - y
@@ -789,8 +846,9 @@ where N: Copy + Num,
     }
 }
 
-impl<N, IS, DS> Index<usize> for CsVec<N, IS, DS>
-where IS: Deref<Target=[usize]>,
+impl<N, I, IS, DS> Index<usize> for CsVecBase<N, I, IS, DS>
+where I: SpIndex,
+      IS: Deref<Target=[I]>,
       DS: Deref<Target=[N]> {
 
     type Output = N;
@@ -800,8 +858,9 @@ where IS: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IS, DS> IndexMut<usize> for CsVec<N, IS, DS>
-where IS: Deref<Target=[usize]>,
+impl<N, I, IS, DS> IndexMut<usize> for CsVecBase<N, I, IS, DS>
+where I: SpIndex,
+      IS: Deref<Target=[I]>,
       DS: DerefMut<Target=[N]> {
 
     fn index_mut(&mut self, index: usize) -> &mut N {
@@ -809,8 +868,9 @@ where IS: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IS, DS> Index<NnzIndex> for CsVec<N, IS, DS>
-where IS: Deref<Target=[usize]>,
+impl<N, I, IS, DS> Index<NnzIndex> for CsVecBase<N, I, IS, DS>
+where I: SpIndex,
+      IS: Deref<Target=[I]>,
       DS: Deref<Target=[N]>
 {
     type Output = N;
@@ -821,8 +881,9 @@ where IS: Deref<Target=[usize]>,
     }
 }
 
-impl<N, IS, DS> IndexMut<NnzIndex> for CsVec<N, IS, DS>
-where IS: Deref<Target=[usize]>,
+impl<N, I, IS, DS> IndexMut<NnzIndex> for CsVecBase<N, I, IS, DS>
+where I: SpIndex,
+      IS: Deref<Target=[I]>,
       DS: DerefMut<Target=[N]>
 {
     fn index_mut(&mut self, index: NnzIndex) -> &mut N {
@@ -831,9 +892,48 @@ where IS: Deref<Target=[usize]>,
     }
 }
 
+/// Deserialized form of a CsVecBase, whose structure has not been checked
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub struct CsVecBaseShadow<N, I, IStorage, DStorage>
+where I: SpIndex,
+      IStorage: Deref<Target=[I]>,
+      DStorage: Deref<Target=[N]> {
+    dim: usize,
+    indices: IStorage,
+    data: DStorage,
+    #[serde(skip)]
+    marker: PhantomData<(N, I)>,
+}
+
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+impl<N, I, IStorage, DStorage> TryFrom<CsVecBaseShadow<N, I, IStorage, DStorage>>
+for CsVecBase<N, I, IStorage, DStorage>
+where I: SpIndex,
+      IStorage: Deref<Target=[I]>,
+      DStorage: Deref<Target=[N]> {
+    type Error = SprsError;
+
+    fn try_from(shadow: CsVecBaseShadow<N, I, IStorage, DStorage>)
+                -> Result<Self, SprsError> {
+        let vec = CsVecBase {
+            dim: shadow.dim,
+            indices: shadow.indices,
+            data: shadow.data,
+        };
+        if vec.indices.len() != vec.data.len() {
+            return Err(SprsError::BadStorageLength);
+        }
+        vec.check_structure().and(Ok(vec))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::CsVec;
+    use super::{CsVec, CsVecI};
+    use errors::SprsError;
     use super::SparseIterTools;
     use ndarray::Array;
 
@@ -973,4 +1073,49 @@ mod test {
         let expected = CsVec::new(8, vec![0, 2, 4, 6], vec![2., 4., 6., 8.]);
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn smaller_index_types() {
+        let vec = CsVec::new(8, vec![0, 2, 4, 6], vec![1., 2., 3., 4.]);
+        let vec_u16: CsVecI<f64, u16> = vec.to_other_index().unwrap();
+        assert_eq!(vec_u16.indices(), &[0, 2, 4, 6]);
+        assert_eq!(vec_u16[4], 3.);
+        assert_eq!(vec_u16.dot(&vec_u16), vec.dot(&vec));
+        let sum = &vec_u16 + &vec_u16;
+        assert_eq!(sum.to_other_index(), Ok(vec.map(|&x| 2. * x)));
+
+        let long = CsVec::new(70000, vec![1, 69999], vec![1., 2.]);
+        let res: Result<CsVecI<f64, u16>, _> = long.to_other_index();
+        assert_eq!(res, Err(SprsError::IndexOverflow));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        use serde_json;
+        let vec = CsVec::new(8, vec![0, 2, 4, 6], vec![1., 2., 3., 4.]);
+        let json = serde_json::to_string(&vec).unwrap();
+        let vec2: CsVecI<f64, usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(vec, vec2);
+
+        let parse = |json| serde_json::from_str::<CsVecI<f64, usize>>(json);
+        assert!(parse(r#"{"dim":4,"indices":[0,2],"data":[1,2]}"#).is_ok());
+        // unsorted indices, out of bounds index, length mismatch
+        assert!(parse(r#"{"dim":4,"indices":[2,0],"data":[1,2]}"#).is_err());
+        assert!(parse(r#"{"dim":4,"indices":[0,4],"data":[1,2]}"#).is_err());
+        assert!(parse(r#"{"dim":4,"indices":[0,2],"data":[1]}"#).is_err());
+    }
+
+    #[test]
+    fn new_dim_zero() {
+        let vec: CsVecI<f64, usize> = CsVec::new(0, vec![], vec![]);
+        assert_eq!(vec.dim(), 0);
+        assert_eq!(vec.nnz(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_out_of_bounds() {
+        let _: CsVecI<f64, usize> = CsVec::new(2, vec![0, 2], vec![1., 2.]);
+    }
 }